@@ -0,0 +1,56 @@
+//! The glue between the commands that emit wasm (`run`, `build`, `test`) and the
+//! compiler's on-disk build cache (`compiler::build_cache`). A command opens an
+//! [`ArtifactCache`] before `check()`; a hit hands back the previously emitted
+//! bytes and the command skips straight to running or writing them. A miss
+//! compiles as usual and, when the compile was clean, stores the result.
+//!
+//! `pluma dev` rides along: its restarts shell out to `pluma run`.
+//!
+//! `pluma check` emits nothing; [`check_incrementally`] persists the analyzed
+//! exports of each clean module instead, so the next check skips re-analyzing
+//! the ones whose source hasn't changed.
+
+use compiler::build_cache::{BuildCache, CachedArtifact, cache_key};
+use compiler::{Compiler, Diagnostic};
+
+pub(crate) struct ArtifactCache {
+	cache: BuildCache,
+	key: String,
+}
+
+impl ArtifactCache {
+	/// The cache slot for `compiler`'s entries under `command`, whose `options`
+	/// are everything else that changes the emitted bytes (target, opt level,
+	/// colour, …). `None` when the cache is disabled (`PLUMA_NO_CACHE=1`).
+	pub(crate) fn open(compiler: &Compiler, command: &str, options: &[&str]) -> Option<Self> {
+		let root = compiler.root_dir.to_string_lossy();
+		let mut parts = vec![env!("PLUMA_VERSION"), command, &root];
+		parts.extend(options);
+		parts.extend(compiler.entry_modules.iter().map(String::as_str));
+		Some(ArtifactCache {
+			cache: BuildCache::open(&compiler.root_dir)?,
+			key: cache_key(&parts),
+		})
+	}
+
+	pub(crate) fn load(&self) -> Option<CachedArtifact> {
+		self.cache.load(&self.key)
+	}
+
+	pub(crate) fn store(&self, compiler: &Compiler, bytes: &[u8], meta: &str) {
+		self.cache.store(&self.key, compiler, bytes, meta);
+	}
+}
+
+/// `compiler.check()`, reusing the exports an earlier check persisted for
+/// unchanged modules and persisting this one's. A plain `check()` when the
+/// cache is disabled.
+pub(crate) fn check_incrementally(compiler: &mut Compiler) -> Result<(), Vec<Diagnostic>> {
+	let Some(cache) = BuildCache::open(&compiler.root_dir) else {
+		return compiler.check();
+	};
+	compiler.enable_incremental(cache.load_exports(env!("PLUMA_VERSION")));
+	let result = compiler.check();
+	cache.store_exports(env!("PLUMA_VERSION"), &compiler.take_incremental_cache());
+	result
+}
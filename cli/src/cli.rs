@@ -82,6 +82,11 @@ pub(crate) enum Command {
 		#[arg(long, hide = true, value_name = "TARGET")]
 		target: Option<String>,
 
+		/// How to print diagnostics: `human` (default), `json` (one object per
+		/// line on stdout), or `sarif` (one SARIF 2.1.0 log on stdout).
+		#[arg(long = "message-format", value_name = "FORMAT", value_parser = ["human", "json", "sarif"])]
+		message_format: Option<String>,
+
 		/// Module to build: a `.pa` file or a fullstack directory.
 		path: String,
 	},
@@ -107,6 +112,28 @@ pub(crate) enum Command {
 		path: String,
 	},
 
+	/// Type-check modules without running or emitting anything.
+	///
+	/// A `.pa` file checks that module and everything it imports. A directory
+	/// checks every module in its package — including `*.test.pa` suites — and a
+	/// fullstack directory also gates its server half as `sys` and its client
+	/// half as `web`, as `pluma build` would. Exits non-zero on any error, so it
+	/// can gate CI.
+	Check {
+		/// Gate module availability against a deploy target (`sys` or `web`).
+		#[arg(long, value_name = "TARGET", value_parser = ["sys", "web"])]
+		target: Option<String>,
+
+		/// How to print diagnostics: `human` (default), `json` (one object per
+		/// line on stdout), or `sarif` (one SARIF 2.1.0 log on stdout).
+		#[arg(long = "message-format", value_name = "FORMAT", value_parser = ["human", "json", "sarif"])]
+		message_format: Option<String>,
+
+		/// Files or directories to check (default: the current directory).
+		#[arg(value_name = "PATH")]
+		paths: Vec<String>,
+	},
+
 	/// Canonicalize formatting in place.
 	///
 	/// Pass `-` to read a single module from stdin (writes to stdout). A
@@ -136,6 +163,11 @@ pub(crate) enum Command {
 		#[arg(long)]
 		fix: bool,
 
+		/// How to print diagnostics: `human` (default), `json` (one object per
+		/// line on stdout), or `sarif` (one SARIF 2.1.0 log on stdout).
+		#[arg(long = "message-format", value_name = "FORMAT", value_parser = ["human", "json", "sarif"])]
+		message_format: Option<String>,
+
 		/// Files or directories to lint; `-` reads stdin.
 		#[arg(value_name = "PATH")]
 		paths: Vec<String>,
@@ -153,14 +185,110 @@ pub(crate) enum Command {
 		#[arg(short = 'f', value_name = "NAME")]
 		filters: Vec<String>,
 
+		/// Only run the module with exactly this name, e.g. `math.test`
+		/// (repeatable).
+		#[arg(long = "module", value_name = "NAME")]
+		modules: Vec<String>,
+
 		/// Re-run the suite on every source change instead of running once.
 		#[arg(short = 'w', long)]
 		watch: bool,
 
+		/// Count which lines, branches and defs of the package the suite ran.
+		/// Writes `coverage/lcov.info` and prints a summary table.
+		#[arg(long)]
+		coverage: bool,
+
+		/// With `--coverage`, fail when line coverage is below this percentage.
+		#[arg(long = "min-coverage", value_name = "PERCENT", requires = "coverage")]
+		min_coverage: Option<f64>,
+
+		/// Rewrite `assert.snapshot` files with this run's values instead of
+		/// comparing against them.
+		#[arg(long = "update-snapshots")]
+		update_snapshots: bool,
+
+		/// Run the example blocks in doc comments as tests instead of the
+		/// `*.test.pa` suites. A `# => value` line checks an example's result.
+		#[arg(long)]
+		doc: bool,
+
+		/// Stop and fail a case still running after this many seconds (default
+		/// 10), unless it sets its own limit with `test.timeout`.
+		#[arg(long, value_name = "SECONDS", value_parser = parse_seconds)]
+		timeout: Option<std::time::Duration>,
+
+		/// Run a failing case up to this many more times. A case that passes
+		/// on a retry is reported as flaky.
+		#[arg(long, value_name = "N", default_value_t = 0)]
+		retries: u32,
+
+		/// Run concurrent tasks in an order shuffled by this seed instead of
+		/// the order they became ready, to shake out races. A failing run
+		/// prints its seed; pass it again to replay the same interleaving.
+		#[arg(long = "schedule-seed", value_name = "N")]
+		schedule_seed: Option<u64>,
+
+		/// Only run the case or group at this path: its group names, then its
+		/// own, joined with ` > ` (e.g. `--case "parsing > empty input"`).
+		#[arg(long, value_name = "PATH")]
+		case: Option<String>,
+
+		/// Also report each case's status, failure message and duration as
+		/// `junit` (XML), `tap` or `json`, for CI tools.
+		#[arg(long, value_name = "FORMAT", value_parser = ["junit", "tap", "json"])]
+		reporter: Option<String>,
+
+		/// With `--reporter`, write the report to this file. Without it the
+		/// report goes to stdout in place of the usual output.
+		#[arg(long, value_name = "FILE", requires = "reporter")]
+		output: Option<String>,
+
+		/// How to print diagnostics: `human` (default), `json` (one object per
+		/// line on stdout), or `sarif` (one SARIF 2.1.0 log on stdout).
+		#[arg(long = "message-format", value_name = "FORMAT", value_parser = ["human", "json", "sarif"])]
+		message_format: Option<String>,
+
 		/// Directory to start the walk-up from (default: current directory).
 		dir: Option<String>,
 	},
 
+	/// Discover and run micro-benchmarks from `*.bench.pa` files.
+	///
+	/// Walks up from the given directory (or cwd) to the nearest `pluma.pa`
+	/// package root, then times every `std/bench` case it finds under V8 and
+	/// prints the mean, median and spread of each.
+	Bench {
+		/// Only run modules whose name contains this (repeatable).
+		#[arg(short = 'f', value_name = "NAME")]
+		filters: Vec<String>,
+
+		/// Save this run's results as a named baseline, under `.pluma/bench/`.
+		#[arg(long = "save-baseline", value_name = "NAME")]
+		save_baseline: Option<String>,
+
+		/// Compare each case's mean against a saved baseline.
+		#[arg(long, value_name = "NAME")]
+		baseline: Option<String>,
+
+		/// How to print diagnostics: `human` (default), `json` (one object per
+		/// line on stdout), or `sarif` (one SARIF 2.1.0 log on stdout).
+		#[arg(long = "message-format", value_name = "FORMAT", value_parser = ["human", "json", "sarif"])]
+		message_format: Option<String>,
+
+		/// Directory to start the walk-up from (default: current directory).
+		dir: Option<String>,
+	},
+
+	/// Explain a diagnostic code, e.g. `pluma explain E0100`.
+	///
+	/// Prints what the error or lint means, why it's reported, a failing example
+	/// and the fixed version.
+	Explain {
+		/// The code from a diagnostic, such as `E0100` or `L0004`.
+		code: String,
+	},
+
 	/// Generate documentation for a module from its source.
 	///
 	/// Analyzes the module and emits a self-contained Pluma data module
@@ -199,3 +327,13 @@ pub(crate) enum Command {
 	#[command(external_subcommand)]
 	External(Vec<String>),
 }
+
+/// A positive number of seconds, whole or fractional (`--timeout 2.5`).
+fn parse_seconds(arg: &str) -> Result<std::time::Duration, String> {
+	arg
+		.parse::<f64>()
+		.ok()
+		.filter(|secs| *secs > 0.0)
+		.and_then(|secs| std::time::Duration::try_from_secs_f64(secs).ok())
+		.ok_or_else(|| format!("`{arg}` isn't a positive number of seconds"))
+}
@@ -2,9 +2,12 @@
 //! a single entry point invoked by the dispatcher in `main`; shared infrastructure
 //! (diagnostics printing, the browser bundle) lives in the top-level modules.
 
+pub(crate) mod bench;
 pub(crate) mod build;
+pub(crate) mod check;
 pub(crate) mod dev;
 pub(crate) mod doc;
+pub(crate) mod explain;
 pub(crate) mod format;
 pub(crate) mod lint;
 pub(crate) mod run;
@@ -0,0 +1,507 @@
+//! `pluma bench`: discover the `*.bench.pa` files under the package root, time
+//! every case through `std/bench`, and print a table of per-run times.
+//!
+//! The timing itself happens in Pluma (`std/bench.run-all`, reached through the
+//! entry `ir::lower_benches` synthesizes); the host hands back the record it
+//! prints per case (`host::run_bench_v8`). This side renders the table and keeps
+//! named baselines: `--save-baseline NAME` writes the run to
+//! `.pluma/bench/NAME.tsv` under the package root, and `--baseline NAME` adds a
+//! column comparing each case's mean against it.
+
+use compiler::*;
+use host::{BenchResult, Echo};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::cache::ArtifactCache;
+use crate::colors::Style;
+use crate::printing::*;
+
+use super::test::{discover_modules, package_root};
+
+/// Where named baselines live, relative to the package root.
+const BASELINE_DIR: &str = ".pluma/bench";
+
+/// The baseline flags of a run.
+pub(crate) struct BenchOptions {
+	/// `--save-baseline`: the name to save this run's results under.
+	pub(crate) save_baseline: Option<String>,
+	/// `--baseline`: the saved run to compare against.
+	pub(crate) baseline: Option<String>,
+}
+
+pub(crate) fn bench_command(
+	filters: Vec<String>,
+	options: BenchOptions,
+	message_format: Option<String>,
+	dir: Option<String>,
+) {
+	set_message_format(message_format.as_deref());
+	for name in [&options.save_baseline, &options.baseline]
+		.into_iter()
+		.flatten()
+	{
+		if !is_baseline_name(name) {
+			print_error(format!(
+				"`{name}` isn't a usable baseline name (use letters, digits, `-`, `_` and `.`)"
+			));
+			exit(1);
+		}
+	}
+	let root_dir = package_root(dir);
+	exit(run_benches(&filters, &options, &root_dir));
+}
+
+fn run_benches(filters: &[String], options: &BenchOptions, root_dir: &Path) -> i32 {
+	let t_start = std::time::Instant::now();
+
+	let mut bench_modules = discover_modules(root_dir, |file| file.ends_with(".bench.pa"));
+	bench_modules.sort();
+	if !filters.is_empty() {
+		bench_modules.retain(|name| filters.iter().any(|f| name.contains(f)));
+	}
+	if bench_modules.is_empty() {
+		if filters.is_empty() {
+			eprintln!(
+				"no benchmark files found (looked for *.bench.pa under {})",
+				root_dir.display()
+			);
+		} else {
+			eprintln!("no benchmark files match {:?}", filters);
+		}
+		return 0;
+	}
+
+	// Read the baseline up front, so a typo fails before the slow part.
+	let baseline = match &options.baseline {
+		Some(name) => match load_baseline(root_dir, name) {
+			Ok(entries) => Some(entries),
+			Err(msg) => {
+				print_error(msg);
+				return 1;
+			}
+		},
+		None => None,
+	};
+
+	// The report shares stdout only with human diagnostics; `--message-format
+	// json|sarif` keeps stdout to itself.
+	let to = if is_machine_readable() {
+		Echo::Stderr
+	} else {
+		Echo::Stdout
+	};
+
+	let count = bench_modules.len();
+	let module_word = if count == 1 { "module" } else { "modules" };
+	echo(
+		to,
+		format!(
+			"running {} bench {} in {}",
+			count,
+			module_word,
+			root_dir.display()
+		),
+	);
+	echo(to, "");
+
+	let mut compiler = Compiler::for_root_dir(root_dir.to_path_buf());
+	compiler.add_entry_module(compiler::PROJECT_MARKER_MODULE.to_string());
+	for name in &bench_modules {
+		compiler.add_entry_module(name.clone());
+	}
+
+	if let Err(diagnostics) = compiler.lock_packages()
+		&& print_diagnostics_is_fatal(diagnostics)
+	{
+		return 1;
+	}
+
+	// An unchanged set of benchmarks re-runs its cached artifact; the runner
+	// needs nothing up front, so the artifact's `meta` is empty.
+	let cache = ArtifactCache::open(&compiler, "bench", &[]);
+	let bytes = match cache.as_ref().and_then(ArtifactCache::load) {
+		Some(hit) => hit.bytes,
+		None => {
+			let clean = match compiler.check() {
+				Ok(()) => true,
+				Err(diagnostics) => {
+					if print_diagnostics_is_fatal(diagnostics) {
+						return 1;
+					}
+					false
+				}
+			};
+			let program = match ir::lower_benches(&compiler) {
+				Ok(p) => p,
+				Err(msg) => {
+					print_error(format!("ir::lower: {msg}"));
+					return 1;
+				}
+			};
+			if program.test_suites.is_empty() {
+				eprintln!(
+					"no benchmarks found (expected a `def benches :: bench.suite` in a *.bench.pa file)"
+				);
+				return 0;
+			}
+			let bytes = match wasm::emit(&program) {
+				Ok(b) => b,
+				Err(diags) => {
+					print_error(format!("wasm codegen error: {}", diags.0.join("; ")));
+					return 1;
+				}
+			};
+			if clean && let Some(cache) = &cache {
+				cache.store(&compiler, &bytes, "");
+			}
+			bytes
+		}
+	};
+
+	let run = host::run_bench_v8(&bytes);
+	let style = Style::detect();
+	for line in render(&run.results, baseline.as_deref(), style).lines() {
+		echo(to, line);
+	}
+
+	let mut code = run.code;
+	if let Some(name) = &options.save_baseline {
+		match save_baseline(root_dir, name, &run.results) {
+			Ok(path) => echo(to, format!("saved baseline `{name}` to {}", path.display())),
+			Err(msg) => {
+				print_error(msg);
+				code = code.max(1);
+			}
+		}
+	}
+	echo(
+		to,
+		style.dim(&format!(
+			"finished in {:.2}s",
+			t_start.elapsed().as_secs_f64()
+		)),
+	);
+	code
+}
+
+/// What a case is called within its module: its groups and name joined with
+/// ` > `. With the module, the key a baseline is matched on.
+fn case_label(result: &BenchResult) -> String {
+	result
+		.path
+		.iter()
+		.map(String::as_str)
+		.chain([result.name.as_str()])
+		.collect::<Vec<_>>()
+		.join(" > ")
+}
+
+/// The report: one block per module, one line per case, with a change column
+/// when there's a baseline to compare against.
+fn render(results: &[BenchResult], baseline: Option<&[Baseline]>, style: Style) -> String {
+	let width = results
+		.iter()
+		.map(|r| case_label(r).chars().count())
+		.max()
+		.unwrap_or(0);
+	let mut out = String::new();
+	let mut module: Option<&str> = None;
+	for r in results {
+		if module != Some(r.module.as_str()) {
+			if module.is_some() {
+				out.push('\n');
+			}
+			let _ = writeln!(out, "{}", style.bold(&r.module));
+			module = Some(&r.module);
+		}
+		let label = case_label(r);
+		let _ = write!(
+			out,
+			"  {label:<width$}  {:>10} ± {:<10}  median {:>10}  {}",
+			nanos(r.mean),
+			nanos(r.stddev),
+			nanos(r.median),
+			style.dim(&format!("{} × {} runs", r.samples, r.runs)),
+		);
+		let before = baseline.and_then(|b| b.iter().find(|b| b.module == r.module && b.case == label));
+		match (baseline, before) {
+			(Some(_), Some(before)) => {
+				let _ = write!(out, "  {}", change(before, r, style));
+			}
+			(Some(_), None) => {
+				let _ = write!(out, "  {}", style.dim("new"));
+			}
+			(None, _) => {}
+		}
+		out.push('\n');
+	}
+	if !results.is_empty() {
+		out.push('\n');
+	}
+	out
+}
+
+/// How `now`'s mean compares with the baseline's. A difference within about
+/// two standard errors of the two means, or under 1%, is noise.
+fn change(before: &Baseline, now: &BenchResult, style: Style) -> String {
+	let delta = now.mean - before.mean;
+	let percent = if before.mean > 0.0 {
+		delta / before.mean * 100.0
+	} else {
+		0.0
+	};
+	let error = (now.stddev.powi(2) / now.samples.max(1) as f64
+		+ before.stddev.powi(2) / before.samples.max(1) as f64)
+		.sqrt();
+	if delta.abs() <= 2.0 * error || percent.abs() < 1.0 {
+		style.dim(&format!("{percent:+.1}% (no change)"))
+	} else if delta < 0.0 {
+		style.green(&format!("{percent:+.1}% faster"))
+	} else {
+		style.red(&format!("{percent:+.1}% slower"))
+	}
+}
+
+/// A time in nanoseconds, in the largest unit that keeps it at least 1.
+fn nanos(ns: f64) -> String {
+	let (value, unit) = if ns >= 1e9 {
+		(ns / 1e9, "s")
+	} else if ns >= 1e6 {
+		(ns / 1e6, "ms")
+	} else if ns >= 1e3 {
+		(ns / 1e3, "µs")
+	} else {
+		(ns, "ns")
+	};
+	format!("{value:.2} {unit}")
+}
+
+// --------------------------------------------------------------------------
+// Baselines.
+// --------------------------------------------------------------------------
+
+/// One case of a saved run: what `change` needs to compare against.
+struct Baseline {
+	module: String,
+	case: String,
+	samples: u64,
+	mean: f64,
+	stddev: f64,
+}
+
+/// The header every baseline file starts with.
+const BASELINE_HEADER: &str = "# pluma bench baseline v1";
+
+fn is_baseline_name(name: &str) -> bool {
+	!name.is_empty()
+		&& !name.starts_with('.')
+		&& name
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn baseline_path(root_dir: &Path, name: &str) -> PathBuf {
+	root_dir.join(BASELINE_DIR).join(format!("{name}.tsv"))
+}
+
+/// Write the run as a tab-separated file: a header, then per case its module,
+/// label, runs, samples and the five times in nanoseconds. Returns the path.
+fn save_baseline(root_dir: &Path, name: &str, results: &[BenchResult]) -> Result<PathBuf, String> {
+	let mut out =
+		format!("{BASELINE_HEADER}\n# module\tcase\truns\tsamples\tmean\tmedian\tstddev\tmin\tmax\n");
+	for r in results {
+		let _ = writeln!(
+			out,
+			"{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+			escape(&r.module),
+			escape(&case_label(r)),
+			r.runs,
+			r.samples,
+			r.mean,
+			r.median,
+			r.stddev,
+			r.min,
+			r.max
+		);
+	}
+	let path = baseline_path(root_dir, name);
+	let dir = root_dir.join(BASELINE_DIR);
+	std::fs::create_dir_all(&dir)
+		.map_err(|err| format!("Could not create `{}`: {}", dir.display(), err))?;
+	std::fs::write(&path, out)
+		.map_err(|err| format!("Could not write `{}`: {}", path.display(), err))?;
+	Ok(path)
+}
+
+fn load_baseline(root_dir: &Path, name: &str) -> Result<Vec<Baseline>, String> {
+	let path = baseline_path(root_dir, name);
+	let text = std::fs::read_to_string(&path).map_err(|_| {
+		format!("No baseline named `{name}` (save one with `pluma bench --save-baseline {name}`)")
+	})?;
+	let mut lines = text.lines();
+	if lines.next() != Some(BASELINE_HEADER) {
+		return Err(format!(
+			"`{}` isn't a `pluma bench` baseline",
+			path.display()
+		));
+	}
+	let mut entries = Vec::new();
+	for line in lines.filter(|l| !l.starts_with('#') && !l.is_empty()) {
+		let fields: Vec<&str> = line.split('\t').collect();
+		let parsed = match fields.as_slice() {
+			[module, case, _runs, samples, mean, _median, stddev, ..] => (|| {
+				Some(Baseline {
+					module: unescape(module),
+					case: unescape(case),
+					samples: samples.parse().ok()?,
+					mean: mean.parse().ok()?,
+					stddev: stddev.parse().ok()?,
+				})
+			})(),
+			_ => None,
+		};
+		match parsed {
+			Some(entry) => entries.push(entry),
+			None => return Err(format!("`{}` is malformed: {line:?}", path.display())),
+		}
+	}
+	Ok(entries)
+}
+
+/// Escape a baseline field: `\` as `\\`, tab as `\t`, newline as `\n`.
+fn escape(field: &str) -> String {
+	field
+		.replace('\\', "\\\\")
+		.replace('\t', "\\t")
+		.replace('\n', "\\n")
+}
+
+fn unescape(field: &str) -> String {
+	let mut out = String::with_capacity(field.len());
+	let mut chars = field.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			out.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('t') => out.push('\t'),
+			Some('n') => out.push('\n'),
+			Some(other) => out.push(other),
+			None => out.push('\\'),
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn result(module: &str, path: &[&str], name: &str, mean: f64, stddev: f64) -> BenchResult {
+		BenchResult {
+			module: module.to_string(),
+			path: path.iter().map(|p| p.to_string()).collect(),
+			name: name.to_string(),
+			runs: 1000,
+			samples: 10,
+			mean,
+			median: mean,
+			stddev,
+			min: mean - stddev,
+			max: mean + stddev,
+		}
+	}
+
+	fn baseline(mean: f64, stddev: f64) -> Baseline {
+		Baseline {
+			module: "sort.bench".to_string(),
+			case: "quick".to_string(),
+			samples: 10,
+			mean,
+			stddev,
+		}
+	}
+
+	#[test]
+	fn a_saved_baseline_loads_back() {
+		let root = temp_tree("bench-baseline", &[]);
+		let results = [
+			result("sort.bench", &["lists", "small"], "quick", 1234.5, 12.25),
+			result("odd\tmodule", &["a\\b"], "line\nbreak", 1e9, 0.0),
+		];
+		let path = save_baseline(&root, "main", &results).unwrap();
+		assert_eq!(path, root.join(".pluma/bench/main.tsv"));
+
+		let loaded = load_baseline(&root, "main").unwrap();
+		let seen: Vec<(&str, &str, u64, f64, f64)> = loaded
+			.iter()
+			.map(|b| {
+				(
+					b.module.as_str(),
+					b.case.as_str(),
+					b.samples,
+					b.mean,
+					b.stddev,
+				)
+			})
+			.collect();
+		assert_eq!(
+			seen,
+			[
+				("sort.bench", "lists > small > quick", 10, 1234.5, 12.25),
+				("odd\tmodule", "a\\b > line\nbreak", 10, 1e9, 0.0),
+			]
+		);
+
+		let missing = load_baseline(&root, "nope").err().unwrap();
+		assert!(missing.contains("--save-baseline nope"), "{missing}");
+		std::fs::write(root.join(".pluma/bench/bad.tsv"), "module\tcase\n").unwrap();
+		assert!(load_baseline(&root, "bad").is_err());
+	}
+
+	#[test]
+	fn changes_within_the_noise_are_no_change() {
+		let plain = Style { on: false };
+		let before = baseline(100.0, 10.0);
+		// Two standard errors of the difference: 2 × √(10²/10 + 10²/10) ≈ 8.9.
+		let now = |mean, stddev| result("sort.bench", &[], "quick", mean, stddev);
+		assert_eq!(
+			change(&before, &now(108.0, 10.0), plain),
+			"+8.0% (no change)"
+		);
+		assert_eq!(change(&before, &now(120.0, 10.0), plain), "+20.0% slower");
+		assert_eq!(change(&before, &now(80.0, 10.0), plain), "-20.0% faster");
+		// Under 1% is noise even when both runs were perfectly steady.
+		let steady = baseline(100.0, 0.0);
+		assert_eq!(
+			change(&steady, &now(100.5, 0.0), plain),
+			"+0.5% (no change)"
+		);
+		assert_eq!(change(&steady, &now(102.0, 0.0), plain), "+2.0% slower");
+	}
+
+	#[test]
+	fn the_report_groups_by_module_and_marks_new_cases() {
+		let results = [
+			result("sort.bench", &[], "quick", 2500.0, 10.0),
+			result("sort.bench", &["big"], "merge", 3.0e6, 1.0e3),
+		];
+		let report = render(
+			&results,
+			Some(&[baseline(5000.0, 10.0)]),
+			Style { on: false },
+		);
+		let lines: Vec<&str> = report.lines().collect();
+		assert_eq!(lines[0], "sort.bench");
+		assert_eq!(
+			lines[1],
+			"  quick           2.50 µs ± 10.00 ns    median    2.50 µs  10 × 1000 runs  -50.0% faster"
+		);
+		assert_eq!(
+			lines[2],
+			"  big > merge     3.00 ms ± 1.00 µs     median    3.00 ms  10 × 1000 runs  new"
+		);
+	}
+}
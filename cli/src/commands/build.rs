@@ -1,6 +1,7 @@
 use compiler::*;
 
 use crate::browser_bundle;
+use crate::cache::ArtifactCache;
 use crate::printing::*;
 
 /// `pluma build <dir> [-o out]` — compile a project directory into `out/`. The mode is
@@ -13,21 +14,24 @@ pub(crate) fn build_command(
 	server_url: Option<String>,
 	optimize: Option<String>,
 	target: Option<String>,
+	message_format: Option<String>,
 	entry_path: String,
 ) {
+	set_message_format(message_format.as_deref());
+
 	if target.is_some() {
 		print_error(
 			"`--target` was removed. The build mode comes from the directory's entry files — \
 			 a `main.pa`, a `client.pa`, or both.",
 		);
-		std::process::exit(1);
+		exit(1);
 	}
 	if web {
 		print_error(
 			"`--web` was removed. A directory with a `client.pa` (and no `main.pa`) builds a \
 			 static site; that's the browser build now.",
 		);
-		std::process::exit(1);
+		exit(1);
 	}
 
 	let start = std::time::Instant::now();
@@ -44,7 +48,7 @@ pub(crate) fn build_command(
 				print_error(format!(
 					"unknown -O level {s:?}; use 2/3/4 for speed, s/z for size, or 0 to skip"
 				));
-				std::process::exit(1);
+				exit(1);
 			}
 		},
 	};
@@ -66,7 +70,7 @@ pub(crate) fn build_command(
 			"`pluma build` takes a project directory, not a file — one with a `main.pa` \
 			 (CLI/server), a `client.pa` (static site), or both (fullstack). Got `{entry_path}`."
 		));
-		std::process::exit(1);
+		exit(1);
 	}
 	let has_main = dir.join("main.pa").is_file();
 	let has_client = dir.join("client.pa").is_file();
@@ -97,23 +101,54 @@ pub(crate) fn build_command(
 				"`{entry_path}` has no `main.pa` or `client.pa` — `pluma build` needs at \
 				 least one (both, for a fullstack app)."
 			));
-			std::process::exit(1);
+			exit(1);
 		}
 	}
+	exit(0);
 }
 
-/// Lower the checked program to WasmGC and run the optional wasm-opt pass; `browser`
-/// selects the web/DOM emit profile. Exits the process on a lowering or codegen error.
-fn lower_and_emit(
-	compiler: &Compiler,
+/// Check the program, lower it to WasmGC, and run the optional wasm-opt pass;
+/// `browser` selects the web/DOM emit profile. When an earlier clean build of the
+/// same sources and options left its emitted bytes in the cache, those are reused
+/// and only wasm-opt runs. `validate` sees a freshly checked program before it's
+/// lowered. Exits the process on a fatal diagnostic or a lowering/codegen error.
+fn compile(
+	compiler: &mut Compiler,
 	browser: bool,
 	opt_level: Option<wasm::OptLevel>,
+	validate: impl FnOnce(&Compiler),
 ) -> Vec<u8> {
+	if let Err(diagnostics) = compiler.lock_packages()
+		&& print_diagnostics_is_fatal(diagnostics)
+	{
+		exit(1);
+	}
+	let server_url = compiler.rpc_base_url.clone().unwrap_or_default();
+	let cache = ArtifactCache::open(
+		compiler,
+		"build",
+		&[if browser { "browser" } else { "" }, &server_url],
+	);
+	if let Some(hit) = cache.as_ref().and_then(ArtifactCache::load) {
+		return run_wasm_opt(hit.bytes, opt_level);
+	}
+
+	let clean = match compiler.check() {
+		Ok(()) => true,
+		Err(diagnostics) => {
+			if print_diagnostics_is_fatal(diagnostics) {
+				exit(1);
+			}
+			false
+		}
+	};
+	validate(compiler);
+
 	let program = match ir::lower(compiler) {
 		Ok(p) => p,
 		Err(msg) => {
 			print_error(format!("ir::lower: {msg}"));
-			std::process::exit(1);
+			exit(1);
 		}
 	};
 	let bytes = match wasm::emit_with_options(
@@ -126,9 +161,12 @@ fn lower_and_emit(
 		Ok(b) => b,
 		Err(diags) => {
 			print_error(format!("wasm codegen error: {}", diags.0.join("; ")));
-			std::process::exit(1);
+			exit(1);
 		}
 	};
+	if clean && let Some(cache) = &cache {
+		cache.store(compiler, &bytes, "");
+	}
 	run_wasm_opt(bytes, opt_level)
 }
 
@@ -147,25 +185,20 @@ fn build_sys(
 			.with_rpc_base_url(server_url),
 		Err(diagnostics) => {
 			print_diagnostics(diagnostics);
-			std::process::exit(1);
+			exit(1);
 		}
 	};
-	if let Err(diagnostics) = compiler.check() {
-		if print_diagnostics_is_fatal(diagnostics) {
-			std::process::exit(1);
-		}
-	}
-	let bytes = lower_and_emit(&compiler, false, opt_level);
+	let bytes = compile(&mut compiler, false, opt_level, |_| {});
 
 	let out_dir = std::path::PathBuf::from(out_base.unwrap_or_else(|| "out".to_string()));
 	let wasm_path = out_dir.join("main.wasm");
 	if let Err(e) = std::fs::create_dir_all(&out_dir) {
 		print_error(format!("creating {}: {e}", out_dir.display()));
-		std::process::exit(1);
+		exit(1);
 	}
 	if let Err(e) = std::fs::write(&wasm_path, &bytes) {
 		print_error(format!("writing {}: {e}", wasm_path.display()));
-		std::process::exit(1);
+		exit(1);
 	}
 	// Carry `<entry>/data` → `out/data` so a standalone server/CLI reads the same files
 	// from the bundle that it does under `pluma dev` (`pluma run` chdirs into the dir).
@@ -196,33 +229,29 @@ fn build_static(
 			.with_rpc_base_url(server_url),
 		Err(diagnostics) => {
 			print_diagnostics(diagnostics);
-			std::process::exit(1);
+			exit(1);
 		}
 	};
-	if let Err(diagnostics) = compiler.check() {
-		if print_diagnostics_is_fatal(diagnostics) {
-			std::process::exit(1);
-		}
-	}
 	// No server means no RPC: a `remote def` here has no endpoint to reach. Reject it
 	// rather than emit a client that fetches a route nothing serves. Adding a `main.pa`
 	// makes it a fullstack app, where the server answers these.
-	if !compiler.rpc_endpoints.is_empty() {
-		print_error(
-			"a static site has no backend, so `remote def` isn't allowed in a \
-			 `client.pa`-only build. Add a `main.pa` (making it a fullstack app) to serve \
-			 these calls.",
-		);
-		std::process::exit(1);
-	}
-	let bytes = lower_and_emit(&compiler, true, opt_level);
+	let bytes = compile(&mut compiler, true, opt_level, |compiler| {
+		if !compiler.rpc_endpoints.is_empty() {
+			print_error(
+				"a static site has no backend, so `remote def` isn't allowed in a \
+				 `client.pa`-only build. Add a `main.pa` (making it a fullstack app) to serve \
+				 these calls.",
+			);
+			exit(1);
+		}
+	});
 
 	let out_dir = std::path::PathBuf::from(out_base.unwrap_or_else(|| "out".to_string()));
 	let wasm_name = match browser_bundle::write_bundle(&out_dir, &bytes) {
 		Ok(name) => name,
 		Err(e) => {
 			print_error(format!("writing web bundle to {}: {e}", out_dir.display()));
-			std::process::exit(1);
+			exit(1);
 		}
 	};
 	print_build_summary(
@@ -349,7 +378,12 @@ fn print_build_summary(
 			human_duration(elapsed),
 		))
 	);
-	print!("{o}");
+	// With `--message-format`, stdout carries only the diagnostics.
+	if is_machine_readable() {
+		eprint!("{o}");
+	} else {
+		print!("{o}");
+	}
 }
 
 /// Render a build duration as `230ms` under a second, or `1.3s` at or above it.
@@ -379,18 +413,23 @@ fn build_fullstack(
 		Ok(c) => c.with_rpc_base_url(server_url),
 		Err(diagnostics) => {
 			print_diagnostics(diagnostics);
-			std::process::exit(1);
+			exit(1);
 		}
 	};
+	if let Err(diagnostics) = compiler.lock_packages()
+		&& print_diagnostics_is_fatal(diagnostics)
+	{
+		exit(1);
+	}
 	if let Err(diagnostics) = compiler.check() {
 		if print_diagnostics_is_fatal(diagnostics) {
-			std::process::exit(1);
+			exit(1);
 		}
 	}
 	// Per-artifact gating: server reachability as `sys`, client reachability as `web`.
 	if let Err(diagnostics) = compiler.gate_fullstack() {
 		print_diagnostics(diagnostics);
-		std::process::exit(1);
+		exit(1);
 	}
 
 	let server_module = compiler.entry_modules[0].clone();
@@ -401,7 +440,7 @@ fn build_fullstack(
 			Ok(p) => p,
 			Err(msg) => {
 				print_error(format!("ir::lower: {msg}"));
-				std::process::exit(1);
+				exit(1);
 			}
 		};
 		match wasm::emit_with_options(
@@ -414,7 +453,7 @@ fn build_fullstack(
 			Ok(b) => b,
 			Err(diags) => {
 				print_error(format!("wasm codegen error: {}", diags.0.join("; ")));
-				std::process::exit(1);
+				exit(1);
 			}
 		}
 	};
@@ -429,12 +468,12 @@ fn build_fullstack(
 	let dir = std::path::PathBuf::from(out_base.unwrap_or_else(|| "out".to_string()));
 	if let Err(e) = browser_bundle::write_built_dir(&dir, &client_bytes) {
 		print_error(format!("writing _built bundle to {}: {e}", dir.display()));
-		std::process::exit(1);
+		exit(1);
 	}
 	let server_path = dir.join("main.wasm");
 	if let Err(e) = std::fs::write(&server_path, &server_bytes) {
 		print_error(format!("writing {}: {e}", server_path.display()));
-		std::process::exit(1);
+		exit(1);
 	}
 
 	let mut artifacts = vec![
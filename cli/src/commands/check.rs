@@ -0,0 +1,342 @@
+use compiler::*;
+use std::path::{Path, PathBuf};
+
+use crate::cache::check_incrementally;
+use crate::printing::*;
+
+/// `pluma check [paths…] [--target sys|web]` — type-check without running or
+/// emitting anything. A `.pa` file checks that module and its imports; a
+/// directory checks every module in its package (the nearest `pluma.pa` at or
+/// above it, else the directory itself), `*.test.pa` suites included. A
+/// fullstack directory (`main.pa` + `client.pa`) additionally gates each half
+/// against its own target, exactly as `pluma build` would. Every diagnostic is
+/// reported; the exit code is non-zero if any is an error.
+pub(crate) fn check_command(
+	target: Option<String>,
+	message_format: Option<String>,
+	paths: Vec<String>,
+) {
+	set_message_format(message_format.as_deref());
+
+	let target = match target.as_deref() {
+		None => None,
+		Some("sys") => Some(Target::Sys),
+		Some("web") => Some(Target::Web),
+		Some(other) => {
+			print_error(format!("unknown target {other:?}; expected `sys` or `web`"));
+			exit(1);
+		}
+	};
+
+	exit(check_paths(paths, target));
+}
+
+/// Check each path argument, print every diagnostic and the summary line, and
+/// return the exit code: 1 if any diagnostic is an error, else 0.
+fn check_paths(paths: Vec<String>, target: Option<Target>) -> i32 {
+	let paths = if paths.is_empty() {
+		vec![".".to_string()]
+	} else {
+		paths
+	};
+
+	let mut errors = 0usize;
+	let mut warnings = 0usize;
+	let mut module_count = 0usize;
+
+	for path in paths {
+		let (modules, diagnostics) = check_path(&path, target);
+		module_count += modules;
+		errors += diagnostics.iter().filter(|d| d.is_error()).count();
+		warnings += diagnostics.iter().filter(|d| !d.is_error()).count();
+		print_diagnostics(diagnostics);
+	}
+
+	eprintln!("{}", summary(errors, warnings, module_count));
+
+	if errors > 0 { 1 } else { 0 }
+}
+
+/// Check one path argument, returning how many modules were checked (roots
+/// plus everything they import, stdlib excluded) and every diagnostic.
+pub(crate) fn check_path(path: &str, target: Option<Target>) -> (usize, Vec<Diagnostic>) {
+	let fail = |diagnostics: Vec<Diagnostic>| (0, diagnostics);
+
+	let fullstack = Compiler::is_fullstack_dir(path);
+	if fullstack && target.is_some() {
+		return fail(vec![Diagnostic::error(format!(
+			"`--target` doesn't apply to `{path}`: a fullstack directory checks its \
+			 server half as `sys` and its client half as `web`."
+		))]);
+	}
+
+	let mut compiler = if fullstack {
+		// Both halves first (`gate_fullstack` reads them as `entry_modules[0..2]`),
+		// then every other module in the package as an extra root.
+		let mut compiler = match Compiler::from_fullstack_dir(path.to_string()) {
+			Ok(c) => c,
+			Err(diagnostics) => return fail(diagnostics),
+		};
+		for name in discover_modules(&compiler.root_dir, true) {
+			if !compiler.entry_modules.contains(&name) {
+				compiler.add_entry_module(name);
+			}
+		}
+		compiler
+	} else if Path::new(path).is_dir() {
+		let dir = match Path::new(path).canonicalize() {
+			Ok(d) => d,
+			Err(err) => {
+				return fail(vec![Diagnostic::error(format!(
+					"Could not resolve `{path}`: {err}"
+				))]);
+			}
+		};
+		let Some(root) = find_project_root(&dir) else {
+			return check_loose_dir(&dir, target);
+		};
+		let mut compiler = Compiler::for_root_dir(root.clone()).with_target(target);
+		for name in discover_modules(&root, true) {
+			compiler.add_entry_module(name);
+		}
+		compiler
+	} else {
+		match Compiler::from_entry_path(path.to_string()) {
+			Ok(c) => c.with_target(target),
+			Err(diagnostics) => return fail(diagnostics),
+		}
+	};
+
+	let mut diagnostics = check_incrementally(&mut compiler).err().unwrap_or_default();
+	if fullstack && !diagnostics.iter().any(Diagnostic::is_error) {
+		// `gate_fullstack` returns the compiler's whole diagnostic buffer, so it
+		// replaces (rather than extends) what `check` reported.
+		if let Err(all) = compiler.gate_fullstack() {
+			diagnostics = all;
+		}
+	}
+
+	let modules = compiler
+		.modules
+		.keys()
+		.filter(|name| !name.starts_with("std/") && name.as_str() != "__prelude__")
+		.count();
+	(modules, diagnostics)
+}
+
+/// A directory with no `pluma.pa` at or above it. Without a package root, a
+/// module's imports resolve against its own directory (the same rule `pluma run`
+/// uses), so each directory is checked on its own: its `.pa` files as one set of
+/// roots, or as a fullstack app / package if it is one.
+fn check_loose_dir(dir: &Path, target: Option<Target>) -> (usize, Vec<Diagnostic>) {
+	// A fullstack app checks as a whole, subdirectories included.
+	let path = dir.to_string_lossy().into_owned();
+	if Compiler::is_fullstack_dir(&path) {
+		return check_path(&path, target);
+	}
+
+	let mut modules = 0;
+	let mut diagnostics = Vec::new();
+
+	let names = discover_modules(dir, false);
+	if !names.is_empty() {
+		let mut compiler = Compiler::for_root_dir(dir.to_path_buf()).with_target(target);
+		for name in names {
+			compiler.add_entry_module(name);
+		}
+		diagnostics.extend(check_incrementally(&mut compiler).err().unwrap_or_default());
+		modules += compiler
+			.modules
+			.keys()
+			.filter(|name| !name.starts_with("std/") && name.as_str() != "__prelude__")
+			.count();
+	}
+
+	let mut subdirs: Vec<PathBuf> = match std::fs::read_dir(dir) {
+		Ok(entries) => entries
+			.flatten()
+			.map(|e| e.path())
+			.filter(|p| p.is_dir())
+			.filter(|p| {
+				!p.file_name()
+					.and_then(|n| n.to_str())
+					.is_some_and(|n| n.starts_with('.'))
+			})
+			.collect(),
+		Err(_) => Vec::new(),
+	};
+	subdirs.sort();
+	for sub in subdirs {
+		let (n, d) = if sub.join(PROJECT_MARKER_FILE).is_file() {
+			check_path(&sub.to_string_lossy(), target)
+		} else {
+			check_loose_dir(&sub, target)
+		};
+		modules += n;
+		diagnostics.extend(d);
+	}
+
+	(modules, diagnostics)
+}
+
+/// Every `.pa` module under `root`, named relative to it (`/`-separated, `.pa`
+/// stripped — `<root>/auth/login.test.pa` is `auth/login.test`). Hidden
+/// directories are skipped, as is any subdirectory holding its own `pluma.pa`:
+/// that's a separate package with its own module namespace. With `recursive`
+/// off, only `root`'s own files are listed.
+fn discover_modules(root: &Path, recursive: bool) -> Vec<String> {
+	fn walk(dir: &Path, root: &Path, recursive: bool, out: &mut Vec<String>) {
+		let entries = match std::fs::read_dir(dir) {
+			Ok(e) => e,
+			Err(_) => return,
+		};
+		for entry in entries.flatten() {
+			let path = entry.path();
+			let name = match path.file_name().and_then(|n| n.to_str()) {
+				Some(n) => n,
+				None => continue,
+			};
+			if name.starts_with('.') {
+				continue;
+			}
+			let file_type = match entry.file_type() {
+				Ok(t) => t,
+				Err(_) => continue,
+			};
+			if file_type.is_dir() {
+				if recursive && !path.join(PROJECT_MARKER_FILE).is_file() {
+					walk(&path, root, recursive, out);
+				}
+			} else if file_type.is_file()
+				&& name.ends_with(&format!(".{FILE_EXTENSION}"))
+				&& let Ok(rel) = path.strip_prefix(root)
+			{
+				let rel: PathBuf = rel.with_extension("");
+				out.push(
+					rel
+						.to_string_lossy()
+						.replace(std::path::MAIN_SEPARATOR, "/"),
+				);
+			}
+		}
+	}
+
+	let mut out = Vec::new();
+	walk(root, root, recursive, &mut out);
+	out.sort();
+	out
+}
+
+/// The trailing summary line, e.g. `checked 12 modules: 2 errors, 1 warning`.
+fn summary(errors: usize, warnings: usize, modules: usize) -> String {
+	let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+	format!(
+		"checked {}: {}, {}",
+		plural(modules, "module"),
+		plural(errors, "error"),
+		plural(warnings, "warning")
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const MANIFEST: &str = "use std/package\n\npublic def package :: package.info = {\n\tname: \"app\",\n\tversion: \"0.0.1\",\n\tauthors: [],\n\tdescription: \"\",\n\tlicense: \"\",\n\trepository: \"\",\n\tdependencies: [],\n}\n";
+
+	fn errors(diagnostics: &[Diagnostic]) -> usize {
+		diagnostics.iter().filter(|d| d.is_error()).count()
+	}
+
+	#[test]
+	fn package_root_checks_every_module_but_nested_packages() {
+		let root = temp_tree(
+			"check-package",
+			&[
+				("pluma.pa", MANIFEST),
+				(
+					"main.pa",
+					"use auth/login\n\ndef main = fun {\n\tlogin.user\n}\n",
+				),
+				("auth/login.pa", "public def user = \"ada\"\n"),
+				("auth/login.test.pa", "def name = \"login\"\n"),
+				("notes.txt", "not a module\n"),
+				(".hidden/skip.pa", "def x = nope\n"),
+				("vendor/pluma.pa", MANIFEST),
+				("vendor/lib.pa", "def x = nope\n"),
+			],
+		);
+		assert_eq!(
+			discover_modules(&root, true),
+			vec!["auth/login", "auth/login.test", "main", "pluma"]
+		);
+		assert_eq!(discover_modules(&root, false), vec!["main", "pluma"]);
+
+		let path = root.to_string_lossy().into_owned();
+		let (modules, diagnostics) = check_path(&path, None);
+		assert_eq!(errors(&diagnostics), 0, "{diagnostics:?}");
+		assert_eq!(modules, 4);
+		assert_eq!(check_paths(vec![path.clone()], None), 0);
+
+		std::fs::write(root.join("auth/login.test.pa"), "def name = missing\n").unwrap();
+		assert!(errors(&check_path(&path, None).1) > 0);
+		assert_eq!(check_paths(vec![path], None), 1);
+	}
+
+	#[test]
+	fn loose_directory_checks_each_directory_on_its_own() {
+		let root = temp_tree(
+			"check-loose",
+			&[
+				("a.pa", "use b\n\ndef x = b.y\n"),
+				("b.pa", "public def y = 1\n"),
+				// `a` would not resolve from here: each directory is its own root.
+				("sub/c.pa", "def z = 2\n"),
+				("pkg/pluma.pa", MANIFEST),
+				("pkg/d.pa", "def w = nope\n"),
+			],
+		);
+		let (modules, diagnostics) = check_loose_dir(&root, None);
+		assert_eq!(modules, 5);
+		assert_eq!(errors(&diagnostics), 1, "{diagnostics:?}");
+
+		std::fs::write(root.join("pkg/d.pa"), "def w = 3\n").unwrap();
+		let path = root.to_string_lossy().into_owned();
+		assert_eq!(check_paths(vec![path], None), 0);
+	}
+
+	#[test]
+	fn fullstack_layout_gates_each_half_against_its_target() {
+		let server = "use std/sys/fs\nuse shared\n\ndef main = fun {\n\tfs.exists shared.name\n}\n";
+		let root = temp_tree(
+			"check-fullstack",
+			&[
+				("main.pa", server),
+				(
+					"client.pa",
+					"use shared\n\ndef main = fun {\n\tshared.name\n}\n",
+				),
+				("shared.pa", "public def name = \"app\"\n"),
+			],
+		);
+		let path = root.to_string_lossy().into_owned();
+		let (modules, diagnostics) = check_path(&path, None);
+		assert_eq!(errors(&diagnostics), 0, "{diagnostics:?}");
+		assert_eq!(modules, 3);
+		assert_eq!(check_paths(vec![path.clone()], None), 0);
+
+		// A single `--target` can't describe both halves.
+		assert_eq!(check_paths(vec![path.clone()], Some(Target::Web)), 1);
+
+		// The client half reaching a `sys`-only module fails the `web` gate.
+		std::fs::write(root.join("client.pa"), server).unwrap();
+		let (_, diagnostics) = check_path(&path, None);
+		assert!(
+			diagnostics
+				.iter()
+				.any(|d| d.is_error() && d.message.contains("std/sys/fs")),
+			"{diagnostics:?}"
+		);
+		assert_eq!(check_paths(vec![path], None), 1);
+	}
+}
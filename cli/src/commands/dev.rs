@@ -302,6 +302,12 @@ fn build_web(entry_path: &str, hmr: bool) -> Result<Vec<u8>, Vec<Diagnostic>> {
 		Ok(c) => c.with_target(Some(Target::Web)).with_hmr(hmr),
 		Err(diagnostics) => return Err(diagnostics),
 	};
+	// A dependency that drifted from its pin fails the build like a type error.
+	if let Err(diagnostics) = compiler.lock_packages()
+		&& diagnostics.iter().any(Diagnostic::is_error)
+	{
+		return Err(diagnostics);
+	}
 	if let Err(diagnostics) = compiler.check() {
 		// Errors fail the build (red panel); warning-only diagnostics don't block dev.
 		if diagnostics.iter().any(Diagnostic::is_error) {
@@ -592,6 +598,12 @@ fn build_fullstack_with_hmr(
 	let mut compiler = Compiler::from_fullstack_dir(entry_path.to_string())?
 		.with_rpc_base_url(server_url.to_string())
 		.with_hmr(hmr);
+	// A dependency that drifted from its pin fails the build like a type error.
+	if let Err(diagnostics) = compiler.lock_packages()
+		&& diagnostics.iter().any(Diagnostic::is_error)
+	{
+		return Err(diagnostics);
+	}
 	if let Err(diagnostics) = compiler.check() {
 		// Errors fail the build (red panel); warning-only diagnostics don't block dev.
 		if diagnostics.iter().any(Diagnostic::is_error) {
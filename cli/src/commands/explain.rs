@@ -0,0 +1,52 @@
+//! `pluma explain <code>` — print the long-form explanation of a diagnostic
+//! code such as `E0100` or `L0004`: what went wrong, why, a failing example and
+//! the fixed version. The text comes from the compiler's registry
+//! (`compiler::explain`), the same one the language server shows on hover.
+
+use compiler::Diagnostic;
+use compiler::explain;
+
+use crate::colors::Style;
+use crate::printing::*;
+
+pub(crate) fn explain_command(code: String) {
+	let Some(entry) = explain::lookup(&code) else {
+		print_diagnostics(vec![
+			Diagnostic::error(format!("no explanation for `{code}`"))
+				.with_help("codes look like `E0100` (compiler errors) or `L0001` (lint warnings)"),
+		]);
+		std::process::exit(1);
+	};
+
+	let style = Style::detect();
+	println!(
+		"{}",
+		style.bold(&format!("{}: {}", entry.code, entry.title()))
+	);
+	println!();
+	print!("{}", render(entry.body(), style));
+}
+
+/// Render an explanation body for the terminal. Prose passes through as
+/// written; fenced examples lose their fence lines and are indented, with the
+/// failing one labelled so it isn't mistaken for the fix.
+fn render(body: &str, style: Style) -> String {
+	let mut out = String::new();
+	let mut in_code = false;
+	for line in body.lines() {
+		if let Some(info) = line.strip_prefix("```") {
+			if !in_code && info.split_whitespace().any(|word| word == "fails") {
+				out.push_str(&format!("    {}\n", style.dim("# fails:")));
+			}
+			in_code = !in_code;
+			continue;
+		}
+		if in_code && !line.is_empty() {
+			out.push_str(&format!("    {}\n", style.cyan(line)));
+		} else {
+			out.push_str(line);
+			out.push('\n');
+		}
+	}
+	out
+}
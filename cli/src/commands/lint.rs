@@ -1,15 +1,25 @@
 use crate::printing::*;
+use compiler::{Diagnostic, Suggestion};
 use std::path::PathBuf;
 
 /// `pluma lint <paths…>` — parse each module and report lint warnings, or with
 /// `--fix` apply the autofixable ones in place. Reports exit non-zero if any
 /// lint fires (so CI can gate on a clean lint) or if any file can't be read.
 /// Files that don't parse are skipped with a note, mirroring `pluma format` — a
-/// lint sweep may include intentionally-broken fixtures.
-pub(crate) fn lint_command(fix: bool, paths: Vec<String>) {
+/// lint sweep may include intentionally-broken fixtures. With a machine-readable
+/// `--message-format`, each warning carries its autofix edits as suggestions.
+pub(crate) fn lint_command(fix: bool, message_format: Option<String>, paths: Vec<String>) {
+	set_message_format(message_format.as_deref());
+
 	if paths.is_empty() {
 		print_error("No path given. Expected a file path or `-` for stdin.");
-		std::process::exit(1);
+		exit(1);
+	}
+	if fix && is_machine_readable() && paths.iter().any(|p| p == "-") {
+		print_error(
+			"`--fix -` writes the fixed module to stdout; it can't be combined with `--message-format json|sarif`.",
+		);
+		exit(1);
 	}
 
 	let paths = crate::commands::expand_paths(paths);
@@ -27,27 +37,25 @@ pub(crate) fn lint_command(fix: bool, paths: Vec<String>) {
 			let mut input = Vec::new();
 			if let Err(err) = std::io::Read::read_to_end(&mut std::io::stdin(), &mut input) {
 				print_error(format!("Failed to read stdin: {}", err));
-				std::process::exit(1);
+				exit(1);
 			}
-			linter::lint_source(&input)
+			linter::lint_findings(&input)
 		} else {
 			let bytes = match std::fs::read(path) {
 				Ok(b) => b,
 				Err(err) => {
 					print_error(format!("Could not read `{}`: {}", path, err));
-					std::process::exit(1);
+					exit(1);
 				}
 			};
-			linter::lint_path(&PathBuf::from(path), &bytes)
+			linter::lint_path_findings(&PathBuf::from(path), &bytes)
 		};
 
 		match result {
-			Ok(warnings) => {
+			Ok(findings) => {
 				file_count += 1;
-				total_issues += warnings.len();
-				if !warnings.is_empty() {
-					print_diagnostics(warnings);
-				}
+				total_issues += findings.len();
+				print_with_suggestions(findings.into_iter().map(with_suggestions).collect());
 			}
 			Err(_diagnostics) => {
 				// Skip unparseable files rather than aborting — the user may be
@@ -59,9 +67,20 @@ pub(crate) fn lint_command(fix: bool, paths: Vec<String>) {
 
 	eprintln!("{}", summary(total_issues, file_count, None));
 
-	if total_issues > 0 {
-		std::process::exit(1);
-	}
+	exit(if total_issues > 0 { 1 } else { 0 });
+}
+
+/// A finding's autofixes, in the shape the machine-readable formats serialize.
+fn with_suggestions(finding: linter::Finding) -> (Diagnostic, Vec<Suggestion>) {
+	let suggestions = finding
+		.fixes
+		.into_iter()
+		.map(|fix| Suggestion {
+			range: fix.range,
+			replacement: fix.replacement,
+		})
+		.collect();
+	(finding.diagnostic, suggestions)
 }
 
 /// `pluma lint --fix` — apply autofixes in place. For each file, rewrite it with
@@ -79,7 +98,7 @@ fn fix_command(paths: Vec<String>) {
 			let mut input = Vec::new();
 			if let Err(err) = std::io::Read::read_to_end(&mut std::io::stdin(), &mut input) {
 				print_error(format!("Failed to read stdin: {}", err));
-				std::process::exit(1);
+				exit(1);
 			}
 			input
 		} else {
@@ -87,7 +106,7 @@ fn fix_command(paths: Vec<String>) {
 				Ok(b) => b,
 				Err(err) => {
 					print_error(format!("Could not read `{}`: {}", path, err));
-					std::process::exit(1);
+					exit(1);
 				}
 			}
 		};
@@ -99,7 +118,7 @@ fn fix_command(paths: Vec<String>) {
 			Err(diagnostics) => {
 				if path == "-" {
 					print_diagnostics(diagnostics);
-					std::process::exit(1);
+					exit(1);
 				}
 				eprintln!("skipping {} (parse error)", path);
 				continue;
@@ -115,7 +134,7 @@ fn fix_command(paths: Vec<String>) {
 			Ok(fixed) => fixed,
 			Err(diagnostics) => {
 				print_diagnostics(diagnostics);
-				std::process::exit(1);
+				exit(1);
 			}
 		};
 
@@ -130,13 +149,14 @@ fn fix_command(paths: Vec<String>) {
 			let out = reformat(fixed.as_bytes());
 			if let Err(err) = std::fs::write(path, out.as_bytes()) {
 				print_error(format!("Could not write `{}`: {}", path, err));
-				std::process::exit(1);
+				exit(1);
 			}
 			eprintln!("fixed {}", path);
 		}
 	}
 
 	eprintln!("{}", summary(total_issues, file_count, Some(total_fixed)));
+	exit(0);
 }
 
 /// The trailing summary line, e.g. `found 3 issues in 2 files` or, in `--fix`
@@ -1,3 +1,4 @@
+use crate::cache::ArtifactCache;
 use crate::printing::*;
 use compiler::*;
 
@@ -40,6 +41,14 @@ pub(crate) fn run_command(hmr: bool, entry_path: String, program_args: Vec<Strin
 		std::process::exit(1);
 	}
 
+	if entry_path.ends_with(".bench.pa") || entry_path.ends_with(".bench") {
+		print_error(format!(
+			"`{}` is a benchmark module. Use `pluma bench` to run benchmarks.",
+			entry_path
+		));
+		std::process::exit(1);
+	}
+
 	let mut compiler = match Compiler::from_entry_path(entry_path) {
 		Ok(c) => c.with_hmr(hmr),
 		Err(diagnostics) => {
@@ -48,12 +57,28 @@ pub(crate) fn run_command(hmr: bool, entry_path: String, program_args: Vec<Strin
 		}
 	};
 
-	if let Err(diagnostics) = compiler.check() {
-		if print_diagnostics_is_fatal(diagnostics) {
-			std::process::exit(1);
-		}
+	if let Err(diagnostics) = compiler.lock_packages()
+		&& print_diagnostics_is_fatal(diagnostics)
+	{
+		std::process::exit(1);
 	}
 
+	// An unchanged program re-runs its cached artifact without compiling.
+	let cache = ArtifactCache::open(&compiler, "run", &[if hmr { "hmr" } else { "" }]);
+	if let Some(hit) = cache.as_ref().and_then(ArtifactCache::load) {
+		std::process::exit(host::run_streaming_v8(&hit.bytes, &program_args));
+	}
+
+	let clean = match compiler.check() {
+		Ok(()) => true,
+		Err(diagnostics) => {
+			if print_diagnostics_is_fatal(diagnostics) {
+				std::process::exit(1);
+			}
+			false
+		}
+	};
+
 	// Compile to a WasmGC artifact and run it under V8 — the deploy engine, the exact
 	// thing `pluma build` ships ("run what you deploy"). Every builtin the language
 	// exposes lowers to wasm, so a program the backend can't emit (today only the
@@ -72,5 +97,8 @@ pub(crate) fn run_command(hmr: bool, entry_path: String, program_args: Vec<Strin
 			std::process::exit(1);
 		}
 	};
+	if clean && let Some(cache) = &cache {
+		cache.store(&compiler, &bytes, "");
+	}
 	std::process::exit(host::run_streaming_v8(&bytes, &program_args));
 }
@@ -1,16 +1,91 @@
 use compiler::*;
+use host::Echo;
 use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 
+use crate::cache::ArtifactCache;
 use crate::printing::*;
+use crate::reporter::Reporter;
 use crate::watch::{POLL_INTERVAL, scan};
 
-pub(crate) fn test_command(filters: Vec<String>, watch: bool, dir: Option<String>) {
+/// How each run of the suite goes, from the `pluma test` flags.
+#[derive(Clone)]
+pub(crate) struct SuiteOptions {
+	/// `--coverage`, with its settings.
+	pub(crate) coverage: Option<Coverage>,
+	/// `--update-snapshots`: rewrite `assert.snapshot` files instead of comparing.
+	pub(crate) update_snapshots: bool,
+	/// `--reporter`, with its `--output`.
+	pub(crate) reporter: Option<Reporter>,
+	/// `--doc`: run the examples in doc comments instead of the `*.test.pa`
+	/// suites.
+	pub(crate) doc: bool,
+	/// `--timeout`: how long a case may run unless it sets its own limit.
+	pub(crate) timeout: std::time::Duration,
+	/// `--retries`: how many more times a failing case runs.
+	pub(crate) retries: u32,
+	/// `--schedule-seed`: shuffle the order ready tasks run in with this seed.
+	pub(crate) schedule_seed: Option<u64>,
+	/// `--case`: the path of the one case or group to run; empty runs them all.
+	pub(crate) case: Vec<String>,
+	/// `--module`: the exact names of the modules to run; empty runs them all.
+	pub(crate) modules: Vec<String>,
+}
+
+impl SuiteOptions {
+	/// Where the console report goes: stdout, unless a reporter has stdout to
+	/// itself (nowhere) or it carries `--message-format json|sarif` diagnostics
+	/// (stderr).
+	fn echo(&self) -> Echo {
+		if self.reporter.as_ref().is_some_and(|r| r.output.is_none()) {
+			Echo::Off
+		} else if is_machine_readable() {
+			Echo::Stderr
+		} else {
+			Echo::Stdout
+		}
+	}
+}
+
+/// `--coverage` settings for a run.
+#[derive(Clone, Copy)]
+pub(crate) struct Coverage {
+	/// `--min-coverage`: the line percentage below which the run fails.
+	pub(crate) min: Option<f64>,
+}
+
+pub(crate) fn test_command(
+	filters: Vec<String>,
+	watch: bool,
+	options: SuiteOptions,
+	message_format: Option<String>,
+	dir: Option<String>,
+) {
+	set_message_format(message_format.as_deref());
+	if watch && message_format.as_deref() == Some("sarif") {
+		print_error(
+			"`--message-format sarif` writes one log per run; it can't be combined with `--watch`.",
+		);
+		exit(1);
+	}
+
+	let root_dir = package_root(dir);
+
+	if watch {
+		watch_suite(&filters, &options, &root_dir);
+	} else {
+		exit(run_suite(&filters, &options, &root_dir));
+	}
+}
+
+/// The package root to run from: the nearest `pluma.pa` at or above `dir` (or
+/// cwd). Exits with an error when there is none. Shared with `pluma bench`.
+pub(crate) fn package_root(dir: Option<String>) -> PathBuf {
 	let cwd = match std::env::current_dir() {
 		Ok(p) => p,
 		Err(err) => {
 			print_error(format!("Could not determine current directory: {}", err));
-			std::process::exit(1);
+			exit(1);
 		}
 	};
 
@@ -20,13 +95,13 @@ pub(crate) fn test_command(filters: Vec<String>, watch: bool, dir: Option<String
 			let p = Path::new(&arg);
 			if !p.is_dir() {
 				print_error(format!("`{}` is not a directory", arg));
-				std::process::exit(1);
+				exit(1);
 			}
 			match p.canonicalize() {
 				Ok(d) => d,
 				Err(err) => {
 					print_error(format!("Could not resolve `{}`: {}", arg, err));
-					std::process::exit(1);
+					exit(1);
 				}
 			}
 		}
@@ -37,18 +112,12 @@ pub(crate) fn test_command(filters: Vec<String>, watch: bool, dir: Option<String
 	// which subtree counts as "the project" and gives every `*.test.pa`
 	// module a stable name to resolve `use` paths against. Without one,
 	// any non-trivial test layout silently mis-resolves siblings.
-	let root_dir = match compiler::find_project_root(&start_dir) {
+	match compiler::find_project_root(&start_dir) {
 		Some(p) => p,
 		None => {
 			print_error("No package root found. Create a `pluma.pa` in your root directory.");
-			std::process::exit(1);
+			exit(1);
 		}
-	};
-
-	if watch {
-		watch_suite(&filters, &root_dir);
-	} else {
-		std::process::exit(run_suite(&filters, &root_dir));
 	}
 }
 
@@ -56,8 +125,8 @@ pub(crate) fn test_command(filters: Vec<String>, watch: bool, dir: Option<String
 /// happens immediately; thereafter a cheap mtime fingerprint is polled and a
 /// change triggers a fresh run. Compile and test failures print and keep the
 /// loop alive — the point of watch mode is to fix-and-rerun without restarting.
-fn watch_suite(filters: &[String], root_dir: &Path) -> ! {
-	let clear = std::io::stdout().is_terminal();
+fn watch_suite(filters: &[String], options: &SuiteOptions, root_dir: &Path) -> ! {
+	let clear = options.echo() == Echo::Stdout && std::io::stdout().is_terminal();
 
 	loop {
 		if clear {
@@ -65,12 +134,15 @@ fn watch_suite(filters: &[String], root_dir: &Path) -> ! {
 			// picture, not a scroll of stale output.
 			print!("\x1b[2J\x1b[3J\x1b[H");
 		}
-		run_suite(filters, root_dir);
-		println!();
-		println!("watching for changes — press ctrl-c to exit");
+		run_suite(filters, options, root_dir);
+		echo(
+			options.echo(),
+			"\nwatching for changes — press ctrl-c to exit",
+		);
 
 		// Baseline taken after the run, so anything the suite itself touched on
 		// disk doesn't read as a change and retrigger immediately.
+		// That includes `coverage/lcov.info` and a `--reporter --output` file.
 		let baseline = scan(root_dir);
 		while scan(root_dir) == baseline {
 			std::thread::sleep(POLL_INTERVAL);
@@ -81,7 +153,7 @@ fn watch_suite(filters: &[String], root_dir: &Path) -> ! {
 /// Discover, compile, and run the suite once, returning the exit code the
 /// process should carry (0 = all passed). Diagnostics and errors are printed
 /// here rather than aborting, so a caller in watch mode can run again.
-fn run_suite(filters: &[String], root_dir: &Path) -> i32 {
+fn run_suite(filters: &[String], options: &SuiteOptions, root_dir: &Path) -> i32 {
 	// PLUMA_TIMING=1 prints a per-phase wall-clock breakdown to stderr.
 	let timing = std::env::var("PLUMA_TIMING").is_ok();
 	let t_start = std::time::Instant::now();
@@ -90,15 +162,48 @@ fn run_suite(filters: &[String], root_dir: &Path) -> i32 {
 	// Module names below are paths relative to the package root, with `/`
 	// flipped to `.` and the `.pa` extension stripped — so
 	// `<root>/foo/bar.test.pa` becomes `foo.bar.test`.
-	let mut test_modules = discover_test_modules(&root_dir);
+	let mut test_modules = match options.doc {
+		false => discover_modules(&root_dir, |file| file.ends_with(".test.pa")),
+		true => discover_modules(&root_dir, |file| {
+			file.ends_with(".pa")
+				&& !file.ends_with(".test.pa")
+				&& !file.ends_with(".bench.pa")
+				&& file != compiler::PROJECT_MARKER_FILE
+		}),
+	};
 	test_modules.sort();
 
 	if !filters.is_empty() {
 		test_modules.retain(|name| filters.iter().any(|f| name.contains(f)));
 	}
+	if !options.modules.is_empty() {
+		test_modules.retain(|name| options.modules.contains(name));
+	}
+
+	// With `--doc`, each source module gives way to the test module synthesized
+	// from its examples; a module with none drops out.
+	let mut doc_sources = Vec::new();
+	if options.doc {
+		test_modules.retain_mut(|module| {
+			let rel = format!("{module}.pa");
+			let Some(source) = crate::doctest::source_for(module, &root_dir.join(&rel), &rel) else {
+				return false;
+			};
+			*module = crate::doctest::module_name(module);
+			doc_sources.push((module.clone(), source));
+			true
+		});
+	}
 
 	if test_modules.is_empty() {
-		if filters.is_empty() {
+		if options.doc {
+			eprintln!(
+				"no doc examples found (looked in the doc comments of *.pa under {})",
+				root_dir.display()
+			);
+		} else if !options.modules.is_empty() {
+			eprintln!("no test modules named {:?}", options.modules);
+		} else if filters.is_empty() {
 			eprintln!(
 				"no test files found (looked for *.test.pa under {})",
 				root_dir.display()
@@ -111,12 +216,18 @@ fn run_suite(filters: &[String], root_dir: &Path) -> i32 {
 
 	let count = test_modules.len();
 	let module_word = if count == 1 { "module" } else { "modules" };
+	// With a reporter or machine-readable diagnostics writing to stdout, they
+	// are all stdout carries.
+	let to = options.echo();
 	if filters.is_empty() {
-		println!(
-			"running {} test {} in {}",
-			count,
-			module_word,
-			root_dir.display()
+		echo(
+			to,
+			format!(
+				"running {} test {} in {}\n",
+				count,
+				module_word,
+				root_dir.display()
+			),
 		);
 	} else {
 		let quoted: Vec<String> = filters.iter().map(|f| format!("'{}'", f)).collect();
@@ -127,15 +238,17 @@ fn run_suite(filters: &[String], root_dir: &Path) -> i32 {
 				format!("{} or {}", rest.join(", "), last)
 			}
 		};
-		println!(
-			"running {} test {} matching {} in {}",
-			count,
-			module_word,
-			joined,
-			root_dir.display()
+		echo(
+			to,
+			format!(
+				"running {} test {} matching {} in {}\n",
+				count,
+				module_word,
+				joined,
+				root_dir.display()
+			),
 		);
 	}
-	println!();
 
 	let mut compiler = Compiler::for_root_dir(root_dir.clone());
 	// Add the project marker as an entry so the analyzer type-checks
@@ -145,59 +258,137 @@ fn run_suite(filters: &[String], root_dir: &Path) -> i32 {
 	for name in &test_modules {
 		compiler.add_entry_module(name.clone());
 	}
+	for (name, source) in doc_sources {
+		compiler.set_module_source(name, source.into_bytes());
+	}
 
-	let t_setup = std::time::Instant::now();
-
-	if let Err(diagnostics) = compiler.check() {
-		if print_diagnostics_is_fatal(diagnostics) {
-			return 1;
-		}
+	if let Err(diagnostics) = compiler.lock_packages()
+		&& print_diagnostics_is_fatal(diagnostics)
+	{
+		return 1;
 	}
 
-	let t_check = std::time::Instant::now();
+	let t_setup = std::time::Instant::now();
 
-	// Synthesize a test entry over the discovered suites and emit a WasmGC module,
-	// then run it under V8 (the deploy engine — `pluma test` exercises the exact
-	// artifact you ship). The runner is itself Pluma: `std/test.run-all` flattens
-	// each suite, runs the cases, prints the tree, and returns ok / err.
-	let use_color = std::io::stdout().is_terminal();
-	let program = match ir::lower_tests(&compiler, use_color) {
-		Ok(p) => p,
-		Err(msg) => {
-			print_error(format!("ir::lower: {msg}"));
-			return 1;
-		}
+	// An unchanged suite re-runs its cached artifact without compiling. The
+	// runner finds the cases itself, so the artifact's `meta` is empty.
+	// A coverage run is instrumented and needs its probe table, so it always
+	// compiles and never touches the cache.
+	let test_options = ir::TestOptions {
+		color: std::io::stdout().is_terminal(),
+		update_snapshots: options.update_snapshots,
+		report: options.reporter.is_some(),
+	};
+	let use_color = test_options.color;
+	let cache = match options.coverage {
+		Some(_) => None,
+		None => ArtifactCache::open(
+			&compiler,
+			"test",
+			&[
+				if use_color { "color" } else { "" },
+				if options.update_snapshots {
+					"update-snapshots"
+				} else {
+					""
+				},
+				if test_options.report { "report" } else { "" },
+			],
+		),
 	};
+	let hit = cache.as_ref().and_then(ArtifactCache::load);
 
-	if program.test_suites.is_empty() {
-		eprintln!("no tests found (expected a `def tests :: test.suite` in a *.test.pa file)");
-		return 0;
-	}
+	let (bytes, probes, t_check) = match hit {
+		Some(hit) => (hit.bytes, Vec::new(), std::time::Instant::now()),
+		None => {
+			let clean = match compiler.check() {
+				Ok(()) => true,
+				Err(diagnostics) => {
+					if print_diagnostics_is_fatal(diagnostics) {
+						return 1;
+					}
+					false
+				}
+			};
 
-	let bytes = match wasm::emit(&program) {
-		Ok(b) => b,
-		Err(diags) => {
-			print_error(format!("wasm codegen error: {}", diags.0.join("; ")));
-			return 1;
+			let t_check = std::time::Instant::now();
+
+			// Synthesize a test entry over the discovered suites and emit a WasmGC
+			// module, then run it under V8 (the deploy engine — `pluma test`
+			// exercises the exact artifact you ship). The runner is itself Pluma:
+			// `std/test.run-all` flattens each suite, runs the cases, prints the
+			// tree, and returns ok / err.
+			let mut program = match ir::lower_tests(&compiler, test_options) {
+				Ok(p) => p,
+				Err(msg) => {
+					print_error(format!("ir::lower: {msg}"));
+					return 1;
+				}
+			};
+
+			if program.test_suites.is_empty() {
+				eprintln!("no tests found (expected a `def tests :: test.suite` in a *.test.pa file)");
+				return 0;
+			}
+
+			let probes = match options.coverage {
+				Some(_) => {
+					let covered = crate::coverage::covered_modules(&compiler);
+					ir::coverage::instrument(&mut program, |m| covered.contains(m))
+				}
+				None => Vec::new(),
+			};
+
+			let bytes = match wasm::emit(&program) {
+				Ok(b) => b,
+				Err(diags) => {
+					print_error(format!("wasm codegen error: {}", diags.0.join("; ")));
+					return 1;
+				}
+			};
+			if clean && let Some(cache) = &cache {
+				cache.store(&compiler, &bytes, "");
+			}
+			(bytes, probes, t_check)
 		}
 	};
 
 	let t_codegen = std::time::Instant::now();
 
-	// Run each test module in its own fresh isolate, in parallel over the
+	// Run each case in its own fresh isolate, in parallel over the
 	// once-compiled module. The exit code reflects pass/fail.
-	let code = host::run_test_v8(&bytes, program.test_suites.len(), use_color);
+	let settings = host::TestSettings {
+		color: use_color,
+		echo: to,
+		timeout: options.timeout,
+		retries: options.retries,
+		schedule_seed: options.schedule_seed,
+		only: options.case.clone(),
+	};
+	let run = host::run_test_v8_detailed(&bytes, &settings);
+	let mut code = run.code;
+	if let Some(Coverage { min }) = options.coverage
+		&& !crate::coverage::report(&compiler, &probes, &run.coverage, min, to)
+	{
+		code = code.max(1);
+	}
+	if let Some(reporter) = &options.reporter
+		&& let Err(msg) = crate::reporter::write(reporter, &run.cases, t_codegen.elapsed())
+	{
+		print_error(msg);
+		code = code.max(1);
+	}
 
 	// Wall-clock for the whole command (discover + compile + run), printed under
 	// the Pluma-rendered summary line so every `pluma test` ends with how long it
 	// took. `PLUMA_TIMING` breaks this down per phase; this is the at-a-glance number.
 	let style = crate::colors::Style::detect();
-	println!(
-		"{}",
+	echo(
+		to,
 		style.dim(&format!(
 			"finished in {:.2}s",
 			t_start.elapsed().as_secs_f64()
-		))
+		)),
 	);
 
 	if timing {
@@ -216,12 +407,19 @@ fn run_suite(filters: &[String], root_dir: &Path) -> i32 {
 	code
 }
 
-// Recursively find every `*.test.pa` file under `root` and return its module
+// Recursively find every file under `root` whose name `keep` accepts (the
+// `*.test.pa` suites, with `--doc` the source modules, or for `pluma bench` the
+// `*.bench.pa` files) and return its module
 // name (path relative to `root`, with `/` → `.` and `.pa` stripped). Hidden
 // directories (anything starting with `.`) are skipped — `.git`, `.cargo`,
 // etc. shouldn't be scanned.
-fn discover_test_modules(root: &std::path::Path) -> Vec<String> {
-	fn walk(dir: &std::path::Path, root: &std::path::Path, out: &mut Vec<String>) {
+pub(crate) fn discover_modules(root: &std::path::Path, keep: fn(&str) -> bool) -> Vec<String> {
+	fn walk(
+		dir: &std::path::Path,
+		root: &std::path::Path,
+		keep: fn(&str) -> bool,
+		out: &mut Vec<String>,
+	) {
 		let entries = match std::fs::read_dir(dir) {
 			Ok(e) => e,
 			Err(_) => return,
@@ -240,8 +438,8 @@ fn discover_test_modules(root: &std::path::Path) -> Vec<String> {
 				Err(_) => continue,
 			};
 			if file_type.is_dir() {
-				walk(&path, root, out);
-			} else if file_type.is_file() && name.ends_with(".test.pa") {
+				walk(&path, root, keep, out);
+			} else if file_type.is_file() && keep(name) {
 				if let Ok(rel) = path.strip_prefix(root) {
 					let rel_str = rel.to_string_lossy();
 					let stem = rel_str.strip_suffix(".pa").unwrap_or(&rel_str);
@@ -253,6 +451,6 @@ fn discover_test_modules(root: &std::path::Path) -> Vec<String> {
 	}
 
 	let mut out = Vec::new();
-	walk(root, root, &mut out);
+	walk(root, root, keep, &mut out);
 	out
 }
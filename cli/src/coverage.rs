@@ -0,0 +1,264 @@
+//! `pluma test --coverage`: which modules get instrumented, and turning the
+//! counters a run hands back into reports. The instrumentation itself is
+//! `ir::coverage`; the host counts `coverage-hit` calls per probe and sums them
+//! across the test isolates (`host::run_test_v8_detailed`).
+//!
+//! Two reports come out of one run: `coverage/lcov.info` under the package
+//! root, for editors and CI tools, and a per-module table on stdout. Probes
+//! that share a source position (a def the lowerer specialized into several
+//! functions) are summed here, so each line, branch arm and def counts once.
+
+use compiler::Compiler;
+use host::Echo;
+use ir::coverage::{Probe, ProbeKind};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::colors::Style;
+use crate::printing::echo;
+
+/// Where the lcov report lands, relative to the package root.
+pub(crate) const LCOV_PATH: &str = "coverage/lcov.info";
+
+/// The modules whose code counts: the package's own sources. The stdlib,
+/// dependencies, the `pluma.pa` manifest and the `*.test.pa` suites themselves
+/// are left out.
+pub(crate) fn covered_modules(compiler: &Compiler) -> HashSet<String> {
+	compiler
+		.modules_sorted()
+		.into_iter()
+		.filter(|(name, module)| {
+			let path = &module.module_path;
+			path.starts_with(&compiler.root_dir)
+				&& !compiler
+					.packages
+					.packages
+					.iter()
+					.any(|p| path.starts_with(&p.root))
+				&& !name.ends_with(".test")
+				&& name.as_str() != compiler::PROJECT_MARKER_MODULE
+		})
+		.map(|(name, _)| name.clone())
+		.collect()
+}
+
+/// One module's counts, merged by source position.
+#[derive(Default)]
+struct ModuleCoverage {
+	path: PathBuf,
+	lines: BTreeMap<u32, u64>,
+	/// `(line, def)` → entries.
+	functions: BTreeMap<(u32, String), u64>,
+	/// `(line, col, arm)` → times taken.
+	branches: BTreeMap<(u32, u32, u32), u64>,
+}
+
+/// `(hit, found)` for a summary column.
+type Ratio = (usize, usize);
+
+impl ModuleCoverage {
+	fn lines(&self) -> Ratio {
+		ratio(self.lines.values())
+	}
+
+	fn functions(&self) -> Ratio {
+		ratio(self.functions.values())
+	}
+
+	fn branches(&self) -> Ratio {
+		ratio(self.branches.values())
+	}
+
+	/// This module's lcov record.
+	fn lcov(&self, out: &mut String) {
+		let _ = writeln!(out, "TN:\nSF:{}", self.path.display());
+		// lcov wants function names unique per file; a def with several functions
+		// (nested lambdas) names the later ones by line.
+		let mut seen = HashSet::new();
+		let names: Vec<(u32, String, u64)> = self
+			.functions
+			.iter()
+			.map(|((line, def), n)| {
+				let name = if seen.insert(def.as_str()) {
+					def.clone()
+				} else {
+					format!("{def}:{line}")
+				};
+				(*line, name, *n)
+			})
+			.collect();
+		for (line, name, _) in &names {
+			let _ = writeln!(out, "FN:{line},{name}");
+		}
+		for (_, name, n) in &names {
+			let _ = writeln!(out, "FNDA:{n},{name}");
+		}
+		let (hit, found) = self.functions();
+		let _ = writeln!(out, "FNF:{found}\nFNH:{hit}");
+
+		// A branch's arms share a block number: its index among the branches
+		// starting on that line. An arm of a branch never reached is `-`.
+		let mut block = (0, 0, 0);
+		for ((line, col, arm), n) in &self.branches {
+			if (block.0, block.1) != (*line, *col) {
+				block = (*line, *col, if block.0 == *line { block.2 + 1 } else { 0 });
+			}
+			let reached = (0..)
+				.map_while(|a| self.branches.get(&(*line, *col, a)))
+				.any(|n| *n > 0);
+			let taken = if reached {
+				n.to_string()
+			} else {
+				"-".to_string()
+			};
+			let _ = writeln!(out, "BRDA:{line},{},{arm},{taken}", block.2);
+		}
+		let (hit, found) = self.branches();
+		let _ = writeln!(out, "BRF:{found}\nBRH:{hit}");
+
+		for (line, n) in &self.lines {
+			let _ = writeln!(out, "DA:{line},{n}");
+		}
+		let (hit, found) = self.lines();
+		let _ = writeln!(out, "LF:{found}\nLH:{hit}\nend_of_record");
+	}
+}
+
+fn ratio<'a>(counts: impl Iterator<Item = &'a u64>) -> Ratio {
+	counts.fold((0, 0), |(hit, found), n| {
+		(hit + (*n > 0) as usize, found + 1)
+	})
+}
+
+/// Write `coverage/lcov.info` and print the summary table for one run.
+/// `counts` is indexed by probe id and may be shorter than `probes` (a probe
+/// never hit may never have grown the host's table). Returns whether line
+/// coverage met `min` percent, when given.
+pub(crate) fn report(
+	compiler: &Compiler,
+	probes: &[Probe],
+	counts: &[u64],
+	min: Option<f64>,
+	to: Echo,
+) -> bool {
+	let paths: BTreeMap<&str, &Path> = compiler
+		.modules_sorted()
+		.into_iter()
+		.map(|(name, module)| (name.as_str(), module.module_path.as_path()))
+		.collect();
+	let mut modules: BTreeMap<&str, ModuleCoverage> = BTreeMap::new();
+	for (id, probe) in probes.iter().enumerate() {
+		let n = counts.get(id).copied().unwrap_or(0);
+		let m = modules.entry(probe.module.as_str()).or_default();
+		match &probe.kind {
+			ProbeKind::Line => *m.lines.entry(probe.line).or_default() += n,
+			ProbeKind::Function { def } => {
+				*m.functions.entry((probe.line, def.clone())).or_default() += n
+			}
+			ProbeKind::Branch { col, arm } => {
+				*m.branches.entry((probe.line, *col, *arm)).or_default() += n
+			}
+		}
+	}
+	for (name, m) in &mut modules {
+		m.path = paths.get(name).map(|p| p.to_path_buf()).unwrap_or_default();
+	}
+
+	let mut lcov = String::new();
+	for m in modules.values() {
+		m.lcov(&mut lcov);
+	}
+	let lcov_path = compiler.root_dir.join(LCOV_PATH);
+	let written = lcov_path
+		.parent()
+		.is_some_and(|dir| std::fs::create_dir_all(dir).is_ok())
+		&& std::fs::write(&lcov_path, lcov).is_ok();
+
+	let style = Style::detect();
+	let total = |f: fn(&ModuleCoverage) -> Ratio| {
+		modules
+			.values()
+			.map(f)
+			.fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
+	};
+	let width = modules
+		.keys()
+		.map(|m| m.len())
+		.max()
+		.unwrap_or(0)
+		.max("coverage".len());
+	let row = |name: &str, lines: Ratio, branches: Ratio, functions: Ratio| {
+		format!(
+			"  {name:<width$}  {}  {}  {}",
+			cell(lines, style),
+			cell(branches, style),
+			cell(functions, style)
+		)
+	};
+	echo(to, "");
+	echo(
+		to,
+		style.bold(&format!(
+			"  {:<width$}  {:>16}  {:>16}  {:>16}",
+			"coverage", "lines", "branches", "functions"
+		)),
+	);
+	for (name, m) in &modules {
+		echo(to, row(name, m.lines(), m.branches(), m.functions()));
+	}
+	let lines = total(ModuleCoverage::lines);
+	if modules.len() > 1 {
+		echo(
+			to,
+			row(
+				"total",
+				lines,
+				total(ModuleCoverage::branches),
+				total(ModuleCoverage::functions),
+			),
+		);
+	}
+	if written {
+		echo(to, style.dim(&format!("  wrote {LCOV_PATH}")));
+	} else {
+		eprintln!("could not write {}", lcov_path.display());
+	}
+
+	match min {
+		Some(min) if percent(lines) < min => {
+			eprintln!(
+				"{}",
+				style.red(&format!(
+					"line coverage {:.1}% is below the required {min}%",
+					percent(lines)
+				))
+			);
+			false
+		}
+		_ => true,
+	}
+}
+
+fn percent((hit, found): Ratio) -> f64 {
+	if found == 0 {
+		100.0
+	} else {
+		hit as f64 * 100.0 / found as f64
+	}
+}
+
+/// `12/15  80.0%`, coloured green from 80%, yellow from 50%, red below.
+fn cell(r: Ratio, style: Style) -> String {
+	let pct = percent(r);
+	let text = format!("{:>6.1}%", pct);
+	let text = if pct >= 80.0 {
+		style.green(&text)
+	} else if pct >= 50.0 {
+		style.yellow(&text)
+	} else {
+		style.red(&text)
+	};
+	let count = format!("{}/{}", r.0, r.1);
+	format!("{count:>8} {text}")
+}
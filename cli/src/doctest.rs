@@ -0,0 +1,394 @@
+//! `pluma test --doc`: run the example blocks in doc comments as tests.
+//!
+//! `compiler::docs::examples` finds the blocks. Each documented module gets a
+//! synthesized test module holding one case per block, which the ordinary test
+//! runner then compiles and runs. The case body is the example's code, with
+//! every expected result turned into an `assert.equals` check. An expected
+//! result is written `# => value`, either after the expression on its line or
+//! on the line below it:
+//!
+//!     list.length [10, 20, 30]   # => 3
+//!     list.reverse [1, 2, 3]
+//!     # => [3, 2, 1]
+//!
+//! The value runs to the end of the line, or to a gap of two spaces that
+//! starts a remark (`# => some 3   (s is now [1, 2])`). A long value carries
+//! on over comment lines indented past the `=>`.
+//!
+//! Only examples that state a result are run. One without a `# =>` is a
+//! sketch, free to lean on names it never defines, and so is one written as a
+//! whole file with its own `use` or `def` lines.
+
+use compiler::{Module, stdlib_sources};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The test module synthesized for `module`'s examples.
+pub(crate) fn module_name(module: &str) -> String {
+	format!("{module}-doc.test")
+}
+
+/// The synthesized test module for one source module: `None` when it has no
+/// runnable examples. `path` is the module's file, `rel` how to name it in the
+/// cases (relative to the package root).
+pub(crate) fn source_for(module: &str, path: &Path, rel: &str) -> Option<String> {
+	let bytes = std::fs::read(path).ok()?;
+	let mut parsed = Module::new(module.to_string(), PathBuf::from(path));
+	parsed.parse_from_bytes(bytes, &mut Vec::new());
+
+	let namespace = module.rsplit('/').next().unwrap_or(module);
+	let mut cases = String::new();
+	let mut used = String::new();
+	for example in compiler::docs::examples(&parsed) {
+		if !has_expectation(&example.code) || is_whole_file(&example.code) {
+			continue;
+		}
+		let name = match &example.item {
+			Some(item) => format!("{namespace}.{item}"),
+			None => namespace.to_string(),
+		};
+		cases.push_str(&format!(
+			"\tdoc-test.case \"{name} ({rel}:{})\" fun {{\n",
+			example.line + 1
+		));
+		for line in case_body(&example.code) {
+			cases.push_str(&format!("\t\t{line}\n"));
+		}
+		cases.push_str("\t},\n");
+		used.push_str(&example.code);
+		used.push('\n');
+	}
+	if cases.is_empty() {
+		return None;
+	}
+
+	let mut source = format!(
+		"# The doc examples of {rel}, run by `pluma test --doc`.\n\
+		 use std/assert as doc-assert\n\
+		 use std/test as doc-test\n\
+		 use {module}\n"
+	);
+	for std_module in stdlib_uses(&used, namespace) {
+		source.push_str(&format!("use {std_module}\n"));
+	}
+	source.push_str(&format!("\ndef tests :: doc-test.suite = [\n{cases}]\n"));
+	Some(source)
+}
+
+/// Whether a block states a result to check. One that doesn't is a sketch
+/// (it may lean on names it never defines) and isn't run.
+fn has_expectation(code: &str) -> bool {
+	code.lines().any(|line| find_marker(line).is_some())
+}
+
+/// A block that is a whole file (`use`/`def` at the top level) rather than
+/// statements to run.
+fn is_whole_file(code: &str) -> bool {
+	code
+		.lines()
+		.any(|line| line.starts_with("use ") || line.starts_with("def "))
+}
+
+/// The case body for one example: its lines, with each expected result turned
+/// into a `doc-check-N` binding, then `doc-assert.all` over the checks.
+fn case_body(code: &str) -> Vec<String> {
+	let mut out: Vec<String> = Vec::new();
+	let mut checks = 0;
+	// Where the statement the last line belongs to starts in `out`.
+	let mut statement = 0;
+
+	let mut check = |out: &mut Vec<String>, statement: usize, expected: &str| {
+		let Some(first) = out.get(statement) else {
+			return;
+		};
+		checks += 1;
+		let binding = format!("let doc-check-{checks} = doc-assert.equals");
+		match let_name(first) {
+			// `let x = …  # => v` checks the value it bound.
+			Some(name) => out.push(format!("{binding} ({name}) ({expected})")),
+			None => {
+				out[statement] = format!("{binding} ({first}");
+				let last = out.len() - 1;
+				out[last] = format!("{}) ({expected})", out[last]);
+			}
+		}
+	};
+
+	let mut lines = code.lines().peekable();
+	while let Some(line) = lines.next() {
+		if let Some(expected) = line.trim_start().strip_prefix("# =>") {
+			// A long value continues on further-indented comment lines.
+			let mut expected = expected_value(expected);
+			while let Some(more) = lines.next_if(|l| continues_value(l)) {
+				expected.push(' ');
+				expected.push_str(&expected_value(&more.trim_start()[1..]));
+			}
+			check(&mut out, statement, &expected);
+			continue;
+		}
+		let (code, expected) = match find_marker(line) {
+			Some(i) => (line[..i].trim_end(), Some(expected_value(&line[i + 4..]))),
+			None => (line, None),
+		};
+		if starts_statement(code) {
+			statement = out.len();
+		}
+		out.push(code.to_string());
+		if let Some(expected) = expected {
+			check(&mut out, statement, &expected);
+		}
+	}
+
+	let all = (1..=checks)
+		.map(|n| format!("doc-check-{n}"))
+		.collect::<Vec<_>>()
+		.join(", ");
+	out.push(format!("doc-assert.all [{all}]"));
+	out
+}
+
+/// Whether `line` begins a statement rather than continuing the one above:
+/// it isn't indented, blank, a comment, or a closing bracket.
+fn starts_statement(line: &str) -> bool {
+	match line.chars().next() {
+		None => false,
+		Some(c) => !c.is_whitespace() && !matches!(c, '#' | ')' | ']' | '}'),
+	}
+}
+
+/// Whether `line` carries on the value of a `# =>` line above it: a comment
+/// indented past the marker's `=>`.
+fn continues_value(line: &str) -> bool {
+	line
+		.trim_start()
+		.strip_prefix('#')
+		.is_some_and(|rest| rest.starts_with("    ") && !rest.trim_start().is_empty())
+}
+
+/// The name a simple `let name = …` statement binds.
+fn let_name(line: &str) -> Option<&str> {
+	let rest = line.strip_prefix("let ")?;
+	let end = rest.find([' ', '='])?;
+	let name = &rest[..end];
+	name
+		.chars()
+		.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+		.then_some(name)
+}
+
+/// The expected value after a `# =>` marker: up to a two-space gap, trimmed.
+fn expected_value(text: &str) -> String {
+	let text = text.trim_start();
+	text
+		.split("  ")
+		.next()
+		.unwrap_or(text)
+		.trim_end()
+		.to_string()
+}
+
+/// The byte offset of a `# =>` marker outside any string literal.
+fn find_marker(line: &str) -> Option<usize> {
+	let mut in_string = false;
+	let mut escaped = false;
+	for (i, c) in line.char_indices() {
+		match c {
+			_ if escaped => escaped = false,
+			'\\' if in_string => escaped = true,
+			'"' => in_string = !in_string,
+			'#' if !in_string => return line[i..].starts_with("# =>").then_some(i),
+			_ => {}
+		}
+	}
+	None
+}
+
+/// The stdlib modules the examples name by namespace (`string.trim …` needs
+/// `std/string`), besides the documented module's own namespace.
+fn stdlib_uses(code: &str, own: &str) -> Vec<&'static str> {
+	let mut by_namespace: BTreeMap<&str, &'static str> = BTreeMap::new();
+	let mut names: Vec<&'static str> = stdlib_sources().iter().map(|(n, _)| *n).collect();
+	names.sort_unstable();
+	for name in names.into_iter().rev() {
+		by_namespace.insert(name.rsplit('/').next().unwrap_or(name), name);
+	}
+
+	let mut found: Vec<&'static str> = Vec::new();
+	for line in code.lines() {
+		let line = without_strings(line);
+		let bytes = line.as_bytes();
+		for (start, word) in words(&line) {
+			let qualified = bytes.get(start + word.len()) == Some(&b'.');
+			let after_dot = start > 0 && bytes[start - 1] == b'.';
+			if !qualified || after_dot || word == own {
+				continue;
+			}
+			if let Some(module) = by_namespace.get(word)
+				&& !found.contains(module)
+			{
+				found.push(module);
+			}
+		}
+	}
+	found.sort_unstable();
+	found
+}
+
+/// `line` with the contents of its string literals blanked out.
+fn without_strings(line: &str) -> String {
+	let mut out = String::with_capacity(line.len());
+	let mut in_string = false;
+	let mut escaped = false;
+	for c in line.chars() {
+		match c {
+			_ if escaped => escaped = false,
+			'\\' if in_string => escaped = true,
+			'"' => in_string = !in_string,
+			c if !in_string => out.push(c),
+			_ => {}
+		}
+	}
+	out
+}
+
+/// Each identifier in `line` with its byte offset.
+fn words(line: &str) -> Vec<(usize, &str)> {
+	let is_word = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+	let mut out = Vec::new();
+	let mut start = None;
+	for (i, c) in line.char_indices().chain([(line.len(), ' ')]) {
+		match (start, is_word(c)) {
+			(None, true) => start = Some(i),
+			(Some(s), false) => {
+				out.push((s, &line[s..i]));
+				start = None;
+			}
+			_ => {}
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn expectations_become_checks() {
+		// On the expression's own line, and on the line below it.
+		assert_eq!(
+			case_body("list.length [10, 20, 30]   # => 3\nlist.reverse [1, 2, 3]\n# => [3, 2, 1]"),
+			vec![
+				"let doc-check-1 = doc-assert.equals (list.length [10, 20, 30]) (3)",
+				"let doc-check-2 = doc-assert.equals (list.reverse [1, 2, 3]) ([3, 2, 1])",
+				"doc-assert.all [doc-check-1, doc-check-2]",
+			]
+		);
+		// A `let` checks the name it bound, and keeps its binding.
+		assert_eq!(
+			case_body("let n = 1 + 2   # => 3\nn * 2   # => 6"),
+			vec![
+				"let n = 1 + 2",
+				"let doc-check-1 = doc-assert.equals (n) (3)",
+				"let doc-check-2 = doc-assert.equals (n * 2) (6)",
+				"doc-assert.all [doc-check-1, doc-check-2]",
+			]
+		);
+	}
+
+	#[test]
+	fn a_check_wraps_the_whole_statement() {
+		assert_eq!(
+			case_body("list.map [1, 2] fun n {\n\tn * 10\n}\n# => [10, 20]"),
+			vec![
+				"let doc-check-1 = doc-assert.equals (list.map [1, 2] fun n {",
+				"\tn * 10",
+				"}) ([10, 20])",
+				"doc-assert.all [doc-check-1]",
+			]
+		);
+	}
+
+	#[test]
+	fn expected_values_stop_at_a_remark_and_carry_on_indented_lines() {
+		assert_eq!(
+			case_body("stack.pop s   # => some 3   (s is now [1, 2])"),
+			vec![
+				"let doc-check-1 = doc-assert.equals (stack.pop s) (some 3)",
+				"doc-assert.all [doc-check-1]",
+			]
+		);
+		assert_eq!(
+			case_body("pair 1 2\n# => {first: 1,\n#      second: 2}"),
+			vec![
+				"let doc-check-1 = doc-assert.equals (pair 1 2) ({first: 1, second: 2})",
+				"doc-assert.all [doc-check-1]",
+			]
+		);
+	}
+
+	#[test]
+	fn markers_inside_strings_are_text() {
+		assert_eq!(find_marker("\"# => no\""), None);
+		assert_eq!(find_marker("\"a \\\" # => b\""), None);
+		assert_eq!(find_marker("string.trim \" x \"  # => \"x\""), Some(19));
+		// A plain comment isn't a marker.
+		assert_eq!(find_marker("x  # note"), None);
+	}
+
+	#[test]
+	fn stdlib_modules_are_found_by_namespace() {
+		assert_eq!(
+			stdlib_uses(
+				"string.trim (list.head xs)   # => \"option.x\"\nxs.list.head\nlist.length xs",
+				"list"
+			),
+			vec!["std/string"]
+		);
+	}
+
+	fn doc_source(name: &str, module: &str) -> Option<String> {
+		let dir = std::env::temp_dir().join(format!("pluma-doctest-{}-{}", name, std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("nums.pa");
+		std::fs::write(&path, module).unwrap();
+		source_for("nums", &path, "nums.pa")
+	}
+
+	#[test]
+	fn only_examples_with_results_run() {
+		let module = "# Small numbers.\n\
+			#\n\
+			#     nums.double 2   # => 4\n\
+			public def double :: fun int -> int = fun n {\n\
+			\tn * 2\n\
+			}\n\
+			\n\
+			# A sketch, with no result to check.\n\
+			#\n\
+			#     nums.double (read-input ())\n\
+			public def half :: fun int -> int = fun n {\n\
+			\tn / 2\n\
+			}\n\
+			\n\
+			# A whole file, not statements to run.\n\
+			#\n\
+			#     use nums\n\
+			#     def main = nums.triple 1   # => 3\n\
+			public def triple :: fun int -> int = fun n {\n\
+			\tn * 3\n\
+			}\n";
+		let source = doc_source("runs", module).unwrap();
+		assert!(
+			source.contains("doc-test.case \"nums.double (nums.pa:3)\""),
+			"{source}"
+		);
+		assert!(!source.contains("nums.half"), "{source}");
+		assert!(!source.contains("triple"), "{source}");
+		assert!(source.contains("use nums\n"), "{source}");
+
+		let sketches =
+			"# Only a sketch.\n#\n#     nums.double x\npublic def double = fun n {\n\tn * 2\n}\n";
+		assert_eq!(doc_source("sketches", sketches), None);
+	}
+}
@@ -1,8 +1,12 @@
 mod browser_bundle;
+mod cache;
 mod cli;
 mod colors;
 mod commands;
+mod coverage;
+mod doctest;
 mod printing;
+mod reporter;
 mod watch;
 
 use clap::Parser;
@@ -25,8 +29,11 @@ fn main() {
 			server_url,
 			optimize,
 			target,
+			message_format,
 			path,
-		} => commands::build::build_command(web, out, server_url, optimize, target, path),
+		} => {
+			commands::build::build_command(web, out, server_url, optimize, target, message_format, path)
+		}
 
 		Command::Dev {
 			web,
@@ -35,15 +42,74 @@ fn main() {
 			path,
 		} => commands::dev::dev_command(web, port, server_url, path),
 
+		Command::Check {
+			target,
+			message_format,
+			paths,
+		} => commands::check::check_command(target, message_format, paths),
+
 		Command::Format { check, paths } => commands::format::format_command(check, paths),
 
-		Command::Lint { fix, paths } => commands::lint::lint_command(fix, paths),
+		Command::Lint {
+			fix,
+			message_format,
+			paths,
+		} => commands::lint::lint_command(fix, message_format, paths),
 
 		Command::Test {
 			filters,
+			modules,
 			watch,
+			coverage,
+			min_coverage,
+			update_snapshots,
+			doc,
+			timeout,
+			retries,
+			schedule_seed,
+			case,
+			reporter,
+			output,
+			message_format,
 			dir,
-		} => commands::test::test_command(filters, watch, dir),
+		} => {
+			let options = commands::test::SuiteOptions {
+				coverage: coverage.then_some(commands::test::Coverage { min: min_coverage }),
+				update_snapshots,
+				reporter: reporter
+					.as_deref()
+					.and_then(reporter::ReportFormat::from_arg)
+					.map(|format| reporter::Reporter {
+						format,
+						output: output.map(Into::into),
+					}),
+				doc,
+				timeout: timeout.unwrap_or(host::TestSettings::DEFAULT_TIMEOUT),
+				retries,
+				schedule_seed,
+				case: case
+					.map(|path| path.split(" > ").map(str::to_string).collect())
+					.unwrap_or_default(),
+				modules,
+			};
+			commands::test::test_command(filters, watch, options, message_format, dir)
+		}
+
+		Command::Bench {
+			filters,
+			save_baseline,
+			baseline,
+			message_format,
+			dir,
+		} => {
+			let options = commands::bench::BenchOptions {
+				save_baseline,
+				baseline,
+			};
+			commands::bench::bench_command(filters, options, message_format, dir)
+		}
+
+		Command::Explain { code } => commands::explain::explain_command(code),
 
 		Command::Doc { module, out } => commands::doc::doc_command(module, out),
 
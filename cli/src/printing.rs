@@ -1,17 +1,106 @@
 use crate::colors;
-use compiler::{Diagnostic, Palette, render_diagnostics};
+use compiler::{
+	Diagnostic, Palette, Suggestion, diagnostic_json, diagnostics_sarif, render_diagnostics,
+};
+use host::Echo;
 use std::fs;
 use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+/// How diagnostics are written, picked once per process by `--message-format`
+/// (`build`, `check`, `lint`, `test`). `Human` renders to stderr; the machine
+/// formats write to stdout, leaving stderr for the commands' own progress lines.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+	/// The rendered, optionally colored excerpt (the default).
+	Human,
+	/// One JSON object per diagnostic per line, printed as it's reported.
+	Json,
+	/// One SARIF 2.1.0 log for the whole run, printed when the process exits.
+	Sarif,
+}
+
+static MESSAGE_FORMAT: OnceLock<MessageFormat> = OnceLock::new();
+
+/// Diagnostics held back for the single SARIF log written by [`exit`].
+static SARIF_RESULTS: Mutex<Vec<(Diagnostic, Vec<Suggestion>)>> = Mutex::new(Vec::new());
+
+/// Select the diagnostic format from a `--message-format` value (clap has
+/// already restricted it to `human`/`json`/`sarif`). Call before anything prints.
+pub fn set_message_format(format: Option<&str>) {
+	let format = match format {
+		Some("json") => MessageFormat::Json,
+		Some("sarif") => MessageFormat::Sarif,
+		_ => MessageFormat::Human,
+	};
+	let _ = MESSAGE_FORMAT.set(format);
+}
+
+pub fn message_format() -> MessageFormat {
+	MESSAGE_FORMAT
+		.get()
+		.copied()
+		.unwrap_or(MessageFormat::Human)
+}
+
+/// Whether stdout is reserved for machine-readable diagnostics, so a command's
+/// own chatter (summaries, progress) should go to stderr instead.
+pub fn is_machine_readable() -> bool {
+	message_format() != MessageFormat::Human
+}
+
+/// Print a line of a command's console report where `to` says: stdout, or
+/// stderr when stdout is taken by machine-readable output.
+pub fn echo(to: Echo, line: impl std::fmt::Display) {
+	match to {
+		Echo::Stdout => println!("{line}"),
+		Echo::Stderr => eprintln!("{line}"),
+		Echo::Off => {}
+	}
+}
 
 pub fn print_error<E: std::fmt::Display>(message: E) {
 	print_diagnostics(vec![Diagnostic::error(message)])
 }
 
 pub fn print_diagnostics(diagnostics: Vec<Diagnostic>) {
+	print_with_suggestions(diagnostics.into_iter().map(|d| (d, Vec::new())).collect());
+}
+
+/// Print diagnostics that may carry suggested edits (the linter's autofixes).
+/// The human format has no place for the edits and renders the diagnostics
+/// alone; the machine formats serialize them alongside.
+pub fn print_with_suggestions(diagnostics: Vec<(Diagnostic, Vec<Suggestion>)>) {
 	if diagnostics.is_empty() {
 		return;
 	}
-	eprint!("{}", render_diagnostics_string(&diagnostics));
+	match message_format() {
+		MessageFormat::Human => {
+			let diagnostics: Vec<Diagnostic> = diagnostics.into_iter().map(|(d, _)| d).collect();
+			eprint!("{}", render_diagnostics_string(&diagnostics));
+		}
+		MessageFormat::Json => {
+			for (d, suggestions) in &diagnostics {
+				println!("{}", diagnostic_json(d, suggestions));
+			}
+		}
+		MessageFormat::Sarif => SARIF_RESULTS
+			.lock()
+			.unwrap_or_else(|e| e.into_inner())
+			.extend(diagnostics),
+	}
+}
+
+/// Write out anything the message format held back (the SARIF log), then exit.
+/// Commands that take `--message-format` exit through here rather than
+/// `std::process::exit`, so the log is written on every path out — a SARIF run
+/// with no diagnostics still prints an empty log, which CI tooling expects.
+pub fn exit(code: i32) -> ! {
+	if message_format() == MessageFormat::Sarif {
+		let results = std::mem::take(&mut *SARIF_RESULTS.lock().unwrap_or_else(|e| e.into_inner()));
+		println!("{}", diagnostics_sarif(&results, env!("PLUMA_VERSION")));
+	}
+	std::process::exit(code)
 }
 
 /// Print diagnostics, then report whether any was fatal (error-severity). Warnings
@@ -0,0 +1,454 @@
+//! `pluma test --reporter`: the run's per-case results in a format CI tools
+//! read. The cases come from the host (`host::run_test_v8_detailed`), which
+//! collects the record line `std/test` prints for each case when the suite is
+//! lowered with `ir::TestOptions::report`.
+//!
+//! Three formats, each covering every case with its group path, status,
+//! failure message and duration. A flaky case (one that passed only on a
+//! `--retries` rerun) passes, and carries the message of its failed run:
+//!   - `junit`: JUnit XML, one `<testsuite>` per test module.
+//!   - `tap`: TAP version 13, failure messages in a YAML block.
+//!   - `json`: one JSON object for the whole run.
+
+use compiler::docs::push_json_str;
+use host::{TestCase, TestStatus};
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// `--reporter`, with where its report goes.
+#[derive(Clone)]
+pub(crate) struct Reporter {
+	pub(crate) format: ReportFormat,
+	/// `--output`; stdout when absent, in which case the console report is
+	/// left out so stdout holds only this one.
+	pub(crate) output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum ReportFormat {
+	Junit,
+	Tap,
+	Json,
+}
+
+impl ReportFormat {
+	/// The format a `--reporter` value names (clap has already restricted it).
+	pub(crate) fn from_arg(arg: &str) -> Option<ReportFormat> {
+		match arg {
+			"junit" => Some(ReportFormat::Junit),
+			"tap" => Some(ReportFormat::Tap),
+			"json" => Some(ReportFormat::Json),
+			_ => None,
+		}
+	}
+}
+
+/// Render the report and write it where the reporter says. Returns an error
+/// message when the file can't be written.
+pub(crate) fn write(
+	reporter: &Reporter,
+	cases: &[TestCase],
+	elapsed: Duration,
+) -> Result<(), String> {
+	let report = match reporter.format {
+		ReportFormat::Junit => junit(cases, elapsed),
+		ReportFormat::Tap => tap(cases),
+		ReportFormat::Json => json(cases, elapsed),
+	};
+	match &reporter.output {
+		Some(path) => {
+			if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+				std::fs::create_dir_all(dir)
+					.map_err(|err| format!("Could not create `{}`: {}", dir.display(), err))?;
+			}
+			std::fs::write(path, report)
+				.map_err(|err| format!("Could not write `{}`: {}", path.display(), err))
+		}
+		None => {
+			print!("{report}");
+			Ok(())
+		}
+	}
+}
+
+fn status_word(status: TestStatus) -> &'static str {
+	match status {
+		TestStatus::Passed => "pass",
+		TestStatus::Failed => "fail",
+		TestStatus::Skipped => "skip",
+		TestStatus::Todo => "todo",
+		TestStatus::Flaky => "flaky",
+	}
+}
+
+fn count(cases: &[&TestCase], status: TestStatus) -> usize {
+	cases.iter().filter(|c| c.status == status).count()
+}
+
+/// The cases grouped by module, in run order.
+fn by_module(cases: &[TestCase]) -> Vec<(&str, Vec<&TestCase>)> {
+	let mut modules: Vec<(&str, Vec<&TestCase>)> = Vec::new();
+	for case in cases {
+		match modules.last_mut() {
+			Some((module, group)) if *module == case.module => group.push(case),
+			_ => modules.push((&case.module, vec![case])),
+		}
+	}
+	modules
+}
+
+/// JUnit XML. A case's `classname` is its module and group path joined with
+/// `.`, which is how most CI dashboards nest them. Skipped and todo cases are
+/// both `<skipped/>`; a todo says so in the message. A flaky case passes with a
+/// `<flakyFailure>`, as Maven Surefire writes it.
+fn junit(cases: &[TestCase], elapsed: Duration) -> String {
+	let all: Vec<&TestCase> = cases.iter().collect();
+	let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+	let _ = writeln!(
+		out,
+		"<testsuites name=\"pluma test\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+		all.len(),
+		count(&all, TestStatus::Failed),
+		count(&all, TestStatus::Skipped) + count(&all, TestStatus::Todo),
+		elapsed.as_secs_f64()
+	);
+	for (module, cases) in by_module(cases) {
+		let time: Duration = cases.iter().map(|c| c.duration).sum();
+		let _ = writeln!(
+			out,
+			"  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+			xml(module),
+			cases.len(),
+			count(&cases, TestStatus::Failed),
+			count(&cases, TestStatus::Skipped) + count(&cases, TestStatus::Todo),
+			time.as_secs_f64()
+		);
+		for case in cases {
+			let classname = std::iter::once(case.module.as_str())
+				.chain(case.path.iter().map(String::as_str))
+				.collect::<Vec<_>>()
+				.join(".");
+			let _ = write!(
+				out,
+				"    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+				xml(&classname),
+				xml(&case.name),
+				case.duration.as_secs_f64()
+			);
+			match case.status {
+				TestStatus::Passed => out.push_str("/>\n"),
+				TestStatus::Failed => {
+					let summary = case.message.lines().next().unwrap_or("");
+					let _ = writeln!(
+						out,
+						">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+						xml(summary),
+						xml(&case.message)
+					);
+				}
+				TestStatus::Skipped => out.push_str(">\n      <skipped/>\n    </testcase>\n"),
+				TestStatus::Todo => out.push_str(">\n      <skipped message=\"todo\"/>\n    </testcase>\n"),
+				TestStatus::Flaky => {
+					let summary = case.message.lines().next().unwrap_or("");
+					let _ = writeln!(
+						out,
+						">\n      <flakyFailure message=\"{}\">{}</flakyFailure>\n    </testcase>",
+						xml(summary),
+						xml(&case.message)
+					);
+				}
+			}
+		}
+		out.push_str("  </testsuite>\n");
+	}
+	out.push_str("</testsuites>\n");
+	out
+}
+
+/// Escape text for an XML attribute or element body.
+fn xml(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'&' => out.push_str("&amp;"),
+			'<' => out.push_str("&lt;"),
+			'>' => out.push_str("&gt;"),
+			'"' => out.push_str("&quot;"),
+			'\n' => out.push_str("&#10;"),
+			// XML 1.0 has no way to write the other control characters.
+			c if (c as u32) < 0x20 && c != '\t' && c != '\r' => {}
+			c => out.push(c),
+		}
+	}
+	out
+}
+
+/// TAP version 13. A description is the case's module, groups and name joined
+/// with ` > `; skipped and todo cases carry the `# SKIP` and `# TODO`
+/// directives, and a failure's message and every case's duration ride in the
+/// YAML block under it. A flaky case is `ok`, its block saying how many
+/// attempts it took and why the first failed.
+fn tap(cases: &[TestCase]) -> String {
+	let mut out = format!("TAP version 13\n1..{}\n", cases.len());
+	for (i, case) in cases.iter().enumerate() {
+		let description = std::iter::once(case.module.as_str())
+			.chain(case.path.iter().map(String::as_str))
+			.chain([case.name.as_str()])
+			.collect::<Vec<_>>()
+			.join(" > ")
+			.replace('#', "\\#");
+		let (ok, directive) = match case.status {
+			TestStatus::Passed | TestStatus::Flaky => ("ok", ""),
+			TestStatus::Failed => ("not ok", ""),
+			TestStatus::Skipped => ("ok", " # SKIP"),
+			TestStatus::Todo => ("not ok", " # TODO"),
+		};
+		let _ = writeln!(out, "{ok} {} - {description}{directive}", i + 1);
+		let _ = writeln!(
+			out,
+			"  ---\n  duration_ms: {:.3}",
+			case.duration.as_secs_f64() * 1000.0
+		);
+		if case.status == TestStatus::Flaky {
+			let _ = writeln!(out, "  flaky: true\n  attempts: {}", case.attempts);
+		}
+		if matches!(case.status, TestStatus::Failed | TestStatus::Flaky) {
+			out.push_str("  message: |-\n");
+			for line in case.message.lines() {
+				let _ = writeln!(out, "    {line}");
+			}
+		}
+		out.push_str("  ...\n");
+	}
+	out
+}
+
+/// One JSON object for the run: totals, then every case.
+///
+///   {"type":"test-run","passed":3,"failed":1,"skipped":0,"todo":1,"flaky":0,
+///    "duration_ms":812.4,"cases":[{"module":"app.test","path":["edge cases"],
+///    "name":"zero","status":"pass","message":null,"duration_ms":0.2,
+///    "attempts":1},…]}
+fn json(cases: &[TestCase], elapsed: Duration) -> String {
+	let all: Vec<&TestCase> = cases.iter().collect();
+	let mut out = String::from("{\"type\":\"test-run\"");
+	for (key, status) in [
+		("passed", TestStatus::Passed),
+		("failed", TestStatus::Failed),
+		("skipped", TestStatus::Skipped),
+		("todo", TestStatus::Todo),
+		("flaky", TestStatus::Flaky),
+	] {
+		let _ = write!(out, ",\"{key}\":{}", count(&all, status));
+	}
+	let _ = write!(
+		out,
+		",\"duration_ms\":{:.3},\"cases\":[",
+		elapsed.as_secs_f64() * 1000.0
+	);
+	for (i, case) in cases.iter().enumerate() {
+		if i > 0 {
+			out.push(',');
+		}
+		out.push_str("{\"module\":");
+		push_json_str(&mut out, &case.module);
+		out.push_str(",\"path\":[");
+		for (j, group) in case.path.iter().enumerate() {
+			if j > 0 {
+				out.push(',');
+			}
+			push_json_str(&mut out, group);
+		}
+		out.push_str("],\"name\":");
+		push_json_str(&mut out, &case.name);
+		out.push_str(",\"status\":");
+		push_json_str(&mut out, status_word(case.status));
+		out.push_str(",\"message\":");
+		match case.status {
+			TestStatus::Failed | TestStatus::Flaky => push_json_str(&mut out, &case.message),
+			_ => out.push_str("null"),
+		}
+		let _ = write!(
+			out,
+			",\"duration_ms\":{:.3},\"attempts\":{}}}",
+			case.duration.as_secs_f64() * 1000.0,
+			case.attempts
+		);
+	}
+	out.push_str("]}\n");
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn case(
+		module: &str,
+		path: &[&str],
+		name: &str,
+		status: TestStatus,
+		message: &str,
+		millis: u64,
+	) -> TestCase {
+		TestCase {
+			module: module.to_string(),
+			path: path.iter().map(|p| p.to_string()).collect(),
+			name: name.to_string(),
+			status,
+			message: message.to_string(),
+			duration: Duration::from_millis(millis),
+			attempts: match status {
+				TestStatus::Skipped | TestStatus::Todo => 0,
+				TestStatus::Flaky => 2,
+				_ => 1,
+			},
+		}
+	}
+
+	/// One case of every status, the failure's message needing escapes in
+	/// every format.
+	fn cases() -> Vec<TestCase> {
+		vec![
+			case("math.test", &["add"], "ones", TestStatus::Passed, "", 1),
+			case(
+				"math.test",
+				&["add"],
+				"divides",
+				TestStatus::Failed,
+				"expected <1> \"x\"\ngot 2",
+				2,
+			),
+			case("math.test", &[], "big #3", TestStatus::Skipped, "", 0),
+			case("net.test", &["sync"], "retries", TestStatus::Todo, "", 0),
+			case(
+				"net.test",
+				&["sync"],
+				"fetches",
+				TestStatus::Flaky,
+				"connection reset",
+				3,
+			),
+		]
+	}
+
+	/// Every tag closes in order and every attribute value is quoted whole.
+	fn assert_well_formed(xml: &str) {
+		let mut open: Vec<&str> = Vec::new();
+		let mut rest = xml;
+		while let Some(start) = rest.find('<') {
+			let end = start + rest[start..].find('>').expect("unclosed tag");
+			let tag = &rest[start + 1..end];
+			assert_eq!(tag.matches('"').count() % 2, 0, "stray quote in <{tag}>");
+			assert!(!tag.contains('<'), "`<` inside <{tag}>");
+			if let Some(name) = tag.strip_prefix('/') {
+				assert_eq!(open.pop(), Some(name), "mismatched </{name}>");
+			} else if !tag.starts_with('?') && !tag.ends_with('/') {
+				open.push(tag.split(' ').next().unwrap());
+			}
+			rest = &rest[end + 1..];
+		}
+		assert!(open.is_empty(), "unclosed: {open:?}");
+	}
+
+	#[test]
+	fn junit_is_well_formed_and_escaped() {
+		let report = junit(&cases(), Duration::from_millis(812));
+		assert_well_formed(&report);
+		assert_eq!(
+			report,
+			r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="pluma test" tests="5" failures="1" skipped="2" time="0.812">
+  <testsuite name="math.test" tests="3" failures="1" skipped="1" time="0.003">
+    <testcase classname="math.test.add" name="ones" time="0.001"/>
+    <testcase classname="math.test.add" name="divides" time="0.002">
+      <failure message="expected &lt;1&gt; &quot;x&quot;">expected &lt;1&gt; &quot;x&quot;&#10;got 2</failure>
+    </testcase>
+    <testcase classname="math.test" name="big #3" time="0.000">
+      <skipped/>
+    </testcase>
+  </testsuite>
+  <testsuite name="net.test" tests="2" failures="0" skipped="1" time="0.003">
+    <testcase classname="net.test.sync" name="retries" time="0.000">
+      <skipped message="todo"/>
+    </testcase>
+    <testcase classname="net.test.sync" name="fetches" time="0.003">
+      <flakyFailure message="connection reset">connection reset</flakyFailure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+		);
+	}
+
+	#[test]
+	fn tap_has_a_plan_directives_and_yaml_blocks() {
+		assert_eq!(
+			tap(&cases()),
+			r#"TAP version 13
+1..5
+ok 1 - math.test > add > ones
+  ---
+  duration_ms: 1.000
+  ...
+not ok 2 - math.test > add > divides
+  ---
+  duration_ms: 2.000
+  message: |-
+    expected <1> "x"
+    got 2
+  ...
+ok 3 - math.test > big \#3 # SKIP
+  ---
+  duration_ms: 0.000
+  ...
+not ok 4 - net.test > sync > retries # TODO
+  ---
+  duration_ms: 0.000
+  ...
+ok 5 - net.test > sync > fetches
+  ---
+  duration_ms: 3.000
+  flaky: true
+  attempts: 2
+  message: |-
+    connection reset
+  ...
+"#
+		);
+	}
+
+	#[test]
+	fn json_round_trips_through_serde() {
+		let report = json(&cases(), Duration::from_millis(812));
+		let run: serde_json::Value = serde_json::from_str(&report).unwrap();
+		assert_eq!(run["type"], "test-run");
+		for key in ["passed", "failed", "skipped", "todo", "flaky"] {
+			assert_eq!(run[key], 1, "{key}");
+		}
+		assert_eq!(run["duration_ms"], 812.0);
+
+		let cases = run["cases"].as_array().unwrap();
+		let statuses: Vec<&str> = cases
+			.iter()
+			.map(|c| c["status"].as_str().unwrap())
+			.collect();
+		assert_eq!(statuses, ["pass", "fail", "skip", "todo", "flaky"]);
+		assert_eq!(
+			cases[1],
+			serde_json::json!({
+				"module": "math.test",
+				"path": ["add"],
+				"name": "divides",
+				"status": "fail",
+				"message": "expected <1> \"x\"\ngot 2",
+				"duration_ms": 2.0,
+				"attempts": 1,
+			})
+		);
+		assert_eq!(cases[0]["message"], serde_json::Value::Null);
+		assert_eq!(cases[4]["message"], "connection reset");
+		assert_eq!(cases[4]["attempts"], 2);
+	}
+}
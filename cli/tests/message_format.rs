@@ -0,0 +1,90 @@
+// `--message-format json|sarif` reserves stdout for the diagnostics: each
+// command's own report (the test tree, banners, timings, summaries) must move to
+// stderr, or a consumer parsing stdout breaks. Runs the built `pluma` binary
+// over a small package and parses what it printed.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const MANIFEST: &str = "use std/package\n\npublic def package :: package.info = {\n\tname: \"app\",\n\tversion: \"0.0.1\",\n\tauthors: [],\n\tdescription: \"\",\n\tlicense: \"\",\n\trepository: \"\",\n\tdependencies: [],\n}\n";
+
+// A passing suite with one warning (the unused binding) and a case that prints.
+const SUITE: &str = "use std/assert\nuse std/test\n\ndef tests :: test.suite = [\n\ttest.case \"adds\" fun {\n\t\tlet unused = 1\n\t\tprint \"from the case\"\n\t\tassert.equals (1 + 1) 2\n\t},\n]\n";
+
+fn package(name: &str) -> PathBuf {
+	let root = std::env::temp_dir().join(format!(
+		"pluma-message-format-{}-{}",
+		name,
+		std::process::id()
+	));
+	let _ = std::fs::remove_dir_all(&root);
+	std::fs::create_dir_all(&root).unwrap();
+	std::fs::write(root.join("pluma.pa"), MANIFEST).unwrap();
+	std::fs::write(root.join("math.test.pa"), SUITE).unwrap();
+	root
+}
+
+fn pluma(dir: &Path, args: &[&str]) -> Output {
+	Command::new(env!("CARGO_BIN_EXE_pluma"))
+		.args(args)
+		.current_dir(dir)
+		.env("NO_COLOR", "1")
+		.output()
+		.unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+	String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_json_stdout_is_json_lines() {
+	let root = package("test-json");
+	let output = pluma(&root, &["test", "--message-format", "json"]);
+	let stdout = stdout(&output);
+	assert!(
+		!stdout.trim().is_empty(),
+		"expected the unused-binding warning"
+	);
+	for line in stdout.lines() {
+		let value: serde_json::Value = serde_json::from_str(line)
+			.unwrap_or_else(|e| panic!("stdout line isn't JSON ({e}): {line:?}"));
+		assert_eq!(value["type"], "diagnostic", "{line}");
+	}
+	// The report still reaches the terminal, beside the diagnostics.
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(stderr.contains("running 1 test module"), "{stderr}");
+	assert!(stderr.contains("finished in"), "{stderr}");
+}
+
+#[test]
+fn test_sarif_stdout_is_one_log() {
+	let root = package("test-sarif");
+	let output = pluma(&root, &["test", "--message-format", "sarif"]);
+	let log: serde_json::Value = serde_json::from_str(&stdout(&output))
+		.unwrap_or_else(|e| panic!("stdout isn't one SARIF log ({e}): {:?}", stdout(&output)));
+	assert_eq!(log["version"], "2.1.0");
+	assert!(!log["runs"][0]["results"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn check_json_stdout_is_json_lines() {
+	let root = package("check-json");
+	let output = pluma(&root, &["check", "--message-format", "json"]);
+	for line in stdout(&output).lines() {
+		serde_json::from_str::<serde_json::Value>(line)
+			.unwrap_or_else(|e| panic!("stdout line isn't JSON ({e}): {line:?}"));
+	}
+}
+
+#[test]
+fn lint_fix_stdin_is_refused() {
+	let root = package("lint-fix-stdin");
+	let output = pluma(&root, &["lint", "--fix", "--message-format", "json", "-"]);
+	assert_eq!(output.status.code(), Some(1));
+	// Stdout carries the refusal as a diagnostic, not a module.
+	let value: serde_json::Value = serde_json::from_str(stdout(&output).trim())
+		.unwrap_or_else(|e| panic!("stdout isn't one diagnostic ({e}): {:?}", stdout(&output)));
+	assert_eq!(value["severity"], "error");
+	assert!(value["message"].as_str().unwrap().contains("`--fix -`"));
+}
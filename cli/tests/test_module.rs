@@ -0,0 +1,43 @@
+// `--module` picks a test file by its whole module name, where `-f` matches any
+// name containing the text. Runs the built `pluma` binary over a package with
+// two suites, one of whose names contains the other's.
+
+use std::process::Command;
+
+const MANIFEST: &str = "use std/package\n\npublic def package :: package.info = {\n\tname: \"app\",\n\tversion: \"0.0.1\",\n\tauthors: [],\n\tdescription: \"\",\n\tlicense: \"\",\n\trepository: \"\",\n\tdependencies: [],\n}\n";
+
+const PASSING: &str = "use std/assert\nuse std/test\n\ndef tests :: test.suite = [\n\ttest.case \"adds\" fun { assert.equals (1 + 1) 2 },\n]\n";
+
+const FAILING: &str = "use std/assert\nuse std/test\n\ndef tests :: test.suite = [\n\ttest.case \"adds\" fun { assert.equals (1 + 1) 3 },\n]\n";
+
+fn pluma_test(root: &std::path::Path, args: &[&str]) -> std::process::Output {
+	Command::new(env!("CARGO_BIN_EXE_pluma"))
+		.arg("test")
+		.args(args)
+		.current_dir(root)
+		.env("NO_COLOR", "1")
+		.env("PLUMA_NO_CACHE", "1")
+		.output()
+		.unwrap()
+}
+
+#[test]
+fn a_module_is_selected_by_its_whole_name() {
+	let root = compiler::temp_tree(
+		"test-module",
+		&[
+			("pluma.pa", MANIFEST),
+			("math.test.pa", PASSING),
+			("more_math.test.pa", FAILING),
+		],
+	);
+
+	let output = pluma_test(&root, &["--module", "math.test"]);
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	assert_eq!(output.status.code(), Some(0), "{stdout}");
+	assert!(stdout.contains("running 1 test module in"), "{stdout}");
+
+	let output = pluma_test(&root, &["--module", "math"]);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(stderr.contains("no test modules named [\"math\"]"), "{stderr}");
+}
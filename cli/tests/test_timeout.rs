@@ -0,0 +1,39 @@
+// A case that never finishes is stopped at its time limit and fails, and the
+// rest of the run goes on without it. Runs the built `pluma` binary over a
+// package whose suite has one such case.
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const MANIFEST: &str = "use std/package\n\npublic def package :: package.info = {\n\tname: \"app\",\n\tversion: \"0.0.1\",\n\tauthors: [],\n\tdescription: \"\",\n\tlicense: \"\",\n\trepository: \"\",\n\tdependencies: [],\n}\n";
+
+// `spin` never returns: a self tail call is a loop.
+const SUITE: &str = "use std/assert\nuse std/test\n\ndef spin :: fun int -> task nothing string = fun n {\n\tspin (n + 1)\n}\n\ndef tests :: test.suite = [\n\ttest.timeout 200ms (test.case \"spins forever\" fun { spin 0 }),\n\ttest.case \"still runs\" fun { assert.equals (1 + 1) 2 },\n]\n";
+
+#[test]
+fn a_hanging_case_times_out_and_the_run_goes_on() {
+	let root = compiler::temp_tree(
+		"test-timeout",
+		&[("pluma.pa", MANIFEST), ("hang.test.pa", SUITE)],
+	);
+	let started = Instant::now();
+	let output = Command::new(env!("CARGO_BIN_EXE_pluma"))
+		.arg("test")
+		.current_dir(&root)
+		.env("NO_COLOR", "1")
+		.env("PLUMA_NO_CACHE", "1")
+		.output()
+		.unwrap();
+	let stdout = String::from_utf8_lossy(&output.stdout);
+
+	assert_eq!(output.status.code(), Some(1), "{stdout}");
+	assert!(stdout.contains("✗ spins forever"), "{stdout}");
+	assert!(stdout.contains("timed out after 200ms"), "{stdout}");
+	assert!(stdout.contains("1 of 2 passed"), "{stdout}");
+	// The case's own limit applies, not the 10s default.
+	assert!(
+		started.elapsed() < Duration::from_secs(8),
+		"took {:?}",
+		started.elapsed()
+	);
+}
@@ -0,0 +1,698 @@
+// The CLI's persistent build cache: emitted wasm per entry (and analyzed
+// exports per module, below), kept under `<root>/.pluma/cache` so re-running an
+// unchanged script or test suite skips the whole pipeline — parse, analyze,
+// lower, emit.
+//
+// An entry is addressed by a *key* hashing everything that shapes the artifact
+// besides the sources: the compiler version, the command and its codegen
+// options, and the entry modules. Next to the artifact sits a manifest of every
+// source file the compile read (modules, plus the `pluma.pa` of the root and of
+// each dependency) with the hash of its contents. A lookup re-hashes each file;
+// any difference — an edit, a deleted file, a new manifest — is a miss, and the
+// caller compiles as usual and stores the fresh result.
+//
+// The emitting commands lock the package's dependencies (`lock_packages`)
+// before the lookup, so a lockfile that drifted from `pluma.pa` fails the
+// command rather than hiding behind a hit.
+//
+// `pluma check` emits nothing, so it persists each module's analyzed exports
+// instead (the LSP's `ModuleCache`), in `exports`: a later check reuses an
+// unchanged module's exports rather than re-analyzing it (see
+// `Compiler::enable_incremental`). The emitting commands can't: lowering reads
+// every module's typed AST, which exports don't carry. A module exporting a
+// trait with default method bodies isn't persisted either, for the same reason
+// — the defaults are ASTs.
+//
+// Only clean compiles are stored (the caller's job — see `check()` returning
+// `Ok`), so a hit never hides a warning. `PLUMA_NO_CACHE=1` disables the cache.
+
+use crate::module::{InstanceExport, TraitExport};
+use crate::types::Type;
+use crate::{
+	Compiler, EnumExport, ModuleCache, ModuleCacheEntry, ModuleExports, PROJECT_MARKER_FILE,
+	ValueConstraintExport,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// Where the cache lives, relative to the package root.
+pub const CACHE_DIR: &str = ".pluma/cache";
+
+// First line of every manifest. Bump when the format changes.
+const MANIFEST_HEADER: &str = "pluma-cache 1";
+
+// Start of the exports file's first line, which goes on with a hash of the
+// compiler version. Bump when the format changes.
+const EXPORTS_HEADER: &str = "pluma-exports 1";
+
+// A cached artifact: the wasm bytes plus a caller-defined `meta` line for
+// whatever else it needs to skip the compile (e.g. `pluma test`'s suite count).
+pub struct CachedArtifact {
+	pub bytes: Vec<u8>,
+	pub meta: String,
+}
+
+pub struct BuildCache {
+	dir: PathBuf,
+}
+
+impl BuildCache {
+	// The cache for the package at `root_dir`, or `None` when disabled via
+	// `PLUMA_NO_CACHE`. Nothing is created on disk until the first `store`.
+	pub fn open(root_dir: &Path) -> Option<BuildCache> {
+		if std::env::var_os("PLUMA_NO_CACHE").is_some_and(|v| v != "0") {
+			return None;
+		}
+		Some(BuildCache {
+			dir: root_dir.join(CACHE_DIR),
+		})
+	}
+
+	// The artifact stored under `key`, if every source it was built from is
+	// still byte-identical to what was read then.
+	pub fn load(&self, key: &str) -> Option<CachedArtifact> {
+		let manifest = std::fs::read_to_string(self.dir.join(format!("{key}.deps"))).ok()?;
+		let mut lines = manifest.lines();
+		if lines.next() != Some(MANIFEST_HEADER) {
+			return None;
+		}
+		let meta = lines.next()?.strip_prefix("meta ")?.to_string();
+		for line in lines {
+			let (hash, path) = line.split_once(' ')?;
+			if hash != file_hash(Path::new(path)) {
+				return None;
+			}
+		}
+		let bytes = std::fs::read(self.dir.join(format!("{key}.wasm"))).ok()?;
+		Some(CachedArtifact { bytes, meta })
+	}
+
+	// Store `bytes` (and `meta`) under `key`, recording every source `compiler`
+	// read. Best-effort: a cache that can't be written is just a miss next time.
+	pub fn store(&self, key: &str, compiler: &Compiler, bytes: &[u8], meta: &str) {
+		let mut manifest = format!("{MANIFEST_HEADER}\nmeta {meta}\n");
+		for path in compiler.source_files() {
+			manifest.push_str(&format!("{} {}\n", file_hash(&path), path.display()));
+		}
+		// The artifact lands before its manifest, so a reader racing a writer
+		// sees either the old pair or a manifest whose hashes vouch for the new
+		// artifact — never a manifest over a half-written one.
+		if self.write(&format!("{key}.wasm"), bytes) {
+			self.write(&format!("{key}.deps"), manifest.as_bytes());
+		}
+	}
+
+	// The analyzed exports a previous `store_exports` persisted under the same
+	// `version` (the caller's, e.g. the CLI's SHA-qualified one). Empty when
+	// there are none, they came from another compiler, or the file is damaged.
+	pub fn load_exports(&self, version: &str) -> ModuleCache {
+		let Ok(text) = std::fs::read_to_string(self.dir.join("exports")) else {
+			return ModuleCache::new();
+		};
+		let Some((header, body)) = text.split_once('\n') else {
+			return ModuleCache::new();
+		};
+		if header != exports_header(version) {
+			return ModuleCache::new();
+		}
+		read_module_cache(&mut Reader { text: body, pos: 0 }).unwrap_or_default()
+	}
+
+	// Persist `cache` for `load_exports`, minus any module whose exports hold
+	// ASTs (trait defaults). Best-effort, like `store`.
+	pub fn store_exports(&self, version: &str, cache: &ModuleCache) {
+		let mut names: Vec<&String> = cache
+			.iter()
+			.filter(|(_, entry)| persistable(&entry.exports))
+			.map(|(name, _)| name)
+			.collect();
+		names.sort();
+		let mut w = Writer(format!("{}\n", exports_header(version)));
+		w.num(names.len());
+		for name in names {
+			let entry = &cache[name];
+			w.str(name);
+			w.word(&format!("{:016x}", entry.source_hash));
+			write_exports(&mut w, &entry.exports);
+		}
+		self.write("exports", w.0.as_bytes());
+	}
+
+	// Write `contents` to `name` in the cache dir via a rename, so a reader
+	// never sees a half-written file.
+	fn write(&self, name: &str, contents: &[u8]) -> bool {
+		if std::fs::create_dir_all(&self.dir).is_err() {
+			return false;
+		}
+		let tmp = self.dir.join(format!("{name}.{}.tmp", std::process::id()));
+		std::fs::write(&tmp, contents).is_ok() && std::fs::rename(&tmp, self.dir.join(name)).is_ok()
+	}
+}
+
+// The key for one artifact: a hash over the compiler version and every
+// caller-supplied part (command, codegen options, root, entry modules).
+pub fn cache_key(parts: &[&str]) -> String {
+	let mut hash = Fnv::new();
+	hash.write(crate::VERSION.as_bytes());
+	for part in parts {
+		// Length-prefixed, so `["ab", "c"]` and `["a", "bc"]` differ.
+		hash.write(&part.len().to_le_bytes());
+		hash.write(part.as_bytes());
+	}
+	format!("{:016x}", hash.0)
+}
+
+// Hex content hash of a file, or `missing` if it can't be read — so a file
+// appearing where there was none (a new `pluma.pa`) invalidates too.
+fn file_hash(path: &Path) -> String {
+	match std::fs::read(path) {
+		Ok(bytes) => {
+			let mut hash = Fnv::new();
+			hash.write(&bytes);
+			format!("{:016x}", hash.0)
+		}
+		Err(_) => "missing".to_string(),
+	}
+}
+
+// 64-bit FNV-1a. Unlike `DefaultHasher`, its output is fixed across Rust
+// releases, which matters for hashes that outlive the process (these, and each
+// module's `source_hash`, which keys the persisted exports).
+pub(crate) struct Fnv(pub(crate) u64);
+
+impl Fnv {
+	pub(crate) fn new() -> Fnv {
+		Fnv(0xcbf2_9ce4_8422_2325)
+	}
+
+	pub(crate) fn write(&mut self, bytes: &[u8]) {
+		for &b in bytes {
+			self.0 ^= b as u64;
+			self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+		}
+	}
+}
+
+fn exports_header(version: &str) -> String {
+	format!("{EXPORTS_HEADER} {}", cache_key(&[version]))
+}
+
+// Whether `exports` can be written out: everything in it is plain data except
+// trait defaults, which are ASTs.
+fn persistable(exports: &ModuleExports) -> bool {
+	exports
+		.traits
+		.values()
+		.chain(exports.private_traits.values())
+		.all(|t| t.defaults.is_empty())
+}
+
+// The exports file's body is a stream of space-terminated words: numbers,
+// single-letter tags, and strings written as `<byte length>:<bytes>` so they
+// may hold anything. Maps are written with their keys sorted, so an unchanged
+// cache rewrites byte-identically.
+struct Writer(String);
+
+impl Writer {
+	fn word(&mut self, word: &str) {
+		self.0.push_str(word);
+		self.0.push(' ');
+	}
+
+	fn num(&mut self, n: usize) {
+		self.word(&n.to_string());
+	}
+
+	fn str(&mut self, s: &str) {
+		self.word(&format!("{}:{s}", s.len()));
+	}
+
+	fn tail(&mut self, tail: Option<usize>) {
+		match tail {
+			Some(row) => self.num(row),
+			None => self.word("-"),
+		}
+	}
+
+	fn ty(&mut self, ty: &Type) {
+		match ty {
+			Type::Unknown => self.word("?"),
+			Type::Var(id) => {
+				self.word("v");
+				self.num(*id);
+			}
+			Type::Bool => self.word("b"),
+			Type::Int => self.word("i"),
+			Type::Float => self.word("f"),
+			Type::Instant => self.word("I"),
+			Type::Duration => self.word("D"),
+			Type::String => self.word("s"),
+			Type::Bytes => self.word("B"),
+			Type::Nothing => self.word("n"),
+			Type::Tuple(items) => {
+				self.word("t");
+				self.types(items);
+			}
+			Type::PartialTuple(fields, tail) => {
+				self.word("p");
+				self.num(fields.len());
+				for (index, ty) in fields {
+					self.num(*index);
+					self.ty(ty);
+				}
+				self.tail(*tail);
+			}
+			Type::Record(fields, tail) => {
+				self.word("r");
+				self.num(fields.len());
+				for (name, ty) in fields {
+					self.str(name);
+					self.ty(ty);
+				}
+				self.tail(*tail);
+			}
+			Type::Fun(params, ret) => {
+				self.word("F");
+				self.types(params);
+				self.ty(ret);
+			}
+			Type::Enum(name, args) => {
+				self.word("e");
+				self.str(name);
+				self.types(args);
+			}
+			Type::List(item) => {
+				self.word("l");
+				self.ty(item);
+			}
+			Type::Dict(key, value) => {
+				self.word("d");
+				self.ty(key);
+				self.ty(value);
+			}
+			Type::Ref(inner) => {
+				self.word("R");
+				self.ty(inner);
+			}
+		}
+	}
+
+	fn types(&mut self, types: &[Type]) {
+		self.num(types.len());
+		for ty in types {
+			self.ty(ty);
+		}
+	}
+
+	// A map's entries in key order, each written by `f`.
+	fn map<V>(&mut self, map: &HashMap<String, V>, mut f: impl FnMut(&mut Writer, &V)) {
+		let mut keys: Vec<&String> = map.keys().collect();
+		keys.sort();
+		self.num(keys.len());
+		for key in keys {
+			self.str(key);
+			f(self, &map[key]);
+		}
+	}
+}
+
+fn write_exports(w: &mut Writer, e: &ModuleExports) {
+	let enums = |w: &mut Writer, map: &HashMap<String, EnumExport>| {
+		w.map(map, |w, en| {
+			w.num(en.param_count);
+			w.num(en.variants.len());
+			for (name, params) in &en.variants {
+				w.str(name);
+				w.types(params);
+			}
+		})
+	};
+	let constraints = |w: &mut Writer, map: &HashMap<String, Vec<ValueConstraintExport>>| {
+		w.map(map, |w, list| {
+			w.num(list.len());
+			for c in list {
+				w.str(&c.trait_name);
+				w.ty(&c.dispatch_var);
+			}
+		})
+	};
+	let traits = |w: &mut Writer, map: &HashMap<String, TraitExport>| {
+		w.map(map, |w, t| {
+			w.num(t.method_order.len());
+			for method in &t.method_order {
+				w.str(method);
+			}
+			w.map(&t.method_types, Writer::ty);
+		})
+	};
+
+	w.map(&e.values, Writer::ty);
+	w.map(&e.aliases, Writer::ty);
+	enums(w, &e.enums);
+	constraints(w, &e.value_constraints);
+	w.num(e.instances.len());
+	for i in &e.instances {
+		w.str(&i.trait_name);
+		w.ty(&i.head_type);
+		w.num(i.param_count);
+		w.num(i.where_clauses.len());
+		for (trait_name, var) in &i.where_clauses {
+			w.str(trait_name);
+			w.num(*var);
+		}
+		w.str(&i.instance_slot_name);
+	}
+	traits(w, &e.traits);
+	let mut private: Vec<&String> = e.private.iter().collect();
+	private.sort();
+	w.num(private.len());
+	for name in private {
+		w.str(name);
+	}
+	w.map(&e.private_values, Writer::ty);
+	constraints(w, &e.private_value_constraints);
+	w.map(&e.private_aliases, Writer::ty);
+	enums(w, &e.private_enums);
+	traits(w, &e.private_traits);
+}
+
+// Reads back what `Writer` wrote. Every method returns `None` on anything
+// malformed, which `load_exports` treats as an empty cache.
+struct Reader<'a> {
+	text: &'a str,
+	pos: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn word(&mut self) -> Option<&'a str> {
+		let rest = &self.text[self.pos..];
+		let end = rest.find(' ')?;
+		self.pos += end + 1;
+		Some(&rest[..end])
+	}
+
+	fn num(&mut self) -> Option<usize> {
+		self.word()?.parse().ok()
+	}
+
+	fn str(&mut self) -> Option<String> {
+		let rest = &self.text[self.pos..];
+		let colon = rest.find(':')?;
+		let len: usize = rest[..colon].parse().ok()?;
+		let s = rest.get(colon + 1..colon + 1 + len)?;
+		if rest[colon + 1 + len..].starts_with(' ') {
+			self.pos += colon + len + 2;
+			Some(s.to_string())
+		} else {
+			None
+		}
+	}
+
+	fn tail(&mut self) -> Option<Option<usize>> {
+		match self.word()? {
+			"-" => Some(None),
+			row => row.parse().ok().map(Some),
+		}
+	}
+
+	fn ty(&mut self) -> Option<Type> {
+		Some(match self.word()? {
+			"?" => Type::Unknown,
+			"v" => Type::Var(self.num()?),
+			"b" => Type::Bool,
+			"i" => Type::Int,
+			"f" => Type::Float,
+			"I" => Type::Instant,
+			"D" => Type::Duration,
+			"s" => Type::String,
+			"B" => Type::Bytes,
+			"n" => Type::Nothing,
+			"t" => Type::Tuple(self.types()?),
+			"p" => {
+				let fields = self.list(|r| Some((r.num()?, r.ty()?)))?;
+				Type::PartialTuple(fields, self.tail()?)
+			}
+			"r" => {
+				let fields = self.list(|r| Some((r.str()?, r.ty()?)))?;
+				Type::Record(fields, self.tail()?)
+			}
+			"F" => Type::Fun(self.types()?, Box::new(self.ty()?)),
+			"e" => Type::Enum(self.str()?, self.types()?),
+			"l" => Type::List(Box::new(self.ty()?)),
+			"d" => Type::Dict(Box::new(self.ty()?), Box::new(self.ty()?)),
+			"R" => Type::Ref(Box::new(self.ty()?)),
+			_ => return None,
+		})
+	}
+
+	fn types(&mut self) -> Option<Vec<Type>> {
+		self.list(Reader::ty)
+	}
+
+	// A count followed by that many items, each read by `f`.
+	fn list<T>(&mut self, mut f: impl FnMut(&mut Reader<'a>) -> Option<T>) -> Option<Vec<T>> {
+		let count = self.num()?;
+		(0..count).map(|_| f(self)).collect()
+	}
+
+	fn map<V>(
+		&mut self,
+		mut f: impl FnMut(&mut Reader<'a>) -> Option<V>,
+	) -> Option<HashMap<String, V>> {
+		Some(
+			self
+				.list(|r| Some((r.str()?, f(r)?)))?
+				.into_iter()
+				.collect(),
+		)
+	}
+}
+
+fn read_module_cache(r: &mut Reader) -> Option<ModuleCache> {
+	let entries = r.list(|r| {
+		let name = r.str()?;
+		let source_hash = u64::from_str_radix(r.word()?, 16).ok()?;
+		let exports = read_exports(r)?;
+		Some((
+			name,
+			ModuleCacheEntry {
+				source_hash,
+				exports,
+			},
+		))
+	})?;
+	Some(entries.into_iter().collect())
+}
+
+fn read_exports(r: &mut Reader) -> Option<ModuleExports> {
+	fn enums(r: &mut Reader) -> Option<HashMap<String, EnumExport>> {
+		r.map(|r| {
+			Some(EnumExport {
+				param_count: r.num()?,
+				variants: r.list(|r| Some((r.str()?, r.types()?)))?,
+			})
+		})
+	}
+	fn constraints(r: &mut Reader) -> Option<HashMap<String, Vec<ValueConstraintExport>>> {
+		r.map(|r| {
+			r.list(|r| {
+				Some(ValueConstraintExport {
+					trait_name: r.str()?,
+					dispatch_var: r.ty()?,
+				})
+			})
+		})
+	}
+	fn traits(r: &mut Reader) -> Option<HashMap<String, TraitExport>> {
+		r.map(|r| {
+			Some(TraitExport {
+				method_order: r.list(Reader::str)?,
+				method_types: r.map(Reader::ty)?,
+				defaults: HashMap::new(),
+			})
+		})
+	}
+
+	Some(ModuleExports {
+		values: r.map(Reader::ty)?,
+		aliases: r.map(Reader::ty)?,
+		enums: enums(r)?,
+		value_constraints: constraints(r)?,
+		instances: r.list(|r| {
+			Some(InstanceExport {
+				trait_name: r.str()?,
+				head_type: r.ty()?,
+				param_count: r.num()?,
+				where_clauses: r.list(|r| Some((r.str()?, r.num()?)))?,
+				instance_slot_name: r.str()?,
+			})
+		})?,
+		traits: traits(r)?,
+		private: r
+			.list(Reader::str)?
+			.into_iter()
+			.collect::<HashSet<String>>(),
+		private_values: r.map(Reader::ty)?,
+		private_value_constraints: constraints(r)?,
+		private_aliases: r.map(Reader::ty)?,
+		private_enums: enums(r)?,
+		private_traits: traits(r)?,
+	})
+}
+
+impl Compiler {
+	// Every on-disk file this compile read from: each loaded module's source
+	// (stdlib modules are baked in and versioned with the compiler) plus the
+	// package manifests that steer module resolution. Sorted, for a stable
+	// build-cache manifest.
+	pub fn source_files(&self) -> Vec<PathBuf> {
+		let mut files: Vec<PathBuf> = self
+			.modules_sorted()
+			.into_iter()
+			.map(|(_, module)| module.module_path.clone())
+			.filter(|path| !path.to_string_lossy().starts_with('<'))
+			.collect();
+		files.push(self.root_dir.join(PROJECT_MARKER_FILE));
+		for package in &self.packages.packages {
+			files.push(package.root.join(PROJECT_MARKER_FILE));
+		}
+		files.sort();
+		files.dedup();
+		files
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::temp_tree;
+
+	fn compiled(dir: &Path) -> Compiler {
+		let mut compiler = Compiler::for_root_dir(dir.to_path_buf());
+		compiler.add_entry_module("main".into());
+		compiler.check().unwrap();
+		compiler
+	}
+
+	#[test]
+	fn hit_until_any_source_changes() {
+		let dir = temp_tree(
+			"cache-hit",
+			&[
+				("main.pa", "use util\n\ndef main = util.two\n"),
+				("util.pa", "public def two = 2\n"),
+			],
+		);
+		let cache = BuildCache {
+			dir: dir.join(CACHE_DIR),
+		};
+		let key = cache_key(&["run", "main"]);
+		assert!(cache.load(&key).is_none(), "cold cache");
+
+		cache.store(&key, &compiled(&dir), b"\0asm", "1");
+		let hit = cache.load(&key).expect("unchanged sources hit");
+		assert_eq!((hit.bytes, hit.meta), (b"\0asm".to_vec(), "1".to_string()));
+
+		// Other options are another entry.
+		assert!(cache.load(&cache_key(&["test", "main"])).is_none());
+
+		// Editing an import (not just the entry) invalidates.
+		std::fs::write(dir.join("util.pa"), "public def two = 3\n").unwrap();
+		assert!(cache.load(&key).is_none());
+
+		// So does a manifest appearing where there was none.
+		cache.store(&key, &compiled(&dir), b"\0asm", "1");
+		assert!(cache.load(&key).is_some());
+		std::fs::write(dir.join(PROJECT_MARKER_FILE), "").unwrap();
+		assert!(cache.load(&key).is_none());
+	}
+
+	#[test]
+	fn key_parts_are_unambiguous() {
+		assert_ne!(cache_key(&["ab", "c"]), cache_key(&["a", "bc"]));
+		assert_eq!(cache_key(&["run", "main"]), cache_key(&["run", "main"]));
+	}
+
+	// `pluma check` over `dir` with the persisted exports swapped in, as the CLI
+	// does; returns the modules it had to analyze.
+	fn checked_incrementally(cache: &BuildCache, dir: &Path) -> HashSet<String> {
+		let mut compiler = Compiler::for_root_dir(dir.to_path_buf());
+		compiler.add_entry_module("main".into());
+		compiler.enable_incremental(cache.load_exports("1.0"));
+		compiler.check().unwrap();
+		cache.store_exports("1.0", &compiler.take_incremental_cache());
+		compiler.reanalyzed_modules().clone()
+	}
+
+	#[test]
+	fn exports_survive_a_round_trip() {
+		let dir = temp_tree("cache-exports", &[]);
+		let cache = BuildCache {
+			dir: dir.join(CACHE_DIR),
+		};
+		// The stdlib covers every kind of export: enums, aliases, traits,
+		// instances, constrained values, records and tuples.
+		let mut compiler = Compiler::for_root_dir(dir.clone());
+		compiler.enable_incremental(ModuleCache::new());
+		for (name, _) in crate::stdlib_sources() {
+			compiler.add_entry_module(name.to_string());
+		}
+		let _ = compiler.check();
+		let exports = compiler.take_incremental_cache();
+		assert!(exports.len() > 10, "only {} modules cached", exports.len());
+
+		cache.store_exports("1.0", &exports);
+		let written = std::fs::read_to_string(dir.join(CACHE_DIR).join("exports")).unwrap();
+		let loaded = cache.load_exports("1.0");
+		assert_eq!(
+			loaded.len(),
+			exports.values().filter(|e| persistable(&e.exports)).count()
+		);
+		cache.store_exports("1.0", &loaded);
+		let rewritten = std::fs::read_to_string(dir.join(CACHE_DIR).join("exports")).unwrap();
+		assert_eq!(
+			written, rewritten,
+			"decoding then encoding changed the exports"
+		);
+
+		assert!(
+			cache.load_exports("2.0").is_empty(),
+			"another compiler's exports"
+		);
+		std::fs::write(
+			dir.join(CACHE_DIR).join("exports"),
+			&written[..written.len() / 2],
+		)
+		.unwrap();
+		assert!(cache.load_exports("1.0").is_empty(), "a truncated file");
+	}
+
+	#[test]
+	fn a_later_check_reuses_unchanged_modules() {
+		let dir = temp_tree(
+			"cache-check",
+			&[
+				("main.pa", "use util\n\ndef main = util.two\n"),
+				(
+					"util.pa",
+					"use std/list\n\npublic def two = list.length [1, 2]\n",
+				),
+			],
+		);
+		let cache = BuildCache {
+			dir: dir.join(CACHE_DIR),
+		};
+
+		let cold = checked_incrementally(&cache, &dir);
+		assert!(cold.contains("util") && cold.contains("std/list"));
+
+		// The entry always re-analyzes; its unchanged imports come from disk.
+		let warm = checked_incrementally(&cache, &dir);
+		assert_eq!(warm, HashSet::from(["main".to_string()]));
+
+		// Editing an import re-analyzes it (and so its importers), not the rest.
+		std::fs::write(dir.join("util.pa"), "use std/list\n\npublic def two = 2\n").unwrap();
+		let edited = checked_incrementally(&cache, &dir);
+		assert!(edited.contains("util") && !edited.contains("std/list"));
+	}
+}
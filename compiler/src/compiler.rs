@@ -2,6 +2,7 @@ use crate::analyzer::*;
 use crate::diagnostic::*;
 use crate::errors::*;
 use crate::module::*;
+use crate::package::PackageGraph;
 use crate::stdlib::lookup_stdlib_source;
 use crate::*;
 use std::collections::{HashMap, HashSet};
@@ -30,6 +31,11 @@ pub const AUTO_IMPORTS: &[(&str, &str)] = &[
 	// whole combinator surface into every module's namespace.
 ];
 
+// The synthetic prelude module's source, baked into the compiler binary so the
+// language doesn't depend on a stdlib install directory. Tools read it for the
+// declarations of built-in types like `option` and `result`.
+pub const PRELUDE_SOURCE: &str = include_str!("prelude.pa");
+
 // One module's analyzed exports plus the hash of the source they came from.
 // The hash gates reuse: a cached entry is valid only while the module's
 // source is byte-identical to what produced it. Only diagnostic-free
@@ -46,6 +52,7 @@ pub struct ModuleCacheEntry {
 // module name. A long-lived caller (the LSP) owns one across `Compiler`
 // instances, swapping it in before `check()` and back out after, so an edit
 // to one file doesn't force re-analysis of the unchanged modules it imports.
+// `pluma check` does the same across runs, via the build cache on disk.
 pub type ModuleCache = HashMap<String, ModuleCacheEntry>;
 
 // PLUMA_TIMING=1 prints per-module parse/analyze wall-clock to stderr.
@@ -101,13 +108,18 @@ pub struct Compiler {
 	// Optional cross-compile export cache for incremental re-analysis. When
 	// set (the LSP swaps one in per keystroke), a module whose source is
 	// unchanged and whose dependencies were all reused skips re-analysis and
-	// reuses its cached exports. `None` for one-shot compiles (CLI), which
-	// always analyze everything.
+	// reuses its cached exports. `None` for the CLI's emitting commands, whose
+	// lowering needs every module's typed AST, so they analyze everything.
 	incremental: Option<ModuleCache>,
 	// Modules (re)analyzed during the current `check()` pass — its source
 	// changed, or a dependency was reanalyzed. A module isn't reused if any of
 	// its imports is in here, so a signature change propagates to dependents.
 	reanalyzed: HashSet<String>,
+	// The dependencies declared in the root `pluma.pa`, resolved to
+	// directories on disk at the start of `check()`. A module whose first
+	// segment names a package (`lib/foo`) loads from that package's root
+	// instead of `root_dir`.
+	pub packages: PackageGraph,
 }
 
 impl Compiler {
@@ -128,6 +140,7 @@ impl Compiler {
 			rpc_endpoints: Vec::new(),
 			incremental: None,
 			reanalyzed: HashSet::new(),
+			packages: PackageGraph::default(),
 		})
 	}
 
@@ -166,6 +179,7 @@ impl Compiler {
 			rpc_endpoints: Vec::new(),
 			incremental: None,
 			reanalyzed: HashSet::new(),
+			packages: PackageGraph::default(),
 		}
 	}
 
@@ -266,6 +280,15 @@ impl Compiler {
 		Ok(compiler)
 	}
 
+	// The file a (non-stdlib) module is read from: under its dependency
+	// package's root when its first segment names one, else under `root_dir`.
+	pub fn module_path(&self, module_name: &str) -> PathBuf {
+		self
+			.packages
+			.module_path(module_name)
+			.unwrap_or_else(|| to_module_path(&self.root_dir, module_name))
+	}
+
 	// Register a Rust-defined native module's exports so any user module that
 	// does `use <name>` type-checks against them. Must be called before
 	// `check()`. Currently unused — every stdlib module is a `.pa` source — but
@@ -293,7 +316,7 @@ impl Compiler {
 	// parsed and skips the disk read for it. Lets editor/LSP integrations
 	// analyze unsaved changes without writing to disk.
 	pub fn set_module_source(&mut self, module_name: String, source: Vec<u8>) {
-		let path = to_module_path(&self.root_dir, &module_name);
+		let path = self.module_path(&module_name);
 		let mut module = Module::new(module_name.clone(), path);
 		module.parse_from_bytes(source, &mut self.diagnostics);
 		self.modules.insert(module_name, module);
@@ -305,14 +328,36 @@ impl Compiler {
 			.first()
 			.cloned()
 			.expect("tokenize() called with no entry modules");
-		let mut entry_module = Module::new(entry.clone(), to_module_path(&self.root_dir, &entry));
+		let mut entry_module = Module::new(entry.clone(), self.module_path(&entry));
 
 		let tokens = entry_module.tokenize(&mut self.diagnostics);
 
 		Ok(tokens)
 	}
 
+	// Resolve the root manifest's dependencies, verify them against
+	// `pluma.lock` and rewrite it if they changed. The commands that build
+	// (`run`, `build`, `test`, …) call this before `check()` — and before any
+	// build-cache lookup, so a cached artifact can't paper over a dependency
+	// that drifted from its pin. `check()` alone never writes the lockfile.
+	pub fn lock_packages(&mut self) -> Result<(), Vec<Diagnostic>> {
+		let mut diagnostics = Vec::new();
+		let graph = PackageGraph::resolve(&self.root_dir, &mut diagnostics);
+		if diagnostics.is_empty() {
+			graph.lock(&self.root_dir, &mut diagnostics);
+		}
+		if diagnostics.is_empty() {
+			Ok(())
+		} else {
+			Err(diagnostics)
+		}
+	}
+
 	pub fn check(&mut self) -> Result<(), Vec<Diagnostic>> {
+		// Resolve the root manifest's dependencies before any `use` of them is
+		// followed.
+		self.packages = PackageGraph::resolve(&self.root_dir, &mut self.diagnostics);
+
 		// Load + analyze the baked-in `__prelude__` module before anything
 		// else. Its exported instances are implicitly visible to every
 		// user module's analyzer.
@@ -362,9 +407,12 @@ impl Compiler {
 	// half (`entry_modules[0]`) as `Sys`, the client half (`[1]`) as `Web`. A single
 	// `target` can't express the split (each side legitimately reaches the other
 	// tier's stdlib through its own root), so the dual build skips the single-target
-	// gate (`target` is `None`) and calls this instead, after `check()`.
+	// gate (`target` is `None`) and calls this instead, after `check()`. Any
+	// entries past the first two (`pluma check` adds the rest of the package as
+	// roots) are type-checked but not gated — neither artifact ships them unless
+	// one of the halves reaches them.
 	pub fn gate_fullstack(&mut self) -> Result<(), Vec<Diagnostic>> {
-		if let [server, client] = self.entry_modules.clone().as_slice() {
+		if let [server, client, ..] = self.entry_modules.clone().as_slice() {
 			self.gate_roots(&[server.clone()], Target::Sys);
 			self.gate_roots(&[client.clone()], Target::Web);
 		}
@@ -402,7 +450,12 @@ impl Compiler {
 				.uses
 				.iter()
 				.filter(|u| live.contains(&u.local_name().name))
-				.map(|u| (u.module_name(), u.range))
+				.map(|u| {
+					(
+						self.packages.import_target(&name, &u.module_name()),
+						u.range,
+					)
+				})
 				.collect();
 			for (full, range) in follow {
 				via
@@ -426,11 +479,8 @@ impl Compiler {
 		}
 	}
 
-	// Parse + analyze the synthetic prelude module. The source is baked
-	// into the compiler binary so the language doesn't depend on a
-	// stdlib install directory.
+	// Parse + analyze the synthetic prelude module.
 	fn load_prelude(&mut self) {
-		const PRELUDE_SOURCE: &str = include_str!("prelude.pa");
 		const NAME: &str = "__prelude__";
 		// Already seeded (e.g. from a long-lived consumer's stdlib export
 		// cache): the prelude's exports are immutable, so don't re-analyze.
@@ -476,6 +526,19 @@ impl Compiler {
 			}
 		}
 
+		// A registry dependency is declared but has nowhere to load from yet.
+		if stdlib_source.is_none() && self.packages.is_registry_module(module_name) {
+			let package = module_name.split('/').next().unwrap_or(module_name);
+			self.diagnostics.push(
+				Diagnostic::error(format!(
+					"Cannot load `{}`: `{}` is a registry dependency, and there is no package registry yet.",
+					module_name, package
+				))
+				.with_help("declare it with `package.dep.path` or `package.dep.git` instead"),
+			);
+			return;
+		}
+
 		if !visiting.insert(module_name.to_string()) {
 			self.diagnostics.push(Diagnostic::error(format!(
 				"Cyclic import detected involving module `{}`.",
@@ -495,7 +558,7 @@ impl Compiler {
 				module.parse_from_bytes(source.as_bytes().to_vec(), &mut self.diagnostics);
 				self.modules.insert(module_name.to_string(), module);
 			} else {
-				let path = to_module_path(&self.root_dir, module_name);
+				let path = self.module_path(module_name);
 				let mut module = Module::new(module_name.to_string(), path);
 				module.parse(&mut self.diagnostics);
 				self.modules.insert(module_name.to_string(), module);
@@ -509,6 +572,8 @@ impl Compiler {
 		// `utils` and `use sub/utils as u` binds `u`. The use-statement range spans
 		// the whole `use …` line (a better caret target for platform gating than
 		// the alias).
+		// A `use` inside a dependency package resolves relative to that package
+		// (see `PackageGraph::import_target`).
 		let imports: Vec<(String, String, Range, Range)> = self
 			.modules
 			.get(module_name)
@@ -519,7 +584,8 @@ impl Compiler {
 					.iter()
 					.map(|u| {
 						let local = u.local_name();
-						(u.module_name(), local.name.clone(), local.range, u.range)
+						let full = self.packages.import_target(module_name, &u.module_name());
+						(full, local.name.clone(), local.range, u.range)
 					})
 					.collect()
 			})
@@ -610,8 +676,11 @@ impl Compiler {
 		// as a path *suffix* of the import — a test rooted at a subdirectory is named
 		// relative to that root (`sys/static.test`) while it imports the module by its
 		// full path (`std/sys/static`), so the stem is a suffix, not always equal.
+		// A benchmark (`foo.bench`) gets the same view as a test.
 		let test_stem: Option<&str> = if importer_is_test {
-			module_name.strip_suffix(".test")
+			COMPANION_SUFFIXES
+				.iter()
+				.find_map(|suffix| module_name.strip_suffix(suffix))
 		} else {
 			None
 		};
@@ -783,24 +852,36 @@ pub fn find_project_root(start: &Path) -> Option<PathBuf> {
 	}
 }
 
-/// Whether `name` is a discovered test *file* (`<segments>.test.pa`), as
-/// opposed to ordinary code. These are import-gated: only other test modules may
-/// `use` them. `std/test` is excluded — it's the test *framework* (stdlib),
-/// importable from anywhere, and only incidentally shares the `.test` suffix.
+/// The module-name suffixes of the files that sit beside ordinary code to
+/// exercise it: test suites (`*.test.pa`) and benchmarks (`*.bench.pa`).
+const COMPANION_SUFFIXES: [&str; 2] = [".test", ".bench"];
+
+/// Whether `name` is a discovered test or benchmark *file*
+/// (`<segments>.test.pa`, `<segments>.bench.pa`), as opposed to ordinary code.
+/// These are import-gated: only other test modules may `use` them. `std/test`
+/// is excluded — it's the test *framework* (stdlib), importable from anywhere,
+/// and only incidentally shares the `.test` suffix.
 fn is_test_module(name: &str) -> bool {
-	name.ends_with(".test") && name != "std/test"
+	COMPANION_SUFFIXES
+		.iter()
+		.any(|suffix| name.ends_with(suffix))
+		&& name != "std/test"
 }
 
 pub fn to_module_path(root_dir: &Path, module_name: &str) -> PathBuf {
 	let mut path = root_dir.to_path_buf();
-	// Test modules live in `<segments>.test.pa` files. Their module name keeps
-	// the `.test` suffix (e.g. `foo/bar.test`), so we peel that off before
-	// pushing the `/`-separated path segments.
-	if let Some(stem) = module_name.strip_suffix(".test") {
+	// Test and benchmark modules live in `<segments>.test.pa` and
+	// `<segments>.bench.pa` files. Their module name keeps the suffix (e.g.
+	// `foo/bar.test`), so we peel that off before pushing the `/`-separated
+	// path segments.
+	let companion = COMPANION_SUFFIXES
+		.iter()
+		.find_map(|suffix| Some((module_name.strip_suffix(suffix)?, suffix)));
+	if let Some((stem, suffix)) = companion {
 		for segment in stem.split('/') {
 			path.push(segment);
 		}
-		path.set_extension(format!("test.{}", FILE_EXTENSION));
+		path.set_extension(format!("{}.{}", &suffix[1..], FILE_EXTENSION));
 	} else {
 		for segment in module_name.split('/') {
 			path.push(segment);
@@ -1144,4 +1225,28 @@ def call-trait :: fun nothing -> string = fun {\n\
 			errors(&diags)
 		);
 	}
+
+	#[test]
+	fn sibling_bench_sees_private_surface() {
+		let foo_bench = "use foo\n\ndef out :: int = foo.secret-add 2 3\n";
+		let diags = check_multi(&[("foo", FOO), ("foo.bench", foo_bench)], "foo.bench");
+		assert!(
+			errors(&diags).is_empty(),
+			"a sibling benchmark was denied access to its module's private surface: {:?}",
+			errors(&diags)
+		);
+	}
+
+	#[test]
+	fn bench_module_lives_in_a_bench_file() {
+		let root = Path::new("/pkg");
+		assert_eq!(
+			to_module_path(root, "foo/bar.bench"),
+			root.join("foo/bar.bench.pa")
+		);
+		assert_eq!(
+			to_module_path(root, "foo/bar.test"),
+			root.join("foo/bar.test.pa")
+		);
+	}
 }
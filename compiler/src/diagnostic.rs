@@ -123,6 +123,13 @@ impl Diagnostic {
 		}
 	}
 
+	pub fn with_help<H: fmt::Display>(self, help: H) -> Diagnostic {
+		Diagnostic {
+			help: Some(format!("{}", help)),
+			..self
+		}
+	}
+
 	pub fn with_label(mut self, label: Label) -> Diagnostic {
 		self.labels.push(label);
 		self
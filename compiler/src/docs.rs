@@ -143,39 +143,41 @@ pub fn extract(module: &Module, module_name: &str, source: &str) -> ModuleDoc {
 	}
 }
 
-/// Split a doc comment into prose paragraphs and example code blocks. After
-/// `doc_comment_for` strips the conventional leading space, an example line
-/// (written `#     list.length …` in source) keeps a 4-space or tab indent;
-/// that's the signal for a code block. Blank lines separate paragraphs.
+/// Split a doc comment into prose paragraphs and example code blocks.
 fn split_blocks(doc: &str) -> Vec<DocBlock> {
-	let mut blocks: Vec<DocBlock> = Vec::new();
-	let mut buf: Vec<String> = Vec::new();
-	let mut in_code = false;
-
-	let flush = |buf: &mut Vec<String>, in_code: bool, blocks: &mut Vec<DocBlock>| {
-		if buf.is_empty() {
-			return;
-		}
-		if in_code {
-			blocks.push(code_block(buf.join("\n")));
-		} else {
+	split_paragraphs(doc)
+		.into_iter()
+		.map(|p| match p.code {
+			true => code_block(p.lines.join("\n")),
 			// Prose lines are hard-wrapped in source; rejoin with spaces so the
 			// renderer reflows them.
-			blocks.push(prose_block(buf.join(" ")));
-		}
-		buf.clear();
-	};
+			false => prose_block(p.lines.join(" ")),
+		})
+		.collect()
+}
 
-	for line in doc.lines() {
+// One paragraph of a doc comment, before highlighting: its lines (a code
+// block's with the example indent removed, prose trimmed) and the line of the
+// comment it starts on.
+struct Paragraph {
+	code: bool,
+	first_line: usize,
+	lines: Vec<String>,
+}
+
+// After `doc_comment_for` strips the conventional leading space, an example
+// line (written `#     list.length …` in source) keeps a 4-space or tab
+// indent; that's the signal for a code block. Blank lines separate paragraphs.
+fn split_paragraphs(doc: &str) -> Vec<Paragraph> {
+	let mut paragraphs: Vec<Paragraph> = Vec::new();
+	let mut open = false;
+
+	for (i, line) in doc.lines().enumerate() {
 		if line.trim().is_empty() {
-			flush(&mut buf, in_code, &mut blocks);
+			open = false;
 			continue;
 		}
 		let is_code = line.starts_with("    ") || line.starts_with('\t');
-		if !buf.is_empty() && is_code != in_code {
-			flush(&mut buf, in_code, &mut blocks);
-		}
-		in_code = is_code;
 		let text = if is_code {
 			line
 				.strip_prefix("    ")
@@ -185,10 +187,61 @@ fn split_blocks(doc: &str) -> Vec<DocBlock> {
 		} else {
 			line.trim().to_string()
 		};
-		buf.push(text);
+		match paragraphs.last_mut() {
+			Some(p) if open && p.code == is_code => p.lines.push(text),
+			_ => paragraphs.push(Paragraph {
+				code: is_code,
+				first_line: i,
+				lines: vec![text],
+			}),
+		}
+		open = true;
+	}
+	paragraphs
+}
+
+/// One example block from a doc comment, for `pluma test --doc`.
+pub struct Example {
+	/// The documented item, or `None` for the module's own doc comment.
+	pub item: Option<String>,
+	/// The 0-based source line the block starts on.
+	pub line: usize,
+	/// The example's code, with the comment markers and indent removed.
+	pub code: String,
+}
+
+/// Every example block in a parsed module's doc comments: the module doc's
+/// first, then each exported item's in source order. Only exported items are
+/// harvested (the same surface `extract` documents), so an example never has
+/// to reach a private def.
+pub fn examples(module: &Module) -> Vec<Example> {
+	let Some(ast) = module.ast.as_ref() else {
+		return Vec::new();
+	};
+	let mut out = Vec::new();
+	let mut harvest = |item: Option<String>, doc: String, start: usize| {
+		for p in split_paragraphs(&doc).into_iter().filter(|p| p.code) {
+			out.push(Example {
+				item: item.clone(),
+				line: start + p.first_line,
+				code: p.lines.join("\n"),
+			});
+		}
+	};
+	if let Some(doc) = module_doc_comment(module) {
+		harvest(None, doc, 0);
+	}
+	for def in &ast.body {
+		if !matches!(def.visibility, Visibility::Public | Visibility::Opaque) {
+			continue;
+		}
+		let line = def.range.start.line;
+		if let Some(doc) = doc_comment_for(module, line) {
+			let start = line - doc.split('\n').count();
+			harvest(Some(def.name.name.clone()), doc, start);
+		}
 	}
-	flush(&mut buf, in_code, &mut blocks);
-	blocks
+	out
 }
 
 // The text of a top-level declaration, verbatim from source, with the leading
@@ -306,7 +359,7 @@ fn push_blocks_json(out: &mut String, blocks: &[DocBlock]) {
 
 /// Append `s` as a JSON string literal — RFC 8259 escaping: `"` and `\` backslashed,
 /// the named short escapes for the common control chars, and `\u00XX` for the rest.
-fn push_json_str(out: &mut String, s: &str) {
+pub fn push_json_str(out: &mut String, s: &str) {
 	out.push('"');
 	for c in s.chars() {
 		match c {
@@ -404,3 +457,41 @@ fn first_item_line(ast: &ModuleNode) -> Option<usize> {
 	let defs = ast.body.iter().map(|d| d.range.start.line);
 	uses.chain(defs).min()
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::path::PathBuf;
+
+	fn parse(source: &str) -> Module {
+		let mut module = Module::new("m".to_string(), PathBuf::new());
+		module.parse_from_bytes(source.as_bytes().to_vec(), &mut Vec::new());
+		module
+	}
+
+	#[test]
+	fn examples_carry_their_item_and_start_line() {
+		let module = parse(
+			"# Doubles.\n#\n#     double 2   # => 4\n#\n# Also:\n#\n#     double 0\n#     # => 0\npublic def double = fun n { n * 2 }\n\n# Private.\n#\n#     hidden ()\ndef hidden = fun { 1 }\n",
+		);
+		let found: Vec<(Option<String>, usize, String)> = examples(&module)
+			.into_iter()
+			.map(|e| (e.item, e.line, e.code))
+			.collect();
+		assert_eq!(
+			found,
+			vec![
+				(
+					Some("double".to_string()),
+					2,
+					"double 2   # => 4".to_string()
+				),
+				(
+					Some("double".to_string()),
+					6,
+					"double 0\n# => 0".to_string()
+				),
+			]
+		);
+	}
+}
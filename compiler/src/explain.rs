@@ -0,0 +1,133 @@
+//! Long-form explanations for diagnostic codes, shown by `pluma explain <code>`
+//! and in the language server's hover over a diagnostic.
+//!
+//! Each code owns one Markdown file under `explain/`: a `# Title` line, prose on
+//! what went wrong and why the language rejects it, a failing example, and the
+//! fixed version. Examples are fenced as `pluma`; the failing one is tagged
+//! `pluma fails`, which the `tests/explain` suite relies on — it compiles (or
+//! lints) every example, checking that the failing one reports its code and
+//! the others don't, and that every code the compiler and linter can emit has
+//! an entry here.
+
+/// One registry entry: a stable code and its Markdown explanation.
+pub struct Explanation {
+	pub code: &'static str,
+	pub markdown: &'static str,
+}
+
+/// A fenced Pluma example from an explanation.
+pub struct Example {
+	pub source: String,
+	/// Whether this is the failing example (fenced as `pluma fails`).
+	pub fails: bool,
+}
+
+impl Explanation {
+	/// The one-line title from the leading `# ` heading.
+	pub fn title(&self) -> &'static str {
+		self
+			.markdown
+			.lines()
+			.next()
+			.and_then(|line| line.strip_prefix("# "))
+			.unwrap_or("")
+	}
+
+	/// Everything after the title line, trimmed — the prose and examples.
+	pub fn body(&self) -> &'static str {
+		match self.markdown.split_once('\n') {
+			Some((_, rest)) => rest.trim(),
+			None => "",
+		}
+	}
+
+	/// Every fenced `pluma` block, in order.
+	pub fn examples(&self) -> Vec<Example> {
+		let mut examples = Vec::new();
+		let mut current: Option<Example> = None;
+		for line in self.markdown.lines() {
+			match (&mut current, line.strip_prefix("```")) {
+				(None, Some(info)) if info.trim().starts_with("pluma") => {
+					current = Some(Example {
+						source: String::new(),
+						fails: info.split_whitespace().any(|word| word == "fails"),
+					});
+				}
+				(Some(_), Some(_)) => examples.extend(current.take()),
+				(Some(example), None) => {
+					example.source.push_str(line);
+					example.source.push('\n');
+				}
+				(None, _) => {}
+			}
+		}
+		examples
+	}
+}
+
+/// Look up a code's explanation. Matching ignores case, so `e0100` finds `E0100`.
+pub fn lookup(code: &str) -> Option<&'static Explanation> {
+	EXPLANATIONS
+		.iter()
+		.find(|e| e.code.eq_ignore_ascii_case(code.trim()))
+}
+
+/// Every explanation, sorted by code.
+pub fn all() -> &'static [Explanation] {
+	EXPLANATIONS
+}
+
+macro_rules! explanations {
+	($($code:literal),* $(,)?) => {
+		&[$(Explanation {
+			code: $code,
+			markdown: include_str!(concat!("explain/", $code, ".md")),
+		}),*]
+	};
+}
+
+const EXPLANATIONS: &[Explanation] = explanations![
+	"E0001", "E0002", "E0003", "E0004", "E0005", "E0006", "E0007", "E0008", "E0009", "E0010",
+	"E0011", "E0012", "E0013", "E0014", "E0015", "E0016", "E0017", "E0018", "E0019", "E0020",
+	"E0021", "E0022", "E0023", "E0024", "E0025", "E0026", "E0027", "E0028", "E0029", "E0030",
+	"E0031", "E0032", "E0100", "E0101", "E0102", "E0103", "E0104", "E0105", "E0106", "E0107",
+	"E0108", "E0109", "E0111", "E0112", "E0113", "E0114", "E0115", "E0116", "E0117", "E0118",
+	"E0119", "E0120", "E0121", "E0122", "E0123", "E0124", "E0125", "E0126", "E0127", "E0128",
+	"E0129", "E0130", "E0131", "E0132", "E0133", "E0134", "E0135", "L0001", "L0002", "L0003",
+	"L0004", "L0005", "L0006", "L0007", "L0008", "L0009", "L0010", "L0011", "L0012", "L0013",
+];
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn every_entry_has_a_title_and_a_failing_and_fixed_example() {
+		for e in all() {
+			assert!(!e.title().is_empty(), "{} has no `# Title` line", e.code);
+			let examples = e.examples();
+			assert_eq!(
+				examples.iter().filter(|x| x.fails).count(),
+				1,
+				"{} needs exactly one `pluma fails` example",
+				e.code
+			);
+			assert!(
+				examples.iter().any(|x| !x.fails),
+				"{} needs a fixed example",
+				e.code
+			);
+		}
+	}
+
+	#[test]
+	fn codes_are_sorted_unique_and_looked_up_case_insensitively() {
+		let codes: Vec<&str> = all().iter().map(|e| e.code).collect();
+		let mut sorted = codes.clone();
+		sorted.sort_unstable();
+		sorted.dedup();
+		assert_eq!(codes, sorted);
+		assert_eq!(lookup("e0109").map(|e| e.code), Some("E0109"));
+		assert!(lookup("E9999").is_none());
+	}
+}
@@ -1,17 +1,21 @@
 mod analyzer;
 pub mod ast;
 mod binding;
+pub mod build_cache;
 mod compiler;
 mod diagnostic;
 pub mod docs;
 mod errors;
+pub mod explain;
 pub mod highlight;
 mod location;
 mod module;
+pub mod package;
 mod parser;
 mod platform;
 mod reachability;
 mod render;
+mod report;
 pub mod rpc;
 mod stdlib;
 mod suggest;
@@ -25,6 +29,7 @@ pub use location::*;
 pub use module::{EnumExport, Module, ModuleExports, ValueConstraintExport};
 pub use platform::{Target, gate};
 pub use render::{Palette, render_diagnostics};
+pub use report::{Suggestion, diagnostic_json, diagnostics_sarif};
 pub use stdlib::{lookup_stdlib_source, stdlib_sources};
 pub use tokenizer::*;
 pub use tokens::Token;
@@ -40,3 +45,19 @@ pub const LANGUAGE_NAME: &str = "Pluma";
 pub const DEFAULT_ENTRY_MODULE_NAME: &str = "main";
 pub const DEFAULT_ENTRY_FILE: &str = "main.pa";
 pub const FILE_EXTENSION: &str = "pa";
+
+// Lay down a throwaway tree of files under the temp dir for a test, keyed by
+// `name` so parallel tests don't collide. Returns the tree's canonical root.
+// Shared with the other crates' tests through the `test-util` feature.
+#[cfg(any(test, feature = "test-util"))]
+pub fn temp_tree(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+	let dir = std::env::temp_dir().join(format!("pluma-{}-{}", name, std::process::id()));
+	let _ = std::fs::remove_dir_all(&dir);
+	std::fs::create_dir_all(&dir).unwrap();
+	for (file, contents) in files {
+		let path = dir.join(file);
+		std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+		std::fs::write(path, contents).unwrap();
+	}
+	dir.canonicalize().unwrap()
+}
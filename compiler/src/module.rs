@@ -126,7 +126,8 @@ pub struct InstanceExport {
 // the importing analyzer mints a fresh local var and substitutes it.
 // `defaults` carries the AST template for each method that has a default
 // body, so an instance in the importing module that omits a defaulted
-// method can clone it (exports are in-memory, so this is a plain clone).
+// method can clone it (exports are in-memory, so this is a plain clone; the
+// build cache doesn't persist a module whose traits have any).
 #[cfg_attr(debug_assertions, derive(Debug))]
 #[derive(Clone)]
 pub struct TraitExport {
@@ -221,10 +222,9 @@ impl Module {
 	}
 
 	fn build_ast(&mut self, bytes: Vec<u8>, diagnostics: &mut Vec<Diagnostic>) {
-		use std::hash::{Hash, Hasher};
-		let mut hasher = std::collections::hash_map::DefaultHasher::new();
-		bytes.hash(&mut hasher);
-		self.source_hash = hasher.finish();
+		let mut hasher = crate::build_cache::Fnv::new();
+		hasher.write(&bytes);
+		self.source_hash = hasher.0;
 
 		let tokenizer = Tokenizer::from_source(&bytes);
 
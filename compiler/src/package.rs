@@ -0,0 +1,699 @@
+// Package dependencies: the `dependencies` list in a project's `pluma.pa`,
+// resolved to directories on disk so `use lib/foo` finds `foo.pa` inside the
+// package declared as `lib`.
+//
+// Resolution is purely local. A `package.dep.path` points at a directory
+// (relative to the declaring package's root); a `package.dep.git` is read from
+// a clone under `<root>/.pluma/git/<name>` that the user (or CI) checks out.
+// There is no package registry yet, so `package.dep.simple`/`full` entries are
+// recorded but can't be resolved — importing one reports why.
+//
+// Every resolved package is pinned in a generated `pluma.lock` next to
+// `pluma.pa`: its source, the git revision it was checked out at, and a hash
+// of its `.pa` sources. A git checkout that drifts from its pinned revision is
+// an error (re-pin by deleting its lock entry); a path dependency is live
+// source, so its hash simply follows the directory. Only the commands that
+// build write the lockfile; type-checking alone (`pluma check`, the language
+// server) resolves the graph and leaves it be.
+
+use crate::ast::*;
+use crate::diagnostic::*;
+use crate::location::Range;
+use crate::module::Module;
+use crate::{FILE_EXTENSION, PROJECT_MARKER_FILE, PROJECT_MARKER_MODULE};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// Name of the generated lockfile, written next to `pluma.pa`.
+pub const LOCKFILE: &str = "pluma.lock";
+
+// Where git dependencies are cloned, relative to the root package.
+pub const GIT_CACHE_DIR: &str = ".pluma/git";
+
+// Where a declared dependency comes from — the `package.dep` variant it was
+// written with. `simple` and `full` both name a registry version.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum DependencySource {
+	Registry { version: String },
+	Git { url: String, branch: String },
+	Path(String),
+}
+
+impl DependencySource {
+	// The one-line form recorded in `pluma.lock` (`path ../lib`,
+	// `git https://…#main`, `registry 1.2.0`).
+	fn describe(&self) -> String {
+		match self {
+			DependencySource::Registry { version } => format!("registry {}", version),
+			DependencySource::Git { url, branch } => format!("git {}#{}", url, branch),
+			DependencySource::Path(path) => format!("path {}", path),
+		}
+	}
+}
+
+// One `(name, package.dep.…)` entry of a manifest's `dependencies` list, with
+// the range of the entry for diagnostics.
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Dependency {
+	pub name: String,
+	pub source: DependencySource,
+	pub range: Range,
+}
+
+// A dependency resolved to a directory on disk. Its modules are named
+// `<name>/<path-in-package>`; the bare `<name>` is the package's `main.pa`.
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Package {
+	pub name: String,
+	pub root: PathBuf,
+	pub source: DependencySource,
+	// The commit a git dependency's clone is checked out at. `None` for path
+	// dependencies.
+	pub revision: Option<String>,
+}
+
+// Every package reachable from the root manifest (transitively), plus the
+// names of registry dependencies that couldn't be resolved.
+#[derive(Clone, Default)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct PackageGraph {
+	pub packages: Vec<Package>,
+	pub registry: Vec<String>,
+}
+
+impl PackageGraph {
+	// Read the manifest at `root` and resolve its dependencies, recursing into
+	// each dependency's own `pluma.pa`. Only manifests are read — sources aren't
+	// hashed and `pluma.lock` isn't touched (see `lock`), so this is cheap enough
+	// for every `check()`. Problems are pushed to `diagnostics`.
+	pub fn resolve(root: &Path, diagnostics: &mut Vec<Diagnostic>) -> PackageGraph {
+		let mut graph = PackageGraph::default();
+		let mut work: Vec<PathBuf> = vec![root.to_path_buf()];
+		let mut seen_roots: Vec<PathBuf> = Vec::new();
+
+		while let Some(package_root) = work.pop() {
+			if seen_roots.contains(&package_root) {
+				continue;
+			}
+			seen_roots.push(package_root.clone());
+			let manifest_path = package_root.join(PROJECT_MARKER_FILE);
+			for dep in read_dependencies(&manifest_path) {
+				let located = |d: Diagnostic| {
+					d.with_range(dep.range)
+						.with_module(PROJECT_MARKER_MODULE.to_string(), manifest_path.clone())
+				};
+				let dir = match &dep.source {
+					DependencySource::Registry { .. } => {
+						if !graph.registry.contains(&dep.name) {
+							graph.registry.push(dep.name.clone());
+						}
+						continue;
+					}
+					DependencySource::Path(path) => package_root.join(path),
+					DependencySource::Git { url, branch } => {
+						let clone = root.join(GIT_CACHE_DIR).join(&dep.name);
+						if !clone.is_dir() {
+							diagnostics.push(
+								located(Diagnostic::error(format!(
+									"Git dependency `{}` has no local clone at `{}/{}`.",
+									dep.name, GIT_CACHE_DIR, dep.name
+								)))
+								.with_help(format!(
+									"clone it with `git clone --branch {} {} {}/{}`",
+									branch, url, GIT_CACHE_DIR, dep.name
+								)),
+							);
+							continue;
+						}
+						clone
+					}
+				};
+				let dir = match dir.canonicalize() {
+					Ok(d) if d.is_dir() => d,
+					_ => {
+						diagnostics.push(located(Diagnostic::error(format!(
+							"Dependency `{}` points at `{}`, which is not a directory.",
+							dep.name,
+							dir.display()
+						))));
+						continue;
+					}
+				};
+				if let Some(existing) = graph.packages.iter().find(|p| p.name == dep.name) {
+					if existing.root != dir {
+						diagnostics.push(located(Diagnostic::error(format!(
+							"Dependency `{}` is declared twice with different sources (`{}` and `{}`).",
+							dep.name,
+							existing.root.display(),
+							dir.display()
+						))));
+					}
+					continue;
+				}
+				let revision = match dep.source {
+					DependencySource::Git { .. } => git_revision(&dir),
+					_ => None,
+				};
+				graph.packages.push(Package {
+					name: dep.name.clone(),
+					root: dir.clone(),
+					source: dep.source,
+					revision,
+				});
+				work.push(dir);
+			}
+		}
+		graph.packages.sort_by(|a, b| a.name.cmp(&b.name));
+		graph
+	}
+
+	// Check the graph against `pluma.lock` and rewrite the lockfile if anything
+	// changed. A git checkout that moved off its pinned revision, or whose
+	// sources differ from the pinned hash, is an error. This hashes every
+	// package's sources, so it's for the commands about to build from them —
+	// not `check()`, which the language server runs on every edit. A project
+	// with no dependencies never touches the lockfile.
+	pub fn lock(&self, root: &Path, diagnostics: &mut Vec<Diagnostic>) {
+		if self.packages.is_empty() {
+			return;
+		}
+		let diag_before = diagnostics.len();
+		let hashes: Vec<String> = self
+			.packages
+			.iter()
+			.map(|p| content_hash(&p.root))
+			.collect();
+
+		let lock_path = root.join(LOCKFILE);
+		let locked = std::fs::read_to_string(&lock_path)
+			.map(|s| parse_lockfile(&s))
+			.unwrap_or_default();
+		for (package, hash) in self.packages.iter().zip(&hashes) {
+			let Some(pinned) = locked.get(&package.name) else {
+				continue;
+			};
+			if pinned.source != package.source.describe() {
+				// The manifest changed where this dependency comes from; the old
+				// pin no longer applies and is simply replaced.
+				continue;
+			}
+			if let (Some(want), Some(have)) = (&pinned.revision, &package.revision) {
+				if want != have {
+					diagnostics.push(
+						Diagnostic::error(format!(
+							"Dependency `{}` is checked out at `{}`, but `{}` pins `{}`.",
+							package.name,
+							short_revision(have),
+							LOCKFILE,
+							short_revision(want)
+						))
+						.with_help(format!(
+							"check out `{}` in `{}`, or delete the `[{}]` entry from `{}` to re-pin it",
+							want,
+							package.root.display(),
+							package.name,
+							LOCKFILE
+						)),
+					);
+					continue;
+				}
+				if pinned.hash != *hash {
+					diagnostics.push(Diagnostic::error(format!(
+						"Dependency `{}` has local modifications: its sources don't match the hash pinned in `{}`.",
+						package.name, LOCKFILE
+					)));
+				}
+			}
+		}
+
+		if diagnostics.len() == diag_before {
+			let rendered = render_lockfile(&self.packages, &hashes);
+			let current = std::fs::read_to_string(&lock_path).ok();
+			if current.as_deref() != Some(rendered.as_str())
+				&& let Err(err) = std::fs::write(&lock_path, rendered)
+			{
+				diagnostics.push(Diagnostic::warning(format!(
+					"Could not write `{}`: {}",
+					lock_path.display(),
+					err
+				)));
+			}
+		}
+	}
+
+	// The package that owns `module_name` — the one whose name is the module
+	// name's first `/` segment.
+	pub fn package_for(&self, module_name: &str) -> Option<&Package> {
+		let head = module_name.split('/').next()?;
+		self.packages.iter().find(|p| p.name == head)
+	}
+
+	// Whether `module_name` lives in a declared registry dependency, which
+	// can't be resolved until there is a registry to fetch it from.
+	pub fn is_registry_module(&self, module_name: &str) -> bool {
+		let head = module_name.split('/').next().unwrap_or(module_name);
+		self.registry.iter().any(|r| r == head)
+	}
+
+	// The file a package module lives in: `lib` is the package's `main.pa`,
+	// `lib/sub/foo` is `sub/foo.pa` under its root. `None` for modules that
+	// don't belong to a package.
+	pub fn module_path(&self, module_name: &str) -> Option<PathBuf> {
+		let package = self.package_for(module_name)?;
+		match module_name.split_once('/') {
+			Some((_, rest)) => Some(crate::to_module_path(&package.root, rest)),
+			None => Some(
+				package
+					.root
+					.join(crate::DEFAULT_ENTRY_MODULE_NAME)
+					.with_extension(FILE_EXTENSION),
+			),
+		}
+	}
+
+	// The fully-qualified module a `use <written>` in `importer` refers to.
+	// Inside a package, a use that names neither the stdlib nor another
+	// package is a sibling in the same package (`use helpers` from `lib/foo`
+	// is `lib/helpers`), so a library's own imports read the same whether it's
+	// compiled standalone or as a dependency.
+	pub fn import_target(&self, importer: &str, written: &str) -> String {
+		let Some(package) = self.package_for(importer) else {
+			return written.to_string();
+		};
+		let head = written.split('/').next().unwrap_or(written);
+		if head == "std" || self.package_for(written).is_some() || self.is_registry_module(written) {
+			return written.to_string();
+		}
+		format!("{}/{}", package.name, written)
+	}
+}
+
+// Extract the `dependencies` of the `def package` record in the manifest at
+// `path`. Parse errors are dropped here — the manifest is also loaded as an
+// ordinary module, which reports them — and entries whose shape isn't a
+// literal `(name, package.dep.<kind> …)` are skipped, since the analyzer
+// already type-checks the manifest against `package.info`.
+pub fn read_dependencies(path: &Path) -> Vec<Dependency> {
+	if !path.is_file() {
+		return Vec::new();
+	}
+	let mut module = Module::new(PROJECT_MARKER_MODULE.to_string(), path.to_path_buf());
+	module.parse(&mut Vec::new());
+	let Some(ast) = module.ast.as_ref() else {
+		return Vec::new();
+	};
+
+	let mut out = Vec::new();
+	for def in &ast.body {
+		let DefinitionKind::Expr(expr) = &def.kind else {
+			continue;
+		};
+		if def.name.name != "package" {
+			continue;
+		}
+		let ExprKind::Record(fields) = &ungroup(expr).kind else {
+			continue;
+		};
+		let Some((_, deps)) = fields.iter().find(|(f, _)| f.name == "dependencies") else {
+			continue;
+		};
+		let ExprKind::List(items) = &ungroup(deps).kind else {
+			continue;
+		};
+		for item in items {
+			let ListItem::Item(entry) = item else {
+				continue;
+			};
+			if let Some(dep) = dependency_entry(entry) {
+				out.push(dep);
+			}
+		}
+	}
+	out
+}
+
+// One `("name", package.dep.<kind> arg)` tuple.
+fn dependency_entry(entry: &ExprNode) -> Option<Dependency> {
+	let ExprKind::Tuple(parts) = &ungroup(entry).kind else {
+		return None;
+	};
+	let [name, dep] = parts.as_slice() else {
+		return None;
+	};
+	let name = string_literal(name)?;
+	let ExprKind::Call(call) = &ungroup(dep).kind else {
+		return None;
+	};
+	let ExprKind::FieldAccess { field, .. } = &ungroup(&call.callee).kind else {
+		return None;
+	};
+	let arg = ungroup(call.args.first()?);
+	let source = match field.name.as_str() {
+		"simple" => DependencySource::Registry {
+			version: string_literal(arg)?,
+		},
+		"full" => DependencySource::Registry {
+			version: string_literal(record_field(arg, "version")?)?,
+		},
+		"git" => DependencySource::Git {
+			url: string_literal(record_field(arg, "url")?)?,
+			branch: string_literal(record_field(arg, "branch")?)?,
+		},
+		"path" => DependencySource::Path(string_literal(arg)?),
+		_ => return None,
+	};
+	Some(Dependency {
+		name,
+		source,
+		range: entry.range,
+	})
+}
+
+fn ungroup(expr: &ExprNode) -> &ExprNode {
+	match &expr.kind {
+		ExprKind::Grouping(inner) => ungroup(inner),
+		_ => expr,
+	}
+}
+
+fn string_literal(expr: &ExprNode) -> Option<String> {
+	match &ungroup(expr).kind {
+		ExprKind::Literal(LiteralNode {
+			kind: LiteralKind::String(s, _),
+			..
+		}) => Some(s.clone()),
+		_ => None,
+	}
+}
+
+fn record_field<'a>(expr: &'a ExprNode, name: &str) -> Option<&'a ExprNode> {
+	match &ungroup(expr).kind {
+		ExprKind::Record(fields) => fields.iter().find(|(f, _)| f.name == name).map(|(_, v)| v),
+		_ => None,
+	}
+}
+
+// The commit a clone's `HEAD` points at, read straight from `.git` (a loose
+// ref, then `packed-refs`) so resolution doesn't shell out to `git`.
+fn git_revision(clone: &Path) -> Option<String> {
+	let git_dir = clone.join(".git");
+	let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+	let head = head.trim();
+	let Some(reference) = head.strip_prefix("ref: ") else {
+		return Some(head.to_string());
+	};
+	if let Ok(rev) = std::fs::read_to_string(git_dir.join(reference)) {
+		return Some(rev.trim().to_string());
+	}
+	let packed = std::fs::read_to_string(git_dir.join("packed-refs")).ok()?;
+	packed.lines().find_map(|line| {
+		let (rev, name) = line.split_once(' ')?;
+		(name == reference).then(|| rev.to_string())
+	})
+}
+
+fn short_revision(rev: &str) -> &str {
+	&rev[..rev.len().min(12)]
+}
+
+// A stable hash of every `.pa` file under `dir` (path and contents, in sorted
+// path order), skipping hidden directories. FNV-1a rather than the std
+// `DefaultHasher`, whose output isn't guaranteed across Rust releases — the
+// lockfile has to read the same on every toolchain.
+pub fn content_hash(dir: &Path) -> String {
+	fn walk(dir: &Path, root: &Path, out: &mut Vec<(String, PathBuf)>) {
+		let Ok(entries) = std::fs::read_dir(dir) else {
+			return;
+		};
+		for entry in entries.flatten() {
+			let path = entry.path();
+			let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+				continue;
+			};
+			if name.starts_with('.') {
+				continue;
+			}
+			if path.is_dir() {
+				walk(&path, root, out);
+			} else if name.ends_with(&format!(".{}", FILE_EXTENSION)) {
+				let rel = path.strip_prefix(root).unwrap_or(&path);
+				let rel = rel
+					.to_string_lossy()
+					.replace(std::path::MAIN_SEPARATOR, "/");
+				out.push((rel, path));
+			}
+		}
+	}
+
+	let mut files = Vec::new();
+	walk(dir, dir, &mut files);
+	files.sort();
+
+	let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+	let mut feed = |bytes: &[u8]| {
+		for &b in bytes {
+			hash ^= b as u64;
+			hash = hash.wrapping_mul(0x0100_0000_01b3);
+		}
+	};
+	for (rel, path) in files {
+		feed(rel.as_bytes());
+		feed(&[0]);
+		feed(&std::fs::read(path).unwrap_or_default());
+		feed(&[0]);
+	}
+	format!("fnv1a64:{:016x}", hash)
+}
+
+// One `[name]` section of `pluma.lock`.
+#[derive(Default)]
+struct LockEntry {
+	source: String,
+	revision: Option<String>,
+	hash: String,
+}
+
+fn render_lockfile(packages: &[Package], hashes: &[String]) -> String {
+	let mut out = String::from(
+		"# Generated by `pluma` from the dependencies in `pluma.pa`. It pins each\n\
+		 # package's exact source; commit it, but don't edit it by hand.\n",
+	);
+	for (package, hash) in packages.iter().zip(hashes) {
+		out.push_str(&format!("\n[{}]\n", package.name));
+		out.push_str(&format!("source = \"{}\"\n", package.source.describe()));
+		if let Some(rev) = &package.revision {
+			out.push_str(&format!("revision = \"{}\"\n", rev));
+		}
+		out.push_str(&format!("hash = \"{}\"\n", hash));
+	}
+	out
+}
+
+fn parse_lockfile(text: &str) -> HashMap<String, LockEntry> {
+	let mut entries: HashMap<String, LockEntry> = HashMap::new();
+	let mut current: Option<String> = None;
+	for line in text.lines() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+			entries.insert(name.to_string(), LockEntry::default());
+			current = Some(name.to_string());
+			continue;
+		}
+		let (Some(name), Some((key, value))) = (&current, line.split_once('=')) else {
+			continue;
+		};
+		let value = value.trim().trim_matches('"').to_string();
+		let entry = entries.get_mut(name).unwrap();
+		match key.trim() {
+			"source" => entry.source = value,
+			"revision" => entry.revision = Some(value),
+			"hash" => entry.hash = value,
+			_ => {}
+		}
+	}
+	entries
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Compiler, temp_tree};
+
+	fn manifest(deps: &str) -> String {
+		format!(
+			"use std/package\n\n\
+			public def package :: package.info = {{\n\
+			\tname: \"app\",\n\
+			\tversion: \"0.1.0\",\n\
+			\tauthors: [],\n\
+			\tdescription: \"\",\n\
+			\tlicense: \"\",\n\
+			\trepository: \"\",\n\
+			\tdependencies: [{}],\n\
+			}}\n",
+			deps
+		)
+	}
+
+	// What a building command does: lock the dependencies, then check.
+	fn check_app(root: &Path) -> Vec<Diagnostic> {
+		let mut compiler = Compiler::for_root_dir(root.join("app"));
+		compiler.add_entry_module("main".to_string());
+		let mut diags = compiler.lock_packages().err().unwrap_or_default();
+		diags.extend(compiler.check().err().unwrap_or_default());
+		diags
+	}
+
+	#[test]
+	fn reads_every_dependency_kind() {
+		let deps = "(\"json\", package.dep.simple \"1.2.0\"), \
+			(\"ui\", package.dep.full {version: \"2.0.0\", features: [\"icons\"]}), \
+			(\"lib\", package.dep.git {url: \"https://example.com/lib\", branch: \"main\"}), \
+			(\"local\", package.dep.path \"../local\")";
+		let root = temp_tree("package-kinds", &[("pluma.pa", &manifest(deps))]);
+		let deps = read_dependencies(&root.join("pluma.pa"));
+		let sources: Vec<(&str, &DependencySource)> =
+			deps.iter().map(|d| (d.name.as_str(), &d.source)).collect();
+		assert_eq!(
+			sources,
+			vec![
+				(
+					"json",
+					&DependencySource::Registry {
+						version: "1.2.0".into()
+					}
+				),
+				(
+					"ui",
+					&DependencySource::Registry {
+						version: "2.0.0".into()
+					}
+				),
+				(
+					"lib",
+					&DependencySource::Git {
+						url: "https://example.com/lib".into(),
+						branch: "main".into()
+					}
+				),
+				("local", &DependencySource::Path("../local".into())),
+			]
+		);
+	}
+
+	#[test]
+	fn path_dependency_resolves_and_is_locked() {
+		let root = temp_tree(
+			"package-path",
+			&[
+				(
+					"app/pluma.pa",
+					&manifest("(\"lib\", package.dep.path \"../lib\")"),
+				),
+				(
+					"app/main.pa",
+					"use lib/greet\n\ndef main = fun {\n\tprint (greet.hello \"you\")\n}\n",
+				),
+				(
+					"lib/greet.pa",
+					"use words\n\npublic def hello :: fun string -> string = fun name {\n\t\"$(words.hi) $(name)\"\n}\n",
+				),
+				("lib/words.pa", "public def hi :: string = \"hello\"\n"),
+			],
+		);
+		let diags = check_app(&root);
+		assert!(
+			diags.iter().all(|d| !d.is_error()),
+			"expected the path dependency to resolve, got: {:?}",
+			diags.iter().map(|d| &d.message).collect::<Vec<_>>()
+		);
+		let lock = std::fs::read_to_string(root.join("app").join(LOCKFILE)).unwrap();
+		assert!(lock.contains("[lib]\nsource = \"path ../lib\"\nhash = \"fnv1a64:"));
+
+		// A plain `check()` (the language server's) resolves the dependency but
+		// leaves the lockfile alone.
+		std::fs::remove_file(root.join("app").join(LOCKFILE)).unwrap();
+		let mut compiler = Compiler::for_root_dir(root.join("app"));
+		compiler.add_entry_module("main".to_string());
+		let diags = compiler.check().err().unwrap_or_default();
+		assert!(diags.iter().all(|d| !d.is_error()));
+		assert!(!root.join("app").join(LOCKFILE).exists());
+	}
+
+	#[test]
+	fn git_dependency_drifting_from_its_pin_is_rejected() {
+		let rev = "0123456789abcdef0123456789abcdef01234567";
+		let root = temp_tree(
+			"package-git",
+			&[
+				(
+					"app/pluma.pa",
+					&manifest(
+						"(\"lib\", package.dep.git {url: \"https://example.com/lib\", branch: \"main\"})",
+					),
+				),
+				(
+					"app/main.pa",
+					"use lib\n\ndef main = fun {\n\tprint lib.name\n}\n",
+				),
+				("app/.pluma/git/lib/.git/HEAD", "ref: refs/heads/main\n"),
+				(
+					"app/.pluma/git/lib/.git/refs/heads/main",
+					&format!("{}\n", rev),
+				),
+				(
+					"app/.pluma/git/lib/main.pa",
+					"public def name :: string = \"lib\"\n",
+				),
+			],
+		);
+		assert!(check_app(&root).iter().all(|d| !d.is_error()));
+		let lock = std::fs::read_to_string(root.join("app").join(LOCKFILE)).unwrap();
+		assert!(lock.contains(&format!("revision = \"{}\"", rev)));
+
+		let moved = "fedcba9876543210fedcba9876543210fedcba98";
+		std::fs::write(
+			root.join("app/.pluma/git/lib/.git/refs/heads/main"),
+			format!("{}\n", moved),
+		)
+		.unwrap();
+		let diags = check_app(&root);
+		assert!(
+			diags
+				.iter()
+				.any(|d| d.is_error() && d.message.contains("pins `0123456789ab`")),
+			"expected a revision-drift error, got: {:?}",
+			diags.iter().map(|d| &d.message).collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	fn registry_dependency_use_is_reported() {
+		let root = temp_tree(
+			"package-registry",
+			&[
+				(
+					"app/pluma.pa",
+					&manifest("(\"json\", package.dep.simple \"1.2.0\")"),
+				),
+				(
+					"app/main.pa",
+					"use json/parse\n\ndef main = fun {\n\t()\n}\n",
+				),
+			],
+		);
+		let diags = check_app(&root);
+		assert!(
+			diags
+				.iter()
+				.any(|d| d.message.contains("`json`") && d.message.contains("registry")),
+			"expected a registry-dependency error, got: {:?}",
+			diags.iter().map(|d| &d.message).collect::<Vec<_>>()
+		);
+	}
+}
@@ -0,0 +1,358 @@
+// Machine-readable diagnostic output — the counterpart to `render.rs` for
+// consumers that aren't a human at a terminal (CI annotators, editors other
+// than our LSP). Two shapes, both hand-written JSON like the doc artifact in
+// `docs.rs`:
+//
+//   - `diagnostic_json`: one self-contained object per diagnostic, printed one
+//     per line (JSON Lines), so a consumer can stream them as they arrive.
+//   - `diagnostics_sarif`: a single SARIF 2.1.0 log covering a whole run, the
+//     format code-scanning UIs ingest to annotate pull requests.
+//
+// Positions are 1-based in both (the same numbers the rendered `file:line:col`
+// footer shows); columns count bytes within the line, as the tokenizer does.
+
+use crate::diagnostic::{Diagnostic, DiagnosticKind};
+use crate::docs::push_json_str;
+use crate::location::Range;
+use std::collections::BTreeSet;
+
+// A suggested edit: replace the source spanning `range` with `replacement`. The
+// linter's autofixes travel to the serializers in this shape; compiler
+// diagnostics carry none.
+#[derive(Clone)]
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Suggestion {
+	pub range: Range,
+	pub replacement: String,
+}
+
+// Serialize one diagnostic (plus any suggested edits) as a single-line JSON
+// object:
+//
+//   {"type":"diagnostic","severity":"warning","code":"L0001","message":…,
+//    "file":"app/main.pa","module":"main","range":{"start":{"line":3,"column":5},
+//    "end":{…}},"help":…,"notes":[…],"labels":[{"range":…,"message":…}],
+//    "suggestions":[{"range":…,"replacement":…}]}
+//
+// `code`, `file`, `module`, `range`, and `help` are `null` when absent. The
+// `type` tag lets a consumer pick diagnostics out of a stream that also carries
+// other output (e.g. a test run's report).
+pub fn diagnostic_json(diagnostic: &Diagnostic, suggestions: &[Suggestion]) -> String {
+	let mut out = String::new();
+	out.push_str("{\"type\":\"diagnostic\",\"severity\":");
+	push_json_str(&mut out, severity(&diagnostic.kind));
+	out.push_str(",\"code\":");
+	push_opt_str(&mut out, diagnostic.code);
+	out.push_str(",\"message\":");
+	push_json_str(&mut out, &diagnostic.message);
+	out.push_str(",\"file\":");
+	push_opt_str(&mut out, file_uri(diagnostic).as_deref());
+	out.push_str(",\"module\":");
+	push_opt_str(&mut out, diagnostic.module_name.as_deref());
+	out.push_str(",\"range\":");
+	match &diagnostic.range {
+		Some(range) => push_range(&mut out, range),
+		None => out.push_str("null"),
+	}
+	out.push_str(",\"help\":");
+	push_opt_str(&mut out, diagnostic.help.as_deref());
+	out.push_str(",\"notes\":[");
+	for (i, note) in diagnostic.notes.iter().enumerate() {
+		if i > 0 {
+			out.push(',');
+		}
+		push_json_str(&mut out, note);
+	}
+	out.push_str("],\"labels\":[");
+	for (i, label) in diagnostic.labels.iter().enumerate() {
+		if i > 0 {
+			out.push(',');
+		}
+		out.push_str("{\"range\":");
+		push_range(&mut out, &label.range);
+		out.push_str(",\"message\":");
+		push_json_str(&mut out, &label.message);
+		out.push('}');
+	}
+	out.push_str("],\"suggestions\":[");
+	for (i, s) in suggestions.iter().enumerate() {
+		if i > 0 {
+			out.push(',');
+		}
+		out.push_str("{\"range\":");
+		push_range(&mut out, &s.range);
+		out.push_str(",\"replacement\":");
+		push_json_str(&mut out, &s.replacement);
+		out.push('}');
+	}
+	out.push_str("]}");
+	out
+}
+
+// Serialize a whole run's diagnostics as one SARIF 2.1.0 log with a single run
+// whose driver is `pluma` at `version`. Each distinct code becomes a rule;
+// diagnostics without a code report under the catch-all `pluma`. Help and notes
+// fold into the result's message text (SARIF has no separate slots a viewer
+// reliably shows), labels become `relatedLocations`, and suggestions become a
+// `fixes` entry of replacements in the diagnostic's own file.
+pub fn diagnostics_sarif(results: &[(Diagnostic, Vec<Suggestion>)], version: &str) -> String {
+	let rules: BTreeSet<&str> = results.iter().map(|(d, _)| rule_id(d)).collect();
+
+	let mut out = String::new();
+	out.push_str(
+		"{\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"version\":\"2.1.0\",",
+	);
+	out.push_str("\"runs\":[{\"tool\":{\"driver\":{\"name\":\"pluma\",\"version\":");
+	push_json_str(&mut out, version);
+	out.push_str(",\"rules\":[");
+	for (i, rule) in rules.iter().enumerate() {
+		if i > 0 {
+			out.push(',');
+		}
+		out.push_str("{\"id\":");
+		push_json_str(&mut out, rule);
+		out.push('}');
+	}
+	out.push_str("]}},\"results\":[");
+	for (i, (diagnostic, suggestions)) in results.iter().enumerate() {
+		if i > 0 {
+			out.push(',');
+		}
+		push_sarif_result(&mut out, diagnostic, suggestions);
+	}
+	out.push_str("]}]}");
+	out
+}
+
+fn push_sarif_result(out: &mut String, diagnostic: &Diagnostic, suggestions: &[Suggestion]) {
+	let uri = file_uri(diagnostic);
+
+	out.push_str("{\"ruleId\":");
+	push_json_str(out, rule_id(diagnostic));
+	out.push_str(",\"level\":");
+	push_json_str(out, severity(&diagnostic.kind));
+
+	let mut text = diagnostic.message.clone();
+	if let Some(help) = &diagnostic.help {
+		text.push_str(&format!("\nhelp: {help}"));
+	}
+	for note in &diagnostic.notes {
+		text.push_str(&format!("\nnote: {note}"));
+	}
+	out.push_str(",\"message\":{\"text\":");
+	push_json_str(out, &text);
+	out.push('}');
+
+	// SARIF wants a location per result, but a path-less diagnostic (a CLI usage
+	// error) has nowhere to point — it's reported with an empty list instead.
+	out.push_str(",\"locations\":[");
+	if let Some(uri) = &uri {
+		push_sarif_location(out, uri, diagnostic.range.as_ref());
+	}
+	out.push(']');
+
+	if let Some(uri) = &uri
+		&& !diagnostic.labels.is_empty()
+	{
+		out.push_str(",\"relatedLocations\":[");
+		for (i, label) in diagnostic.labels.iter().enumerate() {
+			if i > 0 {
+				out.push(',');
+			}
+			out.push_str(&format!("{{\"id\":{i},\"message\":{{\"text\":"));
+			push_json_str(out, &label.message);
+			out.push_str("},\"physicalLocation\":");
+			push_physical_location(out, uri, Some(&label.range));
+			out.push('}');
+		}
+		out.push(']');
+	}
+
+	if let Some(uri) = &uri
+		&& !suggestions.is_empty()
+	{
+		out.push_str(",\"fixes\":[{\"description\":{\"text\":");
+		push_json_str(
+			out,
+			diagnostic
+				.help
+				.as_deref()
+				.unwrap_or("apply the suggested edit"),
+		);
+		out.push_str("},\"artifactChanges\":[{\"artifactLocation\":{\"uri\":");
+		push_json_str(out, uri);
+		out.push_str("},\"replacements\":[");
+		for (i, s) in suggestions.iter().enumerate() {
+			if i > 0 {
+				out.push(',');
+			}
+			out.push_str("{\"deletedRegion\":");
+			push_region(out, &s.range);
+			out.push_str(",\"insertedContent\":{\"text\":");
+			push_json_str(out, &s.replacement);
+			out.push_str("}}");
+		}
+		out.push_str("]}]}]");
+	}
+
+	out.push('}');
+}
+
+fn push_sarif_location(out: &mut String, uri: &str, range: Option<&Range>) {
+	out.push_str("{\"physicalLocation\":");
+	push_physical_location(out, uri, range);
+	out.push('}');
+}
+
+fn push_physical_location(out: &mut String, uri: &str, range: Option<&Range>) {
+	out.push_str("{\"artifactLocation\":{\"uri\":");
+	push_json_str(out, uri);
+	out.push('}');
+	if let Some(range) = range {
+		out.push_str(",\"region\":");
+		push_region(out, range);
+	}
+	out.push('}');
+}
+
+fn push_region(out: &mut String, range: &Range) {
+	out.push_str(&format!(
+		"{{\"startLine\":{},\"startColumn\":{},\"endLine\":{},\"endColumn\":{}}}",
+		range.start.line + 1,
+		range.start.col + 1,
+		range.end.line + 1,
+		range.end.col + 1
+	));
+}
+
+fn push_range(out: &mut String, range: &Range) {
+	out.push_str(&format!(
+		"{{\"start\":{{\"line\":{},\"column\":{}}},\"end\":{{\"line\":{},\"column\":{}}}}}",
+		range.start.line + 1,
+		range.start.col + 1,
+		range.end.line + 1,
+		range.end.col + 1
+	));
+}
+
+fn push_opt_str(out: &mut String, s: Option<&str>) {
+	match s {
+		Some(s) => push_json_str(out, s),
+		None => out.push_str("null"),
+	}
+}
+
+fn severity(kind: &DiagnosticKind) -> &'static str {
+	match kind {
+		DiagnosticKind::Error => "error",
+		DiagnosticKind::Warning => "warning",
+	}
+}
+
+fn rule_id(diagnostic: &Diagnostic) -> &'static str {
+	diagnostic.code.unwrap_or("pluma")
+}
+
+// The diagnostic's file as a `/`-separated path, the form both JSON consumers
+// and SARIF's relative `uri`s expect.
+fn file_uri(diagnostic: &Diagnostic) -> Option<String> {
+	let path = diagnostic.module_path.as_ref()?;
+	Some(
+		path
+			.to_string_lossy()
+			.replace(std::path::MAIN_SEPARATOR, "/"),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::diagnostic::Label;
+	use crate::location::Point;
+	use std::path::PathBuf;
+
+	fn range(line: usize, col: usize, end_col: usize) -> Range {
+		Range {
+			start: Point { line, col },
+			end: Point { line, col: end_col },
+		}
+	}
+
+	fn lint_warning() -> Diagnostic {
+		let mut d = Diagnostic::warning("`let _ = x` binds nothing")
+			.with_range(range(2, 1, 12))
+			.with_help("drop the `let _ =`")
+			.with_module("main".to_string(), PathBuf::from("app/main.pa"));
+		d.code = Some("L0001");
+		d
+	}
+
+	#[test]
+	fn json_carries_every_field_one_based() {
+		let d = lint_warning().with_label(Label {
+			range: range(0, 0, 3),
+			message: "bound \"here\"".to_string(),
+		});
+		let fix = Suggestion {
+			range: range(2, 1, 9),
+			replacement: String::new(),
+		};
+		assert_eq!(
+			diagnostic_json(&d, &[fix]),
+			"{\"type\":\"diagnostic\",\"severity\":\"warning\",\"code\":\"L0001\",\
+			 \"message\":\"`let _ = x` binds nothing\",\"file\":\"app/main.pa\",\
+			 \"module\":\"main\",\"range\":{\"start\":{\"line\":3,\"column\":2},\
+			 \"end\":{\"line\":3,\"column\":13}},\"help\":\"drop the `let _ =`\",\
+			 \"notes\":[],\"labels\":[{\"range\":{\"start\":{\"line\":1,\"column\":1},\
+			 \"end\":{\"line\":1,\"column\":4}},\"message\":\"bound \\\"here\\\"\"}],\
+			 \"suggestions\":[{\"range\":{\"start\":{\"line\":3,\"column\":2},\
+			 \"end\":{\"line\":3,\"column\":10}},\"replacement\":\"\"}]}"
+		);
+	}
+
+	#[test]
+	fn json_nulls_absent_fields() {
+		let d = Diagnostic::error("No path given.");
+		assert_eq!(
+			diagnostic_json(&d, &[]),
+			"{\"type\":\"diagnostic\",\"severity\":\"error\",\"code\":null,\
+			 \"message\":\"No path given.\",\"file\":null,\"module\":null,\"range\":null,\
+			 \"help\":null,\"notes\":[],\"labels\":[],\"suggestions\":[]}"
+		);
+	}
+
+	#[test]
+	fn sarif_lists_rules_results_and_fixes() {
+		let fix = Suggestion {
+			range: range(2, 1, 9),
+			replacement: String::new(),
+		};
+		let results = vec![
+			(lint_warning(), vec![fix]),
+			(Diagnostic::error("No path given."), Vec::new()),
+		];
+		let sarif = diagnostics_sarif(&results, "0.1.0");
+
+		assert!(sarif.starts_with(
+			"{\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"version\":\"2.1.0\""
+		));
+		assert!(sarif.contains(
+			"\"driver\":{\"name\":\"pluma\",\"version\":\"0.1.0\",\
+			 \"rules\":[{\"id\":\"L0001\"},{\"id\":\"pluma\"}]}"
+		));
+		assert!(sarif.contains(
+			"{\"ruleId\":\"L0001\",\"level\":\"warning\",\
+			 \"message\":{\"text\":\"`let _ = x` binds nothing\\nhelp: drop the `let _ =`\"},\
+			 \"locations\":[{\"physicalLocation\":{\"artifactLocation\":{\"uri\":\"app/main.pa\"},\
+			 \"region\":{\"startLine\":3,\"startColumn\":2,\"endLine\":3,\"endColumn\":13}}}]"
+		));
+		assert!(sarif.contains(
+			"\"replacements\":[{\"deletedRegion\":{\"startLine\":3,\"startColumn\":2,\
+			 \"endLine\":3,\"endColumn\":10},\"insertedContent\":{\"text\":\"\"}}]"
+		));
+		assert!(sarif.contains(
+			"{\"ruleId\":\"pluma\",\"level\":\"error\",\"message\":{\"text\":\"No path given.\"},\
+			 \"locations\":[]}"
+		));
+	}
+}
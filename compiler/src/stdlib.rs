@@ -10,6 +10,7 @@ pub fn stdlib_sources() -> &'static [(&'static str, &'static str)] {
 	&[
 		("std/assert", include_str!("../../std/assert.pa")),
 		("std/base64", include_str!("../../std/base64.pa")),
+		("std/bench", include_str!("../../std/bench.pa")),
 		("std/bit", include_str!("../../std/bit.pa")),
 		("std/bytes", include_str!("../../std/bytes.pa")),
 		("std/css", include_str!("../../std/css.pa")),
@@ -45,6 +46,14 @@ pub fn stdlib_sources() -> &'static [(&'static str, &'static str)] {
 		("std/syntax", include_str!("../../std/syntax.pa")),
 		("std/task", include_str!("../../std/task.pa")),
 		("std/test", include_str!("../../std/test.pa")),
+		(
+			"std/test/browser",
+			include_str!("../../std/test/browser.pa"),
+		),
+		(
+			"std/test/property",
+			include_str!("../../std/test/property.pa"),
+		),
 		("std/time", include_str!("../../std/time.pa")),
 		("std/uuid", include_str!("../../std/uuid.pa")),
 		("std/sys/app", include_str!("../../std/sys/app.pa")),
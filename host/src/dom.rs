@@ -0,0 +1,663 @@
+// The headless DOM: what `std/web/dom` builds when a program runs under this host
+// (`pluma test`, `pluma run`) instead of in a browser. The same import set the browser
+// loader backs with the live `document` — create, append, attributes, properties, text,
+// listeners, location — lands on a plain node arena here, so a component's DOM and its
+// signal wiring can be exercised without a browser. `std/test/browser` reads the tree
+// back (as HTML, as text, by selector) and fires events into it.
+//
+// Engine-independent: a node or an event is an index into this arena. The V8 glue
+// (`v8host::dom`) hands indexes across as `externref` numbers and runs the wasm-side
+// handlers a dispatched event reaches.
+
+use std::collections::HashMap;
+
+/// The page's `<body>`, there from the start like the browser's.
+pub(crate) const BODY: usize = 0;
+/// The page's `window` — where `popstate` listeners hang. Not part of the tree.
+pub(crate) const WINDOW: usize = 1;
+
+/// Elements that never have children or a closing tag in HTML.
+const VOID_ELEMENTS: &[&str] = &[
+	"area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+	"wbr",
+];
+
+pub(crate) struct HeadlessDom {
+	nodes: Vec<Node>,
+	events: Vec<Event>,
+	/// `location.pathname` / `.search` / `.hash`, moved by `push-state`/`replace-state`.
+	path: String,
+	search: String,
+	hash: String,
+	/// The dev store (`localStorage` in the browser).
+	store: HashMap<String, String>,
+}
+
+struct Node {
+	kind: Kind,
+	parent: Option<usize>,
+	children: Vec<usize>,
+	/// `(event name, handler token)`, in the order added.
+	listeners: Vec<(String, i32)>,
+}
+
+enum Kind {
+	Element(Element),
+	Text(String),
+	Window,
+}
+
+#[derive(Default)]
+struct Element {
+	tag: String,
+	/// In the order first set, which is the order they serialize in.
+	attrs: Vec<(String, String)>,
+	/// DOM properties (`node[name] = value`) — live state like an input's `value`,
+	/// kept apart from the attributes exactly as the browser does.
+	props: HashMap<String, Prop>,
+}
+
+enum Prop {
+	Str(String),
+	Bool(bool),
+}
+
+struct Event {
+	target: usize,
+	default_prevented: bool,
+}
+
+impl Default for HeadlessDom {
+	fn default() -> Self {
+		let body = Element {
+			tag: "body".to_string(),
+			..Element::default()
+		};
+		HeadlessDom {
+			nodes: vec![Node::new(Kind::Element(body)), Node::new(Kind::Window)],
+			events: Vec::new(),
+			path: "/".to_string(),
+			search: String::new(),
+			hash: String::new(),
+			store: HashMap::new(),
+		}
+	}
+}
+
+impl Node {
+	fn new(kind: Kind) -> Self {
+		Node {
+			kind,
+			parent: None,
+			children: Vec::new(),
+			listeners: Vec::new(),
+		}
+	}
+}
+
+impl HeadlessDom {
+	pub(crate) fn create_element(&mut self, tag: &str) -> usize {
+		let element = Element {
+			tag: tag.to_ascii_lowercase(),
+			..Element::default()
+		};
+		self.push(Kind::Element(element))
+	}
+
+	pub(crate) fn create_text(&mut self, text: &str) -> usize {
+		self.push(Kind::Text(text.to_string()))
+	}
+
+	fn push(&mut self, kind: Kind) -> usize {
+		self.nodes.push(Node::new(kind));
+		self.nodes.len() - 1
+	}
+
+	fn valid(&self, node: usize) -> bool {
+		node < self.nodes.len()
+	}
+
+	/// Take `node` out of its parent's children, if it has a parent.
+	fn detach(&mut self, node: usize) {
+		if let Some(parent) = self.nodes[node].parent.take() {
+			self.nodes[parent].children.retain(|&c| c != node);
+		}
+	}
+
+	/// Whether `node` is `ancestor` or sits somewhere below it.
+	fn is_within(&self, mut node: usize, ancestor: usize) -> bool {
+		loop {
+			if node == ancestor {
+				return true;
+			}
+			match self.nodes[node].parent {
+				Some(parent) => node = parent,
+				None => return false,
+			}
+		}
+	}
+
+	/// Whether `child` can go under `parent`: both real, the parent an element, and no
+	/// cycle. The browser throws on the rest; here the op is dropped.
+	fn can_adopt(&self, parent: usize, child: usize) -> bool {
+		self.valid(parent)
+			&& self.valid(child)
+			&& matches!(self.nodes[parent].kind, Kind::Element(_))
+			&& !matches!(self.nodes[child].kind, Kind::Window)
+			&& !self.is_within(parent, child)
+	}
+
+	/// `parent.appendChild(child)`: moves `child` if it's already in the tree.
+	pub(crate) fn append_child(&mut self, parent: usize, child: usize) {
+		if !self.can_adopt(parent, child) {
+			return;
+		}
+		self.detach(child);
+		self.nodes[parent].children.push(child);
+		self.nodes[child].parent = Some(parent);
+	}
+
+	/// `parent.insertBefore(node, before)`; appends when `before` isn't a child of
+	/// `parent`.
+	pub(crate) fn insert_before(&mut self, parent: usize, node: usize, before: usize) {
+		if !self.can_adopt(parent, node) {
+			return;
+		}
+		self.detach(node);
+		let children = &mut self.nodes[parent].children;
+		let at = children
+			.iter()
+			.position(|&c| c == before)
+			.unwrap_or(children.len());
+		children.insert(at, node);
+		self.nodes[node].parent = Some(parent);
+	}
+
+	pub(crate) fn remove_child(&mut self, parent: usize, child: usize) {
+		if self.valid(child) && self.nodes[child].parent == Some(parent) {
+			self.detach(child);
+		}
+	}
+
+	/// `parent.replaceChild(node, old)`: `node` takes `old`'s place.
+	pub(crate) fn replace_child(&mut self, parent: usize, node: usize, old: usize) {
+		if !self.valid(old) || self.nodes[old].parent != Some(parent) || node == old {
+			return;
+		}
+		self.insert_before(parent, node, old);
+		self.detach(old);
+	}
+
+	/// `parent.childNodes[i]`.
+	pub(crate) fn child_at(&self, parent: usize, i: i32) -> Option<usize> {
+		let children = &self.nodes.get(parent)?.children;
+		children.get(usize::try_from(i).ok()?).copied()
+	}
+
+	fn element(&self, node: usize) -> Option<&Element> {
+		match &self.nodes.get(node)?.kind {
+			Kind::Element(e) => Some(e),
+			_ => None,
+		}
+	}
+
+	fn element_mut(&mut self, node: usize) -> Option<&mut Element> {
+		match &mut self.nodes.get_mut(node)?.kind {
+			Kind::Element(e) => Some(e),
+			_ => None,
+		}
+	}
+
+	pub(crate) fn set_attribute(&mut self, node: usize, name: &str, value: &str) {
+		if let Some(e) = self.element_mut(node) {
+			e.set_attr(name, value);
+		}
+	}
+
+	pub(crate) fn remove_attribute(&mut self, node: usize, name: &str) {
+		if let Some(e) = self.element_mut(node) {
+			e.attrs.retain(|(n, _)| n != name);
+		}
+	}
+
+	pub(crate) fn set_string_property(&mut self, node: usize, name: &str, value: &str) {
+		if let Some(e) = self.element_mut(node) {
+			e.props
+				.insert(name.to_string(), Prop::Str(value.to_string()));
+		}
+	}
+
+	pub(crate) fn set_bool_property(&mut self, node: usize, name: &str, value: bool) {
+		if let Some(e) = self.element_mut(node) {
+			e.props.insert(name.to_string(), Prop::Bool(value));
+		}
+	}
+
+	/// `node.style.setProperty(name, value)`, which the browser reflects into the
+	/// `style` attribute: replace the declaration if it's there, else add it.
+	pub(crate) fn set_style_property(&mut self, node: usize, name: &str, value: &str) {
+		let Some(e) = self.element_mut(node) else {
+			return;
+		};
+		let style = e.attr("style").unwrap_or_default();
+		let mut declarations: Vec<(String, String)> = style
+			.split(';')
+			.filter_map(|d| d.split_once(':'))
+			.map(|(n, v)| (n.trim().to_string(), v.trim().to_string()))
+			.collect();
+		match declarations.iter_mut().find(|(n, _)| n == name) {
+			Some(d) => d.1 = value.to_string(),
+			None => declarations.push((name.to_string(), value.to_string())),
+		}
+		let style: Vec<String> = declarations
+			.iter()
+			.map(|(n, v)| format!("{n}: {v};"))
+			.collect();
+		e.set_attr("style", &style.join(" "));
+	}
+
+	/// `node.textContent = text`: an element's children give way to one text node (none
+	/// for `""`); a text node's content is replaced.
+	pub(crate) fn set_text(&mut self, node: usize, text: &str) {
+		if !self.valid(node) {
+			return;
+		}
+		match &mut self.nodes[node].kind {
+			Kind::Text(t) => *t = text.to_string(),
+			Kind::Element(_) => {
+				for child in std::mem::take(&mut self.nodes[node].children) {
+					self.nodes[child].parent = None;
+				}
+				if !text.is_empty() {
+					let t = self.create_text(text);
+					self.nodes[node].children.push(t);
+					self.nodes[t].parent = Some(node);
+				}
+			}
+			Kind::Window => {}
+		}
+	}
+
+	/// `node.value`: the property once set, otherwise the `value` attribute (an input's
+	/// initial text), otherwise `""`.
+	pub(crate) fn value(&self, node: usize) -> String {
+		let Some(e) = self.element(node) else {
+			return String::new();
+		};
+		match e.props.get("value") {
+			Some(Prop::Str(v)) => v.clone(),
+			Some(Prop::Bool(b)) => b.to_string(),
+			None => e.attr("value").unwrap_or_default(),
+		}
+	}
+
+	/// `node.checked`: the property once set, otherwise whether the `checked` attribute
+	/// is there.
+	pub(crate) fn checked(&self, node: usize) -> bool {
+		let Some(e) = self.element(node) else {
+			return false;
+		};
+		match e.props.get("checked") {
+			Some(Prop::Bool(b)) => *b,
+			Some(Prop::Str(s)) => !s.is_empty(),
+			None => e.attr("checked").is_some(),
+		}
+	}
+
+	/// `node.textContent`: the text of every text node below it, in document order.
+	pub(crate) fn text(&self, node: usize) -> String {
+		let mut out = String::new();
+		if self.valid(node) {
+			self.collect_text(node, &mut out);
+		}
+		out
+	}
+
+	fn collect_text(&self, node: usize, out: &mut String) {
+		match &self.nodes[node].kind {
+			Kind::Text(t) => out.push_str(t),
+			_ => {
+				for &child in &self.nodes[node].children {
+					self.collect_text(child, out);
+				}
+			}
+		}
+	}
+
+	/// `node.outerHTML` — the node and everything below it. Properties aren't
+	/// attributes, so a typed-in input value doesn't show up here (read it with
+	/// `dom.get-value`).
+	pub(crate) fn html(&self, node: usize) -> String {
+		let mut out = String::new();
+		if self.valid(node) {
+			self.write_html(node, &mut out);
+		}
+		out
+	}
+
+	fn write_html(&self, node: usize, out: &mut String) {
+		match &self.nodes[node].kind {
+			Kind::Text(t) => out.push_str(&escape(t, false)),
+			Kind::Window => {}
+			Kind::Element(e) => {
+				out.push('<');
+				out.push_str(&e.tag);
+				for (name, value) in &e.attrs {
+					out.push_str(&format!(" {name}=\"{}\"", escape(value, true)));
+				}
+				out.push('>');
+				if VOID_ELEMENTS.contains(&e.tag.as_str()) {
+					return;
+				}
+				for &child in &self.nodes[node].children {
+					// `<style>`/`<script>` bodies are raw text, not markup.
+					match (&self.nodes[child].kind, e.tag.as_str()) {
+						(Kind::Text(t), "style" | "script") => out.push_str(t),
+						_ => self.write_html(child, out),
+					}
+				}
+				out.push_str(&format!("</{}>", e.tag));
+			}
+		}
+	}
+
+	/// `document.getElementById(id)`, searching the tree under `<body>`.
+	pub(crate) fn element_by_id(&self, id: &str) -> Option<usize> {
+		self
+			.descendants(BODY)
+			.into_iter()
+			.find(|&n| self.element(n).and_then(|e| e.attr("id")).as_deref() == Some(id))
+	}
+
+	/// Every node below `root`, in document order (`root` itself excluded).
+	fn descendants(&self, root: usize) -> Vec<usize> {
+		let mut out = Vec::new();
+		let mut stack: Vec<usize> = self.nodes[root].children.iter().rev().copied().collect();
+		while let Some(node) = stack.pop() {
+			out.push(node);
+			stack.extend(self.nodes[node].children.iter().rev());
+		}
+		out
+	}
+
+	/// `root.querySelectorAll(selector)`: the elements below `root` that match, in
+	/// document order. Understands the everyday subset — tag names, `*`, `#id`,
+	/// `.class`, `[attr]`, `[attr=value]`, descendant and `>` child combinators, and
+	/// `,` lists.
+	pub(crate) fn query(&self, root: usize, selector: &str) -> Result<Vec<usize>, String> {
+		let list =
+			parse_selector_list(selector).ok_or_else(|| format!("invalid selector `{selector}`"))?;
+		if !self.valid(root) {
+			return Ok(Vec::new());
+		}
+		Ok(
+			self
+				.descendants(root)
+				.into_iter()
+				.filter(|&n| list.iter().any(|complex| self.matches_complex(n, complex)))
+				.collect(),
+		)
+	}
+
+	/// Whether `node` matches the last compound of `complex`, with its ancestors
+	/// matching the rest under the combinators between them.
+	fn matches_complex(&self, node: usize, complex: &[(Combinator, Compound)]) -> bool {
+		let Some(((combinator, compound), rest)) = complex.split_last() else {
+			return true;
+		};
+		if !self.matches_compound(node, compound) {
+			return false;
+		}
+		if rest.is_empty() {
+			return true;
+		}
+		let mut up = self.nodes[node].parent;
+		while let Some(ancestor) = up {
+			if self.matches_complex(ancestor, rest) {
+				return true;
+			}
+			if *combinator == Combinator::Child {
+				return false;
+			}
+			up = self.nodes[ancestor].parent;
+		}
+		false
+	}
+
+	fn matches_compound(&self, node: usize, compound: &Compound) -> bool {
+		let Some(e) = self.element(node) else {
+			return false;
+		};
+		if compound.tag.as_ref().is_some_and(|t| *t != e.tag) {
+			return false;
+		}
+		compound.simple.iter().all(|s| match s {
+			Simple::Id(id) => e.attr("id").as_deref() == Some(id.as_str()),
+			Simple::Class(class) => e
+				.attr("class")
+				.is_some_and(|c| c.split_whitespace().any(|c| c == class)),
+			Simple::Has(name) => e.attr(name).is_some(),
+			Simple::Equals(name, value) => e.attr(name).as_deref() == Some(value.as_str()),
+		})
+	}
+
+	pub(crate) fn add_listener(&mut self, node: usize, event: &str, token: i32) {
+		if let Some(node) = self.nodes.get_mut(node) {
+			node.listeners.push((event.to_string(), token));
+		}
+	}
+
+	/// Fire an `event` at `target`: record the event and return it with the handler
+	/// tokens it reaches, in the order they run — `target`'s listeners, then each
+	/// ancestor's as it bubbles up. The caller runs the handlers.
+	pub(crate) fn dispatch(&mut self, target: usize, event: &str) -> (usize, Vec<i32>) {
+		self.events.push(Event {
+			target,
+			default_prevented: false,
+		});
+		let id = self.events.len() - 1;
+		let mut tokens = Vec::new();
+		let mut at = self.valid(target).then_some(target);
+		while let Some(node) = at {
+			let listeners = &self.nodes[node].listeners;
+			tokens.extend(
+				listeners
+					.iter()
+					.filter(|(name, _)| name == event)
+					.map(|&(_, token)| token),
+			);
+			at = self.nodes[node].parent;
+		}
+		(id, tokens)
+	}
+
+	/// The node an event was fired at.
+	pub(crate) fn event_target(&self, event: usize) -> Option<usize> {
+		self.events.get(event).map(|e| e.target)
+	}
+
+	pub(crate) fn prevent_default(&mut self, event: usize) {
+		if let Some(e) = self.events.get_mut(event) {
+			e.default_prevented = true;
+		}
+	}
+
+	pub(crate) fn default_prevented(&self, event: usize) -> bool {
+		self.events.get(event).is_some_and(|e| e.default_prevented)
+	}
+
+	pub(crate) fn path(&self) -> &str {
+		&self.path
+	}
+
+	pub(crate) fn search(&self) -> &str {
+		&self.search
+	}
+
+	pub(crate) fn hash(&self) -> &str {
+		&self.hash
+	}
+
+	/// `history.pushState`/`replaceState` with `url`, resolved against the current
+	/// location the way the browser resolves a same-origin URL: a path replaces all
+	/// three parts, a `?query` keeps the path, a `#fragment` keeps path and query.
+	pub(crate) fn navigate(&mut self, url: &str) {
+		let (rest, hash) = match url.find('#') {
+			Some(i) => (&url[..i], url[i..].to_string()),
+			None => (url, String::new()),
+		};
+		let (path, search) = match rest.find('?') {
+			Some(i) => (&rest[..i], rest[i..].to_string()),
+			None => (rest, String::new()),
+		};
+		if !path.is_empty() {
+			self.path = if path.starts_with('/') {
+				path.to_string()
+			} else {
+				let dir = &self.path[..self.path.rfind('/').map_or(0, |i| i + 1)];
+				format!("{dir}{path}")
+			};
+			self.search = search;
+		} else if !rest.is_empty() {
+			self.search = search;
+		}
+		self.hash = hash;
+	}
+
+	pub(crate) fn store_set(&mut self, key: &str, value: &str) {
+		self.store.insert(key.to_string(), value.to_string());
+	}
+
+	pub(crate) fn store_get(&self, key: &str) -> String {
+		self.store.get(key).cloned().unwrap_or_default()
+	}
+}
+
+impl Element {
+	fn attr(&self, name: &str) -> Option<String> {
+		self
+			.attrs
+			.iter()
+			.find(|(n, _)| n == name)
+			.map(|(_, v)| v.clone())
+	}
+
+	fn set_attr(&mut self, name: &str, value: &str) {
+		match self.attrs.iter_mut().find(|(n, _)| n == name) {
+			Some(a) => a.1 = value.to_string(),
+			None => self.attrs.push((name.to_string(), value.to_string())),
+		}
+	}
+}
+
+/// HTML-escape text, or an attribute value when `attr` (which also escapes `"`).
+fn escape(s: &str, attr: bool) -> String {
+	let mut out = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'&' => out.push_str("&amp;"),
+			'<' => out.push_str("&lt;"),
+			'>' => out.push_str("&gt;"),
+			'"' if attr => out.push_str("&quot;"),
+			c => out.push(c),
+		}
+	}
+	out
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+	Descendant,
+	Child,
+}
+
+/// One compound selector: `div.card[data-open]`.
+#[derive(Default)]
+struct Compound {
+	tag: Option<String>,
+	simple: Vec<Simple>,
+}
+
+enum Simple {
+	Id(String),
+	Class(String),
+	Has(String),
+	Equals(String, String),
+}
+
+/// A selector list, each entry a chain of compounds paired with the combinator that
+/// joins it to the one before (the first's is unused). `None` if it doesn't parse.
+fn parse_selector_list(selector: &str) -> Option<Vec<Vec<(Combinator, Compound)>>> {
+	selector.split(',').map(parse_complex).collect()
+}
+
+fn parse_complex(selector: &str) -> Option<Vec<(Combinator, Compound)>> {
+	let mut out = Vec::new();
+	let mut combinator = Combinator::Descendant;
+	let spaced = selector.replace('>', " > ");
+	for word in spaced.split_whitespace() {
+		if word == ">" {
+			if out.is_empty() || combinator == Combinator::Child {
+				return None;
+			}
+			combinator = Combinator::Child;
+			continue;
+		}
+		out.push((combinator, parse_compound(word)?));
+		combinator = Combinator::Descendant;
+	}
+	(!out.is_empty() && combinator == Combinator::Descendant).then_some(out)
+}
+
+fn parse_compound(word: &str) -> Option<Compound> {
+	let mut compound = Compound::default();
+	let ident_end = |s: &str| {
+		s.find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+			.unwrap_or(s.len())
+	};
+	let mut rest = word;
+	let head = ident_end(rest);
+	if head > 0 {
+		compound.tag = Some(rest[..head].to_ascii_lowercase());
+		rest = &rest[head..];
+	} else if let Some(r) = rest.strip_prefix('*') {
+		rest = r;
+	}
+	while let Some(c) = rest.chars().next() {
+		rest = &rest[1..];
+		match c {
+			'#' | '.' => {
+				let end = ident_end(rest);
+				if end == 0 {
+					return None;
+				}
+				let name = rest[..end].to_string();
+				compound.simple.push(if c == '#' {
+					Simple::Id(name)
+				} else {
+					Simple::Class(name)
+				});
+				rest = &rest[end..];
+			}
+			'[' => {
+				let close = rest.find(']')?;
+				let inner = &rest[..close];
+				rest = &rest[close + 1..];
+				compound.simple.push(match inner.split_once('=') {
+					Some((name, value)) => {
+						let value = value.trim();
+						let value = value
+							.strip_prefix('"')
+							.and_then(|v| v.strip_suffix('"'))
+							.or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+							.unwrap_or(value);
+						Simple::Equals(name.trim().to_string(), value.to_string())
+					}
+					None => Simple::Has(inner.trim().to_string()),
+				});
+			}
+			_ => return None,
+		}
+	}
+	Some(compound)
+}
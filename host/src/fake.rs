@@ -0,0 +1,428 @@
+// The test fakes `std/test` installs around a thunk (`test.with-fake-clock`,
+// `test.with-fake-fs`, `test.with-fake-net`): a controllable clock and an in-memory
+// filesystem. While one is installed, the matching host imports answer from it instead
+// of the machine, so a test that sleeps an hour or writes a config file is instant and
+// leaves nothing behind. The loopback fake network lives with the real sockets in
+// `net` (it shares their id space and reactor); this module switches it on and off.
+//
+// All of it is driven by the one `test-fake` import, a command string per call (see
+// `control`). Engine-independent: the V8 glue reads the scheduler's virtual clock and
+// hands it in, but nothing here touches V8.
+
+use std::collections::BTreeMap;
+
+use crate::HostState;
+use crate::fsop::op;
+use crate::offload::OpResult;
+
+/// The fakes installed in a run. All `None` outside a `with-fake-*` thunk.
+#[derive(Default)]
+pub(crate) struct Fakes {
+	pub(crate) clock: Option<FakeClock>,
+	pub(crate) fs: Option<FakeFs>,
+	/// An installed fake filesystem set aside by `test.real-fs`, put back on `resume`.
+	held_fs: Option<FakeFs>,
+}
+
+/// Run one `test-fake` command against `state`, answering the text the Pluma wrapper
+/// reads back. `virtual_now` is the scheduler's virtual clock (nanoseconds), which the
+/// fake clock follows so a `task.sleep` moves it too. The commands:
+///
+/// - `clock start N` installs a fake clock reading unix nanos `N`; `clock end` removes
+///   it; `clock advance N` moves it `N` nanos forward; `clock set N` sets it to unix
+///   nanos `N`.
+/// - `fs start` / `fs end` install and remove an empty in-memory filesystem;
+///   `fs suspend` / `fs resume` set an installed one aside and put it back.
+/// - `net start` / `net end` switch `std/sys/net` to the in-memory loopback network
+///   and back (closing its sockets).
+///
+/// A `start` or `suspend` answers `"1"` when it changed something and `"0"` when there
+/// was nothing to do (a fake already installed, or none to suspend), so a nested
+/// wrapper knows to leave the teardown to the outer one.
+pub(crate) fn control(
+	state: &mut HostState,
+	command: &str,
+	virtual_now: i64,
+) -> Result<String, String> {
+	let words: Vec<&str> = command.split(' ').collect();
+	let fakes = &mut state.fakes;
+	let changed = |b: bool| Ok(if b { "1" } else { "0" }.to_string());
+	match words.as_slice() {
+		["clock", "start", at] => {
+			let at = nanos_arg(at)?;
+			if fakes.clock.is_some() {
+				return changed(false);
+			}
+			fakes.clock = Some(FakeClock::new(at, virtual_now));
+			changed(true)
+		}
+		["clock", "end"] => {
+			fakes.clock = None;
+			Ok(String::new())
+		}
+		["clock", "advance", by] => {
+			let by = nanos_arg(by)?;
+			installed_clock(fakes)?.advance(by, virtual_now);
+			Ok(String::new())
+		}
+		["clock", "set", at] => {
+			let at = nanos_arg(at)?;
+			installed_clock(fakes)?.set(at, virtual_now);
+			Ok(String::new())
+		}
+		["fs", "start"] => {
+			if fakes.fs.is_some() {
+				return changed(false);
+			}
+			fakes.fs = Some(FakeFs::default());
+			changed(true)
+		}
+		["fs", "end"] => {
+			fakes.fs = None;
+			Ok(String::new())
+		}
+		["fs", "suspend"] => {
+			let held = fakes.fs.take();
+			let suspended = held.is_some();
+			if suspended {
+				fakes.held_fs = held;
+			}
+			changed(suspended)
+		}
+		["fs", "resume"] => {
+			fakes.fs = fakes.held_fs.take();
+			Ok(String::new())
+		}
+		["net", "start"] => changed(state.net.start_fake()),
+		["net", "end"] => {
+			state.net.end_fake(&mut state.reactor);
+			Ok(String::new())
+		}
+		_ => Err(format!("test: unknown fake command `{command}`")),
+	}
+}
+
+fn nanos_arg(word: &str) -> Result<i64, String> {
+	word
+		.parse()
+		.map_err(|_| format!("test: `{word}` isn't a number of nanoseconds"))
+}
+
+fn installed_clock(fakes: &mut Fakes) -> Result<&mut FakeClock, String> {
+	fakes
+		.clock
+		.as_mut()
+		.ok_or_else(|| "no fake clock is installed (use `test.with-fake-clock`)".to_string())
+}
+
+// --- the clock -----------------------------------------------------------------
+
+/// A clock that only moves when the program sleeps or the test moves it. `time.now`
+/// reads `start` plus the time elapsed; `time.monotonic` reads the time elapsed.
+pub(crate) struct FakeClock {
+	/// The wall-clock reading (unix nanos) at elapsed zero.
+	start: i64,
+	/// Nanoseconds moved by `time.sleep` and `advance`.
+	moved: i64,
+	/// The scheduler's virtual clock when the fake was installed: its timers jump it
+	/// forward as `task.sleep`s come due, and the fake follows.
+	virtual_base: i64,
+	/// The largest elapsed time read so far. A nested scheduler (a cleanup task run
+	/// during a defer) restarts the virtual clock from zero; holding at this keeps the
+	/// fake from running backwards meanwhile.
+	floor: i64,
+}
+
+impl FakeClock {
+	fn new(start: i64, virtual_now: i64) -> Self {
+		FakeClock {
+			start,
+			moved: 0,
+			virtual_base: virtual_now,
+			floor: 0,
+		}
+	}
+
+	/// Nanoseconds elapsed on the fake clock since it was installed.
+	pub(crate) fn elapsed(&mut self, virtual_now: i64) -> i64 {
+		let elapsed = self.moved + (virtual_now - self.virtual_base).max(0);
+		self.floor = self.floor.max(elapsed);
+		self.floor
+	}
+
+	/// The fake wall-clock reading, in unix nanos.
+	pub(crate) fn now(&mut self, virtual_now: i64) -> i64 {
+		self.start + self.elapsed(virtual_now)
+	}
+
+	/// Move the clock `nanos` forward (a negative amount is ignored: the clock never
+	/// runs backwards).
+	pub(crate) fn advance(&mut self, nanos: i64, virtual_now: i64) {
+		let elapsed = self.elapsed(virtual_now);
+		self.moved += nanos.max(0);
+		self.floor = elapsed + nanos.max(0);
+	}
+
+	/// Set the wall-clock reading to `unix_nanos`. The monotonic reading is unaffected.
+	fn set(&mut self, unix_nanos: i64, virtual_now: i64) {
+		self.start = unix_nanos - self.elapsed(virtual_now);
+	}
+}
+
+// --- the filesystem ------------------------------------------------------------
+
+/// An in-memory filesystem: a map from absolute, normalized path to entry, holding
+/// only the root directory to begin with. Relative paths resolve against `/`.
+pub(crate) struct FakeFs {
+	entries: BTreeMap<String, Entry>,
+}
+
+enum Entry {
+	Dir { modified: i64 },
+	File { data: Vec<u8>, modified: i64 },
+}
+
+impl Default for FakeFs {
+	fn default() -> Self {
+		let mut entries = BTreeMap::new();
+		entries.insert("/".to_string(), Entry::Dir { modified: 0 });
+		FakeFs { entries }
+	}
+}
+
+// The messages mirror the OS's, minus the errno.
+const NOT_FOUND: &str = "No such file or directory";
+const IS_DIR: &str = "Is a directory";
+const NOT_DIR: &str = "Not a directory";
+const NOT_EMPTY: &str = "Directory not empty";
+const EXISTS: &str = "File exists";
+
+impl FakeFs {
+	/// Run one `std/sys/fs` op (the op-codes of `fsop::op`, with `fsop::dispatch`'s
+	/// arguments and results) against the fake. `now` (unix nanos) stamps what it
+	/// writes.
+	pub(crate) fn dispatch(&mut self, code: i32, path: &str, data: &[u8], now: i64) -> OpResult {
+		let path = normalize(path);
+		let res = match code {
+			op::READ_TEXT => self
+				.read(&path)
+				.and_then(|b| {
+					String::from_utf8(b).map_err(|_| "stream did not contain valid UTF-8".to_string())
+				})
+				.map(|s| OpResult::Bytes(s.into_bytes())),
+			op::READ_BYTES => self.read(&path).map(OpResult::Bytes),
+			op::READ_DIR => self.read_dir(&path),
+			op::STAT => self.stat(&path),
+			op::WRITE => self.write(&path, data.to_vec(), now),
+			op::APPEND => {
+				let mut contents = match self.entries.get(&path) {
+					Some(Entry::File { data, .. }) => data.clone(),
+					_ => Vec::new(),
+				};
+				contents.extend_from_slice(data);
+				self.write(&path, contents, now)
+			}
+			op::MAKE_DIR => self.make_dir(&path, now),
+			op::REMOVE => self.remove(&path, false),
+			op::REMOVE_ALL => self.remove(&path, true),
+			op::RENAME => self.rename(&path, &normalize(&String::from_utf8_lossy(data)), now),
+			op::COPY => self
+				.read(&path)
+				.and_then(|b| self.write(&normalize(&String::from_utf8_lossy(data)), b, now)),
+			op::EXISTS => Ok(boolean(self.entries.contains_key(&path))),
+			op::IS_FILE => Ok(boolean(matches!(
+				self.entries.get(&path),
+				Some(Entry::File { .. })
+			))),
+			op::IS_DIR => Ok(boolean(self.is_dir(&path))),
+			_ => Err(format!("fs: unknown op {code}")),
+		};
+		res.unwrap_or_else(OpResult::Err)
+	}
+
+	fn is_dir(&self, path: &str) -> bool {
+		matches!(self.entries.get(path), Some(Entry::Dir { .. }))
+	}
+
+	fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+		match self.entries.get(path) {
+			Some(Entry::File { data, .. }) => Ok(data.clone()),
+			Some(Entry::Dir { .. }) => Err(IS_DIR.to_string()),
+			None => Err(NOT_FOUND.to_string()),
+		}
+	}
+
+	/// The names of `dir`'s entries, sorted, each followed by a NUL (`fsop`'s blob).
+	fn read_dir(&self, dir: &str) -> Result<OpResult, String> {
+		match self.entries.get(dir) {
+			Some(Entry::Dir { .. }) => {}
+			Some(Entry::File { .. }) => return Err(NOT_DIR.to_string()),
+			None => return Err(NOT_FOUND.to_string()),
+		}
+		let mut blob = Vec::new();
+		for name in self.children(dir) {
+			blob.extend_from_slice(name.as_bytes());
+			blob.push(0);
+		}
+		Ok(OpResult::Bytes(blob))
+	}
+
+	/// `fsop`'s stat record: `"<kind>\t<size>\t<modified-nanos>"`.
+	fn stat(&self, path: &str) -> Result<OpResult, String> {
+		let record = match self.entries.get(path) {
+			Some(Entry::Dir { modified }) => format!("dir\t0\t{modified}"),
+			Some(Entry::File { data, modified }) => format!("file\t{}\t{modified}", data.len()),
+			None => return Err(NOT_FOUND.to_string()),
+		};
+		Ok(OpResult::Bytes(record.into_bytes()))
+	}
+
+	fn write(&mut self, path: &str, data: Vec<u8>, now: i64) -> Result<OpResult, String> {
+		if self.is_dir(path) {
+			return Err(IS_DIR.to_string());
+		}
+		self.check_parent(path)?;
+		self.entries.insert(
+			path.to_string(),
+			Entry::File {
+				data,
+				modified: now,
+			},
+		);
+		Ok(OpResult::Nothing)
+	}
+
+	/// Create `path` and any missing parents, like `mkdir -p`.
+	fn make_dir(&mut self, path: &str, now: i64) -> Result<OpResult, String> {
+		let mut at = String::new();
+		for part in path.split('/').filter(|p| !p.is_empty()) {
+			at.push('/');
+			at.push_str(part);
+			match self.entries.get(&at) {
+				Some(Entry::Dir { .. }) => {}
+				Some(Entry::File { .. }) => return Err(EXISTS.to_string()),
+				None => {
+					self
+						.entries
+						.insert(at.clone(), Entry::Dir { modified: now });
+				}
+			}
+		}
+		Ok(OpResult::Nothing)
+	}
+
+	fn remove(&mut self, path: &str, all: bool) -> Result<OpResult, String> {
+		if path == "/" {
+			return Err("Permission denied".to_string());
+		}
+		match self.entries.get(path) {
+			Some(Entry::File { .. }) => {}
+			Some(Entry::Dir { .. }) if !all && self.children(path).next().is_some() => {
+				return Err(NOT_EMPTY.to_string());
+			}
+			Some(Entry::Dir { .. }) => {}
+			None => return Err(NOT_FOUND.to_string()),
+		}
+		let inside = format!("{path}/");
+		self
+			.entries
+			.retain(|p, _| p != path && !p.starts_with(&inside));
+		Ok(OpResult::Nothing)
+	}
+
+	/// Move `from` (and, for a directory, everything under it) to `to`, replacing a
+	/// file or empty directory already there.
+	fn rename(&mut self, from: &str, to: &str, now: i64) -> Result<OpResult, String> {
+		if !self.entries.contains_key(from) {
+			return Err(NOT_FOUND.to_string());
+		}
+		if from == to {
+			return Ok(OpResult::Nothing);
+		}
+		let inside = format!("{from}/");
+		if to.starts_with(&inside) {
+			return Err("Invalid argument".to_string());
+		}
+		self.check_parent(to)?;
+		match (self.entries.get(from), self.entries.get(to)) {
+			(Some(Entry::File { .. }), Some(Entry::Dir { .. })) => return Err(IS_DIR.to_string()),
+			(Some(Entry::Dir { .. }), Some(Entry::File { .. })) => return Err(NOT_DIR.to_string()),
+			(Some(Entry::Dir { .. }), Some(Entry::Dir { .. })) if self.children(to).next().is_some() => {
+				return Err(NOT_EMPTY.to_string());
+			}
+			_ => {}
+		}
+		let moved: Vec<String> = self
+			.entries
+			.keys()
+			.filter(|p| *p == from || p.starts_with(&inside))
+			.cloned()
+			.collect();
+		for old in moved {
+			let entry = self.entries.remove(&old).expect("listed above");
+			self
+				.entries
+				.insert(format!("{to}{}", &old[from.len()..]), entry);
+		}
+		if let Some(Entry::Dir { modified } | Entry::File { modified, .. }) = self.entries.get_mut(to) {
+			*modified = now;
+		}
+		Ok(OpResult::Nothing)
+	}
+
+	/// Fail unless `path`'s parent is an existing directory.
+	fn check_parent(&self, path: &str) -> Result<(), String> {
+		match self.entries.get(parent(path)) {
+			Some(Entry::Dir { .. }) => Ok(()),
+			Some(Entry::File { .. }) => Err(NOT_DIR.to_string()),
+			None => Err(NOT_FOUND.to_string()),
+		}
+	}
+
+	/// The names of `dir`'s direct entries, in order.
+	fn children<'a>(&'a self, dir: &str) -> impl Iterator<Item = &'a str> {
+		let prefix = if dir == "/" {
+			"/".to_string()
+		} else {
+			format!("{dir}/")
+		};
+		let skip = prefix.len();
+		self
+			.entries
+			.range(prefix.clone()..)
+			.map(|(p, _)| p)
+			.take_while(move |p| p.starts_with(&prefix))
+			.filter_map(move |p| {
+				let name = &p[skip..];
+				(!name.is_empty() && !name.contains('/')).then_some(name)
+			})
+	}
+}
+
+/// `path` made absolute (against `/`) with `.`, `..` and repeated or trailing slashes
+/// resolved away.
+fn normalize(path: &str) -> String {
+	let mut parts: Vec<&str> = Vec::new();
+	for part in path.split('/') {
+		match part {
+			"" | "." => {}
+			".." => {
+				parts.pop();
+			}
+			_ => parts.push(part),
+		}
+	}
+	format!("/{}", parts.join("/"))
+}
+
+/// The directory holding a normalized `path` (`/` for a top-level entry or the root).
+fn parent(path: &str) -> &str {
+	match path.rfind('/') {
+		Some(0) | None => "/",
+		Some(i) => &path[..i],
+	}
+}
+
+fn boolean(b: bool) -> OpResult {
+	OpResult::Bytes(vec![if b { b'1' } else { b'0' }])
+}
@@ -48,12 +48,24 @@ mod procop;
 // `rusqlite::Connection`s + the value/row wire codec, an offload client of `offload.rs`.
 mod db;
 
+// The test fakes `std/test` switches on around a thunk: a controllable clock and an
+// in-memory filesystem (the loopback network lives with the real sockets in `net`).
+mod fake;
+
+// The headless DOM the `std/web/dom` imports build when a program runs here rather than
+// in a browser, so `pluma test` can exercise components (`std/test/browser` reads it
+// back).
+mod dom;
+
 // The V8 backend: instantiates the WasmGC artifact under V8 over the
 // marshalling ABI. Reuses this crate's engine-independent core (`HostState`/`HostNet`/
 // `NetRet`/`BufferedIo`/`read_line_from`) — a descendant module sees its ancestors'
 // private items, so nothing here needs `pub`.
 mod v8host;
-pub use v8host::{run_streaming_v8, run_test_v8, run_wasm_v8, run_wasm_v8_captured};
+pub use v8host::{
+	run_bench_v8, run_streaming_v8, run_test_v8, run_test_v8_detailed, run_wasm_v8,
+	run_wasm_v8_captured,
+};
 
 /// A program's observable result: exit status + captured stdout. (The streaming runner
 /// returns an empty `stdout` — it streamed live to the process — and the caller uses
@@ -72,6 +84,198 @@ pub struct RunCapture {
 	pub status: String,
 	pub stdout: String,
 	pub stderr: String,
+	/// A trap's Pluma stack trace (`  at divide (main.pa:4)` lines, innermost
+	/// first), or empty. Kept out of `status`/`stderr` so the snapshot suite doesn't
+	/// pin it; the streaming and test drivers print it under the failure message.
+	pub trace: String,
+	/// Coverage counts by probe id, for an instrumented program (else empty).
+	pub coverage: Vec<u64>,
+}
+
+/// Where a test run prints its report and the cases' own output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Echo {
+	Stdout,
+	/// Stdout carries machine-readable diagnostics (`--message-format json`
+	/// or `sarif`), so the report goes beside them on stderr.
+	Stderr,
+	/// A reporter has stdout to itself.
+	Off,
+}
+
+/// How `run_test_v8_detailed` runs a suite.
+#[derive(Clone, Debug)]
+pub struct TestSettings {
+	/// ANSI styling in the report.
+	pub color: bool,
+	/// Where the report and the cases' own output go.
+	pub echo: Echo,
+	/// How long a case may run before it's stopped and failed, unless it sets
+	/// its own limit (`test.timeout`).
+	pub timeout: std::time::Duration,
+	/// How many more times a failing case runs before it counts as failed
+	/// (`pluma test --retries`). One that passes on a retry is `Flaky`.
+	pub retries: u32,
+	/// Shuffle the order in which each case's ready tasks run, seeded with
+	/// this (`pluma test --schedule-seed`), in place of the scheduler's FIFO
+	/// order. The same seed replays the same interleaving.
+	pub schedule_seed: Option<u64>,
+	/// Run only the case or group at this path (`pluma test --case`): its
+	/// group names, outermost first, then its own. Empty runs every case.
+	pub only: Vec<String>,
+}
+
+impl TestSettings {
+	/// The time limit of a case that doesn't set its own.
+	pub const DEFAULT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+}
+
+/// Everything a `pluma test` run hands back beyond its exit code
+/// (`run_test_v8_detailed`).
+pub struct TestRun {
+	/// 0 all-pass, 1 on any failure or trap.
+	pub code: i32,
+	/// Coverage counts by probe id, summed across the isolates, for an
+	/// instrumented artifact (else empty).
+	pub coverage: Vec<u64>,
+	/// One entry per case, in module order.
+	pub cases: Vec<TestCase>,
+}
+
+/// One case's result, as `std/test` records it for `pluma test --reporter`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestCase {
+	pub module: String,
+	/// Enclosing group names, outermost first.
+	pub path: Vec<String>,
+	pub name: String,
+	pub status: TestStatus,
+	/// The failure message; empty unless `status` is `Failed`, or for `Flaky`
+	/// the message of the run that failed.
+	pub message: String,
+	pub duration: std::time::Duration,
+	/// How many times the case ran: 0 when skipped or todo, more than 1 only
+	/// with retries.
+	pub attempts: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestStatus {
+	Passed,
+	Failed,
+	Skipped,
+	Todo,
+	/// Failed, then passed on a retry.
+	Flaky,
+}
+
+impl TestCase {
+	/// Parse a record line's fields (the text after its leading US; see
+	/// `std/test.case-record-line`). `None` if it isn't a well-formed record.
+	fn from_record(fields: &str) -> Option<TestCase> {
+		let fields: Vec<String> = fields.split('\u{1f}').map(unescape_field).collect();
+		let [status, nanos, module, path, name, message] = <[String; 6]>::try_from(fields).ok()?;
+		let status = match status.as_str() {
+			"pass" => TestStatus::Passed,
+			"fail" => TestStatus::Failed,
+			"skip" => TestStatus::Skipped,
+			"todo" => TestStatus::Todo,
+			_ => return None,
+		};
+		let path = match path.as_str() {
+			"" => Vec::new(),
+			p => p.split('\u{1d}').map(str::to_string).collect(),
+		};
+		Some(TestCase {
+			module,
+			path,
+			name,
+			status,
+			message,
+			duration: std::time::Duration::from_nanos(nanos.parse().ok()?),
+			attempts: u32::from(matches!(status, TestStatus::Passed | TestStatus::Failed)),
+		})
+	}
+}
+
+/// What a `pluma bench` run hands back (`run_bench_v8`).
+pub struct BenchRun {
+	/// 0 when every case ran, 1 on a trap.
+	pub code: i32,
+	/// One entry per case, in module order.
+	pub results: Vec<BenchResult>,
+}
+
+/// One case's timing, as `std/bench` records it. The times are nanoseconds per
+/// run of the case's body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchResult {
+	pub module: String,
+	/// Enclosing group names, outermost first.
+	pub path: Vec<String>,
+	pub name: String,
+	/// How many times each sample ran the body.
+	pub runs: u64,
+	pub samples: u64,
+	pub mean: f64,
+	pub median: f64,
+	pub stddev: f64,
+	pub min: f64,
+	pub max: f64,
+}
+
+impl BenchResult {
+	/// Parse a record line's fields (the text after its leading US; see
+	/// `std/bench.record-line`). `None` if it isn't a well-formed record.
+	fn from_record(fields: &str) -> Option<BenchResult> {
+		let fields: Vec<String> = fields.split('\u{1f}').map(unescape_field).collect();
+		let [
+			module,
+			path,
+			name,
+			runs,
+			samples,
+			mean,
+			median,
+			stddev,
+			min,
+			max,
+		] = <[String; 10]>::try_from(fields).ok()?;
+		let path = match path.as_str() {
+			"" => Vec::new(),
+			p => p.split('\u{1d}').map(str::to_string).collect(),
+		};
+		Some(BenchResult {
+			module,
+			path,
+			name,
+			runs: runs.parse().ok()?,
+			samples: samples.parse().ok()?,
+			mean: mean.parse().ok()?,
+			median: median.parse().ok()?,
+			stddev: stddev.parse().ok()?,
+			min: min.parse().ok()?,
+			max: max.parse().ok()?,
+		})
+	}
+}
+
+/// Undo `std/test.escape-field` (and `std/bench`'s copy of it): `\\` is a backslash and `\n` a newline.
+fn unescape_field(field: &str) -> String {
+	let mut out = String::with_capacity(field.len());
+	let mut chars = field.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			out.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('n') => out.push('\n'),
+			Some(other) => out.push(other),
+			None => out.push('\\'),
+		}
+	}
+	out
 }
 
 // --------------------------------------------------------------------------
@@ -323,10 +527,20 @@ struct HostState {
 	/// `std/sys/db` runtime state: the pinned SQLite worker (spawned on first use). Reports
 	/// completions through `reactor`'s shared queue via a `CompletionSink`.
 	db: HostDb,
-	/// When this run is one shard of a parallel `pluma test`, its `(id, count)` —
-	/// surfaced only through `io-env`'s reserved `PLUMA_TEST_SHARD` name so the test
-	/// runner can select its slice, while real `io.args`/`io.env` stay untouched.
-	pub shard: Option<(u32, u32)>,
+	/// When this run is one isolate of a pooled `pluma test`, its job: `plan` (list
+	/// the cases) or `case <suite> <case>` (run one). Surfaced only through
+	/// `io-env`'s reserved `PLUMA_TEST_SHARD` name so the test runner can pick its
+	/// work, while real `io.args`/`io.env` stay untouched.
+	pub shard: Option<String>,
+	/// The fakes a test has installed (`test.with-fake-clock`, `-fs`); the time and
+	/// filesystem imports answer from these while they're in place. Empty in normal runs.
+	fakes: fake::Fakes,
+	/// The page `std/web/dom` builds into (the headless DOM): a bare `<body>` until the
+	/// program adds to it.
+	dom: dom::HeadlessDom,
+	/// `pluma test --coverage` hit counts, indexed by probe id (`coverage-hit`).
+	/// Grown on demand; empty for an uninstrumented program.
+	coverage: Vec<u64>,
 }
 
 impl HostState {
@@ -353,3 +567,62 @@ impl HostState {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn a_case_record_parses_into_its_result() {
+		let pass =
+			TestCase::from_record("pass\u{1f}1500\u{1f}math.test\u{1f}add\u{1d}edge\u{1f}ones\u{1f}");
+		assert_eq!(
+			pass,
+			Some(TestCase {
+				module: "math.test".to_string(),
+				path: vec!["add".to_string(), "edge".to_string()],
+				name: "ones".to_string(),
+				status: TestStatus::Passed,
+				message: String::new(),
+				duration: Duration::from_nanos(1500),
+				attempts: 1,
+			})
+		);
+
+		// `std/test.escape-field` writes `\` as `\\` and a newline as `\n`.
+		let fail = TestCase::from_record(
+			"fail\u{1f}0\u{1f}math.test\u{1f}\u{1f}divides\u{1f}expected <1> \"x\"\\ngot C:\\\\tmp",
+		)
+		.unwrap();
+		assert_eq!((fail.status, fail.attempts), (TestStatus::Failed, 1));
+		assert!(fail.path.is_empty());
+		assert_eq!(fail.message, "expected <1> \"x\"\ngot C:\\tmp");
+
+		for (word, status) in [("skip", TestStatus::Skipped), ("todo", TestStatus::Todo)] {
+			let case =
+				TestCase::from_record(&format!("{word}\u{1f}0\u{1f}m\u{1f}\u{1f}n\u{1f}")).unwrap();
+			assert_eq!((case.status, case.attempts), (status, 0));
+		}
+	}
+
+	#[test]
+	fn a_malformed_record_is_none() {
+		for record in [
+			"",
+			"pass\u{1f}0\u{1f}m\u{1f}\u{1f}n",
+			"pass\u{1f}0\u{1f}m\u{1f}\u{1f}n\u{1f}\u{1f}extra",
+			"passed\u{1f}0\u{1f}m\u{1f}\u{1f}n\u{1f}",
+			"pass\u{1f}soon\u{1f}m\u{1f}\u{1f}n\u{1f}",
+		] {
+			assert_eq!(TestCase::from_record(record), None, "{record:?}");
+		}
+	}
+
+	#[test]
+	fn fields_unescape() {
+		assert_eq!(unescape_field("a\\nb"), "a\nb");
+		assert_eq!(unescape_field("C:\\\\tmp\\\\n"), "C:\\tmp\\n");
+		assert_eq!(unescape_field("trailing\\"), "trailing\\");
+	}
+}
@@ -7,10 +7,16 @@
 // the fiber and later drives `net-poll`. listen/close/local-addr/connect are
 // synchronous (v1 connect blocks — a loopback dial completes in-kernel).
 //
+// Under `test.with-fake-net` the table switches to an in-memory loopback network: a
+// listen binds nothing, a connect pairs two in-memory ends, and bytes written to one end
+// queue up for the other. A fiber waiting on a fake socket parks here rather than on
+// the poller, and whatever makes it ready (a write, a close, a dial) wakes it through
+// the reactor (`Reactor::wake`).
+//
 // Engine-independent: the V8 net callbacks in `v8host::net` shape these `NetRet`s
 // into the marshalling ABI, but nothing here touches V8.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream, ToSocketAddrs};
 use std::os::fd::{AsRawFd, RawFd};
@@ -23,13 +29,28 @@ use crate::offload::{Interest, Reactor};
 enum SocketEntry {
 	Listener(TcpListener),
 	Conn(TcpStream),
+	/// A listener on the loopback fake network: its address, and the server ends of
+	/// the connections dialed to it, waiting to be accepted.
+	FakeListener {
+		addr: String,
+		backlog: VecDeque<u32>,
+	},
+	/// One end of a loopback fake connection: its address, the other end's id (`None`
+	/// once that end closed), and the bytes written to this end not yet read.
+	FakeConn {
+		addr: String,
+		peer: Option<u32>,
+		inbox: VecDeque<u8>,
+	},
 }
 
 impl SocketEntry {
-	fn raw_fd(&self) -> RawFd {
+	/// The OS socket to wait on, for a real socket.
+	fn raw_fd(&self) -> Option<RawFd> {
 		match self {
-			SocketEntry::Listener(l) => l.as_raw_fd(),
-			SocketEntry::Conn(c) => c.as_raw_fd(),
+			SocketEntry::Listener(l) => Some(l.as_raw_fd()),
+			SocketEntry::Conn(c) => Some(c.as_raw_fd()),
+			SocketEntry::FakeListener { .. } | SocketEntry::FakeConn { .. } => None,
 		}
 	}
 }
@@ -54,6 +75,47 @@ pub(crate) enum NetRet {
 pub(crate) struct HostNet {
 	sockets: HashMap<u32, SocketEntry>,
 	next_id: u32,
+	/// The loopback fake network, while `test.with-fake-net` has it switched on.
+	fake: Option<FakeNet>,
+}
+
+/// The loopback fake network's bookkeeping beside the socket table.
+#[derive(Default)]
+struct FakeNet {
+	/// Bound ports, each to its listener's id.
+	ports: HashMap<u16, u32>,
+	/// The fiber waiting on each fake socket (an accept on a listener, a read on a
+	/// connection), woken when the socket becomes ready.
+	parked: HashMap<u32, i32>,
+	/// The last port handed out for a `:0` bind or a dialing end.
+	last_port: u16,
+}
+
+impl FakeNet {
+	/// A port from the ephemeral range that nothing is bound to.
+	fn free_port(&mut self) -> u16 {
+		loop {
+			self.last_port = self.last_port.max(49151).wrapping_add(1).max(49152);
+			if !self.ports.contains_key(&self.last_port) {
+				return self.last_port;
+			}
+		}
+	}
+
+	/// Wake the fiber waiting on `sid`, if any.
+	fn wake(&mut self, reactor: &mut Reactor, sid: u32) {
+		if let Some(fid) = self.parked.remove(&sid) {
+			reactor.wake(fid);
+		}
+	}
+}
+
+/// Split `"host:port"` into its host and port.
+fn split_addr(addr: &str) -> Result<(&str, u16), String> {
+	addr
+		.rsplit_once(':')
+		.and_then(|(host, port)| Some((host, port.parse().ok()?)))
+		.ok_or_else(|| format!("invalid socket address `{addr}`"))
 }
 
 /// Bind a listening socket with `SO_REUSEADDR` set, the equivalent of std's
@@ -84,6 +146,9 @@ impl HostNet {
 	}
 
 	pub(crate) fn listen(&mut self, addr: &str) -> NetRet {
+		if self.fake.is_some() {
+			return self.fake_listen(addr);
+		}
 		match bind_reusable(addr) {
 			Ok(l) => match l.set_nonblocking(true) {
 				Ok(()) => NetRet::OkInt(self.store(SocketEntry::Listener(l)) as i32),
@@ -93,9 +158,12 @@ impl HostNet {
 		}
 	}
 
-	pub(crate) fn close(&mut self, id: u32) -> NetRet {
+	pub(crate) fn close(&mut self, reactor: &mut Reactor, id: u32) -> NetRet {
 		match self.sockets.remove(&id) {
-			Some(_) => NetRet::OkNothing,
+			Some(entry) => {
+				self.drop_fake(reactor, id, entry);
+				NetRet::OkNothing
+			}
 			None => NetRet::Err(format!("net.close: no such socket ({id})")),
 		}
 	}
@@ -104,6 +172,9 @@ impl HostNet {
 		let addr = match self.sockets.get(&id) {
 			Some(SocketEntry::Listener(l)) => l.local_addr(),
 			Some(SocketEntry::Conn(c)) => c.local_addr(),
+			Some(SocketEntry::FakeListener { addr, .. } | SocketEntry::FakeConn { addr, .. }) => {
+				return NetRet::OkStr(addr.clone());
+			}
 			None => return NetRet::Err(format!("net.local-addr: no such socket ({id})")),
 		};
 		match addr {
@@ -123,8 +194,14 @@ impl HostNet {
 	}
 
 	pub(crate) fn try_accept(&mut self, reactor: &mut Reactor, fid: i32, lid: u32) -> NetRet {
-		let res = match self.sockets.get(&lid) {
+		let res = match self.sockets.get_mut(&lid) {
 			Some(SocketEntry::Listener(l)) => l.accept(),
+			Some(SocketEntry::FakeListener { backlog, .. }) => {
+				return match backlog.pop_front() {
+					Some(sid) => NetRet::OkInt(sid as i32),
+					None => self.park(reactor, fid, lid, Interest::Read),
+				};
+			}
 			_ => return NetRet::Err(format!("net.accept: not a listener ({lid})")),
 		};
 		match res {
@@ -149,6 +226,14 @@ impl HostNet {
 		let mut buf = vec![0u8; max];
 		let res = match self.sockets.get_mut(&cid) {
 			Some(SocketEntry::Conn(c)) => c.read(&mut buf),
+			Some(SocketEntry::FakeConn { peer, inbox, .. }) => {
+				// Nothing queued: wait for the peer, unless it's gone (a clean EOF).
+				if inbox.is_empty() && peer.is_some() {
+					return self.park(reactor, fid, cid, Interest::Read);
+				}
+				let n = inbox.len().min(max);
+				return NetRet::OkBytes(inbox.drain(..n).collect());
+			}
 			_ => return NetRet::Err(format!("net.read: not a connection ({cid})")),
 		};
 		match res {
@@ -173,6 +258,18 @@ impl HostNet {
 	) -> NetRet {
 		let res = match self.sockets.get_mut(&cid) {
 			Some(SocketEntry::Conn(c)) => c.write(data),
+			Some(SocketEntry::FakeConn { peer, .. }) => {
+				let Some(peer) = *peer else {
+					return NetRet::Err("Broken pipe".to_string());
+				};
+				if let Some(SocketEntry::FakeConn { inbox, .. }) = self.sockets.get_mut(&peer) {
+					inbox.extend(data);
+				}
+				if let Some(fake) = &mut self.fake {
+					fake.wake(reactor, peer);
+				}
+				return NetRet::OkInt(data.len() as i32);
+			}
 			_ => return NetRet::Err(format!("net.write: not a connection ({cid})")),
 		};
 		match res {
@@ -185,17 +282,153 @@ impl HostNet {
 	}
 
 	/// Register fiber `fid` against socket `sid`'s readiness on the shared reactor (token =
-	/// fid), then report would-block.
-	fn park(&self, reactor: &mut Reactor, fid: i32, sid: u32, interest: Interest) -> NetRet {
+	/// fid), then report would-block. A fake socket has no fd to wait on: the fiber is
+	/// noted against it instead, for whatever makes it ready to wake.
+	fn park(&mut self, reactor: &mut Reactor, fid: i32, sid: u32, interest: Interest) -> NetRet {
 		let fd = match self.sockets.get(&sid) {
 			Some(e) => e.raw_fd(),
 			None => return NetRet::Err(format!("net: park on unknown socket {sid}")),
 		};
+		let Some(fd) = fd else {
+			return match &mut self.fake {
+				Some(fake) => {
+					fake.parked.insert(sid, fid);
+					NetRet::WouldBlock
+				}
+				None => NetRet::Err(format!(
+					"net: socket {sid} belongs to a finished fake network"
+				)),
+			};
+		};
 		match reactor.register_socket(fid, fd, interest) {
 			Ok(()) => NetRet::WouldBlock,
 			Err(e) => NetRet::Err(e),
 		}
 	}
+
+	/// Forget fiber `fid`'s wait on a fake socket (the `io-unwatch` of a cancelled
+	/// fiber), so a later wake can't reach a recycled fiber id.
+	pub(crate) fn unpark(&mut self, fid: i32) {
+		if let Some(fake) = &mut self.fake {
+			fake.parked.retain(|_, parked| *parked != fid);
+		}
+	}
+
+	// --- the loopback fake network (`test.with-fake-net`) ----------------------
+
+	/// Switch to the fake network. `false` if it was already on.
+	pub(crate) fn start_fake(&mut self) -> bool {
+		if self.fake.is_some() {
+			return false;
+		}
+		self.fake = Some(FakeNet::default());
+		true
+	}
+
+	/// Switch back to real sockets, closing every fake one.
+	pub(crate) fn end_fake(&mut self, reactor: &mut Reactor) {
+		let Some(fake) = self.fake.take() else { return };
+		for fid in fake.parked.into_values() {
+			reactor.wake(fid);
+		}
+		self.sockets.retain(|_, e| e.raw_fd().is_some());
+	}
+
+	/// Whether `connect` should dial the fake network.
+	pub(crate) fn is_fake(&self) -> bool {
+		self.fake.is_some()
+	}
+
+	fn fake_listen(&mut self, addr: &str) -> NetRet {
+		let (host, port) = match split_addr(addr) {
+			Ok(a) => a,
+			Err(e) => return NetRet::Err(e),
+		};
+		let fake = self.fake.as_mut().expect("fake network on");
+		let port = if port == 0 { fake.free_port() } else { port };
+		if fake.ports.contains_key(&port) {
+			return NetRet::Err("Address already in use".to_string());
+		}
+		let addr = format!("{host}:{port}");
+		let id = self.store(SocketEntry::FakeListener {
+			addr,
+			backlog: VecDeque::new(),
+		});
+		self
+			.fake
+			.as_mut()
+			.expect("fake network on")
+			.ports
+			.insert(port, id);
+		NetRet::OkInt(id as i32)
+	}
+
+	/// Dial `addr` on the fake network: pair a new connection with a server end queued
+	/// on the listener bound to its port, and wake the listener's accept. Any host
+	/// reaches the port; there's only the one machine.
+	pub(crate) fn fake_connect(&mut self, reactor: &mut Reactor, addr: &str) -> NetRet {
+		let port = match split_addr(addr) {
+			Ok((_host, port)) => port,
+			Err(e) => return NetRet::Err(e),
+		};
+		let fake = self.fake.as_mut().expect("fake network on");
+		let Some(&lid) = fake.ports.get(&port) else {
+			return NetRet::Err("Connection refused".to_string());
+		};
+		let local = format!("127.0.0.1:{}", fake.free_port());
+		let server_addr = match self.sockets.get(&lid) {
+			Some(SocketEntry::FakeListener { addr, .. }) => addr.clone(),
+			_ => return NetRet::Err("Connection refused".to_string()),
+		};
+		let client = self.store(SocketEntry::FakeConn {
+			addr: local,
+			peer: None,
+			inbox: VecDeque::new(),
+		});
+		let server = self.store(SocketEntry::FakeConn {
+			addr: server_addr,
+			peer: Some(client),
+			inbox: VecDeque::new(),
+		});
+		if let Some(SocketEntry::FakeConn { peer, .. }) = self.sockets.get_mut(&client) {
+			*peer = Some(server);
+		}
+		if let Some(SocketEntry::FakeListener { backlog, .. }) = self.sockets.get_mut(&lid) {
+			backlog.push_back(server);
+		}
+		if let Some(fake) = &mut self.fake {
+			fake.wake(reactor, lid);
+		}
+		NetRet::OkInt(client as i32)
+	}
+
+	/// Tidy up after a closed fake socket: a connection's peer sees end of input, and
+	/// a listener's port frees up and its unaccepted connections close.
+	fn drop_fake(&mut self, reactor: &mut Reactor, id: u32, entry: SocketEntry) {
+		let Some(fake) = &mut self.fake else { return };
+		fake.wake(reactor, id);
+		match entry {
+			SocketEntry::FakeConn {
+				peer: Some(peer), ..
+			} => {
+				if let Some(SocketEntry::FakeConn { peer: back, .. }) = self.sockets.get_mut(&peer) {
+					*back = None;
+				}
+				fake.wake(reactor, peer);
+			}
+			SocketEntry::FakeListener { addr, backlog } => {
+				if let Ok((_host, port)) = split_addr(&addr) {
+					fake.ports.remove(&port);
+				}
+				for sid in backlog {
+					if let Some(entry) = self.sockets.remove(&sid) {
+						self.drop_fake(reactor, sid, entry);
+					}
+				}
+			}
+			_ => {}
+		}
+	}
 }
 
 // --- std/web/fetch transport (the native/V8 host) ------------------------------
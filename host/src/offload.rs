@@ -178,6 +178,12 @@ impl Reactor {
 		self.inflight.insert(fid);
 	}
 
+	/// Mark fiber `fid` woken without the poller: a fake socket (`net`'s loopback fake
+	/// network) became ready. The next `poll` hands it back.
+	pub(crate) fn wake(&mut self, fid: i32) {
+		self.ready.push_back(fid);
+	}
+
 	/// Block until a parked socket is ready or a worker completion lands (or `deadline`
 	/// nanos elapse; `-1` = block indefinitely), returning one woken fid (`-1` on timeout /
 	/// nothing pending). Drains both wake sources — socket-readiness events and the worker
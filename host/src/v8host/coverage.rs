@@ -0,0 +1,20 @@
+// Native import callback for `pluma test --coverage`: an instrumented program (see
+// `ir::coverage`) calls `coverage-hit(id)` at every probe, and the host bumps that
+// counter. The counts leave the run in `RunCapture::coverage`.
+
+use super::marshal::{argi, ctx_and_mem};
+
+/// `coverage-hit(id)`: count one execution of probe `id`.
+pub(super) fn cb_coverage_hit(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_r: v8::ReturnValue,
+) {
+	let id = argi(scope, &args, 0).max(0) as usize;
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	let counts = &mut ctx.state.coverage;
+	if counts.len() <= id {
+		counts.resize(id + 1, 0);
+	}
+	counts[id] += 1;
+}
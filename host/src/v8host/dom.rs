@@ -0,0 +1,492 @@
+// `std/web/dom` under the sys host: the browser loader's `dom-*`/`event-*` imports,
+// backed by the run's headless DOM (`crate::dom`) instead of a `document`, plus the
+// `test-dom-*` probes `std/test/browser` reads it back with. A node or an event
+// crosses as an `externref` holding its arena index (a JS number); a missing node
+// comes back as `undefined`, the null handle. Strings ride scratch exactly as in the
+// loader.
+
+use super::marshal::{argi, ctx_and_mem, get_prop, read_str, write_mem};
+use crate::dom::{BODY, WINDOW};
+
+/// A node or event argument: its arena index, or an index nothing has (so the op is a
+/// no-op) when the handle is null.
+fn handle(scope: &mut v8::HandleScope, args: &v8::FunctionCallbackArguments, i: i32) -> usize {
+	let v = args.get(i);
+	match v.is_number() {
+		true => v.uint32_value(scope).map_or(usize::MAX, |n| n as usize),
+		false => usize::MAX,
+	}
+}
+
+/// Return a node handle, or leave `undefined` (the null handle) for `None`.
+fn set_handle(scope: &mut v8::HandleScope, rv: &mut v8::ReturnValue, node: Option<usize>) {
+	if let Some(node) = node {
+		rv.set(v8::Number::new(scope, node as f64).into());
+	}
+}
+
+/// Write `s` into scratch at `dst` (≤ `cap` bytes) and return its true length — the
+/// loader's `writeStr`; the wasm side clamps to what it reserved.
+fn write_str(
+	scope: &mut v8::HandleScope,
+	mem: v8::Local<v8::Object>,
+	dst: i32,
+	cap: i32,
+	s: &str,
+) -> i32 {
+	let n = s.len().min(cap.max(0) as usize);
+	write_mem(scope, mem, dst.max(0) as usize, &s.as_bytes()[..n]);
+	s.len() as i32
+}
+
+/// The string argument at `(i, i + 1)`.
+fn arg_str(scope: &mut v8::HandleScope, args: &v8::FunctionCallbackArguments, i: i32) -> String {
+	let (ptr, len) = (argi(scope, args, i), argi(scope, args, i + 1));
+	let (_ctx, mem) = ctx_and_mem(scope, args);
+	read_str(scope, mem, ptr, len)
+}
+
+/// `dom-body() -> externref`.
+pub(super) fn cb_dom_body(
+	scope: &mut v8::HandleScope,
+	_args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	set_handle(scope, &mut rv, Some(BODY));
+}
+
+/// `dom-window() -> externref`.
+pub(super) fn cb_dom_window(
+	scope: &mut v8::HandleScope,
+	_args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	set_handle(scope, &mut rv, Some(WINDOW));
+}
+
+/// `dom-create-element(ptr, len) -> externref`.
+pub(super) fn cb_dom_create_element(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let tag = arg_str(scope, &args, 0);
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	let node = ctx.state.dom.create_element(&tag);
+	set_handle(scope, &mut rv, Some(node));
+}
+
+/// `dom-create-text(ptr, len) -> externref`.
+pub(super) fn cb_dom_create_text(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let text = arg_str(scope, &args, 0);
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	let node = ctx.state.dom.create_text(&text);
+	set_handle(scope, &mut rv, Some(node));
+}
+
+/// `dom-append-child(parent, child) -> ()`.
+pub(super) fn cb_dom_append_child(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let (parent, child) = (handle(scope, &args, 0), handle(scope, &args, 1));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.append_child(parent, child);
+}
+
+/// `dom-insert-before(parent, node, before) -> ()`.
+pub(super) fn cb_dom_insert_before(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let parent = handle(scope, &args, 0);
+	let (node, before) = (handle(scope, &args, 1), handle(scope, &args, 2));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.insert_before(parent, node, before);
+}
+
+/// `dom-remove-child(parent, child) -> ()`.
+pub(super) fn cb_dom_remove_child(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let (parent, child) = (handle(scope, &args, 0), handle(scope, &args, 1));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.remove_child(parent, child);
+}
+
+/// `dom-replace-child(parent, node, old) -> ()`.
+pub(super) fn cb_dom_replace_child(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let parent = handle(scope, &args, 0);
+	let (node, old) = (handle(scope, &args, 1), handle(scope, &args, 2));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.replace_child(parent, node, old);
+}
+
+/// `dom-child-at(node, i) -> externref` — `undefined` past the last child.
+pub(super) fn cb_dom_child_at(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let (node, i) = (handle(scope, &args, 0), argi(scope, &args, 1));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	let child = ctx.state.dom.child_at(node, i);
+	set_handle(scope, &mut rv, child);
+}
+
+/// `dom-set-attribute(node, np, nl, vp, vl) -> ()`.
+pub(super) fn cb_dom_set_attribute(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let node = handle(scope, &args, 0);
+	let (name, value) = (arg_str(scope, &args, 1), arg_str(scope, &args, 3));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.set_attribute(node, &name, &value);
+}
+
+/// `dom-remove-attribute(node, ptr, len) -> ()`.
+pub(super) fn cb_dom_remove_attribute(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let (node, name) = (handle(scope, &args, 0), arg_str(scope, &args, 1));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.remove_attribute(node, &name);
+}
+
+/// `dom-set-string-property(node, np, nl, vp, vl) -> ()`.
+pub(super) fn cb_dom_set_string_property(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let node = handle(scope, &args, 0);
+	let (name, value) = (arg_str(scope, &args, 1), arg_str(scope, &args, 3));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.set_string_property(node, &name, &value);
+}
+
+/// `dom-set-bool-property(node, np, nl, flag) -> ()`.
+pub(super) fn cb_dom_set_bool_property(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let (node, name) = (handle(scope, &args, 0), arg_str(scope, &args, 1));
+	let flag = argi(scope, &args, 3) != 0;
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.set_bool_property(node, &name, flag);
+}
+
+/// `dom-set-style-property(node, np, nl, vp, vl) -> ()`.
+pub(super) fn cb_dom_set_style_property(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let node = handle(scope, &args, 0);
+	let (name, value) = (arg_str(scope, &args, 1), arg_str(scope, &args, 3));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.set_style_property(node, &name, &value);
+}
+
+/// `dom-set-text(node, ptr, len) -> ()`.
+pub(super) fn cb_dom_set_text(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let (node, text) = (handle(scope, &args, 0), arg_str(scope, &args, 1));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.set_text(node, &text);
+}
+
+/// `dom-get-value(node, dst, cap) -> len`.
+pub(super) fn cb_dom_get_value(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let node = handle(scope, &args, 0);
+	let (dst, cap) = (argi(scope, &args, 1), argi(scope, &args, 2));
+	let (ctx, mem) = ctx_and_mem(scope, &args);
+	let value = ctx.state.dom.value(node);
+	rv.set_int32(write_str(scope, mem, dst, cap, &value));
+}
+
+/// `dom-element-text(idp, idl, dst, cap) -> len` — `""` when no element has the id.
+pub(super) fn cb_dom_element_text(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let id = arg_str(scope, &args, 0);
+	let (dst, cap) = (argi(scope, &args, 2), argi(scope, &args, 3));
+	let (ctx, mem) = ctx_and_mem(scope, &args);
+	let dom = &ctx.state.dom;
+	let text = dom
+		.element_by_id(&id)
+		.map(|n| dom.text(n))
+		.unwrap_or_default();
+	rv.set_int32(write_str(scope, mem, dst, cap, &text));
+}
+
+/// The shared body of the three `location` readers (`(window, dst, cap) -> len`).
+fn location_impl(
+	scope: &mut v8::HandleScope,
+	args: &v8::FunctionCallbackArguments,
+	rv: &mut v8::ReturnValue,
+	part: fn(&crate::dom::HeadlessDom) -> &str,
+) {
+	let (dst, cap) = (argi(scope, args, 1), argi(scope, args, 2));
+	let (ctx, mem) = ctx_and_mem(scope, args);
+	let value = part(&ctx.state.dom).to_string();
+	rv.set_int32(write_str(scope, mem, dst, cap, &value));
+}
+
+/// `dom-location-path(window, dst, cap) -> len`.
+pub(super) fn cb_dom_location_path(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	location_impl(scope, &args, &mut rv, |dom| dom.path());
+}
+
+/// `dom-location-search(window, dst, cap) -> len`.
+pub(super) fn cb_dom_location_search(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	location_impl(scope, &args, &mut rv, |dom| dom.search());
+}
+
+/// `dom-location-hash(window, dst, cap) -> len`.
+pub(super) fn cb_dom_location_hash(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	location_impl(scope, &args, &mut rv, |dom| dom.hash());
+}
+
+/// `dom-push-state(window, ptr, len) -> ()` and `dom-replace-state`: there's no back
+/// button here, so both just move the location.
+pub(super) fn cb_dom_navigate(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let url = arg_str(scope, &args, 1);
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.navigate(&url);
+}
+
+/// `dom-add-listener(node, np, nl, token) -> ()`: record the token; `test-dom-dispatch`
+/// hands it to `__dom_dispatch` when the event reaches the node.
+pub(super) fn cb_dom_add_listener(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let (node, event) = (handle(scope, &args, 0), arg_str(scope, &args, 1));
+	let token = argi(scope, &args, 3);
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.add_listener(node, &event, token);
+}
+
+/// `dom-dev-store-set(kp, kl, vp, vl) -> ()`.
+pub(super) fn cb_dom_dev_store_set(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let (key, value) = (arg_str(scope, &args, 0), arg_str(scope, &args, 2));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.store_set(&key, &value);
+}
+
+/// `dom-dev-store-get(kp, kl, dst, cap) -> len` — `""` when the key is absent.
+pub(super) fn cb_dom_dev_store_get(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let key = arg_str(scope, &args, 0);
+	let (dst, cap) = (argi(scope, &args, 2), argi(scope, &args, 3));
+	let (ctx, mem) = ctx_and_mem(scope, &args);
+	let value = ctx.state.dom.store_get(&key);
+	rv.set_int32(write_str(scope, mem, dst, cap, &value));
+}
+
+/// `event-target-value(event, dst, cap) -> len` — the `.value` of the node the event
+/// was fired at.
+pub(super) fn cb_event_target_value(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let event = handle(scope, &args, 0);
+	let (dst, cap) = (argi(scope, &args, 1), argi(scope, &args, 2));
+	let (ctx, mem) = ctx_and_mem(scope, &args);
+	let dom = &ctx.state.dom;
+	let value = dom
+		.event_target(event)
+		.map(|n| dom.value(n))
+		.unwrap_or_default();
+	rv.set_int32(write_str(scope, mem, dst, cap, &value));
+}
+
+/// `event-target-checked(event, dst, cap) -> len` — `"true"`/`"false"`.
+pub(super) fn cb_event_target_checked(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let event = handle(scope, &args, 0);
+	let (dst, cap) = (argi(scope, &args, 1), argi(scope, &args, 2));
+	let (ctx, mem) = ctx_and_mem(scope, &args);
+	let dom = &ctx.state.dom;
+	let checked = dom.event_target(event).is_some_and(|n| dom.checked(n));
+	rv.set_int32(write_str(scope, mem, dst, cap, &checked.to_string()));
+}
+
+/// `event-prevent-default(event) -> ()`.
+pub(super) fn cb_event_prevent_default(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	let event = handle(scope, &args, 0);
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	ctx.state.dom.prevent_default(event);
+}
+
+/// `test-dom-html(node, dst, cap) -> len` — the node's outer HTML.
+pub(super) fn cb_test_dom_html(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let node = handle(scope, &args, 0);
+	let (dst, cap) = (argi(scope, &args, 1), argi(scope, &args, 2));
+	let (ctx, mem) = ctx_and_mem(scope, &args);
+	let html = ctx.state.dom.html(node);
+	rv.set_int32(write_str(scope, mem, dst, cap, &html));
+}
+
+/// `test-dom-text(node, dst, cap) -> len` — the node's text content.
+pub(super) fn cb_test_dom_text(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let node = handle(scope, &args, 0);
+	let (dst, cap) = (argi(scope, &args, 1), argi(scope, &args, 2));
+	let (ctx, mem) = ctx_and_mem(scope, &args);
+	let text = ctx.state.dom.text(node);
+	rv.set_int32(write_str(scope, mem, dst, cap, &text));
+}
+
+/// `test-dom-dispatch(node, ptr, len) -> i32`: fire the named event at the node and run
+/// every handler it reaches as it bubbles, through the module's `__dom_dispatch` — the
+/// same re-entry the browser's listeners make. Returns 1 if a handler called
+/// `event.prevent-default`, else 0. A handler that traps stops the dispatch and unwinds
+/// through here to the caller.
+pub(super) fn cb_test_dom_dispatch(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let (target, name) = (handle(scope, &args, 0), arg_str(scope, &args, 1));
+	let (event, tokens, dispatch) = {
+		let (ctx, _mem) = ctx_and_mem(scope, &args);
+		let (event, tokens) = ctx.state.dom.dispatch(target, &name);
+		(event, tokens, ctx.dom_dispatch.clone())
+	};
+	// A module with no listeners doesn't export `__dom_dispatch` — and has nothing to run.
+	if let Some(dispatch) = dispatch {
+		let dispatch = v8::Local::new(scope, dispatch);
+		let recv: v8::Local<v8::Value> = v8::undefined(scope).into();
+		let event: v8::Local<v8::Value> = v8::Number::new(scope, event as f64).into();
+		for token in tokens {
+			let token: v8::Local<v8::Value> = v8::Integer::new(scope, token).into();
+			if dispatch.call(scope, recv, &[token, event]).is_none() {
+				return;
+			}
+		}
+	}
+	// The handlers re-entered the host; take the context afresh.
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	rv.set_int32(ctx.state.dom.default_prevented(event) as i32);
+}
+
+/// `test-dom-count(node, ptr, len) -> i32` — how many nodes below `node` match the
+/// selector. A selector that doesn't parse fails the run, like `io.fail`.
+pub(super) fn cb_test_dom_count(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let (node, selector) = (handle(scope, &args, 0), arg_str(scope, &args, 1));
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	match ctx.state.dom.query(node, &selector) {
+		Ok(found) => rv.set_int32(found.len() as i32),
+		Err(e) => fail(scope, &mut ctx.state, e),
+	}
+}
+
+/// `test-dom-match(node, ptr, len, i) -> externref` — the `i`-th node below `node` that
+/// matches the selector, in document order; `undefined` past the last.
+pub(super) fn cb_test_dom_match(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let (node, selector) = (handle(scope, &args, 0), arg_str(scope, &args, 1));
+	let i = argi(scope, &args, 3);
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	match ctx.state.dom.query(node, &selector) {
+		Ok(found) => {
+			let hit = usize::try_from(i).ok().and_then(|i| found.get(i).copied());
+			set_handle(scope, &mut rv, hit);
+		}
+		Err(e) => fail(scope, &mut ctx.state, e),
+	}
+}
+
+/// Abort the run with `message`, the way `io.fail` does (see `cb_io_fail`).
+fn fail(scope: &mut v8::HandleScope, state: &mut crate::HostState, message: String) {
+	state.fail = Some(message);
+	let msg = v8::String::new(scope, "io.fail").unwrap();
+	let exc = v8::Exception::error(scope, msg);
+	scope.throw_exception(exc);
+}
+
+/// The module's `__dom_dispatch` export, if it has one (only a module that adds a
+/// listener does).
+pub(super) fn dispatch_export(
+	scope: &mut v8::HandleScope,
+	exports: v8::Local<v8::Object>,
+) -> Option<v8::Global<v8::Function>> {
+	let f: v8::Local<v8::Function> = get_prop(scope, exports, "__dom_dispatch")?
+		.try_into()
+		.ok()?;
+	Some(v8::Global::new(scope, f))
+}
@@ -0,0 +1,31 @@
+// `std/test`'s fakes (`test.with-fake-clock`, `-fs`, `-net`). One import drives them
+// all: it reads a command string out of scratch, runs it against the run's `HostState`
+// (`crate::fake::control`), and answers the text back like a path read. The callbacks
+// that consult an installed fake live with their capability (`time`, `fs`, `offload`,
+// `net`).
+
+use super::marshal::{argi, ctx_and_mem, deliver_read_v8, read_str};
+use super::time::virtual_now;
+
+/// `test-fake(cmd_ptr, cmd_len, dst, cap) -> len`: run one fake command, delivering its
+/// answer into `(dst, cap)`; on failure stash the message for `io-last-error` and return
+/// -1, which the wasm side shapes into `err` via `__io_result`.
+pub(super) fn cb_test_fake(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let (cp, cl) = (argi(scope, &args, 0), argi(scope, &args, 1));
+	let (dst, cap) = (argi(scope, &args, 2), argi(scope, &args, 3));
+	let (ctx, mem) = ctx_and_mem(scope, &args);
+	let command = read_str(scope, mem, cp, cl);
+	let virtual_now = virtual_now(scope, ctx);
+	let n = match crate::fake::control(&mut ctx.state, &command, virtual_now) {
+		Ok(answer) => deliver_read_v8(scope, mem, ctx, dst, cap, answer.into_bytes()),
+		Err(e) => {
+			ctx.state.last_error = e;
+			-1
+		}
+	};
+	rv.set_int32(n);
+}
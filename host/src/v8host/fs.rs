@@ -1,10 +1,40 @@
 // std/sys/io reads / fs, plus the process surface (argv/env/exit). Each callback reads
 // path/data out of scratch, runs the `std::fs`/stdin op, delivers bytes back into the
 // caller's `(dst, cap)` buffer (overflow → `read_stash` for `io-copyout`), and sets
-// `last_error` on failure.
+// `last_error` on failure. Under `test.with-fake-fs` the file ops run against the
+// in-memory filesystem instead (`fake_fs_op`).
 
 use super::Ctx;
 use super::marshal::{argi, ctx_and_mem, deliver_read_v8, read_mem, read_str, write_mem};
+use super::time::wall_now;
+use crate::fsop::op;
+use crate::offload::OpResult;
+
+/// Run fs op `code` (see `fsop::op`) against the fake filesystem, if a test installed
+/// one; `None` means the real filesystem answers. Shared with the `std/sys/fs` ops.
+pub(super) fn fake_fs_op(
+	scope: &mut v8::HandleScope,
+	ctx: &mut Ctx,
+	code: i32,
+	path: &str,
+	data: &[u8],
+) -> Option<OpResult> {
+	ctx.state.fakes.fs.as_ref()?;
+	let now = wall_now(scope, ctx);
+	let fs = ctx.state.fakes.fs.as_mut()?;
+	Some(fs.dispatch(code, path, data, now))
+}
+
+/// A fake fs op's outcome in the real ops' terms: its payload (empty for a void op),
+/// or the error message.
+fn outcome(res: OpResult) -> Result<Vec<u8>, String> {
+	match res {
+		OpResult::Bytes(b) => Ok(b),
+		OpResult::Nothing => Ok(Vec::new()),
+		OpResult::Err(e) => Err(e),
+		OpResult::Count(_) | OpResult::Conn(_) => unreachable!("fs op produced a Count/Conn"),
+	}
+}
 
 pub(super) fn cb_io_read(
 	scope: &mut v8::HandleScope,
@@ -57,15 +87,22 @@ fn read_file_impl(
 	let (dst, cap) = (argi(scope, args, 2), argi(scope, args, 3));
 	let (ctx, mem) = ctx_and_mem(scope, args);
 	let path = read_str(scope, mem, pp, pl);
-	let res = if as_bytes {
-		std::fs::read(&path)
+	let code = if as_bytes {
+		op::READ_BYTES
 	} else {
-		std::fs::read_to_string(&path).map(String::into_bytes)
+		op::READ_TEXT
+	};
+	let res = match fake_fs_op(scope, ctx, code, &path, &[]) {
+		Some(res) => outcome(res),
+		None if as_bytes => std::fs::read(&path).map_err(|e| e.to_string()),
+		None => std::fs::read_to_string(&path)
+			.map(String::into_bytes)
+			.map_err(|e| e.to_string()),
 	};
 	let n = match res {
 		Ok(b) => deliver_read_v8(scope, mem, ctx, dst, cap, b),
 		Err(e) => {
-			ctx.state.last_error = e.to_string();
+			ctx.state.last_error = e;
 			-1
 		}
 	};
@@ -95,6 +132,17 @@ pub(super) fn cb_read_dir(
 	let (dst, cap) = (argi(scope, &args, 2), argi(scope, &args, 3));
 	let (ctx, mem) = ctx_and_mem(scope, &args);
 	let path = read_str(scope, mem, pp, pl);
+	if let Some(res) = fake_fs_op(scope, ctx, op::READ_DIR, &path, &[]) {
+		let n = match outcome(res) {
+			Ok(blob) => deliver_read_v8(scope, mem, ctx, dst, cap, blob),
+			Err(e) => {
+				ctx.state.last_error = e;
+				-1
+			}
+		};
+		rv.set_int32(n);
+		return;
+	}
 	let n = match std::fs::read_dir(&path) {
 		Ok(entries) => {
 			let mut names: Vec<String> = Vec::new();
@@ -143,6 +191,11 @@ fn write_file_impl(
 	let (ctx, mem) = ctx_and_mem(scope, args);
 	let path = read_str(scope, mem, pp, pl);
 	let data = read_mem(scope, mem, dp.max(0) as usize, dl.max(0) as usize);
+	let code = if append { op::APPEND } else { op::WRITE };
+	if let Some(res) = fake_fs_op(scope, ctx, code, &path, &data) {
+		rv.set_int32(io_status(ctx, outcome(res).map(|_| ())));
+		return;
+	}
 	let res = if append {
 		use std::io::Write;
 		std::fs::OpenOptions::new()
@@ -153,7 +206,7 @@ fn write_file_impl(
 	} else {
 		std::fs::write(&path, &data)
 	};
-	rv.set_int32(io_status(ctx, res));
+	rv.set_int32(io_status(ctx, res.map_err(|e| e.to_string())));
 }
 pub(super) fn cb_write_file(
 	s: &mut v8::HandleScope,
@@ -170,16 +223,21 @@ pub(super) fn cb_append_file(
 	write_file_impl(s, &a, true, &mut r);
 }
 
+/// A path-only op: `real` on the real filesystem, or fs op `code` on a fake one.
 fn path_op_impl(
 	scope: &mut v8::HandleScope,
 	args: &v8::FunctionCallbackArguments,
-	op: impl FnOnce(&str) -> std::io::Result<()>,
+	code: i32,
+	real: impl FnOnce(&str) -> std::io::Result<()>,
 	rv: &mut v8::ReturnValue,
 ) {
 	let (pp, pl) = (argi(scope, args, 0), argi(scope, args, 1));
 	let (ctx, mem) = ctx_and_mem(scope, args);
 	let path = read_str(scope, mem, pp, pl);
-	let res = op(&path);
+	let res = match fake_fs_op(scope, ctx, code, &path, &[]) {
+		Some(res) => outcome(res).map(|_| ()),
+		None => real(&path).map_err(|e| e.to_string()),
+	};
 	rv.set_int32(io_status(ctx, res));
 }
 pub(super) fn cb_delete_file(
@@ -187,14 +245,14 @@ pub(super) fn cb_delete_file(
 	a: v8::FunctionCallbackArguments,
 	mut r: v8::ReturnValue,
 ) {
-	path_op_impl(s, &a, |p| std::fs::remove_file(p), &mut r);
+	path_op_impl(s, &a, op::REMOVE, |p| std::fs::remove_file(p), &mut r);
 }
 pub(super) fn cb_make_dir(
 	s: &mut v8::HandleScope,
 	a: v8::FunctionCallbackArguments,
 	mut r: v8::ReturnValue,
 ) {
-	path_op_impl(s, &a, |p| std::fs::create_dir_all(p), &mut r);
+	path_op_impl(s, &a, op::MAKE_DIR, |p| std::fs::create_dir_all(p), &mut r);
 }
 
 fn query_impl(
@@ -204,10 +262,16 @@ fn query_impl(
 	rv: &mut v8::ReturnValue,
 ) {
 	let (pp, pl) = (argi(scope, args, 0), argi(scope, args, 1));
-	let (_ctx, mem) = ctx_and_mem(scope, args);
+	let (ctx, mem) = ctx_and_mem(scope, args);
 	let path = read_str(scope, mem, pp, pl);
-	let p = std::path::Path::new(&path);
-	let b = if is_dir { p.is_dir() } else { p.exists() };
+	let code = if is_dir { op::IS_DIR } else { op::EXISTS };
+	let b = match fake_fs_op(scope, ctx, code, &path, &[]) {
+		Some(res) => outcome(res).is_ok_and(|b| b == b"1"),
+		None => {
+			let p = std::path::Path::new(&path);
+			if is_dir { p.is_dir() } else { p.exists() }
+		}
+	};
 	rv.set_int32(b as i32);
 }
 pub(super) fn cb_file_exists(
@@ -285,11 +349,12 @@ pub(super) fn cb_io_env(
 	let (dst, cap) = (argi(scope, &args, 2), argi(scope, &args, 3));
 	let (ctx, mem) = ctx_and_mem(scope, &args);
 	let name = read_str(scope, mem, np, nl);
-	// Reserved name: a parallel `pluma test` shard reads its `(id, count)` here
-	// rather than from a real env var or argv, so test code that inspects the real
-	// environment is unaffected. The synthesized test entry is the only caller.
+	// Reserved name: a pooled `pluma test` isolate reads its job (`plan`, or
+	// `case <suite> <case>`) here rather than from a real env var or argv, so test
+	// code that inspects the real environment is unaffected. The synthesized test
+	// entry is the only caller.
 	let value = if name == "PLUMA_TEST_SHARD" {
-		ctx.state.shard.map(|(id, count)| format!("{id} {count}"))
+		ctx.state.shard.clone()
 	} else {
 		std::env::var(&name).ok()
 	};
@@ -310,7 +375,12 @@ pub(super) fn cb_io_cwd(
 ) {
 	let (dst, cap) = (argi(scope, &args, 0), argi(scope, &args, 1));
 	let (ctx, mem) = ctx_and_mem(scope, &args);
-	let n = match std::env::current_dir() {
+	// A fake filesystem resolves relative paths against its root.
+	let cwd = match ctx.state.fakes.fs {
+		Some(_) => Ok(std::path::PathBuf::from("/")),
+		None => std::env::current_dir(),
+	};
+	let n = match cwd {
 		Ok(p) => deliver_read_v8(
 			scope,
 			mem,
@@ -339,11 +409,11 @@ pub(super) fn cb_io_exit(
 }
 
 /// Shape an fs `Result<()>` into a `(0 ok / 2 err)` status, stashing the errno text.
-fn io_status(ctx: &mut Ctx, res: std::io::Result<()>) -> i32 {
+fn io_status(ctx: &mut Ctx, res: Result<(), String>) -> i32 {
 	match res {
 		Ok(()) => 0,
 		Err(e) => {
-			ctx.state.last_error = e.to_string();
+			ctx.state.last_error = e;
 			2
 		}
 	}
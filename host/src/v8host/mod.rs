@@ -9,25 +9,34 @@
 // This is the deploy engine `cli` ships and `tests` snapshots against.
 //
 // Layout: this module holds the run drivers + the `pluma.*` registration table; the
+// `pool` submodule holds the parallel `pluma test` driver built on them; the
 // `marshal` submodule holds the shared V8↔scratch helpers; and one submodule per
 // capability holds that capability's native import callbacks (`writers`, `fs`, `math`,
-// `entropy`, `time`, `net`).
+// `entropy`, `time`, `net`, `dom` for the headless DOM, and `fake` for the test fakes).
 
 use std::sync::Once;
 
 use crate::db::HostDb;
+use crate::dom::HeadlessDom;
+use crate::fake::Fakes;
 use crate::net::HostNet;
 use crate::offload::Reactor;
-use crate::{BufferedIo, CapturingIo, HostIo, HostState, RunCapture, RunResult, StdioIo};
+use crate::{
+	BenchResult, BenchRun, BufferedIo, CapturingIo, HostIo, HostState, RunCapture, RunResult, StdioIo,
+};
 
 mod compile;
+mod coverage;
 mod db;
+mod dom;
 mod entropy;
+mod fake;
 mod fs;
 mod marshal;
 mod math;
 mod net;
 mod offload;
+mod pool;
 mod regex;
 mod time;
 mod writers;
@@ -36,8 +45,11 @@ use marshal::{get_prop, read_mem, register};
 // The native import callbacks, grouped by capability. Glob-imported so the registration
 // table below can name each `cb_*` bare (the table is the canonical `pluma.*` surface).
 use compile::cb_compile_wasm_hex;
+use coverage::cb_coverage_hit;
 use db::*;
+use dom::*;
 use entropy::*;
+use fake::*;
 use fs::*;
 use math::*;
 use net::*;
@@ -46,6 +58,8 @@ use regex::*;
 use time::*;
 use writers::*;
 
+pub use pool::{run_test_v8, run_test_v8_detailed};
+
 /// V8 platform init is process-global and one-shot.
 static V8_INIT: Once = Once::new();
 
@@ -69,6 +83,18 @@ fn ensure_v8() {
 struct Ctx {
 	state: HostState,
 	memory: Option<v8::Global<v8::Object>>,
+	/// The module's exported `"__now"` (a `WebAssembly.Global`): the scheduler's
+	/// virtual clock, which a fake clock follows (`time::virtual_now`).
+	now: Option<v8::Global<v8::Object>>,
+	/// The module's exported `"__dom_dispatch"`, through which a headless-DOM event runs
+	/// the handlers it reaches (`dom::cb_test_dom_dispatch`). `None` when the module adds
+	/// no listeners.
+	dom_dispatch: Option<v8::Global<v8::Function>>,
+	/// `pluma test --schedule-seed`: seeds the scheduler's ready-queue shuffle
+	/// before `_entry` runs. `None` keeps FIFO order.
+	schedule_seed: Option<u64>,
+	/// The Pluma stack trace of the run's trap, if it trapped (`RunCapture::trace`).
+	trace: String,
 }
 
 /// Compile + instantiate `bytes` under V8, run `_entry`, and report status + captured
@@ -91,7 +117,7 @@ pub fn run_wasm_v8_captured(bytes: &[u8], stdin: &[u8]) -> RunCapture {
 /// Compile + instantiate `bytes` and run `_entry` once under V8, streaming
 /// stdout/stderr to the process and reading stdin from it (the `cli`'s `pluma run`
 /// path). `args` is the program's argv (`io.args`). Returns the process exit code; a
-/// failure's message is already on stderr.
+/// failure's message (and a trap's Pluma stack trace) is already on stderr.
 pub fn run_streaming_v8(bytes: &[u8], args: &[String]) -> i32 {
 	let result = run_v8(bytes, Box::new(StdioIo::new()), args.to_vec());
 	match result.status.as_str() {
@@ -99,142 +125,31 @@ pub fn run_streaming_v8(bytes: &[u8], args: &[String]) -> i32 {
 		other => {
 			let msg = other.strip_prefix("runtime error: ").unwrap_or(other);
 			eprintln!("{msg}");
+			eprint!("{}", result.trace);
 			1
 		}
 	}
 }
 
-/// Run a `pluma test` artifact and map the outcome to a process exit code.
+/// Run a `pluma bench` artifact and collect the timing record `std/bench`
+/// prints for each case; the cases' own output passes through to stdout.
 ///
-/// Every test module runs in its OWN fresh V8 isolate — fresh wasm globals,
-/// scheduler, and `HostState` — so no module can observe state another module
-/// left behind; that isolation is the point. The artifact is compiled once and
-/// its `CompiledWasmModule` shared (no recompilation) across the isolates, which
-/// a bounded thread pool runs in parallel. `num_modules` is the suite count
-/// (`program.test_suites.len()`); each isolate is told its index via the host's
-/// reserved `PLUMA_TEST_SHARD` env name and runs just that module. Each isolate
-/// captures its own report; reports print in module order so output is
-/// deterministic regardless of which thread finished first, ending with one
-/// aggregate summary. Exit code: 0 all-pass, 1 on any failure or trap.
-pub fn run_test_v8(bytes: &[u8], num_modules: usize, color: bool) -> i32 {
-	use std::sync::atomic::{AtomicUsize, Ordering};
-	use std::sync::{Arc, Mutex};
-
-	ensure_v8();
-	let num_items = num_modules;
-	if num_items == 0 {
-		return 0;
-	}
-
-	// Compile once on a throwaway isolate, then extract the shareable compiled
-	// module. The native code outlives that isolate (it's held behind a shared
-	// pointer), so every work item rebuilds its module object from it for free.
-	let compiled = match compile_to_shared(bytes) {
-		Some(c) => Arc::new(c),
-		None => {
-			eprintln!("wasm compile failed");
-			return 1;
-		}
-	};
-
-	// A bounded pool of `workers` threads pulls work items off a shared cursor.
-	// Each item runs in its OWN fresh isolate, told its index via the reserved
-	// `PLUMA_TEST_SHARD` host value, which the in-wasm runner (`run-all-sharded`)
-	// turns into "run the suite at this index". The pool is sized to the machine.
-	let workers = std::thread::available_parallelism()
-		.map(|n| n.get())
-		.unwrap_or(4)
-		.clamp(1, num_items);
-	let cursor = Arc::new(AtomicUsize::new(0));
-	let results: Arc<Mutex<Vec<Option<RunCapture>>>> =
-		Arc::new(Mutex::new((0..num_items).map(|_| None).collect()));
-
-	let handles: Vec<_> = (0..workers)
-		.map(|_| {
-			let compiled = Arc::clone(&compiled);
-			let cursor = Arc::clone(&cursor);
-			let results = Arc::clone(&results);
-			std::thread::spawn(move || {
-				loop {
-					let i = cursor.fetch_add(1, Ordering::Relaxed);
-					if i >= num_items {
-						break;
-					}
-					let cap = run_in_fresh_isolate(
-						ModuleSource::Compiled(&compiled),
-						Box::new(CapturingIo::new(&[])),
-						Vec::new(),
-						Some((i as u32, num_items as u32)),
-					);
-					results.lock().unwrap()[i] = Some(cap);
-				}
-			})
-		})
-		.collect();
-
-	let mut code = 0;
-	for handle in handles {
-		if handle.join().is_err() {
-			eprintln!("a test worker thread panicked");
-			code = 1;
+/// Unlike a test run, everything runs in ONE isolate on this thread, one case
+/// after another: benchmarks running side by side would compete for cores and
+/// memory bandwidth and skew each other's timings.
+pub fn run_bench_v8(bytes: &[u8]) -> BenchRun {
+	let cap = run_v8(bytes, Box::new(CapturingIo::new(&[])), Vec::new());
+	let mut results = Vec::new();
+	for line in cap.stdout.split_inclusive('\n') {
+		match line.strip_prefix('\u{1f}') {
+			Some(record) => results.extend(BenchResult::from_record(record.trim_end_matches('\n'))),
+			None => print!("{line}"),
 		}
 	}
-
-	// Print in work-item order so the report is deterministic regardless of which
-	// thread finished first. Each shard's output ends with a `<RS>p f s t` counts
-	// line (see `std/test.shard-counts-line`); pick those out, sum them, and print
-	// the module trees without them — then one aggregate summary for the whole run.
-	let results = Arc::try_unwrap(results)
-		.unwrap_or_else(|_| unreachable!("workers joined"))
-		.into_inner()
-		.unwrap();
-	let mut totals = [0i64; 4]; // passed, failed, skipped, todo
-	for cap in results.into_iter().flatten() {
-		for line in cap.stdout.split_inclusive('\n') {
-			match line.strip_prefix('\u{1e}') {
-				Some(counts) => {
-					for (slot, n) in totals.iter_mut().zip(
-						counts
-							.split_whitespace()
-							.filter_map(|tok| tok.parse::<i64>().ok()),
-					) {
-						*slot += n;
-					}
-				}
-				None => print!("{line}"),
-			}
-		}
-		eprint!("{}", cap.stderr);
-		if test_exit_code(&cap.status) != 0 {
-			code = 1;
-		}
-	}
-
-	print_pool_summary(totals, color);
-	if totals[1] > 0 {
-		code = 1;
-	}
-	code
-}
-
-/// Print the one aggregate summary line for a pooled test run, mirroring
-/// `std/test.summary-line`'s wording and color (bold green all-pass, bold red
-/// otherwise) so a sharded run reads identically to a single-process one.
-fn print_pool_summary(totals: [i64; 4], color: bool) {
-	let [passed, failed, skipped, todo] = totals;
-	let mut line = format!("{} of {} passed", passed, passed + failed);
-	if skipped > 0 {
-		line += &format!(", {skipped} skipped");
-	}
-	if todo > 0 {
-		line += &format!(", {todo} todo");
-	}
-	println!();
-	if color {
-		let sgr = if failed == 0 { "1;32" } else { "1;31" };
-		println!("\x1b[{sgr}m{line}\x1b[0m");
-	} else {
-		println!("{line}");
+	eprint!("{}", cap.stderr);
+	BenchRun {
+		code: test_exit_code(&cap),
+		results,
 	}
 }
 
@@ -252,14 +167,15 @@ fn compile_to_shared(bytes: &[u8]) -> Option<v8::CompiledWasmModule> {
 
 /// Map a run's status string to a `pluma test` exit code: `ok` → 0, a clean test
 /// failure (`run-all` returns `err ""`) → 1 silently, and a genuine trap → 1 with
-/// its message on stderr.
-fn test_exit_code(status: &str) -> i32 {
-	match status {
+/// its message and Pluma stack trace on stderr.
+fn test_exit_code(cap: &RunCapture) -> i32 {
+	match cap.status.as_str() {
 		"ok" => 0,
 		"runtime error: " => 1,
 		other => {
 			let msg = other.strip_prefix("runtime error: ").unwrap_or(other);
 			eprintln!("{msg}");
+			eprint!("{}", cap.trace);
 			1
 		}
 	}
@@ -271,13 +187,13 @@ fn test_exit_code(status: &str) -> i32 {
 /// core.
 fn run_v8(bytes: &[u8], io: Box<dyn HostIo>, args: Vec<String>) -> RunCapture {
 	ensure_v8();
-	run_in_fresh_isolate(ModuleSource::Bytes(bytes), io, args, None)
+	run_in_fresh_isolate(ModuleSource::Bytes(bytes), io, args, None, None, None)
 }
 
 /// A fresh `HostState` for one run — every per-run field at its empty default, the
-/// io sink, argv, and optional test-shard supplied. Shared by the single-run and
-/// per-shard drivers.
-fn fresh_state(io: Box<dyn HostIo>, args: Vec<String>, shard: Option<(u32, u32)>) -> HostState {
+/// io sink, argv, and optional test job supplied. Shared by the single-run and
+/// pooled test drivers.
+fn fresh_state(io: Box<dyn HostIo>, args: Vec<String>, shard: Option<String>) -> HostState {
 	HostState {
 		io,
 		args,
@@ -291,12 +207,25 @@ fn fresh_state(io: Box<dyn HostIo>, args: Vec<String>, shard: Option<(u32, u32)>
 		reactor: Reactor::default(),
 		db: HostDb::default(),
 		shard,
+		fakes: Fakes::default(),
+		dom: HeadlessDom::default(),
+		coverage: Vec::new(),
 	}
 }
 
+/// The scheduler's shuffle state (`__sched_seed`) for `--schedule-seed seed`: the seed
+/// run through splitmix64, so neighbouring seeds give unrelated interleavings, and never
+/// zero, which the scheduler reads as "don't shuffle".
+fn shuffle_state(seed: u64) -> i64 {
+	let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+	z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+	(z ^ (z >> 31)).max(1) as i64
+}
+
 /// Where `run_in_context` gets its module: freshly compiled from wire bytes, or
 /// rebuilt — without recompiling — from a `CompiledWasmModule` shared across the
-/// sharded test driver's isolates.
+/// pooled test driver's isolates.
 enum ModuleSource<'a> {
 	Bytes(&'a [u8]),
 	Compiled(&'a v8::CompiledWasmModule),
@@ -305,20 +234,32 @@ enum ModuleSource<'a> {
 /// Build a fresh isolate + context, run `_entry` through it from the given module
 /// source, and return its status + captured output. The isolate and all V8 handles
 /// stay confined to this call (only the `Send` `CompiledWasmModule` ever crosses a
-/// thread), so this is safe to call on a worker thread per shard.
+/// thread), so this is safe to call on a worker thread per test case. `watch`, if
+/// given, is sent the isolate's thread-safe handle before anything runs, so
+/// another thread can terminate a run that outstays its time limit. `schedule_seed`
+/// shuffles the order ready tasks run in (`pluma test --schedule-seed`).
 fn run_in_fresh_isolate(
 	src: ModuleSource,
 	io: Box<dyn HostIo>,
 	args: Vec<String>,
-	shard: Option<(u32, u32)>,
+	shard: Option<String>,
+	watch: Option<std::sync::mpsc::Sender<v8::IsolateHandle>>,
+	schedule_seed: Option<u64>,
 ) -> RunCapture {
 	let mut ctx = Ctx {
 		state: fresh_state(io, args, shard),
 		memory: None,
+		now: None,
+		dom_dispatch: None,
+		schedule_seed,
+		trace: String::new(),
 	};
 	let ctx_ptr = &mut ctx as *mut Ctx;
 
 	let isolate = &mut v8::Isolate::new(Default::default());
+	if let Some(watch) = watch {
+		let _ = watch.send(isolate.thread_safe_handle());
+	}
 	let scope = &mut v8::HandleScope::new(isolate);
 	let context = v8::Context::new(scope, Default::default());
 	let scope = &mut v8::ContextScope::new(scope, context);
@@ -330,12 +271,19 @@ fn run_in_fresh_isolate(
 		status,
 		stdout,
 		stderr,
+		trace: ctx.trace,
+		coverage: ctx.state.coverage,
 	}
 }
 
 /// The body of a run, inside an entered context: compile the WasmGC module, then
 /// instantiate it and run `_entry`. Returns the program status string.
 fn run_in_context(scope: &mut v8::HandleScope, src: ModuleSource, ctx_ptr: *mut Ctx) -> String {
+	// The wire bytes carry the line table a trap's trace is symbolicated through.
+	let wire: &[u8] = match src {
+		ModuleSource::Bytes(bytes) => bytes,
+		ModuleSource::Compiled(compiled) => compiled.get_wire_bytes_ref(),
+	};
 	// Get the WasmGC module object — compile from bytes, or rebuild it from a
 	// shared `CompiledWasmModule` (no recompilation; the native code is shared).
 	let module = match src {
@@ -413,6 +361,8 @@ fn run_in_context(scope: &mut v8::HandleScope, src: ModuleSource, ctx_ptr: *mut
 	register(scope, pluma, data, "io-args", cb_io_args);
 	register(scope, pluma, data, "io-env", cb_io_env);
 	register(scope, pluma, data, "io-exit", cb_io_exit);
+	// `pluma test --coverage` counters (`ir::coverage`).
+	register(scope, pluma, data, "coverage-hit", cb_coverage_hit);
 	register(scope, pluma, data, "io-cwd", cb_io_cwd);
 	// Unary float math — the libm calls (`(f64) -> f64`).
 	register(scope, pluma, data, "math-log", cb_math_log);
@@ -436,6 +386,8 @@ fn run_in_context(scope: &mut v8::HandleScope, src: ModuleSource, ctx_ptr: *mut
 	register(scope, pluma, data, "time-monotonic", cb_time_monotonic);
 	register(scope, pluma, data, "time-sleep", cb_time_sleep);
 	register(scope, pluma, data, "time-parse", cb_time_parse);
+	// `std/test`'s fakes (`test.with-fake-clock`/`-fs`/`-net`): one command per call.
+	register(scope, pluma, data, "test-fake", cb_test_fake);
 	// std/sys/net — socket ops (the multi-result ones return a `[status, n]` JS array).
 	register(scope, pluma, data, "net-listen", cb_net_listen);
 	register(scope, pluma, data, "net-connect", cb_net_connect);
@@ -461,8 +413,111 @@ fn run_in_context(scope: &mut v8::HandleScope, src: ModuleSource, ctx_ptr: *mut
 	register(scope, pluma, data, "db-op", cb_db_op);
 	// std/web/fetch — the browser HTTP transport, here a blocking HTTP/1.1 exchange.
 	register(scope, pluma, data, "web-fetch", cb_web_fetch);
-	// std/event — SSR stubs (a server build constructs view handlers but never runs
-	// them; these link the import and are never actually called).
+	// std/web/dom + std/event, against the run's headless DOM (host/src/dom.rs) — what
+	// a `pluma test` of a component renders into — and `std/test/browser`'s probes.
+	register(scope, pluma, data, "dom-body", cb_dom_body);
+	register(scope, pluma, data, "dom-window", cb_dom_window);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-create-element",
+		cb_dom_create_element,
+	);
+	register(scope, pluma, data, "dom-create-text", cb_dom_create_text);
+	register(scope, pluma, data, "dom-append-child", cb_dom_append_child);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-insert-before",
+		cb_dom_insert_before,
+	);
+	register(scope, pluma, data, "dom-remove-child", cb_dom_remove_child);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-replace-child",
+		cb_dom_replace_child,
+	);
+	register(scope, pluma, data, "dom-child-at", cb_dom_child_at);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-set-attribute",
+		cb_dom_set_attribute,
+	);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-remove-attribute",
+		cb_dom_remove_attribute,
+	);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-set-string-property",
+		cb_dom_set_string_property,
+	);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-set-bool-property",
+		cb_dom_set_bool_property,
+	);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-set-style-property",
+		cb_dom_set_style_property,
+	);
+	register(scope, pluma, data, "dom-set-text", cb_dom_set_text);
+	register(scope, pluma, data, "dom-get-value", cb_dom_get_value);
+	register(scope, pluma, data, "dom-element-text", cb_dom_element_text);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-location-path",
+		cb_dom_location_path,
+	);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-location-search",
+		cb_dom_location_search,
+	);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-location-hash",
+		cb_dom_location_hash,
+	);
+	register(scope, pluma, data, "dom-push-state", cb_dom_navigate);
+	register(scope, pluma, data, "dom-replace-state", cb_dom_navigate);
+	register(scope, pluma, data, "dom-add-listener", cb_dom_add_listener);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-dev-store-set",
+		cb_dom_dev_store_set,
+	);
+	register(
+		scope,
+		pluma,
+		data,
+		"dom-dev-store-get",
+		cb_dom_dev_store_get,
+	);
 	register(
 		scope,
 		pluma,
@@ -484,7 +539,17 @@ fn run_in_context(scope: &mut v8::HandleScope, src: ModuleSource, ctx_ptr: *mut
 		"event-prevent-default",
 		cb_event_prevent_default,
 	);
-	register(scope, pluma, data, "dom-child-at", cb_dom_child_at);
+	register(scope, pluma, data, "test-dom-html", cb_test_dom_html);
+	register(scope, pluma, data, "test-dom-text", cb_test_dom_text);
+	register(
+		scope,
+		pluma,
+		data,
+		"test-dom-dispatch",
+		cb_test_dom_dispatch,
+	);
+	register(scope, pluma, data, "test-dom-count", cb_test_dom_count);
+	register(scope, pluma, data, "test-dom-match", cb_test_dom_match);
 	let imports = v8::Object::new(scope);
 	let pluma_key = v8::String::new(scope, "pluma").unwrap();
 	imports.set(scope, pluma_key.into(), pluma.into());
@@ -503,6 +568,16 @@ fn run_in_context(scope: &mut v8::HandleScope, src: ModuleSource, ctx_ptr: *mut
 		.and_then(|v| v.to_object(scope))
 		.expect("memory export");
 	unsafe { &mut *ctx_ptr }.memory = Some(v8::Global::new(scope, memory));
+	let now = get_prop(scope, exports, "__now").and_then(|v| v.to_object(scope));
+	unsafe { &mut *ctx_ptr }.now = now.map(|now| v8::Global::new(scope, now));
+	unsafe { &mut *ctx_ptr }.dom_dispatch = dispatch_export(scope, exports);
+	if let Some(seed) = unsafe { &*ctx_ptr }.schedule_seed
+		&& let Some(global) = get_prop(scope, exports, "__sched_seed").and_then(|v| v.to_object(scope))
+	{
+		let key = v8::String::new(scope, "value").unwrap();
+		let state = v8::BigInt::new_from_i64(scope, shuffle_state(seed));
+		global.set(scope, key.into(), state.into());
+	}
 
 	let entry: v8::Local<v8::Function> = get_prop(scope, exports, "_entry")
 		.and_then(|v| v.try_into().ok())
@@ -521,6 +596,11 @@ fn run_in_context(scope: &mut v8::HandleScope, src: ModuleSource, ctx_ptr: *mut
 		None => {
 			// A trap. An `io.fail` stashed its message host-side; surface that, else the
 			// raw V8 exception text (e.g. a wasm RuntimeError) so the reason isn't lost.
+			// Either way the exception's stack maps back to Pluma frames.
+			if let Some(stack) = tc.stack_trace() {
+				let stack = stack.to_rust_string_lossy(tc);
+				unsafe { &mut *ctx_ptr }.trace = pluma_trace(wire, &stack);
+			}
 			match unsafe { &*ctx_ptr }.state.fail.clone() {
 				Some(msg) => format!("runtime error: {msg}"),
 				None => {
@@ -544,6 +624,16 @@ fn run_in_context(scope: &mut v8::HandleScope, src: ModuleSource, ctx_ptr: *mut
 	}
 }
 
+/// Map a trap's V8 stack to Pluma frames through the module's line table
+/// (`wasm::debuginfo`). Empty when the module carries none (an optimized build) or
+/// no frame lands in Pluma code.
+fn pluma_trace(wire: &[u8], v8_stack: &str) -> String {
+	match wasm::debuginfo::LineTable::read(wire) {
+		Some(table) => wasm::debuginfo::render_trace(&table.symbolicate(v8_stack)),
+		None => String::new(),
+	}
+}
+
 /// `new WebAssembly.Instance(module, imports)`.
 fn instantiate<'s>(
 	scope: &mut v8::HandleScope<'s>,
@@ -65,7 +65,8 @@ pub(super) fn cb_net_listen(
 /// stall the scheduler thread (host/src/offload.rs). Submit-or-collect like the other offload ops:
 /// the first call submits the blocking `TcpStream::connect` and reports would-block (status
 /// 1); after the wake re-runs the parked task, the second call adopts the connected socket
-/// into the table and returns its id. status: 0 ok, 1 would-block, 2 error.
+/// into the table and returns its id. status: 0 ok, 1 would-block, 2 error. Under
+/// `test.with-fake-net` the dial is answered inline by the loopback fake.
 pub(super) fn cb_net_connect(
 	scope: &mut v8::HandleScope,
 	args: v8::FunctionCallbackArguments,
@@ -85,6 +86,12 @@ pub(super) fn cb_net_connect(
 			set_pair(scope, &mut rv, 2, 0);
 		}
 		Some(_) => unreachable!("net-connect collected a non-conn result"),
+		None if ctx.state.net.is_fake() => {
+			let addr = read_str(scope, mem, ap, al);
+			let ret = ctx.state.net.fake_connect(&mut ctx.state.reactor, &addr);
+			let (s, n) = net_scalar_v8(ctx, ret);
+			set_pair(scope, &mut rv, s, n);
+		}
 		None => {
 			let addr = read_str(scope, mem, ap, al);
 			ctx.state.reactor.submit(
@@ -106,7 +113,7 @@ pub(super) fn cb_net_close(
 ) {
 	let id = argi(scope, &args, 0) as u32;
 	let (ctx, _mem) = ctx_and_mem(scope, &args);
-	let ret = ctx.state.net.close(id);
+	let ret = ctx.state.net.close(&mut ctx.state.reactor, id);
 	rv.set_int32(net_scalar_v8(ctx, ret).0);
 }
 
@@ -8,6 +8,7 @@
 use std::time::Duration;
 
 use super::Ctx;
+use super::fs::fake_fs_op;
 use super::marshal::{argi, ctx_and_mem, deliver_read_v8, read_mem, read_str, set_pair};
 use crate::offload::OpResult;
 
@@ -44,7 +45,8 @@ pub(super) fn cb_io_poll(
 }
 
 /// `io-unwatch(i32 fid) -> ()`: drop a parked socket wait or an in-flight offload op on
-/// cancellation / reaping (the reaped fiber's `wait::IO` registration). Idempotent.
+/// cancellation / reaping (the reaped fiber's `wait::IO` registration), including a wait on
+/// a fake socket. Idempotent.
 pub(super) fn cb_io_unwatch(
 	scope: &mut v8::HandleScope,
 	args: v8::FunctionCallbackArguments,
@@ -53,6 +55,7 @@ pub(super) fn cb_io_unwatch(
 	let fid = argi(scope, &args, 0);
 	let (ctx, _mem) = ctx_and_mem(scope, &args);
 	ctx.state.reactor.unwatch(fid);
+	ctx.state.net.unpark(fid);
 }
 
 /// `offload-sleep(i32 fid, i64 nanos) -> (i32 status, i32 n)`: the v0 proving op — sleep
@@ -128,7 +131,8 @@ fn deliver_fs(
 /// see `fsop::op`) on a worker thread. `data` is the write payload or the rename/copy
 /// destination path; the ok payload comes back through `(dst, cap)` (overflow stashed for
 /// `io-copyout`). Submit-or-collect like the net/sleep ops: the first call submits + would-
-/// blocks (status 1), the wake's re-run collects the worker's result.
+/// blocks (status 1), the wake's re-run collects the worker's result. A fake filesystem
+/// (`test.with-fake-fs`) answers on the first call, without a round trip through the pool.
 pub(super) fn cb_fs_op(
 	scope: &mut v8::HandleScope,
 	args: v8::FunctionCallbackArguments,
@@ -148,6 +152,11 @@ pub(super) fn cb_fs_op(
 		None => {
 			let path = read_str(scope, mem, pp, pl);
 			let data = read_mem(scope, mem, dp.max(0) as usize, dl.max(0) as usize);
+			if let Some(res) = fake_dispatch(scope, ctx, op, &path, &data) {
+				let (s, n) = deliver_fs(scope, mem, ctx, dst, cap, res);
+				set_pair(scope, &mut rv, s, n);
+				return;
+			}
 			ctx
 				.state
 				.reactor
@@ -173,7 +182,11 @@ pub(super) fn cb_fs_op_sync(
 	let (ctx, mem) = ctx_and_mem(scope, &args);
 	let path = read_str(scope, mem, pp, pl);
 	let data = read_mem(scope, mem, dp.max(0) as usize, dl.max(0) as usize);
-	let (s, n) = deliver_fs(scope, mem, ctx, dst, cap, sys_dispatch(op, &path, &data));
+	let res = match fake_dispatch(scope, ctx, op, &path, &data) {
+		Some(res) => res,
+		None => sys_dispatch(op, &path, &data),
+	};
+	let (s, n) = deliver_fs(scope, mem, ctx, dst, cap, res);
 	rv.set_int32(if s == 0 { n } else { -1 });
 }
 
@@ -187,3 +200,18 @@ fn sys_dispatch(op: i32, path: &str, data: &[u8]) -> OpResult {
 		crate::fsop::dispatch(op, path, data)
 	}
 }
+
+/// The fake filesystem's answer to an fs op, if a test installed one. Subprocess ops
+/// always run for real.
+fn fake_dispatch(
+	scope: &mut v8::HandleScope,
+	ctx: &mut Ctx,
+	op: i32,
+	path: &str,
+	data: &[u8],
+) -> Option<OpResult> {
+	if crate::procop::is_proc_op(op) {
+		return None;
+	}
+	fake_fs_op(scope, ctx, op, path, data)
+}
@@ -0,0 +1,789 @@
+// The pooled `pluma test` driver. Every case runs in its OWN fresh V8 isolate —
+// fresh wasm globals, scheduler, and `HostState` — so no case can observe state
+// another left behind, and the cases of one big module spread across the pool
+// instead of running in line on a single isolate. The artifact is compiled once
+// and its `CompiledWasmModule` shared (no recompilation) across the isolates.
+//
+// A run takes two steps, both through the in-wasm runner
+// (`std/test.run-all-sharded`), which reads its job from the reserved
+// `PLUMA_TEST_SHARD` host value:
+//   - `plan`: one isolate lists every case, with whether it runs and the time
+//     limit it set (`test.timeout`), if any;
+//   - `case <suite> <case>`: one isolate per case runs it and prints its record.
+// Each case's isolate gets a thread of its own, watched by the pool worker that
+// started it. Past the case's limit the worker terminates the isolate and fails
+// the case without waiting for the thread, so a hang costs one case, not the
+// run. The report is rendered here from the records, in module order, the way
+// `std/test.report-module` renders a single-process run.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{ModuleSource, compile_to_shared, ensure_v8, run_in_fresh_isolate};
+use crate::{
+	CapturingIo, Echo, RunCapture, TestCase, TestRun, TestSettings, TestStatus, unescape_field,
+};
+
+/// Run a `pluma test` artifact with the default settings and map the outcome to a
+/// process exit code: 0 all-pass, 1 on any failure or trap.
+pub fn run_test_v8(bytes: &[u8], color: bool) -> i32 {
+	let settings = TestSettings {
+		color,
+		echo: Echo::Stdout,
+		timeout: TestSettings::DEFAULT_TIMEOUT,
+		retries: 0,
+		schedule_seed: None,
+		only: Vec::new(),
+	};
+	run_test_v8_detailed(bytes, &settings).code
+}
+
+/// Run a `pluma test` artifact case by case over a bounded pool of isolates,
+/// printing the report where `settings.echo` says and handing back what the
+/// run recorded: every case's result, and probe hit counts for a
+/// coverage-instrumented artifact (`pluma test --coverage`), summed across the
+/// isolates.
+pub fn run_test_v8_detailed(bytes: &[u8], settings: &TestSettings) -> TestRun {
+	ensure_v8();
+	let mut run = TestRun {
+		code: 0,
+		coverage: Vec::new(),
+		cases: Vec::new(),
+	};
+
+	// Compile once on a throwaway isolate, then extract the shareable compiled
+	// module. The native code outlives that isolate (it's held behind a shared
+	// pointer), so every case rebuilds its module object from it for free.
+	let compiled = match compile_to_shared(bytes) {
+		Some(c) => Arc::new(c),
+		None => {
+			eprintln!("wasm compile failed");
+			run.code = 1;
+			return run;
+		}
+	};
+
+	let plan = run_in_fresh_isolate(
+		ModuleSource::Compiled(&compiled),
+		Box::new(CapturingIo::new(&[])),
+		Vec::new(),
+		Some("plan".to_string()),
+		None,
+		None,
+	);
+	if plan.status != "ok" {
+		let msg = plan
+			.status
+			.strip_prefix("runtime error: ")
+			.unwrap_or(&plan.status);
+		eprintln!("{msg}");
+		eprint!("{}{}", plan.trace, plan.stderr);
+		run.code = 1;
+		return run;
+	}
+	let (suites, mut planned) = parse_plan(&plan.stdout);
+	// Cases outside `--case` drop out of the run and the report alike. Each
+	// keeps its index in its suite, which is what its job names.
+	if !settings.only.is_empty() {
+		planned.retain(|p| selected(p, &settings.only));
+	}
+
+	// The cases that run, each a work item; skipped and todo cases are settled
+	// from the plan alone.
+	let items: Vec<usize> = (0..planned.len())
+		.filter(|&i| planned[i].kind == Kind::Run)
+		.collect();
+	let results: Arc<Mutex<Vec<Option<CaseRun>>>> =
+		Arc::new(Mutex::new((0..planned.len()).map(|_| None).collect()));
+	let coverage: Arc<Mutex<Vec<u64>>> = Arc::new(Mutex::new(Vec::new()));
+
+	// A bounded pool of `workers` threads pulls work items off a shared cursor;
+	// the pool is sized to the machine.
+	let workers = std::thread::available_parallelism()
+		.map(|n| n.get())
+		.unwrap_or(4)
+		.clamp(1, items.len().max(1));
+	let planned = Arc::new(planned);
+	let items = Arc::new(items);
+	let cursor = Arc::new(AtomicUsize::new(0));
+	let handles: Vec<_> = (0..workers)
+		.map(|_| {
+			let compiled = Arc::clone(&compiled);
+			let planned = Arc::clone(&planned);
+			let items = Arc::clone(&items);
+			let cursor = Arc::clone(&cursor);
+			let results = Arc::clone(&results);
+			let coverage = Arc::clone(&coverage);
+			let settings = settings.clone();
+			std::thread::spawn(move || {
+				loop {
+					let n = cursor.fetch_add(1, Ordering::Relaxed);
+					let Some(&i) = items.get(n) else {
+						break;
+					};
+					let case_run = run_with_retries(&compiled, &planned[i], &settings, &coverage);
+					results.lock().unwrap()[i] = Some(case_run);
+				}
+			})
+		})
+		.collect();
+	for handle in handles {
+		if handle.join().is_err() {
+			eprintln!("a test worker thread panicked");
+			run.code = 1;
+		}
+	}
+
+	run.coverage = std::mem::take(&mut *coverage.lock().unwrap());
+	let results = std::mem::take(&mut *results.lock().unwrap());
+	let mut cases: Vec<Vec<CaseRun>> = suites.iter().map(|_| Vec::new()).collect();
+	for (planned, result) in planned.iter().zip(results) {
+		let mut case_run = result.unwrap_or_else(|| CaseRun::settled(planned));
+		case_run.case.module = suites[planned.suite].clone();
+		cases[planned.suite].push(case_run);
+	}
+
+	// Print in module order so the report is deterministic regardless of which
+	// thread finished first: each module's own output, then its report line.
+	for (name, module_cases) in suites.iter().zip(&cases) {
+		// A module `--case` picked nothing from isn't part of the run.
+		if module_cases.is_empty() && !settings.only.is_empty() {
+			continue;
+		}
+		for case_run in module_cases {
+			echo(settings.echo, &case_run.stdout);
+			eprint!("{}", case_run.stderr);
+		}
+		let results: Vec<&TestCase> = module_cases.iter().map(|c| &c.case).collect();
+		echo(
+			settings.echo,
+			&module_report(name, &results, settings.color),
+		);
+	}
+	run.cases = cases.into_iter().flatten().map(|c| c.case).collect();
+
+	let flaky: Vec<&TestCase> = run
+		.cases
+		.iter()
+		.filter(|c| c.status == TestStatus::Flaky)
+		.collect();
+	echo(settings.echo, &flaky_report(&flaky, settings.color));
+	echo(settings.echo, &summary_report(&run.cases, settings.color));
+	if run.cases.iter().any(|c| c.status == TestStatus::Failed) {
+		run.code = 1;
+		// A failure under a shuffled schedule is only useful if it can be replayed.
+		if let Some(seed) = settings.schedule_seed {
+			eprintln!(
+				"schedule seed {seed}: rerun with `pluma test --schedule-seed {seed}` to replay it"
+			);
+		}
+	}
+	run
+}
+
+// --------------------------------------------------------------------------
+// The plan.
+// --------------------------------------------------------------------------
+
+/// What the plan says to do with a case.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+	Run,
+	Skip,
+	Todo,
+}
+
+/// One case as the plan lists it.
+struct Planned {
+	/// The index of its suite, in module order.
+	suite: usize,
+	/// Its index among its suite's cases.
+	index: usize,
+	path: Vec<String>,
+	name: String,
+	kind: Kind,
+	/// The limit it set with `test.timeout`, if any.
+	limit: Option<Duration>,
+}
+
+/// The suite names and every case, from the `plan` isolate's output (see
+/// `std/test.print-plan`).
+fn parse_plan(stdout: &str) -> (Vec<String>, Vec<Planned>) {
+	let mut suites: Vec<String> = Vec::new();
+	let mut planned: Vec<Planned> = Vec::new();
+	let mut index = 0;
+	for line in stdout.lines() {
+		let Some(record) = line.strip_prefix('\u{1f}') else {
+			continue;
+		};
+		let fields: Vec<String> = record.split('\u{1f}').map(unescape_field).collect();
+		match fields.as_slice() {
+			[tag, name] if tag == "suite" => {
+				suites.push(name.clone());
+				index = 0;
+			}
+			[tag, kind, limit, path, name] if tag == "case" && !suites.is_empty() => {
+				let kind = match kind.as_str() {
+					"run" => Kind::Run,
+					"todo" => Kind::Todo,
+					_ => Kind::Skip,
+				};
+				let limit = limit
+					.parse::<u64>()
+					.ok()
+					.filter(|&nanos| nanos > 0)
+					.map(Duration::from_nanos);
+				planned.push(Planned {
+					suite: suites.len() - 1,
+					index,
+					path: match path.as_str() {
+						"" => Vec::new(),
+						p => p.split('\u{1d}').map(str::to_string).collect(),
+					},
+					name: name.clone(),
+					kind,
+					limit,
+				});
+				index += 1;
+			}
+			_ => {}
+		}
+	}
+	(suites, planned)
+}
+
+/// Whether a case is the one `only` names or sits in the group it names.
+fn selected(planned: &Planned, only: &[String]) -> bool {
+	let mut full = planned.path.iter().chain(std::iter::once(&planned.name));
+	only.iter().all(|part| full.next() == Some(part))
+}
+
+// --------------------------------------------------------------------------
+// Running a case.
+// --------------------------------------------------------------------------
+
+/// A case's result, with what it printed. The result's `module` is filled in
+/// from the plan once the pool is done.
+struct CaseRun {
+	case: TestCase,
+	stdout: String,
+	stderr: String,
+}
+
+impl CaseRun {
+	/// A case that never runs: skipped or todo.
+	fn settled(planned: &Planned) -> CaseRun {
+		CaseRun {
+			case: TestCase {
+				module: String::new(),
+				path: planned.path.clone(),
+				name: planned.name.clone(),
+				status: match planned.kind {
+					Kind::Todo => TestStatus::Todo,
+					Kind::Run | Kind::Skip => TestStatus::Skipped,
+				},
+				message: String::new(),
+				duration: Duration::ZERO,
+				attempts: 0,
+			},
+			stdout: String::new(),
+			stderr: String::new(),
+		}
+	}
+
+	/// A case that ran but didn't report a result: its isolate trapped,
+	/// crashed or was stopped.
+	fn failed(planned: &Planned, message: String, duration: Duration) -> CaseRun {
+		CaseRun {
+			case: TestCase {
+				module: String::new(),
+				path: planned.path.clone(),
+				name: planned.name.clone(),
+				status: TestStatus::Failed,
+				message,
+				duration,
+				attempts: 1,
+			},
+			stdout: String::new(),
+			stderr: String::new(),
+		}
+	}
+}
+
+/// Run a case, and with `settings.retries` run it again while it fails.
+fn run_with_retries(
+	compiled: &Arc<v8::CompiledWasmModule>,
+	planned: &Planned,
+	settings: &TestSettings,
+	coverage: &Mutex<Vec<u64>>,
+) -> CaseRun {
+	let limit = planned.limit.unwrap_or(settings.timeout);
+	retrying(settings.retries, || {
+		run_once(compiled, planned, limit, settings.schedule_seed, coverage)
+	})
+}
+
+/// Call `run` until it doesn't fail, at most `retries` times more than once. A
+/// case that passes on a retry is flaky, and keeps the message of its first
+/// failure.
+fn retrying(retries: u32, mut run: impl FnMut() -> CaseRun) -> CaseRun {
+	let mut first_failure: Option<String> = None;
+	let mut attempts = 0;
+	loop {
+		attempts += 1;
+		let mut case_run = run();
+		case_run.case.attempts = attempts;
+		match case_run.case.status {
+			TestStatus::Failed if attempts <= retries => {
+				first_failure.get_or_insert(case_run.case.message);
+			}
+			TestStatus::Passed if first_failure.is_some() => {
+				case_run.case.status = TestStatus::Flaky;
+				case_run.case.message = first_failure.unwrap_or_default();
+				return case_run;
+			}
+			_ => return case_run,
+		}
+	}
+}
+
+/// How long a timed-out case's thread gets to hand over its isolate handle.
+const HANDLE_WAIT: Duration = Duration::from_secs(5);
+
+/// Run a case once, in a fresh isolate on a thread of its own, stopping it if
+/// it's still running at `limit`. Every run of a case under a `schedule_seed`
+/// gets the same seed, so a retry replays the interleaving rather than rolling a
+/// new one.
+fn run_once(
+	compiled: &Arc<v8::CompiledWasmModule>,
+	planned: &Planned,
+	limit: Duration,
+	schedule_seed: Option<u64>,
+	coverage: &Mutex<Vec<u64>>,
+) -> CaseRun {
+	let (watch, isolate) = mpsc::channel();
+	let (done, finished) = mpsc::channel();
+	let shared = Arc::clone(compiled);
+	let job = format!("case {} {}", planned.suite, planned.index);
+	std::thread::spawn(move || {
+		let cap = run_in_fresh_isolate(
+			ModuleSource::Compiled(&shared),
+			Box::new(CapturingIo::new(&[])),
+			Vec::new(),
+			Some(job),
+			Some(watch),
+			schedule_seed,
+		);
+		let _ = done.send(cap);
+	});
+
+	let cap = match finished.recv_timeout(limit) {
+		Ok(cap) => cap,
+		Err(RecvTimeoutError::Timeout) => {
+			// Stop the isolate where it is. One blocked in a host call (a sleep, a
+			// socket read) stops once the call returns; either way its thread is
+			// left to wind down on its own. The handle is sent before the case
+			// starts, but a loaded machine may still be setting the isolate up, so
+			// wait a moment for it rather than leave the case running unbounded.
+			if let Ok(handle) = isolate.recv_timeout(HANDLE_WAIT) {
+				handle.terminate_execution();
+			}
+			let message = format!("timed out after {}", human_duration(limit));
+			return CaseRun::failed(planned, message, limit);
+		}
+		Err(RecvTimeoutError::Disconnected) => {
+			return CaseRun::failed(
+				planned,
+				"the test isolate crashed".to_string(),
+				Duration::ZERO,
+			);
+		}
+	};
+
+	{
+		let mut totals = coverage.lock().unwrap();
+		if totals.len() < cap.coverage.len() {
+			totals.resize(cap.coverage.len(), 0);
+		}
+		for (total, n) in totals.iter_mut().zip(&cap.coverage) {
+			*total += n;
+		}
+	}
+	case_run_of(planned, cap)
+}
+
+/// A finished isolate's case: the record `std/test` printed, with the rest of
+/// its output kept as the case's own. An isolate that trapped before printing a
+/// record fails the case with the trap's message and trace.
+fn case_run_of(planned: &Planned, cap: RunCapture) -> CaseRun {
+	let mut case: Option<TestCase> = None;
+	let mut stdout = String::new();
+	for line in cap.stdout.split_inclusive('\n') {
+		match line.strip_prefix('\u{1f}') {
+			Some(record) => case = TestCase::from_record(record.trim_end_matches('\n')).or(case),
+			None => stdout.push_str(line),
+		}
+	}
+	let case = match case {
+		Some(case) => case,
+		None => {
+			let status = cap.status.as_str();
+			let msg = status.strip_prefix("runtime error: ").unwrap_or(status);
+			let message = match msg {
+				"ok" => "the case didn't report a result".to_string(),
+				msg => format!("{msg}\n{}", cap.trace).trim_end().to_string(),
+			};
+			CaseRun::failed(planned, message, Duration::ZERO).case
+		}
+	};
+	CaseRun {
+		case,
+		stdout,
+		stderr: cap.stderr,
+	}
+}
+
+/// A time limit as a person would write it: `10s`, `1.5s`, `250ms`.
+fn human_duration(d: Duration) -> String {
+	if d.as_millis() < 1000 {
+		format!("{}ms", d.as_millis())
+	} else {
+		format!("{}s", d.as_secs_f64())
+	}
+}
+
+// --------------------------------------------------------------------------
+// The report.
+// --------------------------------------------------------------------------
+
+/// Wrap `s` in an SGR sequence when color is on, like `std/test.paint`.
+fn paint(color: bool, sgr: &str, s: &str) -> String {
+	if color {
+		format!("\x1b[{sgr}m{s}\x1b[0m")
+	} else {
+		s.to_string()
+	}
+}
+
+/// Print report text where `to` says.
+fn echo(to: Echo, text: &str) {
+	match to {
+		Echo::Stdout => print!("{text}"),
+		Echo::Stderr => eprint!("{text}"),
+		Echo::Off => {}
+	}
+}
+
+fn count(cases: &[&TestCase], status: TestStatus) -> usize {
+	cases.iter().filter(|c| c.status == status).count()
+}
+
+/// Report one module the way `std/test.report-module` does: a clean module is a
+/// single green line; a failing one leads with a red line, then lists its
+/// failing cases under their groups, each with its message.
+fn module_report(name: &str, cases: &[&TestCase], color: bool) -> String {
+	let failed = count(cases, TestStatus::Failed);
+	let flaky = count(cases, TestStatus::Flaky);
+	let passed = count(cases, TestStatus::Passed) + flaky;
+	let mut counts = Vec::new();
+	if failed > 0 {
+		counts.push(format!("{failed} failed"));
+	}
+	counts.push(format!("{passed} passed"));
+	for (n, word) in [
+		(flaky, "flaky"),
+		(count(cases, TestStatus::Skipped), "skipped"),
+		(count(cases, TestStatus::Todo), "todo"),
+	] {
+		if n > 0 {
+			counts.push(format!("{n} {word}"));
+		}
+	}
+	let detail = paint(color, "2", &format!("({})", counts.join(", ")));
+	let (mark, sgr) = if failed == 0 {
+		("✓", "1;32")
+	} else {
+		("✗", "1;31")
+	};
+	let mut out = format!(
+		"{}  {} {}\n",
+		paint(color, sgr, mark),
+		paint(color, "1", name),
+		detail
+	);
+
+	// Group headers are printed once per run of cases sharing them.
+	let mut printed: &[String] = &[];
+	for case in cases.iter().filter(|c| c.status == TestStatus::Failed) {
+		let common = printed
+			.iter()
+			.zip(&case.path)
+			.take_while(|(a, b)| a == b)
+			.count();
+		for (depth, group) in case.path.iter().enumerate().skip(common) {
+			out += &format!("{}{}\n", "  ".repeat(depth + 1), paint(color, "2;1", group));
+		}
+		printed = &case.path;
+		let indent = "  ".repeat(case.path.len() + 1);
+		out += &format!("{indent}{} {}\n", paint(color, "1;31", "✗"), case.name);
+		for line in case.message.split('\n') {
+			out += &format!("{indent}    {line}\n");
+		}
+	}
+	out
+}
+
+/// List the cases that failed and then passed on a retry, apart from the module
+/// reports, so a flaky test doesn't go unnoticed just because the run passed.
+fn flaky_report(flaky: &[&TestCase], color: bool) -> String {
+	let mut out = String::new();
+	if flaky.is_empty() {
+		return out;
+	}
+	out += &format!("\n{}\n", paint(color, "1;33", "flaky (passed on a retry):"));
+	for case in flaky {
+		let label = std::iter::once(case.module.as_str())
+			.chain(case.path.iter().map(String::as_str))
+			.chain([case.name.as_str()])
+			.collect::<Vec<_>>()
+			.join(" > ");
+		out += &format!(
+			"  {} {label} {}\n",
+			paint(color, "1;33", "⚠"),
+			paint(
+				color,
+				"2",
+				&format!("(passed on attempt {})", case.attempts)
+			)
+		);
+		for line in case.message.split('\n') {
+			out += &format!("      {line}\n");
+		}
+	}
+	out
+}
+
+/// The one aggregate summary line for the run, mirroring
+/// `std/test.summary-line`'s wording and color (bold green all-pass, bold red
+/// otherwise) so a pooled run reads like a single-process one.
+fn summary_report(cases: &[TestCase], color: bool) -> String {
+	let all: Vec<&TestCase> = cases.iter().collect();
+	let failed = count(&all, TestStatus::Failed);
+	let flaky = count(&all, TestStatus::Flaky);
+	let passed = count(&all, TestStatus::Passed) + flaky;
+	let mut line = format!("{} of {} passed", passed, passed + failed);
+	for (n, word) in [
+		(flaky, "flaky"),
+		(count(&all, TestStatus::Skipped), "skipped"),
+		(count(&all, TestStatus::Todo), "todo"),
+	] {
+		if n > 0 {
+			line += &format!(", {n} {word}");
+		}
+	}
+	let sgr = if failed == 0 { "1;32" } else { "1;31" };
+	format!("\n{}\n", paint(color, sgr, &line))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn case(path: &[&str], name: &str, status: TestStatus, message: &str) -> TestCase {
+		TestCase {
+			module: "math.test".to_string(),
+			path: path.iter().map(|p| p.to_string()).collect(),
+			name: name.to_string(),
+			status,
+			message: message.to_string(),
+			duration: Duration::ZERO,
+			attempts: 1,
+		}
+	}
+
+	fn planned(path: &[&str], name: &str) -> Planned {
+		Planned {
+			suite: 0,
+			index: 0,
+			path: path.iter().map(|p| p.to_string()).collect(),
+			name: name.to_string(),
+			kind: Kind::Run,
+			limit: None,
+		}
+	}
+
+	#[test]
+	fn the_plan_lists_each_suites_cases() {
+		let plan = [
+			"\u{1f}suite\u{1f}math.test",
+			"\u{1f}case\u{1f}run\u{1f}0\u{1f}\u{1f}adds",
+			"a line the suite printed",
+			"\u{1f}case\u{1f}skip\u{1f}250000000\u{1f}edge\u{1d}big\u{1f}line\\nbreak",
+			"\u{1f}suite\u{1f}net.test",
+			"\u{1f}case\u{1f}todo\u{1f}0\u{1f}\u{1f}retries",
+		]
+		.join("\n");
+		let (suites, cases) = parse_plan(&plan);
+		assert_eq!(suites, ["math.test", "net.test"]);
+		let seen: Vec<_> = cases
+			.iter()
+			.map(|c| {
+				(
+					c.suite,
+					c.index,
+					c.path.clone(),
+					c.name.as_str(),
+					c.kind,
+					c.limit,
+				)
+			})
+			.collect();
+		assert_eq!(
+			seen,
+			[
+				(0, 0, vec![], "adds", Kind::Run, None),
+				(
+					0,
+					1,
+					vec!["edge".to_string(), "big".to_string()],
+					"line\nbreak",
+					Kind::Skip,
+					Some(Duration::from_millis(250)),
+				),
+				(1, 0, vec![], "retries", Kind::Todo, None),
+			]
+		);
+	}
+
+	#[test]
+	fn a_case_is_selected_by_its_path_or_a_group_above_it() {
+		let add = planned(&["math", "add"], "ones");
+		let only = |parts: &[&str]| {
+			let parts: Vec<String> = parts.iter().map(|p| p.to_string()).collect();
+			selected(&add, &parts)
+		};
+		assert!(only(&[]));
+		assert!(only(&["math"]));
+		assert!(only(&["math", "add"]));
+		assert!(only(&["math", "add", "ones"]));
+		assert!(!only(&["math", "sub"]));
+		// Whole names only, not prefixes of them.
+		assert!(!only(&["mat"]));
+		assert!(!only(&["math", "add", "ones", "more"]));
+	}
+
+	// A run of `retrying` over scripted outcomes, one per attempt.
+	fn retried(retries: u32, outcomes: &[(TestStatus, &str)]) -> TestCase {
+		let mut outcomes = outcomes.iter();
+		retrying(retries, || {
+			let (status, message) = outcomes.next().expect("ran too many times");
+			CaseRun {
+				case: case(&[], "sync", *status, message),
+				stdout: String::new(),
+				stderr: String::new(),
+			}
+		})
+		.case
+	}
+
+	#[test]
+	fn a_case_passing_on_a_retry_is_flaky_and_one_that_never_does_failed() {
+		use TestStatus::*;
+		let passed = retried(2, &[(Passed, "")]);
+		assert_eq!((passed.status, passed.attempts), (Passed, 1));
+
+		let flaky = retried(2, &[(Failed, "reset"), (Failed, "refused"), (Passed, "")]);
+		assert_eq!((flaky.status, flaky.attempts), (Flaky, 3));
+		assert_eq!(flaky.message, "reset");
+
+		let failed = retried(1, &[(Failed, "reset"), (Failed, "refused")]);
+		assert_eq!((failed.status, failed.attempts), (Failed, 2));
+		assert_eq!(failed.message, "refused");
+
+		let once = retried(0, &[(Failed, "reset")]);
+		assert_eq!((once.status, once.attempts), (Failed, 1));
+	}
+
+	#[test]
+	fn limits_read_as_written() {
+		assert_eq!(human_duration(Duration::from_millis(250)), "250ms");
+		assert_eq!(human_duration(Duration::from_millis(999)), "999ms");
+		assert_eq!(human_duration(Duration::from_secs(10)), "10s");
+		assert_eq!(human_duration(Duration::from_millis(1500)), "1.5s");
+	}
+
+	#[test]
+	fn a_clean_module_is_one_line() {
+		let cases = [
+			case(&[], "adds", TestStatus::Passed, ""),
+			case(&[], "subtracts", TestStatus::Passed, ""),
+			case(&[], "divides", TestStatus::Skipped, ""),
+			case(&[], "overflows", TestStatus::Todo, ""),
+		];
+		let cases: Vec<&TestCase> = cases.iter().collect();
+		assert_eq!(
+			module_report("math.test", &cases, false),
+			"✓  math.test (2 passed, 1 skipped, 1 todo)\n"
+		);
+		assert_eq!(
+			module_report("math.test", &cases[..1], true),
+			"\x1b[1;32m✓\x1b[0m  \x1b[1mmath.test\x1b[0m \x1b[2m(1 passed)\x1b[0m\n"
+		);
+	}
+
+	#[test]
+	fn a_failing_module_lists_its_failures_under_their_groups() {
+		let cases = [
+			case(&["add"], "ones", TestStatus::Failed, "expected 2\ngot 3"),
+			case(&["add"], "twos", TestStatus::Failed, "boom"),
+			case(&["add"], "threes", TestStatus::Passed, ""),
+			case(&[], "net", TestStatus::Flaky, "reset"),
+		];
+		let cases: Vec<&TestCase> = cases.iter().collect();
+		assert_eq!(
+			module_report("math.test", &cases, false),
+			"✗  math.test (2 failed, 2 passed, 1 flaky)\n\
+			 \x20 add\n\
+			 \x20   ✗ ones\n\
+			 \x20       expected 2\n\
+			 \x20       got 3\n\
+			 \x20   ✗ twos\n\
+			 \x20       boom\n"
+		);
+	}
+
+	#[test]
+	fn the_summary_counts_every_status() {
+		let mut flaky = case(&["sync"], "fetches", TestStatus::Flaky, "connection reset");
+		flaky.module = "net.test".to_string();
+		flaky.attempts = 2;
+		let cases = [
+			case(&[], "adds", TestStatus::Passed, ""),
+			case(&[], "divides", TestStatus::Failed, "boom"),
+			flaky.clone(),
+			case(&[], "overflows", TestStatus::Todo, ""),
+		];
+		assert_eq!(
+			summary_report(&cases, false),
+			"\n2 of 3 passed, 1 flaky, 1 todo\n"
+		);
+		assert_eq!(
+			summary_report(&cases[..1], true),
+			"\n\x1b[1;32m1 of 1 passed\x1b[0m\n"
+		);
+		assert_eq!(
+			summary_report(&cases[..2], true),
+			"\n\x1b[1;31m1 of 2 passed\x1b[0m\n"
+		);
+
+		assert_eq!(flaky_report(&[], false), "");
+		assert_eq!(
+			flaky_report(&[&flaky], false),
+			"\nflaky (passed on a retry):\n\
+			 \x20 ⚠ net.test > sync > fetches (passed on attempt 2)\n\
+			 \x20     connection reset\n"
+		);
+	}
+}
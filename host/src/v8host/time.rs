@@ -1,36 +1,68 @@
 // std/time (the `Clock` capability). Wall clock + monotonic clock + blocking sleep +
 // strtime parse, using the `jiff` crate. `time.now` is an `instant` (unix nanos),
 // `time.monotonic` a `duration` (nanos since a process-start anchor); both cross as i64
-// BigInts and the wasm side boxes them under the right tag.
+// BigInts and the wasm side boxes them under the right tag. Under `test.with-fake-clock`
+// all three answer from the fake clock (`crate::fake::FakeClock`) instead, and a sleep
+// just moves it.
 
-use super::marshal::{argi, ctx_and_mem, read_str, write_mem};
+use super::Ctx;
+use super::marshal::{argi, ctx_and_mem, get_prop, read_str, write_mem};
 
 /// Process-start anchor for `time.monotonic` (a static `OnceLock`).
 static MONOTONIC_START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
 
+/// The scheduler's virtual clock (nanos), read from the module's `"__now"` export; 0
+/// for a module without one.
+pub(super) fn virtual_now(scope: &mut v8::HandleScope, ctx: &Ctx) -> i64 {
+	let Some(now) = &ctx.now else { return 0 };
+	let now = v8::Local::new(scope, now);
+	get_prop(scope, now, "value")
+		.and_then(|v| v.to_big_int(scope))
+		.map(|b| b.i64_value().0)
+		.unwrap_or(0)
+}
+
+/// The current wall-clock time in unix nanos: the fake clock's if one is installed.
+pub(super) fn wall_now(scope: &mut v8::HandleScope, ctx: &mut Ctx) -> i64 {
+	let virtual_now = virtual_now(scope, ctx);
+	match &mut ctx.state.fakes.clock {
+		Some(clock) => clock.now(virtual_now),
+		None => jiff::Timestamp::now().as_nanosecond() as i64,
+	}
+}
+
 /// `time-now() -> i64`: wall-clock unix nanos (boxed `instant` in wasm).
 pub(super) fn cb_time_now(
 	scope: &mut v8::HandleScope,
-	_a: v8::FunctionCallbackArguments,
+	args: v8::FunctionCallbackArguments,
 	mut rv: v8::ReturnValue,
 ) {
-	let n = jiff::Timestamp::now().as_nanosecond() as i64;
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	let n = wall_now(scope, ctx);
 	rv.set(v8::BigInt::new_from_i64(scope, n).into());
 }
 
-/// `time-monotonic() -> i64`: nanos since the process-start anchor (boxed `duration`).
+/// `time-monotonic() -> i64`: nanos since the process-start anchor (boxed `duration`),
+/// or since the fake clock was installed.
 pub(super) fn cb_time_monotonic(
 	scope: &mut v8::HandleScope,
-	_a: v8::FunctionCallbackArguments,
+	args: v8::FunctionCallbackArguments,
 	mut rv: v8::ReturnValue,
 ) {
-	let start = MONOTONIC_START.get_or_init(std::time::Instant::now);
-	let n = start.elapsed().as_nanos() as i64;
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	let virtual_now = virtual_now(scope, ctx);
+	let n = match &mut ctx.state.fakes.clock {
+		Some(clock) => clock.elapsed(virtual_now),
+		None => {
+			let start = MONOTONIC_START.get_or_init(std::time::Instant::now);
+			start.elapsed().as_nanos() as i64
+		}
+	};
 	rv.set(v8::BigInt::new_from_i64(scope, n).into());
 }
 
-/// `time-sleep(i64 nanos)`: block the thread (synchronous host call, like `net-poll`).
-/// Returns nothing.
+/// `time-sleep(i64 nanos)`: block the thread (synchronous host call, like `net-poll`),
+/// or move a fake clock forward without waiting. Returns nothing.
 pub(super) fn cb_time_sleep(
 	scope: &mut v8::HandleScope,
 	args: v8::FunctionCallbackArguments,
@@ -41,7 +73,11 @@ pub(super) fn cb_time_sleep(
 		.to_big_int(scope)
 		.map(|b| b.i64_value().0)
 		.unwrap_or(0);
-	if nanos > 0 {
+	let (ctx, _mem) = ctx_and_mem(scope, &args);
+	let virtual_now = virtual_now(scope, ctx);
+	if let Some(clock) = &mut ctx.state.fakes.clock {
+		clock.advance(nanos, virtual_now);
+	} else if nanos > 0 {
 		std::thread::sleep(std::time::Duration::from_nanos(nanos as u64));
 	}
 }
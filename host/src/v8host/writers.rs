@@ -136,7 +136,8 @@ pub(super) fn cb_write_err(
 
 /// `io-fail(ptr, len)`: stash the pre-rendered message host-side, then throw — the
 /// `_entry` call unwinds, and the runner surfaces the stashed message as the
-/// program's `runtime error: <msg>` status.
+/// program's `runtime error: <msg>` status. The throw is an `Error` (not a bare
+/// string) so V8 captures the wasm frames it unwinds through for the trace.
 pub(super) fn cb_io_fail(
 	scope: &mut v8::HandleScope,
 	args: v8::FunctionCallbackArguments,
@@ -146,8 +147,9 @@ pub(super) fn cb_io_fail(
 	let (ctx, mem) = ctx_and_mem(scope, &args);
 	let bytes = read_mem(scope, mem, ptr.max(0) as usize, len.max(0) as usize);
 	ctx.state.fail = Some(String::from_utf8_lossy(&bytes).into_owned());
-	let exc = v8::String::new(scope, "io.fail").unwrap();
-	scope.throw_exception(exc.into());
+	let msg = v8::String::new(scope, "io.fail").unwrap();
+	let exc = v8::Exception::error(scope, msg);
+	scope.throw_exception(exc);
 }
 
 /// `float_to_str(f64, ptr, cap) -> i32 len`: format the float in the canonical
@@ -171,46 +173,3 @@ pub(super) fn cb_float_to_str(
 	}
 	rv.set_int32(bytes.len() as i32);
 }
-
-/// SSR stubs for the two browser-only event accessors. `std/view` names them in
-/// every handler closure, so a server build that *constructs* a view (to render
-/// it with `view.to-string`) declares these imports even though `to-string` drops
-/// the handlers and never calls them. Registered so the server module links; if
-/// one were ever actually invoked under the sys host it returns the empty answer.
-///
-/// `event-target-value(externref, dst, cap) -> i32 len` — no value, length 0.
-pub(super) fn cb_event_target_value(
-	_scope: &mut v8::HandleScope,
-	_args: v8::FunctionCallbackArguments,
-	mut rv: v8::ReturnValue,
-) {
-	rv.set_int32(0);
-}
-
-/// `event-target-checked(externref, dst, cap) -> i32 len` — no value, length 0
-/// (decodes to `""`, i.e. `false`, in `std/event`).
-pub(super) fn cb_event_target_checked(
-	_scope: &mut v8::HandleScope,
-	_args: v8::FunctionCallbackArguments,
-	mut rv: v8::ReturnValue,
-) {
-	rv.set_int32(0);
-}
-
-/// `event-prevent-default(externref) -> ()` — no-op.
-pub(super) fn cb_event_prevent_default(
-	_scope: &mut v8::HandleScope,
-	_args: v8::FunctionCallbackArguments,
-	_rv: v8::ReturnValue,
-) {
-}
-
-/// `dom-child-at(externref, i32) -> externref` — SSR/hydration is browser-only, so
-/// under the sys host this is never reached; registered only so an ungated
-/// `pluma run`/`test` of `std/web/render` links. Returns undefined (a null node).
-pub(super) fn cb_dom_child_at(
-	_scope: &mut v8::HandleScope,
-	_args: v8::FunctionCallbackArguments,
-	_rv: v8::ReturnValue,
-) {
-}
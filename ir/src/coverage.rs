@@ -0,0 +1,273 @@
+// Coverage instrumentation — the `pluma test --coverage` pass.
+//
+// Inserts a `StmtKind::Probe(n)` wherever something worth counting starts: the
+// entry of each def's function body, the first statement on each source line,
+// and the head of each arm of an `If`/`Switch`/`Match` (what `if`, `when`, and
+// `try` lower to). The emitter turns a probe into a call to the `coverage-hit`
+// host import; the host keeps one counter per probe id and the CLI folds the
+// counts back through the returned `Probe` table into lcov.
+//
+// Runs on freshly-lowered IR, before `wasm::emit`'s pipeline, so every later
+// pass sees probes as ordinary straight-line statements. Only modules the caller
+// selects are instrumented (a package's own sources, not the stdlib or its
+// tests), and only code with a source location: synthetic scaffolding and the
+// thunk that merely wraps a `def f = fun { … }` lambda stay untouched.
+//
+// A generic def specialized by the lowerer appears as several functions over
+// the same source, so its probes repeat. Each `Probe` carries its source
+// position rather than an identity; consumers sum counters that share one.
+
+use crate::types::*;
+use compiler::Range;
+
+/// What one coverage counter counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeKind {
+	/// Entry to a function body. `def` is the top-level def it belongs to (a
+	/// lambda nested in `helper` is `helper` too).
+	Function { def: String },
+	/// The first statement on a source line.
+	Line,
+	/// Arm `arm` of the branch whose statement starts at column `col` of the
+	/// probe's line.
+	Branch { col: u32, arm: u32 },
+}
+
+/// One counter: where it is and what it counts. Indexed by the `Probe` id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Probe {
+	pub module: String,
+	/// 1-based source line.
+	pub line: u32,
+	pub kind: ProbeKind,
+}
+
+/// Instrument every function whose module `include` accepts, returning the
+/// probe table: `Probe(n)` statements count into entry `n`.
+pub fn instrument(program: &mut IrProgram, include: impl Fn(&str) -> bool) -> Vec<Probe> {
+	let mut probes = Vec::new();
+	for f in &mut program.functions {
+		if !include(&f.module) || !is_source_function(f) {
+			continue;
+		}
+		let mut ins = Instrumenter {
+			module: &f.module,
+			probes: &mut probes,
+		};
+		let mut body = std::mem::replace(&mut f.body, Block(Vec::new()));
+		ins.block(&mut body);
+		if !f.name.ends_with("@thunk")
+			&& let Some(line) = function_line(f, &body)
+		{
+			let probe = ins.probe(
+				line,
+				ProbeKind::Function {
+					def: def_of(&f.name, &f.module),
+				},
+			);
+			body.0.insert(0, Stmt::synthetic(probe));
+		}
+		f.body = body;
+	}
+	probes
+}
+
+struct Instrumenter<'a> {
+	module: &'a str,
+	probes: &'a mut Vec<Probe>,
+}
+
+impl Instrumenter<'_> {
+	fn probe(&mut self, line: u32, kind: ProbeKind) -> StmtKind {
+		self.probes.push(Probe {
+			module: self.module.to_string(),
+			line,
+			kind,
+		});
+		StmtKind::Probe(self.probes.len() as u32 - 1)
+	}
+
+	/// Probe each new line in `b`, and each arm of the branches it contains.
+	fn block(&mut self, b: &mut Block) {
+		let mut out = Vec::with_capacity(b.0.len());
+		let mut last_line = None;
+		for mut s in std::mem::take(&mut b.0) {
+			if !is_synthetic(s.range) {
+				let line = s.range.start.line as u32 + 1;
+				if last_line != Some(line) {
+					last_line = Some(line);
+					out.push(Stmt::new(self.probe(line, ProbeKind::Line), s.range));
+				}
+			}
+			self.branches(&mut s);
+			out.push(s);
+		}
+		b.0 = out;
+	}
+
+	fn branches(&mut self, s: &mut Stmt) {
+		let at =
+			(!is_synthetic(s.range)).then(|| (s.range.start.line as u32 + 1, s.range.start.col as u32));
+		let mut arms: Vec<&mut Block> = match &mut s.kind {
+			StmtKind::If(_, t, e) => vec![t, e],
+			StmtKind::Switch { arms, default, .. } => arms
+				.iter_mut()
+				.map(|(_, b)| b)
+				.chain(std::iter::once(&mut **default))
+				.collect(),
+			StmtKind::Match { arms, .. } => arms.iter_mut().map(|a| &mut a.body).collect(),
+			StmtKind::Loop(b) => {
+				self.block(b);
+				return;
+			}
+			_ => return,
+		};
+		for (i, arm) in arms.iter_mut().enumerate() {
+			self.block(arm);
+			if let Some((line, col)) = at {
+				let probe = self.probe(line, ProbeKind::Branch { col, arm: i as u32 });
+				let range = Range::collapsed(line as usize - 1, col as usize);
+				arm.0.insert(0, Stmt::new(probe, range));
+			}
+		}
+	}
+}
+
+fn is_synthetic(r: Range) -> bool {
+	r.is_collapsed() && r.start.line == 0 && r.start.col == 0
+}
+
+/// Whether `f` is user code worth counting: a def's body, a lambda, a `defer`
+/// or `scope` block, or a value def's thunk — not RPC/wire/builtin scaffolding,
+/// and not a thunk that only builds the closure of a `def f = fun { … }`.
+fn is_source_function(f: &Function) -> bool {
+	let (base, suffix) = match f.name.split_once('@') {
+		Some((base, suffix)) => (base, Some(suffix)),
+		None => (f.name.as_str(), None),
+	};
+	let lambda = [".fun", ".defer", ".scope"]
+		.iter()
+		.any(|k| base.ends_with(k));
+	let source = match suffix {
+		None => true,
+		Some("thunk") => !builds_closure_only(&f.body),
+		Some(_) => lambda,
+	};
+	source && f.body.0.iter().any(|s| !is_synthetic(s.range))
+}
+
+fn builds_closure_only(b: &Block) -> bool {
+	b.0.iter().all(|s| {
+		matches!(
+			s.kind,
+			StmtKind::Let(_, Rvalue::MakeClosure(..)) | StmtKind::Return(_)
+		)
+	})
+}
+
+/// The line a function's `FN` record sits on: a lambda's own position
+/// (`main.helper.fun@7:35` → 8), else its first located statement.
+fn function_line(f: &Function, body: &Block) -> Option<u32> {
+	let at = f.name.split_once('@').map(|(_, at)| at).unwrap_or("");
+	if let Some(line) = at.split(':').next().and_then(|l| l.parse::<u32>().ok()) {
+		return Some(line + 1);
+	}
+	body
+		.0
+		.iter()
+		.find(|s| !is_synthetic(s.range))
+		.map(|s| s.range.start.line as u32 + 1)
+}
+
+/// `main.helper.fun@7:35` in module `main` → `helper`.
+fn def_of(name: &str, module: &str) -> String {
+	let name = name.split('@').next().unwrap_or(name);
+	let name = name
+		.strip_prefix(module)
+		.and_then(|n| n.strip_prefix('.'))
+		.unwrap_or(name);
+	name.split('.').next().unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use compiler::Compiler;
+
+	fn lowered(src: &str) -> IrProgram {
+		let dir = std::env::temp_dir().join(format!("ir-coverage-test-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("main.pa");
+		std::fs::write(&path, src).unwrap();
+		let mut compiler =
+			Compiler::from_entry_path(path.to_str().unwrap().to_string()).expect("from_entry_path");
+		compiler.check().expect("check");
+		crate::lower(&compiler).expect("lower")
+	}
+
+	#[test]
+	fn probes_defs_lines_and_branches_of_included_modules() {
+		let mut program = lowered(
+			"def sign = fun n {\n\tif n < 0 {\n\t\t0 - 1\n\t} else {\n\t\t1\n\t}\n}\n\ndef main = fun {\n\tprint (sign 3)\n}\n",
+		);
+		let probes = instrument(&mut program, |m| m == "main");
+		assert!(probes.iter().all(|p| p.module == "main"));
+
+		let defs: Vec<_> = probes
+			.iter()
+			.filter_map(|p| match &p.kind {
+				ProbeKind::Function { def } => Some((def.as_str(), p.line)),
+				_ => None,
+			})
+			.collect();
+		assert!(
+			defs.contains(&("sign", 1)) && defs.contains(&("main", 9)),
+			"{defs:?}"
+		);
+
+		let arms: Vec<_> = probes
+			.iter()
+			.filter_map(|p| match p.kind {
+				ProbeKind::Branch { arm, .. } => Some((p.line, arm)),
+				_ => None,
+			})
+			.collect();
+		assert!(arms.contains(&(2, 0)) && arms.contains(&(2, 1)), "{arms:?}");
+
+		let lines: Vec<u32> = probes
+			.iter()
+			.filter(|p| p.kind == ProbeKind::Line)
+			.map(|p| p.line)
+			.collect();
+		for line in [2, 3, 5, 10] {
+			assert!(lines.contains(&line), "line {line} not probed: {lines:?}");
+		}
+
+		// Every id a `Probe` statement uses is in the table.
+		fn ids(b: &Block, out: &mut Vec<u32>) {
+			for s in &b.0 {
+				match &s.kind {
+					StmtKind::Probe(n) => out.push(*n),
+					StmtKind::If(_, t, e) => {
+						ids(t, out);
+						ids(e, out);
+					}
+					StmtKind::Switch { arms, default, .. } => {
+						arms.iter().for_each(|(_, b)| ids(b, out));
+						ids(default, out);
+					}
+					StmtKind::Match { arms, .. } => arms.iter().for_each(|a| ids(&a.body, out)),
+					StmtKind::Loop(b) => ids(b, out),
+					_ => {}
+				}
+			}
+		}
+		let mut used = Vec::new();
+		program
+			.functions
+			.iter()
+			.for_each(|f| ids(&f.body, &mut used));
+		used.sort();
+		assert_eq!(used, (0..probes.len() as u32).collect::<Vec<_>>());
+	}
+}
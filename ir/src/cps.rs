@@ -252,7 +252,8 @@ impl Cfg {
 				}
 				// `PushDefer` is straight-line (it neither suspends nor branches);
 				// `build_poll_fn` rewrites it to an append into the `__defers` var.
-				StmtKind::PushDefer(_) => {
+				// A coverage `Probe` is straight-line too.
+				StmtKind::PushDefer(_) | StmtKind::Probe(_) => {
 					simple.push(stmts[i].clone());
 					i += 1;
 				}
@@ -364,7 +365,7 @@ impl Cfg {
 				self.new_block(simple, Term::Jump(header))
 			}
 			// `RunDefer` is never emitted by lowering (cleanups run at `Return`);
-			// `PushDefer`/`Await`/`Let`/`Discard` were handled above.
+			// `PushDefer`/`Probe`/`Await`/`Let`/`Discard` were handled above.
 			_ => unreachable!("cps: unexpected statement in flattener: {:?}", s.kind),
 		}
 	}
@@ -880,7 +881,7 @@ fn collect_stmt_reads(s: &Stmt, set: &mut HashSet<u32>) {
 			arms.iter().for_each(|a| collect_block_reads(&a.body, set));
 		}
 		StmtKind::Loop(b) => collect_block_reads(b, set),
-		StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) => {}
+		StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) | StmtKind::Probe(_) => {}
 	}
 }
 
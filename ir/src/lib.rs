@@ -21,6 +21,7 @@
 // transform that rewrites `Function::is_async` + `Rvalue::Await` into poll
 // functions; the `is_async` flag and the `Await` node are its inputs).
 
+pub mod coverage;
 pub mod cps;
 pub mod loopify;
 pub mod lower;
@@ -30,5 +31,5 @@ pub mod reuse;
 pub mod simplify;
 pub mod types;
 
-pub use lower::{lower, lower_entry, lower_tests};
+pub use lower::{TestOptions, lower, lower_benches, lower_entry, lower_tests};
 pub use types::*;
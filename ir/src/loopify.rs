@@ -327,7 +327,7 @@ fn next_var(f: &Function) -> u32 {
 					}
 				}
 				StmtKind::Loop(blk) => walk(blk, bump),
-				StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) => {}
+				StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) | StmtKind::Probe(_) => {}
 			}
 		}
 	}
@@ -52,12 +52,33 @@ pub fn lower(compiler: &Compiler) -> Result<IrProgram, String> {
 	Lowerer::new(compiler).run()
 }
 
+/// How `pluma test` wants its suites run. Baked into the synthesized runner call,
+/// so a change here is a different program (and a different cached artifact).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TestOptions {
+	/// ANSI styling in the rendered report.
+	pub color: bool,
+	/// Rewrite `assert.snapshot` files rather than compare against them
+	/// (`--update-snapshots`).
+	pub update_snapshots: bool,
+	/// Print a machine-readable record per case for the host to collect
+	/// (`--reporter`).
+	pub report: bool,
+}
+
 /// Lower for `pluma test`: synthesize an entry that runs every module's `tests`
-/// suite through `std/test.run-all`, rather than calling a `main`. `color`
-/// enables ANSI styling in the rendered report.
-pub fn lower_tests(compiler: &Compiler, color: bool) -> Result<IrProgram, String> {
+/// suite through `std/test.run-all`, rather than calling a `main`.
+pub fn lower_tests(compiler: &Compiler, options: TestOptions) -> Result<IrProgram, String> {
 	let mut lowerer = Lowerer::new(compiler);
-	lowerer.test_color = Some(color);
+	lowerer.test_options = Some(options);
+	lowerer.run()
+}
+
+/// Lower for `pluma bench`: synthesize an entry that runs every module's
+/// `benches` suite through `std/bench.run-all`, rather than calling a `main`.
+pub fn lower_benches(compiler: &Compiler) -> Result<IrProgram, String> {
+	let mut lowerer = Lowerer::new(compiler);
+	lowerer.benches = true;
 	lowerer.run()
 }
 
@@ -91,9 +112,12 @@ struct Lowerer<'a> {
 	imports: HashMap<String, String>,
 	// A single shared thunk for every unsupported def, built lazily.
 	poison: Option<FuncId>,
-	// `Some(color)` when lowering for `pluma test`: `build_entry` then synthesizes
-	// a runner over every `tests` suite instead of the module's `main`.
-	test_color: Option<bool>,
+	// `Some(options)` when lowering for `pluma test`: `build_entry` then
+	// synthesizes a runner over every `tests` suite instead of the module's `main`.
+	test_options: Option<TestOptions>,
+	// Set when lowering for `pluma bench`: `build_entry` then synthesizes a
+	// runner over every `benches` suite instead.
+	benches: bool,
 	// Which module's `main` is the program entry, overriding `entry_modules[0]`.
 	// Set for a FULLSTACK dual build, which lowers the one analyzed program twice —
 	// once rooted at `server`'s `main`, once at `client`'s — and lets the emitter's
@@ -225,7 +249,8 @@ impl<'a> Lowerer<'a> {
 			current_module: String::new(),
 			imports: HashMap::new(),
 			poison: None,
-			test_color: None,
+			test_options: None,
+			benches: false,
 			entry_override: None,
 			mono_subst: Substitution::empty(),
 			def_index: build_def_index(compiler),
@@ -253,15 +278,16 @@ impl<'a> Lowerer<'a> {
 			}
 		}
 
+		let suite_def = if self.benches { "benches" } else { "tests" };
 		let test_suites: Vec<(String, GlobalId)> = self
 			.compiler
 			.entry_modules
 			.iter()
-			.filter_map(|m| self.globals.lookup(m, "tests").map(|g| (m.clone(), g)))
+			.filter_map(|m| self.globals.lookup(m, suite_def).map(|g| (m.clone(), g)))
 			.collect();
-		// In test mode (`lower_tests`), synthesize a runner over these suites;
-		// otherwise the entry is the module's `main` (with a no-op fallback for a
-		// suite-bearing module that has no `main`).
+		// In test or bench mode (`lower_tests`, `lower_benches`), synthesize a
+		// runner over these suites; otherwise the entry is the module's `main`
+		// (with a no-op fallback for a suite-bearing module that has no `main`).
 		let entry = self.build_entry(&test_suites)?;
 
 		// Annotate every function's bindings with a `Repr` (uniform-boxed except
@@ -294,7 +320,7 @@ impl<'a> Lowerer<'a> {
 
 	fn lower_module(&mut self, module: &str, ast: &ModuleNode) {
 		self.current_module = module.to_string();
-		self.imports = build_imports(ast);
+		self.imports = build_imports(self.compiler, module, ast);
 		for def in &ast.body {
 			match &def.kind {
 				DefinitionKind::Expr(expr) => self.lower_value_def(
@@ -2748,7 +2774,9 @@ impl<'a> Lowerer<'a> {
 			.collect();
 		let fn_name = format!(
 			"{}.fun@{}:{}",
-			self.current_module, fun.range.start.line, fun.range.start.col
+			self.closure_prefix(),
+			fun.range.start.line,
+			fun.range.start.col
 		);
 		// Inside a specialized body, a nested lambda's record params are concrete too
 		// (under the active subst) — record their nominal shapes so this closure (e.g.
@@ -2859,7 +2887,9 @@ impl<'a> Lowerer<'a> {
 	fn lower_defer(&mut self, inner: &ExprNode, range: Range) -> Result<Atom, String> {
 		let fn_name = format!(
 			"{}.defer@{}:{}",
-			self.current_module, inner.range.start.line, inner.range.start.col
+			self.closure_prefix(),
+			inner.range.start.line,
+			inner.range.start.col
 		);
 		let closure =
 			self.lower_closure(fn_name, &[], &[], std::slice::from_ref(inner), range, None)?;
@@ -2914,7 +2944,9 @@ impl<'a> Lowerer<'a> {
 		let handle_name = node.handle.as_ref().map_or("__scope", |h| h.name.as_str());
 		let fn_name = format!(
 			"{}.scope@{}:{}",
-			self.current_module, range.start.line, range.start.col
+			self.closure_prefix(),
+			range.start.line,
+			range.start.col
 		);
 		let body = self.lower_closure(
 			fn_name,
@@ -3154,6 +3186,22 @@ impl<'a> Lowerer<'a> {
 		self.scopes.last_mut().expect("a scope is active")
 	}
 
+	/// The name prefix for a closure lowered here: the enclosing top-level def
+	/// (`main.helper`, from the outermost scope's `main.helper@thunk`), so a
+	/// lambda's `main.helper.fun@7:35` says which def it came from in a stack
+	/// trace. Just the module outside any def.
+	fn closure_prefix(&self) -> String {
+		match self.scopes.first() {
+			Some(outer) if outer.module == self.current_module => outer
+				.name
+				.split('@')
+				.next()
+				.unwrap_or(&outer.name)
+				.to_string(),
+			_ => self.current_module.clone(),
+		}
+	}
+
 	fn push_scope(&mut self, name: String, param_names: &[&str]) {
 		let mut scope = FnScope {
 			name,
@@ -3347,14 +3395,15 @@ impl<'a> Lowerer<'a> {
 
 	fn build_entry(&mut self, test_suites: &[(String, GlobalId)]) -> Result<FuncId, String> {
 		// The entry drives one root to completion, then returns its result. The
-		// only choice is *what* root: a `pluma test` runner over the discovered
-		// suites, or the module's `main`. Everything downstream — the scheduler
+		// only choice is *what* root: a `pluma test` or `pluma bench` runner over
+		// the discovered suites, or the module's `main`. Everything downstream — the scheduler
 		// wrapper, sync/async tolerance — is shared, so this is the single place
 		// the two programs diverge.
 		let mut body: Vec<Stmt> = Vec::new();
 		let mut next: u32 = 0;
-		let result = match self.test_color {
-			Some(color) => self.emit_test_runner(&mut body, &mut next, test_suites, color)?,
+		let result = match self.test_options {
+			Some(options) => self.emit_test_runner(&mut body, &mut next, test_suites, options)?,
+			None if self.benches => self.emit_bench_runner(&mut body, &mut next, test_suites)?,
 			None => self.emit_main_call(&mut body, &mut next, test_suites)?,
 		};
 		body.push(Stmt::synthetic(StmtKind::Return(result)));
@@ -3403,8 +3452,9 @@ impl<'a> Lowerer<'a> {
 		Ok(Atom::Var(result))
 	}
 
-	/// The `pluma test` root: build a `list {name, tests}` from the discovered
-	/// suites and call `std/test.run-all color suites`. The suites are referenced
+	/// The `pluma test` root: build a `list {name, snapshots, tests}` from the
+	/// discovered suites and call `std/test.run-all-sharded options shard suites`.
+	/// The suites are referenced
 	/// by `GlobalId`, so their privacy (a `*.test.pa`'s `tests` is private) doesn't
 	/// matter — no source-level import is involved.
 	fn emit_test_runner(
@@ -3412,7 +3462,7 @@ impl<'a> Lowerer<'a> {
 		body: &mut Vec<Stmt>,
 		next: &mut u32,
 		test_suites: &[(String, GlobalId)],
-		color: bool,
+		options: TestOptions,
 	) -> Result<Atom, String> {
 		let run_all = self.globals.lookup("std/test", "run-all-sharded").ok_or(
 			"`std/test.run-all-sharded` was not compiled — does a `*.test.pa` file `use std/test`?",
@@ -3438,11 +3488,27 @@ impl<'a> Lowerer<'a> {
 			),
 		);
 
-		// One `{name, tests}` record per suite. Field order is name-sorted to
-		// match the record-shape layout the backends expect from `MakeRecord`.
+		// One `{name, snapshots, tests}` record per suite. Field order is
+		// name-sorted to match the record-shape layout the backends expect from
+		// `MakeRecord`. `snapshots` is the directory the suite's `assert.snapshot`
+		// files live in: `foo/bar.test.pa` keeps them in `foo/__snapshots__/bar/`.
 		let mut items: Vec<ListItem> = Vec::new();
 		for (module, gid) in test_suites {
 			let display = module.strip_suffix(".test").unwrap_or(module).to_string();
+			let snapshots = self
+				.compiler
+				.modules
+				.get(module)
+				.and_then(|m| m.module_path.parent())
+				.map(|dir| {
+					let stem = display.rsplit('/').next().unwrap_or(&display);
+					dir
+						.join("__snapshots__")
+						.join(stem)
+						.to_string_lossy()
+						.into_owned()
+				})
+				.unwrap_or_default();
 			let tests = fresh_let(body, next, Rvalue::GlobalRef(*gid));
 			let rec = fresh_let(
 				body,
@@ -3450,6 +3516,7 @@ impl<'a> Lowerer<'a> {
 				Rvalue::MakeRecord(
 					vec![
 						("name".to_string(), Atom::Const(Const::Str(display))),
+						("snapshots".to_string(), Atom::Const(Const::Str(snapshots))),
 						("tests".to_string(), Atom::Var(tests)),
 					],
 					None,
@@ -3459,22 +3526,79 @@ impl<'a> Lowerer<'a> {
 		}
 
 		let list = fresh_let(body, next, Rvalue::MakeList(items));
+		let opts = fresh_let(
+			body,
+			next,
+			Rvalue::MakeRecord(
+				vec![
+					("color".to_string(), Atom::Const(Const::Bool(options.color))),
+					(
+						"report".to_string(),
+						Atom::Const(Const::Bool(options.report)),
+					),
+					(
+						"update-snapshots".to_string(),
+						Atom::Const(Const::Bool(options.update_snapshots)),
+					),
+				],
+				None,
+			),
+		);
 		let runner = fresh_let(body, next, Rvalue::GlobalRef(run_all));
 		let result = fresh_let(
 			body,
 			next,
 			Rvalue::CallClosure(
 				Atom::Var(runner),
-				vec![
-					Atom::Const(Const::Bool(color)),
-					Atom::Var(shard),
-					Atom::Var(list),
-				],
+				vec![Atom::Var(opts), Atom::Var(shard), Atom::Var(list)],
 			),
 		);
 		Ok(Atom::Var(result))
 	}
 
+	/// The `pluma bench` root: build a `list {name, benches}` from the discovered
+	/// suites and call `std/bench.run-all suites`. Like the test runner, the
+	/// suites are referenced by `GlobalId`, so their privacy doesn't matter.
+	fn emit_bench_runner(
+		&mut self,
+		body: &mut Vec<Stmt>,
+		next: &mut u32,
+		bench_suites: &[(String, GlobalId)],
+	) -> Result<Atom, String> {
+		let run_all = self
+			.globals
+			.lookup("std/bench", "run-all")
+			.ok_or("`std/bench.run-all` was not compiled — does a `*.bench.pa` file `use std/bench`?")?;
+
+		// One `{benches, name}` record per suite, fields name-sorted for `MakeRecord`.
+		let mut items: Vec<ListItem> = Vec::new();
+		for (module, gid) in bench_suites {
+			let display = module.strip_suffix(".bench").unwrap_or(module).to_string();
+			let benches = fresh_let(body, next, Rvalue::GlobalRef(*gid));
+			let rec = fresh_let(
+				body,
+				next,
+				Rvalue::MakeRecord(
+					vec![
+						("benches".to_string(), Atom::Var(benches)),
+						("name".to_string(), Atom::Const(Const::Str(display))),
+					],
+					None,
+				),
+			);
+			items.push(ListItem::Elem(Atom::Var(rec)));
+		}
+
+		let list = fresh_let(body, next, Rvalue::MakeList(items));
+		let runner = fresh_let(body, next, Rvalue::GlobalRef(run_all));
+		let result = fresh_let(
+			body,
+			next,
+			Rvalue::CallClosure(Atom::Var(runner), vec![Atom::Var(list)]),
+		);
+		Ok(Atom::Var(result))
+	}
+
 	/// Wrap a synthesized entry body in the `__entry__` function. Zero params, no
 	/// captures, not async (the scheduler wrapper tolerates a task or plain value
 	/// the body returns) — the single shape every entry takes.
@@ -3560,12 +3684,17 @@ fn fresh_let(body: &mut Vec<Stmt>, next: &mut u32, rv: Rvalue) -> VarId {
 }
 
 /// Build the module's local-namespace -> qualified-module map: explicit `use`
-/// declarations plus the auto-imported modules (unless shadowed).
-fn build_imports(ast: &ModuleNode) -> HashMap<String, String> {
+/// declarations (resolved the way the compiler loaded them, so a dependency
+/// package's sibling imports map to its own modules) plus the auto-imported
+/// modules (unless shadowed).
+fn build_imports(compiler: &Compiler, module: &str, ast: &ModuleNode) -> HashMap<String, String> {
 	let mut imports: HashMap<String, String> = ast
 		.uses
 		.iter()
-		.map(|u| (u.local_name().name.clone(), u.module_name()))
+		.map(|u| {
+			let full = compiler.packages.import_target(module, &u.module_name());
+			(u.local_name().name.clone(), full)
+		})
 		.collect();
 	for (full, local) in compiler::AUTO_IMPORTS {
 		imports
@@ -3607,7 +3736,12 @@ fn build_module_imports(compiler: &Compiler) -> HashMap<String, HashMap<String,
 	compiler
 		.modules_sorted()
 		.into_iter()
-		.filter_map(|(m, data)| data.ast.as_ref().map(|ast| (m.clone(), build_imports(ast))))
+		.filter_map(|(m, data)| {
+			data
+				.ast
+				.as_ref()
+				.map(|ast| (m.clone(), build_imports(compiler, m, ast)))
+		})
 		.collect()
 }
 
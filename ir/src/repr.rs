@@ -224,7 +224,8 @@ fn assign_block(
 			| StmtKind::Break
 			| StmtKind::Continue
 			| StmtKind::RunDefer(_)
-			| StmtKind::PushDefer(_) => {}
+			| StmtKind::PushDefer(_)
+			| StmtKind::Probe(_) => {}
 		}
 	}
 }
@@ -604,7 +605,10 @@ impl Coercer<'_> {
 				StmtKind::Match { subject, arms }
 			}
 			StmtKind::Loop(b) => StmtKind::Loop(self.block(b)),
-			other @ (StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_)) => other,
+			other @ (StmtKind::Break
+			| StmtKind::Continue
+			| StmtKind::RunDefer(_)
+			| StmtKind::Probe(_)) => other,
 		};
 		out.extend(pre);
 		out.push(Stmt::new(kind, range));
@@ -680,7 +684,7 @@ fn check_block(
 				}
 			}
 			StmtKind::Loop(b) => check_block(b, reprs, fname, sigs, self_ret, nominal)?,
-			StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) => {}
+			StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) | StmtKind::Probe(_) => {}
 		}
 	}
 	Ok(())
@@ -766,7 +770,7 @@ fn block_vars(b: &Block, bump: &mut impl FnMut(VarId)) {
 				}
 			}
 			StmtKind::Loop(b) => block_vars(b, bump),
-			StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) => {}
+			StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) | StmtKind::Probe(_) => {}
 		}
 	}
 }
@@ -961,7 +965,7 @@ fn walk_join(
 				let cb = child_block(parent, block);
 				walk_join(blk, cb, parent, assigns, uses);
 			}
-			StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) => {}
+			StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) | StmtKind::Probe(_) => {}
 		}
 	}
 }
@@ -488,7 +488,7 @@ fn used_vars(f: &Function) -> HashSet<u32> {
 					}
 				}
 				StmtKind::Loop(blk) => walk(blk, note),
-				StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) => {}
+				StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) | StmtKind::Probe(_) => {}
 			}
 		}
 	}
@@ -572,7 +572,7 @@ mod tests {
 			entry: FuncId(2),
 			test_suites: vec![],
 			param_shapes: std::collections::HashMap::new(),
-		extra_nominal: std::collections::HashMap::new(),
+			extra_nominal: std::collections::HashMap::new(),
 		};
 		resolve_direct_calls(&mut program);
 
@@ -605,7 +605,7 @@ mod tests {
 			entry: FuncId(1),
 			test_suites: vec![],
 			param_shapes: std::collections::HashMap::new(),
-		extra_nominal: std::collections::HashMap::new(),
+			extra_nominal: std::collections::HashMap::new(),
 		};
 		resolve_direct_calls(&mut program);
 		let body = &program.functions[0].body.0;
@@ -635,7 +635,7 @@ mod tests {
 			entry: FuncId(2),
 			test_suites: vec![],
 			param_shapes: std::collections::HashMap::new(),
-		extra_nominal: std::collections::HashMap::new(),
+			extra_nominal: std::collections::HashMap::new(),
 		};
 		resolve_direct_calls(&mut program);
 		let body = &program.functions[2].body.0;
@@ -676,7 +676,7 @@ mod tests {
 			entry: FuncId(1),
 			test_suites: vec![],
 			param_shapes: std::collections::HashMap::new(),
-		extra_nominal: std::collections::HashMap::new(),
+			extra_nominal: std::collections::HashMap::new(),
 		};
 		resolve_direct_calls(&mut program);
 		let body = &program.functions[1].body.0;
@@ -722,7 +722,7 @@ mod tests {
 			entry: FuncId(2),
 			test_suites: vec![],
 			param_shapes: std::collections::HashMap::new(),
-		extra_nominal: std::collections::HashMap::new(),
+			extra_nominal: std::collections::HashMap::new(),
 		};
 		resolve_direct_calls(&mut program);
 
@@ -771,7 +771,7 @@ mod tests {
 			entry: FuncId(0),
 			test_suites: vec![],
 			param_shapes: std::collections::HashMap::new(),
-		extra_nominal: std::collections::HashMap::new(),
+			extra_nominal: std::collections::HashMap::new(),
 		};
 		fold_variant_ctor_calls(&mut program);
 
@@ -339,7 +339,11 @@ fn read_counts(f: &Function) -> std::collections::HashMap<u32, usize> {
 				StmtKind::If(cnd, _, _) => bump(cnd),
 				StmtKind::Switch { scrutinee, .. } => bump(scrutinee),
 				StmtKind::Match { subject, .. } => bump(subject),
-				StmtKind::Loop(_) | StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) => {}
+				StmtKind::Loop(_)
+				| StmtKind::Break
+				| StmtKind::Continue
+				| StmtKind::RunDefer(_)
+				| StmtKind::Probe(_) => {}
 			}
 			match &s.kind {
 				StmtKind::If(_, t, e) => {
@@ -423,7 +427,7 @@ impl UseScan {
 					}
 				}
 				StmtKind::Loop(blk) => self.block(blk),
-				StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) => {}
+				StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) | StmtKind::Probe(_) => {}
 			}
 		}
 	}
@@ -905,7 +909,7 @@ impl ListUseScan {
 					}
 				}
 				StmtKind::Loop(blk) => self.block(blk),
-				StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) => {}
+				StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) | StmtKind::Probe(_) => {}
 			}
 		}
 	}
@@ -1245,7 +1249,11 @@ fn stmt_reads_var(kind: &StmtKind, d: u32) -> bool {
 		StmtKind::If(c, _, _) => a(c),
 		StmtKind::Switch { scrutinee, .. } => a(scrutinee),
 		StmtKind::Match { subject, .. } => a(subject),
-		StmtKind::Loop(_) | StmtKind::Break | StmtKind::Continue | StmtKind::RunDefer(_) => {}
+		StmtKind::Loop(_)
+		| StmtKind::Break
+		| StmtKind::Continue
+		| StmtKind::RunDefer(_)
+		| StmtKind::Probe(_) => {}
 	}
 	found
 }
@@ -1465,7 +1473,7 @@ mod tests {
 			entry: FuncId(0),
 			test_suites: vec![],
 			param_shapes: std::collections::HashMap::new(),
-		extra_nominal: std::collections::HashMap::new(),
+			extra_nominal: std::collections::HashMap::new(),
 		};
 		reuse(&mut p);
 		p.functions.pop().unwrap()
@@ -72,7 +72,8 @@ pub struct IrProgram {
 	pub entry: FuncId,
 	/// `tests` suites (`std/test`) discovered in entry modules: (module, global).
 	/// `lower_tests` synthesizes the entry over these; `pluma test` also reads it to
-	/// detect "no tests found".
+	/// detect "no tests found". Under `lower_benches` these are the `benches`
+	/// suites (`std/bench`) instead.
 	pub test_suites: Vec<(String, GlobalId)>,
 	/// Record-shape monomorphization output: `FuncId.0 -> per-param nominal shapes`
 	/// for the specialized functions the lowerer's substitution-driven engine
@@ -279,6 +280,9 @@ pub enum StmtKind {
 	/// `PushDefer`. The cleanup stack is walked LIFO at `Return` (and on
 	/// `try`-failure short-circuit).
 	PushDefer(Atom),
+	/// Bump coverage counter `n` — inserted by `coverage::instrument` for
+	/// `pluma test --coverage`, never by lowering. Straight-line; no value.
+	Probe(u32),
 }
 
 /// One arm of a `Match`: a pattern and the block to run on a match.
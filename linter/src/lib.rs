@@ -305,13 +305,24 @@ fn offset(line_starts: &[usize], point: Point) -> Option<usize> {
 }
 
 /// Like [`lint_source`], but stamps each warning with `path` so the diagnostic
-/// renderer can pull the source excerpt for the caret. Used by `pluma lint`.
+/// renderer can pull the source excerpt for the caret.
 pub fn lint_path(path: &Path, source: &[u8]) -> Result<Vec<Diagnostic>, Vec<Diagnostic>> {
+	lint_path_findings(path, source)
+		.map(|findings| findings.into_iter().map(|f| f.diagnostic).collect())
+}
+
+/// Like [`lint_findings`], but stamps each warning with `path`, as
+/// [`lint_path`] does. Used by `pluma lint`, which reports the fixes alongside
+/// the warnings in its machine-readable formats.
+pub fn lint_path_findings(path: &Path, source: &[u8]) -> Result<Vec<Finding>, Vec<Diagnostic>> {
 	let name = path.to_string_lossy().into_owned();
-	lint_source(source).map(|warnings| {
-		warnings
+	collect(source).map(|findings| {
+		findings
 			.into_iter()
-			.map(|d| d.with_module(name.clone(), path.to_path_buf()))
+			.map(|f| Finding {
+				diagnostic: f.diagnostic.with_module(name.clone(), path.to_path_buf()),
+				fixes: f.fixes,
+			})
 			.collect()
 	})
 }
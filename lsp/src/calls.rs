@@ -0,0 +1,386 @@
+use crate::goto::{self, RefKind, Resolver, contains, range_size, same_range};
+use crate::references::{self, Overlay};
+use compiler::ast::*;
+use compiler::{Diagnostic, Module, Range};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// Call hierarchy: who calls a def, and what it calls. An item is a top-level
+// def, a trait method, or an instance's implementation of one. Incoming calls
+// are the item's references across the project, found the way find-references
+// finds them and filed under the def each sits in; outgoing calls are the
+// references in the item's body that resolve to another function. A function
+// passed by name (`list.map xs render`) counts as a call: syntax alone can't
+// tell whether it ends up invoked, and the caller depends on it either way.
+
+pub struct CallItem {
+	pub name: String,
+	pub kind: ItemKind,
+	pub path: PathBuf,
+	// The module it's declared in, shown beside the name.
+	pub module: Option<String>,
+	// The whole def, and its name.
+	pub range: Range,
+	pub selection: Range,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ItemKind {
+	Function,
+	Method,
+	// A def that isn't a function, e.g. a suite or a table of handlers. It can
+	// call things, but isn't called.
+	Value,
+}
+
+/// A call between two items: the other item, and where each call is spelled
+/// in the calling one.
+pub struct Call {
+	pub item: CallItem,
+	pub ranges: Vec<Range>,
+}
+
+/// The item under the cursor: the def whose name it's on, or the one the
+/// name under it refers to.
+pub fn prepare(source: &[u8], path: &Path, line: u32, character: u32) -> Option<CallItem> {
+	let module = parse(source)?;
+	let ast = module.ast.as_ref()?;
+	let (l, c) = (line as usize, character as usize);
+	if let Some(d) = defs(ast).into_iter().find(|d| contains(&d.selection, l, c)) {
+		return Some(d.item(path));
+	}
+	match goto::goto_definition(source, path, line, character)? {
+		goto::Target::Here(range) => item_at(ast, path, &range),
+		goto::Target::OtherFile { path, range } => {
+			let module = parse(&std::fs::read(&path).ok()?)?;
+			item_at(module.ast.as_ref()?, &path, &range)
+		}
+	}
+}
+
+/// Every item calling the one whose name is at `selection` in `path`, with
+/// where it calls it.
+pub fn incoming(path: &Path, selection: &Range, overlay: &Overlay) -> Vec<Call> {
+	let Some(source) = read(path, overlay) else {
+		return Vec::new();
+	};
+	let occurrences = references::find_references(
+		source.as_bytes(),
+		path,
+		selection.start.line as u32,
+		selection.start.col as u32,
+		overlay,
+	);
+
+	let mut files: HashMap<PathBuf, Option<Module>> = HashMap::new();
+	let mut calls: Vec<Call> = Vec::new();
+	for o in occurrences.iter().filter(|o| !o.declaration) {
+		let parsed = files
+			.entry(o.path.clone())
+			.or_insert_with(|| read(&o.path, overlay).and_then(|s| parse(s.as_bytes())));
+		let Some(ast) = parsed.as_ref().and_then(|m| m.ast.as_ref()) else {
+			continue;
+		};
+		// The innermost def around the call: an instance method, not the
+		// whole `implement` block.
+		let Some(caller) = defs(ast)
+			.into_iter()
+			.filter(|d| contains(&d.range, o.range.start.line, o.range.start.col))
+			.min_by_key(|d| range_size(&d.range))
+		else {
+			continue;
+		};
+		// An instance method's own name refers to the trait method it
+		// implements, without calling it.
+		if same_range(&caller.selection, &o.range) {
+			continue;
+		}
+		add(&mut calls, caller.item(&o.path), o.range);
+	}
+	sort(&mut calls);
+	calls
+}
+
+/// Every function the item whose name is at `selection` in `path` calls,
+/// with where it calls it.
+pub fn outgoing(path: &Path, selection: &Range, overlay: &Overlay) -> Vec<Call> {
+	let Some(module) = read(path, overlay).and_then(|s| parse(s.as_bytes())) else {
+		return Vec::new();
+	};
+	let Some(ast) = module.ast.as_ref() else {
+		return Vec::new();
+	};
+	let Some(caller) = defs(ast)
+		.into_iter()
+		.find(|d| same_range(&d.selection, selection))
+	else {
+		return Vec::new();
+	};
+	let mut r = Resolver::new(ast);
+	r.walk_module(ast);
+	let inside = |range: &Range| {
+		contains(&caller.range, range.start.line, range.start.col)
+			&& !same_range(range, &caller.selection)
+	};
+
+	// Bare names nothing here binds may be the methods of an imported trait.
+	let imports: Vec<(Module, PathBuf)> = ast
+		.uses
+		.iter()
+		.filter_map(|u| goto::module_file(&u.module_name(), path))
+		.collect();
+
+	let mut calls: Vec<Call> = Vec::new();
+	for rf in r
+		.refs
+		.iter()
+		.filter(|rf| rf.kind == RefKind::Value && inside(&rf.range))
+	{
+		let callee = match r.resolve_ref(rf) {
+			Some((RefKind::Value, b)) if b.scope.is_none() => item_at(ast, path, &b.def_range),
+			Some(_) => None,
+			None => imports.iter().find_map(|(module, file)| {
+				let ast = module.ast.as_ref()?;
+				let d = defs(ast)
+					.into_iter()
+					.find(|d| d.in_trait && d.name == rf.name)?;
+				Some(d.item(file))
+			}),
+		};
+		if let Some(callee) = callee.filter(|c| c.kind != ItemKind::Value) {
+			add(&mut calls, callee, rf.range);
+		}
+	}
+	for q in r
+		.qualified
+		.iter()
+		.filter(|q| q.kind == RefKind::Value && q.enum_name.is_none() && inside(&q.range))
+	{
+		let Some((module, file, range)) = goto::qualified_target(&ast.uses, path, q) else {
+			continue;
+		};
+		let callee = module
+			.ast
+			.as_ref()
+			.and_then(|ast| item_at(ast, &file, &range));
+		if let Some(callee) = callee.filter(|c| c.kind != ItemKind::Value) {
+			add(&mut calls, callee, q.range);
+		}
+	}
+	sort(&mut calls);
+	calls
+}
+
+// A def that can be a call hierarchy item.
+struct Def<'a> {
+	name: &'a str,
+	kind: ItemKind,
+	// Declared by a trait, rather than implemented by an instance.
+	in_trait: bool,
+	range: Range,
+	selection: Range,
+}
+
+impl Def<'_> {
+	fn item(&self, path: &Path) -> CallItem {
+		CallItem {
+			name: self.name.to_string(),
+			kind: self.kind,
+			path: path.to_path_buf(),
+			module: references::project_of(path).map(|(_, m)| m),
+			range: self.range,
+			selection: self.selection,
+		}
+	}
+}
+
+fn defs(ast: &ModuleNode) -> Vec<Def<'_>> {
+	let mut out = Vec::new();
+	for d in &ast.body {
+		match &d.kind {
+			DefinitionKind::Expr(_) => out.push(Def {
+				name: &d.name.name,
+				kind: if is_function(d) {
+					ItemKind::Function
+				} else {
+					ItemKind::Value
+				},
+				in_trait: false,
+				range: d.range,
+				selection: d.name.range,
+			}),
+			DefinitionKind::Trait(t) => out.extend(t.methods.iter().map(|m| Def {
+				name: &m.name.name,
+				kind: ItemKind::Method,
+				in_trait: true,
+				range: m.range,
+				selection: m.name.range,
+			})),
+			DefinitionKind::Instance(inst) => out.extend(inst.methods.iter().map(|m| Def {
+				name: &m.name.name,
+				kind: ItemKind::Method,
+				in_trait: false,
+				range: m.range,
+				selection: m.name.range,
+			})),
+			_ => {}
+		}
+	}
+	out
+}
+
+// A def whose value is a function: written as one, or annotated as one (a
+// built-in, or a function built by a call).
+fn is_function(d: &DefinitionNode) -> bool {
+	if let DefinitionKind::Expr(ExprNode {
+		kind: ExprKind::Fun(_),
+		..
+	}) = &d.kind
+	{
+		return true;
+	}
+	let mut ty = d.type_annotation.as_ref();
+	while let Some(TypeExprNode {
+		kind: TypeExprKind::Grouping(inner),
+		..
+	}) = ty
+	{
+		ty = Some(inner);
+	}
+	matches!(
+		ty,
+		Some(TypeExprNode {
+			kind: TypeExprKind::Func(..),
+			..
+		})
+	)
+}
+
+fn item_at(ast: &ModuleNode, path: &Path, selection: &Range) -> Option<CallItem> {
+	defs(ast)
+		.into_iter()
+		.find(|d| same_range(&d.selection, selection))
+		.map(|d| d.item(path))
+}
+
+fn add(calls: &mut Vec<Call>, item: CallItem, range: Range) {
+	match calls
+		.iter_mut()
+		.find(|c| c.item.path == item.path && same_range(&c.item.selection, &item.selection))
+	{
+		Some(call) => call.ranges.push(range),
+		None => calls.push(Call {
+			item,
+			ranges: vec![range],
+		}),
+	}
+}
+
+fn sort(calls: &mut [Call]) {
+	calls.sort_by_key(|c| {
+		(
+			c.item.path.clone(),
+			c.item.selection.start.line,
+			c.item.selection.start.col,
+		)
+	});
+}
+
+fn parse(source: &[u8]) -> Option<Module> {
+	let mut module = Module::new("<lsp>".to_string(), PathBuf::new());
+	let mut diagnostics: Vec<Diagnostic> = Vec::new();
+	module.parse_from_bytes(source.to_vec(), &mut diagnostics);
+	module.ast.as_ref()?;
+	Some(module)
+}
+
+fn read(path: &Path, overlay: &Overlay) -> Option<String> {
+	overlay
+		.get(path)
+		.cloned()
+		.or_else(|| std::fs::read_to_string(path).ok())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::temp_project;
+
+	// Each call as (caller or callee name, module, lines of the calls).
+	fn summary(calls: &[Call]) -> Vec<(String, String, Vec<usize>)> {
+		calls
+			.iter()
+			.map(|c| {
+				(
+					c.item.name.clone(),
+					c.item.module.clone().unwrap_or_default(),
+					c.ranges.iter().map(|r| r.start.line).collect(),
+				)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn calls_cross_modules_both_ways() {
+		let shapes = "def square = fun n { n * n }\n\npublic def area = fun s {\n\tsquare s\n}\n";
+		let app = "use shapes\n\ndef total = fun xs {\n\tshapes.area 1 + shapes.area 2\n}\n\ndef main = fun {\n\ttotal [1]\n}\n";
+		let dir = temp_project("calls-cross", &[("shapes.pa", shapes), ("app.pa", app)]);
+		let overlay = Overlay::new();
+
+		// Prepared from a use in another file, the item is the def itself.
+		let item = prepare(app.as_bytes(), &dir.join("app.pa"), 3, 9).unwrap();
+		assert_eq!(
+			(item.name.as_str(), item.kind, item.module.as_deref()),
+			("area", ItemKind::Function, Some("shapes"))
+		);
+		assert_eq!(item.path, dir.join("shapes.pa"));
+
+		assert_eq!(
+			summary(&incoming(&item.path, &item.selection, &overlay)),
+			vec![("total".to_string(), "app".to_string(), vec![3, 3])]
+		);
+		assert_eq!(
+			summary(&outgoing(&item.path, &item.selection, &overlay)),
+			vec![("square".to_string(), "shapes".to_string(), vec![3])]
+		);
+
+		let total = prepare(app.as_bytes(), &dir.join("app.pa"), 2, 5).unwrap();
+		assert_eq!(
+			summary(&outgoing(&total.path, &total.selection, &overlay)),
+			vec![("area".to_string(), "shapes".to_string(), vec![3, 3])]
+		);
+		assert_eq!(
+			summary(&incoming(&total.path, &total.selection, &overlay)),
+			vec![("main".to_string(), "app".to_string(), vec![7])]
+		);
+	}
+
+	#[test]
+	fn trait_methods_are_called_through_their_instances() {
+		let src = "trait sized a {\n\tsize :: fun a -> int\n}\n\nalias box {w :: int}\n\nimplement sized box {\n\tdef size = fun b { double b.w }\n}\n\ndef double = fun n { n * 2 }\n\ndef limit = 10\n\ndef fits = fun b {\n\tsize b < limit\n}\n";
+		let dir = temp_project("calls-traits", &[("main.pa", src)]);
+		let path = dir.join("main.pa");
+		let overlay = Overlay::new();
+
+		// The instance's own method name isn't a call of the trait's.
+		let method = prepare(src.as_bytes(), &path, 1, 1).unwrap();
+		assert_eq!(method.kind, ItemKind::Method);
+		assert_eq!(
+			summary(&incoming(&path, &method.selection, &overlay)),
+			vec![("fits".to_string(), "main".to_string(), vec![15])]
+		);
+
+		// An instance method calls what its body calls; `fits` reads `limit`,
+		// which is no function.
+		let implementation = prepare(src.as_bytes(), &path, 7, 6).unwrap();
+		assert_eq!(
+			summary(&outgoing(&path, &implementation.selection, &overlay)),
+			vec![("double".to_string(), "main".to_string(), vec![7])]
+		);
+		let fits = prepare(src.as_bytes(), &path, 14, 5).unwrap();
+		assert_eq!(
+			summary(&outgoing(&path, &fits.selection, &overlay)),
+			vec![("size".to_string(), "main".to_string(), vec![15])]
+		);
+	}
+}
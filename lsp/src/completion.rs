@@ -34,6 +34,9 @@ pub struct Completion {
 	// An explicit replacement: `(range, new_text)`. `use`-path completion
 	// replaces just the current path segment (the text after the last `/`).
 	pub edit: Option<(compiler::Range, String)>,
+	// An edit elsewhere in the file, made along with accepting the item: the
+	// `use` line an unimported module's item brings in.
+	pub import: Option<(compiler::Range, String)>,
 	// Ask the client to re-open completion after accepting this item. Set on
 	// `use`-path directories so accepting `sys/` immediately offers its
 	// contents — the drill-down feel.
@@ -54,6 +57,7 @@ impl Completion {
 			doc,
 			filter_text: None,
 			edit: None,
+			import: None,
 			retrigger: false,
 		}
 	}
@@ -78,7 +82,7 @@ pub fn complete(source: &[u8], path: &Path, line: u32, character: u32) -> Vec<Co
 	}
 	match member_receiver(&prefix) {
 		Some(receiver) => member_completions(source, path, line, character, &receiver),
-		None => scope_completions(source, line, character),
+		None => scope_completions(source, path, line, character),
 	}
 }
 
@@ -151,6 +155,12 @@ fn member_completions(
 		return variants;
 	}
 
+	// Or a module the file hasn't imported yet.
+	let members = unimported_member_completions(source, path, line, character, receiver);
+	if !members.is_empty() {
+		return members;
+	}
+
 	// Last: the receiver is a value. If its inferred type is a record, offer
 	// the record's fields.
 	record_field_completions(source, path, line, character)
@@ -287,10 +297,91 @@ fn local_enum_variants(source: &[u8], receiver: &str) -> Option<Vec<Completion>>
 	None
 }
 
+// Members of a module named like `receiver` that the file doesn't import —
+// the stdlib's `json` or the project's — each item adding the `use`. One
+// that's imported under an alias already (`use std/json as j`) isn't imported
+// twice: its items rewrite `json.` to `j.` instead.
+fn unimported_member_completions(
+	source: &[u8],
+	path: &Path,
+	line: u32,
+	character: u32,
+	receiver: &str,
+) -> Vec<Completion> {
+	// A local value shadows any module of the same name.
+	if goto::visible_symbols(source, line, character)
+		.iter()
+		.any(|(name, _)| name == receiver)
+	{
+		return Vec::new();
+	}
+	let prefix = line_prefix(source, line, character);
+	let partial_len = prefix
+		.chars()
+		.rev()
+		.take_while(|c| is_ident_char(*c))
+		.count();
+	let receiver_col = prefix.chars().count() - partial_len - 1 - receiver.chars().count();
+	let replaced = compiler::Range::within_line(line as usize, receiver_col, character as usize);
+	let uses = scan_uses(source);
+
+	let mut out = Vec::new();
+	for full_name in importable_modules(path) {
+		if full_name.rsplit('/').next() != Some(receiver) {
+			continue;
+		}
+		let Some(module) = goto::imported_module(&full_name, path) else {
+			continue;
+		};
+		let alias = uses
+			.iter()
+			.find(|(full, _)| *full == full_name)
+			.map(|(_, local)| local.clone());
+		let import = use_insertion(source, &full_name);
+		for mut c in module_member_completions(&module, &full_name) {
+			match &alias {
+				Some(alias) => {
+					c.filter_text = Some(format!("{}.{}", receiver, c.label));
+					c.edit = Some((replaced, format!("{}.{}", alias, c.label)));
+				}
+				None => {
+					c.import = Some((
+						compiler::Range::between(import.0, import.0),
+						import.1.clone(),
+					));
+				}
+			}
+			out.push(c);
+		}
+	}
+	out
+}
+
 // -- open-position completion ---------------------------------------------
 
-fn scope_completions(source: &[u8], line: u32, character: u32) -> Vec<Completion> {
-	let mut out: Vec<Completion> = goto::visible_symbols(source, line, character)
+fn scope_completions(source: &[u8], path: &Path, line: u32, character: u32) -> Vec<Completion> {
+	let visible = goto::visible_symbols(source, line, character);
+	// Modules not imported yet, by the name they'd be imported as.
+	let imported: Vec<String> = scan_uses(source)
+		.into_iter()
+		.map(|(full, _)| full)
+		.collect();
+	let unimported: Vec<Completion> = importable_modules(path)
+		.into_iter()
+		.filter(|full| !imported.contains(full))
+		.filter_map(|full| {
+			let local = full.rsplit('/').next()?.to_string();
+			if visible.iter().any(|(name, _)| *name == local) {
+				return None;
+			}
+			let (at, text) = use_insertion(source, &full);
+			let mut c = Completion::new(local, CompletionKind::Module, Some(full), None);
+			c.import = Some((compiler::Range::between(at, at), text));
+			Some(c)
+		})
+		.collect();
+
+	let mut out: Vec<Completion> = visible
 		.into_iter()
 		.map(|(name, kind)| {
 			let kind = match kind {
@@ -306,6 +397,7 @@ fn scope_completions(source: &[u8], line: u32, character: u32) -> Vec<Completion
 	for kw in KEYWORDS {
 		out.push(Completion::new(*kw, CompletionKind::Keyword, None, None));
 	}
+	out.extend(unimported);
 	out
 }
 
@@ -388,6 +480,55 @@ pub(crate) fn scan_uses(source: &[u8]) -> Vec<(String, String)> {
 	out
 }
 
+// Where the `use` for `module_name` goes, and the text to insert there: in
+// order among the file's uses, after them if it sorts last, or — for the
+// first one — above the first definition and its doc comment. Lexical, like
+// `scan_uses`, so it works on a file that doesn't parse.
+pub(crate) fn use_insertion(source: &[u8], module_name: &str) -> (compiler::Point, String) {
+	let text = String::from_utf8_lossy(source);
+	let lines: Vec<&str> = text.lines().collect();
+	let line = format!("use {}\n", module_name);
+	let uses: Vec<(usize, &str)> = lines
+		.iter()
+		.enumerate()
+		.filter_map(|(i, l)| Some((i, l.strip_prefix("use ")?.split_whitespace().next()?)))
+		.collect();
+	if let Some((i, _)) = uses.iter().find(|(_, name)| *name > module_name) {
+		return (compiler::Point::at(*i, 0), line);
+	}
+	if let Some((i, _)) = uses.last() {
+		return (compiler::Point::at(i + 1, 0), line);
+	}
+	let is_comment = |l: &str| l.trim_start().starts_with('#');
+	match lines
+		.iter()
+		.position(|l| !l.trim().is_empty() && !is_comment(l))
+	{
+		Some(first) => {
+			let mut start = first;
+			while start > 0 && is_comment(lines[start - 1]) {
+				start -= 1;
+			}
+			(compiler::Point::at(start, 0), line + "\n")
+		}
+		None => (compiler::Point::at(lines.len(), 0), line),
+	}
+}
+
+// Every module a file could import: the stdlib's, less those every module
+// sees already, and the project's own.
+fn importable_modules(current: &Path) -> Vec<String> {
+	let mut names: Vec<String> = compiler::stdlib_sources()
+		.iter()
+		.map(|(n, _)| (*n).to_string())
+		.filter(|n| !compiler::AUTO_IMPORTS.iter().any(|(full, _)| full == n))
+		.collect();
+	names.extend(project_module_names(current));
+	names.sort();
+	names.dedup();
+	names
+}
+
 // -- use-path completion --------------------------------------------------
 
 // If the cursor sits in the path of a `use` line (`use std/lis|`, `use |`),
@@ -532,6 +673,7 @@ fn project_module_names(current: &Path) -> Vec<String> {
 				}
 			} else if name.ends_with(".pa")
 				&& !name.ends_with(".test.pa")
+				&& !name.ends_with(".bench.pa")
 				&& name != compiler::PROJECT_MARKER_FILE
 			{
 				if let Some(m) = module_name_of(&root, &path) {
@@ -547,7 +689,7 @@ fn project_module_names(current: &Path) -> Vec<String> {
 
 // A file's module name relative to the project root: path separators become
 // `/`, the `.pa` suffix is dropped (`src/util.pa` → `src/util`).
-fn module_name_of(root: &Path, file: &Path) -> Option<String> {
+pub fn module_name_of(root: &Path, file: &Path) -> Option<String> {
 	let rel = file.strip_prefix(root).ok()?;
 	let s = rel.to_string_lossy().replace('\\', "/");
 	Some(s.strip_suffix(".pa").unwrap_or(&s).to_string())
@@ -813,6 +955,64 @@ mod tests {
 		assert!(labels.contains(&"green".to_string()), "got {:?}", labels);
 	}
 
+	// The `use` line an item adds, as (line, col, text).
+	fn import_of(c: &Completion) -> Option<(usize, usize, &str)> {
+		c.import
+			.as_ref()
+			.map(|(r, text)| (r.start.line, r.start.col, text.as_str()))
+	}
+
+	#[test]
+	fn unimported_module_members_add_their_use() {
+		let dir = std::env::temp_dir().join(format!("pluma-autoimport-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(&dir).unwrap();
+		std::fs::write(dir.join("pluma.pa"), "").unwrap();
+		std::fs::write(dir.join("helpers.pa"), "public def shown = fun { 1 }\n").unwrap();
+		let main = dir.join("main.pa");
+
+		// The first use goes above the first definition and its doc.
+		let src = "# Entry.\ndef main = json.par\n";
+		let items = complete(src.as_bytes(), &main, 1, 19);
+		let parse = items.iter().find(|c| c.label == "parse").unwrap();
+		assert_eq!(import_of(parse), Some((0, 0, "use std/json\n\n")));
+
+		// Later ones go in order among the rest.
+		let src = "use std/assert\nuse std/list\n\ndef main = json.\n";
+		let items = complete(src.as_bytes(), &main, 3, 16);
+		let parse = items.iter().find(|c| c.label == "parse").unwrap();
+		assert_eq!(import_of(parse), Some((1, 0, "use std/json\n")));
+
+		// The project's own modules too.
+		let src = "use std/list\n\ndef main = helpers.\n";
+		let items = complete(src.as_bytes(), &main, 2, 19);
+		let shown = items.iter().find(|c| c.label == "shown").unwrap();
+		assert_eq!(import_of(shown), Some((0, 0, "use helpers\n")));
+
+		// Imported under an alias: no second `use`, the receiver becomes the alias.
+		let src = "use std/json as j\n\ndef main = json.\n";
+		let items = complete(src.as_bytes(), &main, 2, 16);
+		let parse = items.iter().find(|c| c.label == "parse").unwrap();
+		assert!(parse.import.is_none());
+		let (range, text) = parse.edit.as_ref().unwrap();
+		assert_eq!(
+			(range.start.col, range.end.col, text.as_str()),
+			(11, 16, "j.parse")
+		);
+
+		// A local of the same name isn't a module.
+		let src = "def main = fun json {\n\tjson.\n}\n";
+		assert!(complete(src.as_bytes(), &main, 1, 6).is_empty());
+
+		// In open position, the module itself.
+		let src = "def main = js\n";
+		let items = complete(src.as_bytes(), &main, 0, 13);
+		let json = items.iter().find(|c| c.label == "json").unwrap();
+		assert_eq!(json.detail.as_deref(), Some("std/json"));
+		assert_eq!(import_of(json), Some((0, 0, "use std/json\n\n")));
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
 	#[test]
 	fn open_position_offers_scope_and_keywords() {
 		let src = "def helper = fun { 1 }\ndef main = fun {\n\tlet local = 1\n\t\n}\n";
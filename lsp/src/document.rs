@@ -0,0 +1,118 @@
+// Incremental text sync. The client sends each edit as the range it replaces
+// plus the new text, rather than the whole document per keystroke, and the
+// server splices it into its own copy. Ranges count columns in UTF-16 code
+// units, as the protocol does, so each position is mapped to a byte offset in
+// the current text before splicing.
+
+use tower_lsp::lsp_types::{Position, TextDocumentContentChangeEvent};
+
+// Apply one change to `text`. Changes in a notification build on each other,
+// so they must be applied in the order sent. A change without a range replaces
+// the whole document; clients still send those, e.g. after a reload from disk.
+pub fn apply_change(text: &mut String, change: TextDocumentContentChangeEvent) {
+	match change.range {
+		Some(range) => {
+			let start = byte_offset(text, range.start);
+			let end = byte_offset(text, range.end).max(start);
+			text.replace_range(start..end, &change.text);
+		}
+		None => *text = change.text,
+	}
+}
+
+// The byte offset of a protocol position. A column past the end of its line
+// lands at the line end (before any `\r\n`), and a line past the last one at
+// the end of the text, as the spec asks of out-of-range positions.
+pub fn byte_offset(text: &str, pos: Position) -> usize {
+	let mut line_start = 0;
+	for _ in 0..pos.line {
+		match text[line_start..].find('\n') {
+			Some(i) => line_start += i + 1,
+			None => return text.len(),
+		}
+	}
+	let rest = &text[line_start..];
+	let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+	let line = line.strip_suffix('\r').unwrap_or(line);
+
+	let mut units = 0;
+	for (i, ch) in line.char_indices() {
+		if units >= pos.character {
+			return line_start + i;
+		}
+		units += ch.len_utf16() as u32;
+	}
+	line_start + line.len()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tower_lsp::lsp_types::Range;
+
+	fn edit(sl: u32, sc: u32, el: u32, ec: u32, text: &str) -> TextDocumentContentChangeEvent {
+		TextDocumentContentChangeEvent {
+			range: Some(Range {
+				start: Position {
+					line: sl,
+					character: sc,
+				},
+				end: Position {
+					line: el,
+					character: ec,
+				},
+			}),
+			range_length: None,
+			text: text.to_string(),
+		}
+	}
+
+	#[test]
+	fn edits_apply_in_order() {
+		let mut text = "def a = 1\ndef b = 2\n".to_string();
+		for change in [
+			// Insert, then replace across the line break the insert shifted.
+			edit(0, 8, 0, 8, "10 + "),
+			edit(0, 14, 1, 3, "\n\ndef"),
+			// Delete the blank line.
+			edit(1, 0, 2, 0, ""),
+			// Append past the end.
+			edit(2, 0, 2, 0, "def c = 3\n"),
+		] {
+			apply_change(&mut text, change);
+		}
+		assert_eq!(text, "def a = 10 + 1\ndef b = 2\ndef c = 3\n");
+
+		// A change without a range is the whole new document.
+		apply_change(
+			&mut text,
+			TextDocumentContentChangeEvent {
+				range: None,
+				range_length: None,
+				text: "def d = 4\n".to_string(),
+			},
+		);
+		assert_eq!(text, "def d = 4\n");
+	}
+
+	#[test]
+	fn columns_count_utf16_units() {
+		// `é` is one unit and two bytes; `𝄞` is two units and four bytes.
+		let text = "let s = \"é𝄞x\"\r\nnext";
+		let at = |line, character| byte_offset(text, Position { line, character });
+		assert_eq!(at(0, 9), 9);
+		assert_eq!(at(0, 10), 11);
+		assert_eq!(at(0, 12), 15);
+		assert_eq!(&text[at(0, 12)..at(0, 13)], "x");
+
+		// Out-of-range positions clamp: to the line end before `\r\n`, and to
+		// the end of the text.
+		assert_eq!(at(0, 99), text.find('\r').unwrap());
+		assert_eq!(at(1, 2), text.len() - 2);
+		assert_eq!(at(7, 0), text.len());
+
+		let mut text = text.to_string();
+		apply_change(&mut text, edit(0, 10, 0, 12, "♪"));
+		assert_eq!(text, "let s = \"é♪x\"\r\nnext");
+	}
+}
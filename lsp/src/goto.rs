@@ -53,41 +53,45 @@ pub enum ModuleLocation {
 
 /// What a reference resolves against. A bare value identifier may actually
 /// name a (payload-less) enum variant, so `Value` falls back to the variant
-/// table when no value binding matches.
+/// table when no value binding matches. Record fields are structural, so a
+/// `Field` reference resolves by name to any record type declaring it.
 #[derive(Clone, Copy, PartialEq)]
-enum RefKind {
+pub enum RefKind {
 	Value,
 	Type,
 	Variant,
 	Namespace,
+	Field,
 }
 
-struct Reference {
-	range: Range,
-	name: String,
-	kind: RefKind,
+pub struct Reference {
+	pub range: Range,
+	pub name: String,
+	pub kind: RefKind,
 }
 
 // A `module.symbol` access: the receiver names an imported namespace, the
 // field/type names a top-level def in that module. Resolved by loading the
 // imported module's file.
-struct QualifiedRef {
-	range: Range,
+pub struct QualifiedRef {
+	pub range: Range,
 	// Local namespace name (the receiver), e.g. `colors` in `colors.color`.
-	namespace: String,
+	pub namespace: String,
 	// The symbol named after the dot.
-	name: String,
-	// True in type position (`module.Type`), so we prefer a type def.
-	is_type: bool,
+	pub name: String,
+	// `Type` in type position (`module.Type`), so we prefer a type def;
+	// `Variant` for `module.enum.variant`, naming the enum in `enum_name`.
+	pub kind: RefKind,
+	pub enum_name: Option<String>,
 }
 
-struct Binding {
-	name: String,
+pub struct Binding {
+	pub name: String,
 	// Where to jump: the identifier's own range.
-	def_range: Range,
+	pub def_range: Range,
 	// The region the binding is visible in. `None` means module-global
 	// (top-level defs, enums, variants, imports) — always in scope.
-	scope: Option<Range>,
+	pub scope: Option<Range>,
 }
 
 /// Resolve the identifier under the cursor to a navigable definition. `path`
@@ -185,6 +189,7 @@ pub fn resolve(source: &[u8], path: &Path, line: u32, character: u32) -> Option<
 		RefKind::Type => resolve_binding(&r.types, &reference.name, line, character),
 		RefKind::Variant => resolve_binding(&r.variants, &reference.name, line, character),
 		RefKind::Namespace => resolve_binding(&r.namespaces, &reference.name, line, character),
+		RefKind::Field => resolve_binding(&r.fields, &reference.name, line, character),
 	};
 	range.map(Resolved::Here)
 }
@@ -206,7 +211,11 @@ fn resolve_cross_module(uses: &[UseNode], current: &Path, q: &QualifiedRef) -> O
 		.map(|u| u.module_name())?;
 
 	let (module, location) = load_imported_module(&full_name, current)?;
-	let range = find_top_level_def(module.ast.as_ref()?, &q.name, q.is_type)?;
+	let ast = module.ast.as_ref()?;
+	let range = match &q.enum_name {
+		Some(enum_name) => find_variant(ast, enum_name, &q.name)?,
+		None => find_top_level_def(ast, &q.name, q.kind == RefKind::Type)?,
+	};
 	Some(Resolved::OtherModule {
 		module,
 		range,
@@ -221,6 +230,39 @@ pub fn imported_module(module_name: &str, current: &Path) -> Option<Module> {
 	load_imported_module(module_name, current).map(|(m, _)| m)
 }
 
+/// An imported module, parsed, with the file to open for a location in it
+/// (for a stdlib module, its materialized copy).
+pub fn module_file(module_name: &str, current: &Path) -> Option<(Module, PathBuf)> {
+	let (module, location) = load_imported_module(module_name, current)?;
+	let file = match location {
+		ModuleLocation::Disk(p) => p,
+		ModuleLocation::Stdlib(name) => stdlib_cache_path(&name, None)?,
+	};
+	Some((module, file))
+}
+
+/// Where a `module.symbol` access lands: the parsed module, its file, and the
+/// def's name range in it.
+pub fn qualified_target(
+	uses: &[UseNode],
+	current: &Path,
+	q: &QualifiedRef,
+) -> Option<(Module, PathBuf, Range)> {
+	let Resolved::OtherModule {
+		module,
+		range,
+		location,
+	} = resolve_cross_module(uses, current, q)?
+	else {
+		return None;
+	};
+	let file = match location {
+		ModuleLocation::Disk(p) => p,
+		ModuleLocation::Stdlib(name) => stdlib_cache_path(&name, None)?,
+	};
+	Some((module, file, range))
+}
+
 /// The category of a name visible at a cursor, used to pick a completion icon.
 #[derive(Clone, Copy, PartialEq)]
 pub enum SymKind {
@@ -300,6 +342,12 @@ fn load_imported_module(module_name: &str, current: &Path) -> Option<(Module, Mo
 
 // -- stdlib materialization -----------------------------------------------
 
+/// The materialized file for a stdlib module, so a location inside it can be
+/// opened. Written to the OS cache directory on first use.
+pub fn stdlib_file(module_name: &str) -> Option<PathBuf> {
+	stdlib_cache_path(module_name, None)
+}
+
 // Write the inlined stdlib tree to a versioned cache directory (once) and
 // return the file path for one module. The whole tree plus a `pluma.pa`
 // marker is written so intra-stdlib `use`s resolve and the opened file
@@ -380,9 +428,24 @@ fn find_top_level_def(ast: &ModuleNode, name: &str, prefer_type: bool) -> Option
 	preferred.or(fallback)
 }
 
+// Find the variant `name` of the top-level enum `enum_name`.
+fn find_variant(ast: &ModuleNode, enum_name: &str, name: &str) -> Option<Range> {
+	ast.body.iter().find_map(|def| match &def.kind {
+		DefinitionKind::Enum(en) if def.name.name == enum_name => en
+			.variants
+			.iter()
+			.find(|v| v.name.name == name)
+			.map(|v| v.name.range),
+		_ => None,
+	})
+}
+
 // -- collection -----------------------------------------------------------
 
-struct Resolver {
+/// Every binding a parsed module introduces and every reference it makes,
+/// collected syntactically. Goto resolves the one reference under the
+/// cursor; find-references and rename resolve all of them.
+pub struct Resolver {
 	module_names: HashSet<String>,
 	enum_names: HashSet<String>,
 	// Every variant name declared in the file. A nullary variant pattern
@@ -390,16 +453,25 @@ struct Resolver {
 	// from a fresh binding without analysis — so we lean on this set to
 	// classify it as a variant reference instead.
 	variant_names: HashSet<String>,
-	values: Vec<Binding>,
-	types: Vec<Binding>,
-	variants: Vec<Binding>,
-	namespaces: Vec<Binding>,
-	refs: Vec<Reference>,
-	qualified: Vec<QualifiedRef>,
+	pub values: Vec<Binding>,
+	pub types: Vec<Binding>,
+	pub variants: Vec<Binding>,
+	pub namespaces: Vec<Binding>,
+	// Field labels declared by record types (`{name: string}`).
+	pub fields: Vec<Binding>,
+	pub refs: Vec<Reference>,
+	pub qualified: Vec<QualifiedRef>,
+	// Where a record's field shorthand (`{a}` for `{a: a}`) puts the label
+	// and the value on the same identifier, keyed by its start. Renaming
+	// either side has to spell the pair out.
+	pub shorthands: HashSet<(usize, usize)>,
+	// The labels of each record type, literal, update and pattern, so a
+	// field rename can tell when it would collide with a sibling.
+	pub records: Vec<Vec<String>>,
 }
 
 impl Resolver {
-	fn new(ast: &ModuleNode) -> Self {
+	pub fn new(ast: &ModuleNode) -> Self {
 		let mut module_names = HashSet::new();
 		for u in &ast.uses {
 			module_names.insert(u.local_name().name.clone());
@@ -422,11 +494,61 @@ impl Resolver {
 			types: Vec::new(),
 			variants: Vec::new(),
 			namespaces: Vec::new(),
+			fields: Vec::new(),
 			refs: Vec::new(),
 			qualified: Vec::new(),
+			shorthands: HashSet::new(),
+			records: Vec::new(),
+		}
+	}
+
+	/// The binding table a reference of `kind` resolves against.
+	pub fn table(&self, kind: RefKind) -> &[Binding] {
+		match kind {
+			RefKind::Value => &self.values,
+			RefKind::Type => &self.types,
+			RefKind::Variant => &self.variants,
+			RefKind::Namespace => &self.namespaces,
+			RefKind::Field => &self.fields,
 		}
 	}
 
+	/// The binding a reference resolves to, with the kind of table it was
+	/// found in (a bare value can land on a variant).
+	pub fn resolve_ref(&self, rf: &Reference) -> Option<(RefKind, &Binding)> {
+		let (line, character) = (rf.range.start.line, rf.range.start.col);
+		let lookup = |kind: RefKind| {
+			self
+				.table(kind)
+				.iter()
+				.filter(|b| b.name == rf.name && scope_contains(&b.scope, line, character))
+				.min_by_key(|b| scope_size(&b.scope))
+				.map(|b| (kind, b))
+		};
+		match rf.kind {
+			RefKind::Value => lookup(RefKind::Value).or_else(|| lookup(RefKind::Variant)),
+			kind => lookup(kind),
+		}
+	}
+
+	// `module` when the expression is a bare imported namespace name.
+	fn namespace_of<'a>(&self, e: &'a ExprNode) -> Option<&'a IdentifierNode> {
+		match &e.kind {
+			ExprKind::Identifier(id) if self.module_names.contains(&id.name) => Some(id),
+			_ => None,
+		}
+	}
+
+	fn qualify(&mut self, namespace: &IdentifierNode, member: &IdentifierNode, kind: RefKind) {
+		self.qualified.push(QualifiedRef {
+			range: member.range,
+			namespace: namespace.name.clone(),
+			name: member.name.clone(),
+			kind,
+			enum_name: None,
+		});
+	}
+
 	fn bind_value(&mut self, id: &IdentifierNode, scope: Option<Range>) {
 		self.values.push(Binding {
 			name: id.name.clone(),
@@ -443,7 +565,7 @@ impl Resolver {
 		});
 	}
 
-	fn walk_module(&mut self, m: &ModuleNode) {
+	pub fn walk_module(&mut self, m: &ModuleNode) {
 		for u in &m.uses {
 			// The local name (alias or last path segment) is the namespace
 			// binding; jumping to it lands on the import.
@@ -530,7 +652,16 @@ impl Resolver {
 					self.reference(&c.trait_name, RefKind::Type);
 				}
 				for method in &inst.methods {
-					self.walk_def(method);
+					// An instance method implements the trait's method of the
+					// same name, so its name refers to that method rather than
+					// binding a new value.
+					self.reference(&method.name, RefKind::Value);
+					if let Some(ann) = &method.type_annotation {
+						self.walk_type_expr(ann, Some(inst.range));
+					}
+					if let DefinitionKind::Expr(expr) = &method.kind {
+						self.walk_expr(expr, Some(inst.range));
+					}
 				}
 			}
 		}
@@ -559,22 +690,35 @@ impl Resolver {
 						// `module.value`: the namespace resolves to its import; the
 						// field resolves cross-module into the other file.
 						self.reference(id, RefKind::Namespace);
-						self.qualified.push(QualifiedRef {
-							range: field.range,
-							namespace: id.name.clone(),
-							name: field.name.clone(),
-							is_type: false,
-						});
+						self.qualify(id, field, RefKind::Value);
 					}
 					ExprKind::Identifier(id) if self.enum_names.contains(&id.name) => {
 						// `enum.variant`: receiver is the enum type, field the variant.
 						self.reference(id, RefKind::Type);
 						self.reference(field, RefKind::Variant);
 					}
+					ExprKind::FieldAccess {
+						receiver: inner,
+						field: enum_name,
+					} if self.namespace_of(inner).is_some() => {
+						// `module.enum.variant`: both the enum and the variant live
+						// in the other module.
+						let module = self.namespace_of(inner).unwrap();
+						self.reference(module, RefKind::Namespace);
+						self.qualify(module, enum_name, RefKind::Type);
+						self.qualified.push(QualifiedRef {
+							range: field.range,
+							namespace: module.name.clone(),
+							name: field.name.clone(),
+							kind: RefKind::Variant,
+							enum_name: Some(enum_name.name.clone()),
+						});
+					}
 					_ => {
-						// A record field access — receiver is an expression, the
-						// field is a structural label with no definition site.
+						// A record field access — the label resolves structurally,
+						// by name, to whichever record types declare it.
 						self.walk_expr(receiver, scope);
+						self.reference(field, RefKind::Field);
 					}
 				}
 			}
@@ -614,16 +758,10 @@ impl Resolver {
 				}
 				self.walk_expr(&l.value, scope);
 			}
-			ExprKind::Record(fields) => {
-				for (_, value) in fields {
-					self.walk_expr(value, scope);
-				}
-			}
+			ExprKind::Record(fields) => self.walk_record_fields(fields, scope),
 			ExprKind::RecordUpdate { base, fields } => {
 				self.walk_expr(base, scope);
-				for (_, value) in fields {
-					self.walk_expr(value, scope);
-				}
+				self.walk_record_fields(fields, scope);
 			}
 			ExprKind::If(i) => {
 				let inner = Some(i.range);
@@ -676,12 +814,7 @@ impl Resolver {
 				// namespace's range points back at the `using` header (the
 				// parser clones it in), so only the member gets a reference here.
 				if self.module_names.contains(&namespace.name) {
-					self.qualified.push(QualifiedRef {
-						range: member.range,
-						namespace: namespace.name.clone(),
-						name: member.name.clone(),
-						is_type: false,
-					});
+					self.qualify(namespace, member, RefKind::Value);
 				}
 			}
 			ExprKind::Try(t) => {
@@ -699,6 +832,23 @@ impl Resolver {
 		}
 	}
 
+	// Record labels are field references; the values are walked first so a
+	// shorthand `{a}` resolves to the local `a` before the field.
+	fn walk_record_fields(&mut self, fields: &[(IdentifierNode, ExprNode)], scope: Option<Range>) {
+		for (label, value) in fields {
+			self.walk_expr(value, scope);
+			if same_range(&label.range, &value.range) {
+				self
+					.shorthands
+					.insert((label.range.start.line, label.range.start.col));
+			}
+			self.reference(label, RefKind::Field);
+		}
+		self
+			.records
+			.push(fields.iter().map(|(label, _)| label.name.clone()).collect());
+	}
+
 	fn walk_fun(&mut self, f: &FunNode, _outer: Option<Range>) {
 		let inner = Some(f.range);
 		for p in &f.params {
@@ -723,7 +873,25 @@ impl Resolver {
 				}
 			}
 			PatternKind::Constructor(head, inner) => {
-				self.reference(&head.variant, RefKind::Variant);
+				match (&head.module, &head.enum_name) {
+					(Some(module), Some(enum_name)) => {
+						// `module.enum.variant`, as in the expression form.
+						self.reference(module, RefKind::Namespace);
+						self.qualify(module, enum_name, RefKind::Type);
+						self.qualified.push(QualifiedRef {
+							range: head.variant.range,
+							namespace: module.name.clone(),
+							name: head.variant.name.clone(),
+							kind: RefKind::Variant,
+							enum_name: Some(enum_name.name.clone()),
+						});
+					}
+					(None, Some(enum_name)) => {
+						self.reference(enum_name, RefKind::Type);
+						self.reference(&head.variant, RefKind::Variant);
+					}
+					_ => self.reference(&head.variant, RefKind::Variant),
+				}
 				for ip in inner {
 					self.bind_pattern(ip, scope);
 				}
@@ -734,9 +902,18 @@ impl Resolver {
 				}
 			}
 			PatternKind::Record { fields, rest } => {
-				for (_, sub) in fields {
+				for (label, sub) in fields {
+					if same_range(&label.range, &sub.range) {
+						self
+							.shorthands
+							.insert((label.range.start.line, label.range.start.col));
+					}
+					self.reference(label, RefKind::Field);
 					self.bind_pattern(sub, scope);
 				}
+				self
+					.records
+					.push(fields.iter().map(|(label, _)| label.name.clone()).collect());
 				if let Some(rp) = rest {
 					if let Some(name) = &rp.binding {
 						self.bind_value(name, scope);
@@ -776,7 +953,8 @@ impl Resolver {
 							range: Range::within_line(module.range.end.line, name_col, name_col + id.name.len()),
 							namespace: module.name.clone(),
 							name: id.name.clone(),
-							is_type: true,
+							kind: RefKind::Type,
+							enum_name: None,
 						});
 					}
 					None => {
@@ -807,9 +985,17 @@ impl Resolver {
 				}
 			}
 			TypeExprKind::Record(fields) => {
-				for (_, ty) in fields {
+				for (label, ty) in fields {
+					self.fields.push(Binding {
+						name: label.name.clone(),
+						def_range: label.range,
+						scope: None,
+					});
 					self.walk_type_expr(ty, scope);
 				}
+				self
+					.records
+					.push(fields.iter().map(|(label, _)| label.name.clone()).collect());
 			}
 			TypeExprKind::EmptyTuple | TypeExprKind::Grouping(_) => {
 				if let TypeExprKind::Grouping(inner) = &t.kind {
@@ -822,7 +1008,7 @@ impl Resolver {
 
 // -- range helpers --------------------------------------------------------
 
-fn contains(r: &Range, line: usize, character: usize) -> bool {
+pub fn contains(r: &Range, line: usize, character: usize) -> bool {
 	if line < r.start.line || line > r.end.line {
 		return false;
 	}
@@ -835,14 +1021,14 @@ fn contains(r: &Range, line: usize, character: usize) -> bool {
 	true
 }
 
-fn scope_contains(scope: &Option<Range>, line: usize, character: usize) -> bool {
+pub fn scope_contains(scope: &Option<Range>, line: usize, character: usize) -> bool {
 	match scope {
 		None => true,
 		Some(r) => contains(r, line, character),
 	}
 }
 
-fn range_size(r: &Range) -> usize {
+pub fn range_size(r: &Range) -> usize {
 	let lines = r.end.line.saturating_sub(r.start.line);
 	let cols = if r.start.line == r.end.line {
 		r.end.col.saturating_sub(r.start.col)
@@ -852,16 +1038,22 @@ fn range_size(r: &Range) -> usize {
 	lines * 100_000 + cols
 }
 
-fn scope_size(scope: &Option<Range>) -> usize {
+pub fn scope_size(scope: &Option<Range>) -> usize {
 	match scope {
 		None => usize::MAX,
 		Some(r) => range_size(r),
 	}
 }
 
+pub fn same_range(a: &Range, b: &Range) -> bool {
+	(a.start.line, a.start.col, a.end.line, a.end.col)
+		== (b.start.line, b.start.col, b.end.line, b.end.col)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::temp_project;
 
 	// Resolve a same-file definition at (line, col), returned as
 	// (start_line, start_col). Non-`Here` targets count as no match.
@@ -872,19 +1064,6 @@ mod tests {
 		}
 	}
 
-	// Lay down a throwaway project (a `pluma.pa` marker + the given files)
-	// under the temp dir, keyed by `name` so parallel tests don't collide.
-	fn temp_project(name: &str, files: &[(&str, &str)]) -> PathBuf {
-		let dir = std::env::temp_dir().join(format!("pluma-goto-{}-{}", name, std::process::id()));
-		let _ = std::fs::remove_dir_all(&dir);
-		std::fs::create_dir_all(&dir).unwrap();
-		std::fs::write(dir.join("pluma.pa"), "").unwrap();
-		for (file, contents) in files {
-			std::fs::write(dir.join(file), contents).unwrap();
-		}
-		dir
-	}
-
 	#[test]
 	fn local_param_use_jumps_to_param() {
 		// `name` used on line 1 resolves to the param on line 0.
@@ -948,6 +1127,13 @@ mod tests {
 		assert_eq!(goto(src, 1, 9), Some((0, 6)));
 	}
 
+	#[test]
+	fn record_field_jumps_to_its_type() {
+		let src = "alias point {x :: int}\ndef f = fun p {\n\tp.x\n}\n";
+		// `x` in `p.x` resolves by label to the field in the record type.
+		assert_eq!(goto(src, 2, 3), Some((0, 13)));
+	}
+
 	#[test]
 	fn unresolved_returns_none() {
 		// `int` is a built-in with no definition site in the file.
@@ -961,7 +1147,7 @@ mod tests {
 	#[test]
 	fn cross_module_value_jumps_into_other_file() {
 		let dir = temp_project(
-			"value",
+			"goto-value",
 			&[(
 				"colors.pa",
 				"enum color {\n\tred\n}\ndef helper = fun { 1 }\n",
@@ -985,7 +1171,7 @@ mod tests {
 	fn using_block_implicit_member_jumps_into_other_file() {
 		// `.gap` inside `using css { ... }` is sugar for `css.gap`, so it must
 		// resolve cross-module exactly like an explicit `css.gap` access.
-		let dir = temp_project("using", &[("css.pa", "def gap = fun { 1 }\n")]);
+		let dir = temp_project("goto-using", &[("css.pa", "def gap = fun { 1 }\n")]);
 		let main = "use css\n\ndef d = using css {\n\t.gap\n}\n";
 		let main_path = dir.join("main.pa");
 		// `gap` in `.gap` is on line 3 (the `.` is at col 1, `gap` at col 2).
@@ -1000,6 +1186,24 @@ mod tests {
 		let _ = std::fs::remove_dir_all(&dir);
 	}
 
+	#[test]
+	fn qualified_variant_jumps_into_other_file() {
+		let dir = temp_project(
+			"goto-variant",
+			&[("colors.pa", "enum color {\n\tred\n\tgreen\n}\n")],
+		);
+		let main = "use colors\n\ndef x = colors.color.green\n";
+		let main_path = dir.join("main.pa");
+		// `green` in `colors.color.green` is at line 2, col 21.
+		match goto_definition(main.as_bytes(), &main_path, 2, 22) {
+			Some(Target::OtherFile { range, .. }) => {
+				assert_eq!((range.start.line, range.start.col), (2, 1));
+			}
+			other => panic!("expected OtherFile, got {}", target_kind(&other)),
+		}
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
 	#[test]
 	fn using_block_namespace_jumps_to_import() {
 		// The `css` in the `using css` header resolves to its import's local name.
@@ -1010,7 +1214,7 @@ mod tests {
 
 	#[test]
 	fn cross_module_type_jumps_into_other_file() {
-		let dir = temp_project("type", &[("colors.pa", "enum color {\n\tred\n}\n")]);
+		let dir = temp_project("goto-type", &[("colors.pa", "enum color {\n\tred\n}\n")]);
 		let main = "use colors\n\nalias t colors.color\n";
 		let main_path = dir.join("main.pa");
 		// `color` in `colors.color` (type position) is at line 2, col 16.
@@ -1027,7 +1231,7 @@ mod tests {
 
 	#[test]
 	fn use_path_jumps_to_module_file() {
-		let dir = temp_project("usepath", &[("colors.pa", "enum color {\n\tred\n}\n")]);
+		let dir = temp_project("goto-usepath", &[("colors.pa", "enum color {\n\tred\n}\n")]);
 		let main = "use colors\n\ndef x = 1\n";
 		let main_path = dir.join("main.pa");
 		// `colors` in the `use` path is at line 0, col 4-9.
@@ -1044,7 +1248,7 @@ mod tests {
 	#[test]
 	fn unknown_module_resolves_to_nothing() {
 		// A module that's neither stdlib nor an on-disk file can't resolve.
-		let dir = temp_project("unknown", &[]);
+		let dir = temp_project("goto-unknown", &[]);
 		let main = "use whatever\n\ndef x = whatever.foo ()\n";
 		let main_path = dir.join("main.pa");
 		// `foo` in `whatever.foo` is at line 2, col 17.
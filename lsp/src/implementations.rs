@@ -0,0 +1,227 @@
+use crate::goto::{self, contains, same_range};
+use crate::references::{self, Occurrence, Overlay};
+use compiler::ast::*;
+use compiler::{Diagnostic, Module, Range};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// Go to implementation. From a trait, every `implement` block for it; from a
+// trait method, each instance's def of it; from an instance's method, back to
+// the trait method it implements. Instances are found among the trait's
+// references — an `implement` names its trait, and each of its methods names
+// the trait method — so they come from the same project files
+// find-references searches: the trait's module and those importing it.
+
+/// Where the symbol under the cursor is implemented, or, on an instance's
+/// method, declared. Each location is a file and the range to show.
+pub fn implementations(
+	source: &[u8],
+	path: &Path,
+	line: u32,
+	character: u32,
+	overlay: &Overlay,
+) -> Vec<(PathBuf, Range)> {
+	let Some(module) = parse(source) else {
+		return Vec::new();
+	};
+	let Some(ast) = module.ast.as_ref() else {
+		return Vec::new();
+	};
+	let (l, c) = (line as usize, character as usize);
+
+	// An instance method: the trait method it implements.
+	for def in &ast.body {
+		let DefinitionKind::Instance(inst) = &def.kind else {
+			continue;
+		};
+		if let Some(m) = inst.methods.iter().find(|m| contains(&m.name.range, l, c)) {
+			return trait_method(&module, path, &inst.trait_name.name, &m.name.name)
+				.into_iter()
+				.collect();
+		}
+	}
+
+	let occurrences = references::find_references(source, path, line, character, overlay);
+	let Some(kind) = declared(&occurrences, source, path, line, character) else {
+		return Vec::new();
+	};
+	let mut files: HashMap<PathBuf, Option<Module>> = HashMap::new();
+	let mut out = Vec::new();
+	for o in occurrences.iter().filter(|o| !o.declaration) {
+		let parsed = files
+			.entry(o.path.clone())
+			.or_insert_with(|| read(&o.path, overlay).and_then(|s| parse(s.as_bytes())));
+		let Some(ast) = parsed.as_ref().and_then(|m| m.ast.as_ref()) else {
+			continue;
+		};
+		for def in &ast.body {
+			let DefinitionKind::Instance(inst) = &def.kind else {
+				continue;
+			};
+			match kind {
+				// The block's header, `implement show color`.
+				Declared::Trait if same_range(&inst.trait_name.range, &o.range) => {
+					out.push((
+						o.path.clone(),
+						Range::between(def.range.start, inst.head.range.end),
+					));
+				}
+				Declared::Method => {
+					if let Some(m) = inst
+						.methods
+						.iter()
+						.find(|m| same_range(&m.name.range, &o.range))
+					{
+						out.push((o.path.clone(), m.range));
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+	out
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Declared {
+	Trait,
+	Method,
+}
+
+// Whether the name under the cursor is a trait or a trait method, judged by
+// its declaration: among its occurrences for a project's own, through goto
+// for one in the stdlib.
+fn declared(
+	occurrences: &[Occurrence],
+	source: &[u8],
+	path: &Path,
+	line: u32,
+	character: u32,
+) -> Option<Declared> {
+	let (file, range) = match occurrences.iter().find(|o| o.declaration) {
+		Some(o) => (o.path.clone(), o.range),
+		None => match goto::goto_definition(source, path, line, character)? {
+			goto::Target::Here(range) => (path.to_path_buf(), range),
+			goto::Target::OtherFile { path, range } => (path, range),
+		},
+	};
+	let module = if file == path {
+		parse(source)?
+	} else {
+		parse(&std::fs::read(&file).ok()?)?
+	};
+	module
+		.ast
+		.as_ref()?
+		.body
+		.iter()
+		.find_map(|def| match &def.kind {
+			DefinitionKind::Trait(_) if same_range(&def.name.range, &range) => Some(Declared::Trait),
+			DefinitionKind::Trait(t) if t.methods.iter().any(|m| same_range(&m.name.range, &range)) => {
+				Some(Declared::Method)
+			}
+			_ => None,
+		})
+}
+
+// The declaration of the method `name` in the trait an instance names: one
+// this file declares, or one of its imports does.
+fn trait_method(
+	module: &Module,
+	path: &Path,
+	trait_name: &str,
+	name: &str,
+) -> Option<(PathBuf, Range)> {
+	let find = |module: &Module| {
+		module
+			.ast
+			.as_ref()?
+			.body
+			.iter()
+			.find_map(|def| match &def.kind {
+				DefinitionKind::Trait(t) if def.name.name == trait_name => t
+					.methods
+					.iter()
+					.find(|m| m.name.name == name)
+					.map(|m| m.name.range),
+				_ => None,
+			})
+	};
+	if let Some(range) = find(module) {
+		return Some((path.to_path_buf(), range));
+	}
+	module.ast.as_ref()?.uses.iter().find_map(|u| {
+		let (imported, file) = goto::module_file(&u.module_name(), path)?;
+		find(&imported).map(|range| (file, range))
+	})
+}
+
+fn parse(source: &[u8]) -> Option<Module> {
+	let mut module = Module::new("<lsp>".to_string(), PathBuf::new());
+	let mut diagnostics: Vec<Diagnostic> = Vec::new();
+	module.parse_from_bytes(source.to_vec(), &mut diagnostics);
+	module.ast.as_ref()?;
+	Some(module)
+}
+
+fn read(path: &Path, overlay: &Overlay) -> Option<String> {
+	overlay
+		.get(path)
+		.cloned()
+		.or_else(|| std::fs::read_to_string(path).ok())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::temp_project;
+
+	fn lines(found: &[(PathBuf, Range)], dir: &Path) -> Vec<(String, usize, usize)> {
+		found
+			.iter()
+			.map(|(p, r)| {
+				(
+					p.strip_prefix(dir).unwrap().to_string_lossy().into_owned(),
+					r.start.line,
+					r.end.line,
+				)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn traits_and_instances_link_both_ways() {
+		let shapes = "public trait shape a {\n\tarea :: fun a -> int\n}\n\nimplement shape int {\n\tdef area = fun n { n }\n}\n";
+		let app = "use shapes\n\nalias square {side :: int}\n\nimplement shape square {\n\tdef area = fun s {\n\t\ts.side * s.side\n\t}\n}\n\ndef total = fun s { area s }\n";
+		let dir = temp_project("impls-both", &[("shapes.pa", shapes), ("app.pa", app)]);
+		let (shapes_path, app_path) = (dir.join("shapes.pa"), dir.join("app.pa"));
+		let overlay = Overlay::new();
+
+		// The trait, from its declaration: both headers.
+		let found = implementations(shapes.as_bytes(), &shapes_path, 0, 14, &overlay);
+		assert_eq!(
+			lines(&found, &dir),
+			vec![
+				("app.pa".to_string(), 4, 4),
+				("shapes.pa".to_string(), 4, 4)
+			]
+		);
+
+		// The trait method, from a call: each instance's def, whole.
+		let found = implementations(app.as_bytes(), &app_path, 10, 20, &overlay);
+		assert_eq!(
+			lines(&found, &dir),
+			vec![
+				("app.pa".to_string(), 5, 7),
+				("shapes.pa".to_string(), 5, 5)
+			]
+		);
+
+		// An instance's method: the trait's declaration of it.
+		let found = implementations(app.as_bytes(), &app_path, 5, 6, &overlay);
+		assert_eq!(lines(&found, &dir), vec![("shapes.pa".to_string(), 1, 1)]);
+
+		// Nothing implements a plain function.
+		assert!(implementations(app.as_bytes(), &app_path, 10, 5, &overlay).is_empty());
+	}
+}
@@ -0,0 +1,339 @@
+use compiler::ast::*;
+use compiler::{Diagnostic as PlumaDiagnostic, Module, Point, Range};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::{CodeLens, Command, Diagnostic, DiagnosticSeverity, Url};
+
+// Code lenses that run code from the editor: "▶ Run" above `def main`, and in a
+// `*.test.pa` suite "▶ Run file" at the top plus "▶ Run test" / "▶ Run group"
+// above each case and group. Parse-only, like `symbols`. The suite is read off
+// its literal shape — `test.case "name" …` and `test.group "name" [...]` calls,
+// through any `test.skip` / `test.focus` / `test.timeout` around them — so a
+// case built in a loop or by a helper has no lens of its own; the file's lens
+// still runs it.
+//
+// Clicking a lens runs `pluma run` or `pluma test` in a child process (see
+// `Backend::spawn_test`), and a test run's results come back here to be laid
+// on the cases as diagnostics.
+
+// The commands the lenses run, served by `workspace/executeCommand`. Both take
+// the file's URI; `pluma.test` also takes the path of the case or group to run,
+// its group names then its own, and runs the whole file without one.
+pub const RUN_COMMAND: &str = "pluma.run";
+pub const TEST_COMMAND: &str = "pluma.test";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LensKind {
+	Main,
+	File,
+	Group,
+	Case,
+}
+
+pub struct Lens {
+	pub kind: LensKind,
+	// From the start of the def or call to the end of its name.
+	pub range: Range,
+	// A case's or group's path: its group names, then its own. Empty for the
+	// file and `main`.
+	pub path: Vec<String>,
+}
+
+pub fn lenses(source: &[u8], path: &Path) -> Vec<Lens> {
+	let mut module = Module::new("<lsp>".to_string(), PathBuf::new());
+	let mut diagnostics: Vec<PlumaDiagnostic> = Vec::new();
+	module.parse_from_bytes(source.to_vec(), &mut diagnostics);
+	let Some(ast) = module.ast.as_ref() else {
+		return vec![];
+	};
+
+	let is_suite = path
+		.file_name()
+		.and_then(|n| n.to_str())
+		.is_some_and(|n| n.ends_with(".test.pa"));
+	let mut out = Vec::new();
+	if is_suite {
+		out.push(Lens {
+			kind: LensKind::File,
+			range: Range {
+				start: Point::zero(),
+				end: Point::zero(),
+			},
+			path: Vec::new(),
+		});
+	}
+
+	// The local name `std/test` goes by; without the import nothing in the
+	// file can build a case.
+	let test_ns = ast
+		.uses
+		.iter()
+		.find(|u| u.module_name() == "std/test")
+		.map(|u| u.local_name().name.as_str());
+
+	for def in &ast.body {
+		let DefinitionKind::Expr(expr) = &def.kind else {
+			continue;
+		};
+		match def.name.name.as_str() {
+			"main" => out.push(Lens {
+				kind: LensKind::Main,
+				range: Range {
+					start: def.range.start,
+					end: def.name.range.end,
+				},
+				path: Vec::new(),
+			}),
+			"tests" if is_suite => {
+				if let Some(ns) = test_ns {
+					walk_suite(expr, ns, &mut Vec::new(), &mut out);
+				}
+			}
+			_ => {}
+		}
+	}
+	out
+}
+
+fn walk_suite(expr: &ExprNode, ns: &str, groups: &mut Vec<String>, out: &mut Vec<Lens>) {
+	match &expr.kind {
+		ExprKind::List(items) => {
+			for item in items {
+				if let ListItem::Item(e) = item {
+					walk_suite(e, ns, groups, out);
+				}
+			}
+		}
+		ExprKind::Grouping(inner) => walk_suite(inner, ns, groups, out),
+		ExprKind::Call(call) => {
+			let Some(member) = member_of(&call.callee, ns) else {
+				return;
+			};
+			match (member, call.args.as_slice()) {
+				("case", [name, ..]) => {
+					if let Some(name_str) = string_literal(name) {
+						out.push(Lens {
+							kind: LensKind::Case,
+							range: head(call, name),
+							path: [groups.as_slice(), &[name_str.to_string()]].concat(),
+						});
+					}
+				}
+				("group", [name, body, ..]) => {
+					if let Some(name_str) = string_literal(name) {
+						groups.push(name_str.to_string());
+						out.push(Lens {
+							kind: LensKind::Group,
+							range: head(call, name),
+							path: groups.clone(),
+						});
+						walk_suite(body, ns, groups, out);
+						groups.pop();
+					}
+				}
+				("skip" | "focus" | "timeout", [.., inner]) => walk_suite(inner, ns, groups, out),
+				_ => {}
+			}
+		}
+		_ => {}
+	}
+}
+
+// The member `ns.member` names, written out or as `.member` in a `using` block.
+fn member_of<'a>(callee: &'a ExprNode, ns: &str) -> Option<&'a str> {
+	match &callee.kind {
+		ExprKind::FieldAccess { receiver, field } => match &receiver.kind {
+			ExprKind::Identifier(id) if id.name == ns => Some(&field.name),
+			_ => None,
+		},
+		ExprKind::ImplicitMember { namespace, member } if namespace.name == ns => Some(&member.name),
+		_ => None,
+	}
+}
+
+fn string_literal(expr: &ExprNode) -> Option<&str> {
+	match &expr.kind {
+		ExprKind::Literal(LiteralNode {
+			kind: LiteralKind::String(s, _),
+			..
+		}) => Some(s),
+		_ => None,
+	}
+}
+
+fn head(call: &CallNode, name: &ExprNode) -> Range {
+	Range {
+		start: call.range.start,
+		end: name.range.end,
+	}
+}
+
+pub fn code_lens(lens: &Lens, uri: &Url) -> CodeLens {
+	let uri_arg = Value::String(uri.to_string());
+	let (title, command, arguments) = match lens.kind {
+		LensKind::Main => ("▶ Run", RUN_COMMAND, vec![uri_arg]),
+		LensKind::File => ("▶ Run file", TEST_COMMAND, vec![uri_arg]),
+		LensKind::Group => ("▶ Run group", TEST_COMMAND, vec![uri_arg, path_arg(lens)]),
+		LensKind::Case => ("▶ Run test", TEST_COMMAND, vec![uri_arg, path_arg(lens)]),
+	};
+	CodeLens {
+		range: crate::pluma_range_to_lsp(&lens.range),
+		command: Some(Command {
+			title: title.to_string(),
+			command: command.to_string(),
+			arguments: Some(arguments),
+		}),
+		data: None,
+	}
+}
+
+fn path_arg(lens: &Lens) -> Value {
+	Value::Array(lens.path.iter().cloned().map(Value::String).collect())
+}
+
+// What a `pluma test --reporter json` run says about the cases of `module`,
+// laid on the lenses as diagnostics: an error for each failure with its
+// message, a warning for each flaky pass, and a hint for each clean one.
+// Skipped and todo cases say nothing. A case without a lens of its own reports
+// on the innermost group around it, else on the file.
+pub fn test_diagnostics(report: &Value, module: &str, lenses: &[Lens]) -> Vec<Diagnostic> {
+	let cases = report["cases"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+	cases
+		.iter()
+		.filter(|case| case["module"].as_str() == Some(module))
+		.filter_map(|case| {
+			let name = case["name"].as_str()?;
+			let full: Vec<&str> = case["path"]
+				.as_array()
+				.map(Vec::as_slice)
+				.unwrap_or(&[])
+				.iter()
+				.filter_map(Value::as_str)
+				.chain(std::iter::once(name))
+				.collect();
+			let anchor = lenses
+				.iter()
+				.filter(|l| match l.kind {
+					LensKind::Main => false,
+					LensKind::Case => l.path.len() == full.len(),
+					LensKind::File | LensKind::Group => l.path.len() < full.len(),
+				})
+				.filter(|l| l.path.iter().zip(&full).all(|(a, b)| a == b))
+				.max_by_key(|l| l.path.len())?;
+
+			let message = case["message"].as_str().unwrap_or_default();
+			let ms = case["duration_ms"].as_f64().unwrap_or_default();
+			// On a lens that isn't the case's own, say which case it was.
+			let who = match anchor.kind {
+				LensKind::Case => String::new(),
+				_ => format!("{}: ", full.join(" > ")),
+			};
+			let (severity, text) = match case["status"].as_str()? {
+				"pass" => (
+					DiagnosticSeverity::HINT,
+					format!("{who}passed in {ms:.0}ms"),
+				),
+				"fail" => (DiagnosticSeverity::ERROR, format!("{who}failed: {message}")),
+				"flaky" => (
+					DiagnosticSeverity::WARNING,
+					format!("{who}flaky: passed on a retry after failing with: {message}"),
+				),
+				_ => return None,
+			};
+			Some(Diagnostic {
+				range: crate::pluma_range_to_lsp(&anchor.range),
+				severity: Some(severity),
+				code: None,
+				code_description: None,
+				source: Some("pluma test".to_string()),
+				message: text,
+				related_information: None,
+				tags: None,
+				data: None,
+			})
+		})
+		.collect()
+}
+
+// One line on how a run went, e.g. `3 passed, 1 failed`.
+pub fn test_summary(report: &Value) -> String {
+	let parts: Vec<String> = ["passed", "failed", "flaky", "skipped", "todo"]
+		.iter()
+		.filter_map(|key| {
+			let n = report[*key].as_u64()?;
+			(n > 0 || *key == "passed").then(|| format!("{n} {key}"))
+		})
+		.collect();
+	parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn summary(src: &str, file: &str) -> Vec<(LensKind, usize, String)> {
+		lenses(src.as_bytes(), Path::new(file))
+			.into_iter()
+			.map(|l| (l.kind, l.range.start.line, l.path.join(" > ")))
+			.collect()
+	}
+
+	const SUITE: &str = "use std/assert\nuse std/test as t\n\ndef tests :: t.suite = [\n\tt.case \"adds\" fun { assert.equals (1 + 1) 2 },\n\tt.group \"edge cases\" [\n\t\tt.skip (t.case \"zero\" fun { assert.equals 0 0 }),\n\t\tt.todo \"overflow\",\n\t],\n\tt.timeout 5s (t.group \"slow\" [\n\t\tt.case \"waits\" fun { assert.equals 1 1 },\n\t]),\n]\n";
+
+	#[test]
+	fn suite_lenses_follow_cases_through_groups_and_wrappers() {
+		assert_eq!(
+			summary(SUITE, "/p/math.test.pa"),
+			vec![
+				(LensKind::File, 0, String::new()),
+				(LensKind::Case, 4, "adds".to_string()),
+				(LensKind::Group, 5, "edge cases".to_string()),
+				(LensKind::Case, 6, "edge cases > zero".to_string()),
+				(LensKind::Group, 9, "slow".to_string()),
+				(LensKind::Case, 10, "slow > waits".to_string()),
+			]
+		);
+
+		// Outside a suite only `main` gets a lens; a `tests` def there is just a
+		// def.
+		let src =
+			"use std/test\n\ndef tests = [test.case \"x\" fun { 1 }]\n\ndef main = fun {\n\t()\n}\n";
+		assert_eq!(
+			summary(src, "/p/main.pa"),
+			vec![(LensKind::Main, 4, String::new())]
+		);
+	}
+
+	#[test]
+	fn test_results_land_on_their_cases() {
+		let lenses = lenses(SUITE.as_bytes(), Path::new("/p/math.test.pa"));
+		let report: Value = serde_json::from_str(
+			r#"{"type":"test-run","passed":2,"failed":1,"skipped":1,"todo":1,"flaky":0,"cases":[
+				{"module":"math.test","path":[],"name":"adds","status":"fail","message":"expected 2, got 3","duration_ms":1.2,"attempts":1},
+				{"module":"math.test","path":["edge cases"],"name":"zero","status":"skip","message":null,"duration_ms":0,"attempts":0},
+				{"module":"math.test","path":["slow"],"name":"waits","status":"pass","message":null,"duration_ms":3.4,"attempts":1},
+				{"module":"math.test","path":["slow"],"name":"generated 1","status":"pass","message":null,"duration_ms":0.2,"attempts":1},
+				{"module":"other.test","path":[],"name":"adds","status":"fail","message":"elsewhere","duration_ms":1,"attempts":1}
+			]}"#,
+		)
+		.unwrap();
+
+		let diags: Vec<(u32, String)> = test_diagnostics(&report, "math.test", &lenses)
+			.into_iter()
+			.map(|d| (d.range.start.line, d.message))
+			.collect();
+		assert_eq!(
+			diags,
+			vec![
+				(4, "failed: expected 2, got 3".to_string()),
+				(10, "passed in 3ms".to_string()),
+				// No lens of its own: reported on its group's.
+				(9, "slow > generated 1: passed in 0ms".to_string()),
+			]
+		);
+		assert_eq!(
+			test_summary(&report),
+			"2 passed, 1 failed, 1 skipped, 1 todo"
+		);
+	}
+}
@@ -9,13 +9,22 @@ use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
 mod analysis;
+mod calls;
 mod completion;
+mod document;
 mod goto;
 mod hover;
+mod implementations;
 mod inlay_hints;
+mod lenses;
+mod quick_fixes;
+mod ranges;
+mod refactor;
+mod references;
 mod semantic_tokens;
 mod signature_help;
 mod symbols;
+mod type_definition;
 
 // How long to wait for typing to pause before analyzing. A burst of
 // keystrokes only triggers one analysis — the last one — instead of one per
@@ -42,7 +51,12 @@ impl Revisions {
 
 	// Whether `rev` is still the latest edit for `uri`.
 	fn is_current(&self, uri: &str, rev: u64) -> bool {
-		self.0.get(uri).map(|r| *r) == Some(rev)
+		self.current(uri) == Some(rev)
+	}
+
+	// The document's latest revision, if it's open.
+	fn current(&self, uri: &str) -> Option<u64> {
+		self.0.get(uri).map(|r| *r)
 	}
 
 	// Forget a document (on close), so any pending analysis for it bails.
@@ -51,6 +65,9 @@ impl Revisions {
 	}
 }
 
+// A document's quick fixes, each with the diagnostic it resolves.
+type Fixes = Vec<(Diagnostic, quick_fixes::QuickFix)>;
+
 // Shared state is `Arc`-wrapped so the whole `Backend` clones cheaply into a
 // spawned debounce task (which needs `'static`), while every clone still sees
 // the same maps. The DashMaps themselves are concurrent, so reads/writes from
@@ -69,17 +86,47 @@ struct Backend {
 	// same analysis pass. Send-only (`position` + `String`) for the same
 	// reason the hover index is.
 	inlay_map: Arc<DashMap<String, Arc<Vec<inlay_hints::InlayHint>>>>,
+	// The diagnostics last published for each URI, kept so hover can explain
+	// the one under the cursor.
+	diagnostic_map: Arc<DashMap<String, Arc<Vec<Diagnostic>>>>,
+	// Quick fixes for the analyzer's diagnostics, each with the diagnostic it
+	// resolves, computed with the analysis so their edits match its ranges.
+	fix_map: Arc<DashMap<String, Arc<Fixes>>>,
+	// The results of the last test run started from a code lens, published
+	// alongside the analysis diagnostics until the file is next edited.
+	test_diagnostics: Arc<DashMap<String, Arc<Vec<Diagnostic>>>>,
 	revisions: Revisions,
+	// The project roots of the editor's workspace folders, recorded at
+	// `initialize` for workspace symbol search.
+	workspace_roots: Arc<std::sync::RwLock<Vec<std::path::PathBuf>>>,
+	// Top-level names per project file, kept between searches so only files
+	// that changed are re-parsed.
+	workspace_index: Arc<std::sync::Mutex<symbols::WorkspaceIndex>>,
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-	async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+	async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+		// A workspace folder inside a project stands for the whole project; one
+		// with no `pluma.pa` above it is searched as it is.
+		#[allow(deprecated)] // `root_uri` is the fallback for clients without folders.
+		let folders: Vec<Url> = match params.workspace_folders {
+			Some(folders) => folders.into_iter().map(|f| f.uri).collect(),
+			None => params.root_uri.into_iter().collect(),
+		};
+		*self.workspace_roots.write().unwrap() = folders
+			.iter()
+			.filter_map(|uri| uri.to_file_path().ok())
+			.map(|dir| compiler::find_project_root(&dir).unwrap_or(dir))
+			.collect();
+
 		Ok(InitializeResult {
 			server_info: None,
 			offset_encoding: None,
 			capabilities: ServerCapabilities {
-				text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+				text_document_sync: Some(TextDocumentSyncCapability::Kind(
+					TextDocumentSyncKind::INCREMENTAL,
+				)),
 				workspace: None,
 				// Plain options, not RegistrationOptions: the latter carries a
 				// `document_selector` keyed on the LSP languageId, which differs
@@ -100,12 +147,45 @@ impl LanguageServer for Backend {
 					},
 				)),
 				document_formatting_provider: Some(OneOf::Left(true)),
-				// Quick-fixes built from the linter's autofixes: each fixable lint
-				// at the cursor becomes a clickable edit.
-				code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+				// Quick fixes for the diagnostics at the cursor (the analyzer's and
+				// the linter's autofixes), plus refactorings of the selection.
+				code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+					code_action_kinds: Some(vec![
+						CodeActionKind::QUICKFIX,
+						CodeActionKind::REFACTOR_EXTRACT,
+						CodeActionKind::REFACTOR_INLINE,
+						CodeActionKind::REFACTOR_REWRITE,
+					]),
+					..CodeActionOptions::default()
+				})),
 				hover_provider: Some(HoverProviderCapability::Simple(true)),
 				definition_provider: Some(OneOf::Left(true)),
+				type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+				implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
+				references_provider: Some(OneOf::Left(true)),
+				call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+				// `prepareRename` lets the client turn a rename down up front, with
+				// the reason, on a name that can't change (a stdlib def, a
+				// built-in) before the user has typed a new one.
+				rename_provider: Some(OneOf::Right(RenameOptions {
+					prepare_provider: Some(true),
+					work_done_progress_options: WorkDoneProgressOptions::default(),
+				})),
 				document_symbol_provider: Some(OneOf::Left(true)),
+				document_highlight_provider: Some(OneOf::Left(true)),
+				folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+				selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+				code_lens_provider: Some(CodeLensOptions {
+					resolve_provider: Some(false),
+				}),
+				execute_command_provider: Some(ExecuteCommandOptions {
+					commands: vec![
+						lenses::RUN_COMMAND.to_string(),
+						lenses::TEST_COMMAND.to_string(),
+					],
+					work_done_progress_options: Default::default(),
+				}),
+				workspace_symbol_provider: Some(OneOf::Left(true)),
 				inlay_hint_provider: Some(OneOf::Left(true)),
 				completion_provider: Some(CompletionOptions {
 					// `.` re-triggers for member access (`list.`); `/` drills into
@@ -145,16 +225,19 @@ impl LanguageServer for Backend {
 		self.schedule_analysis(uri);
 	}
 
-	async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
-		let uri = params.text_document.uri.clone();
-		self
-			.on_document_change(TextDocumentItem {
-				language_id: "pluma".into(),
-				uri: params.text_document.uri,
-				text: std::mem::take(&mut params.content_changes[0].text),
-				version: params.text_document.version,
-			})
-			.await;
+	async fn did_change(&self, params: DidChangeTextDocumentParams) {
+		let uri = params.text_document.uri;
+		// Spliced in without awaiting, so a request queued behind this
+		// notification sees the edit, and the next notification's ranges apply
+		// to the text this one left.
+		if let Some(mut text) = self.document_map.get_mut(&uri.to_string()) {
+			for change in params.content_changes {
+				document::apply_change(&mut text, change);
+			}
+		}
+		// Test results sit on the lines the cases were on; the next analysis
+		// publishes without them.
+		self.test_diagnostics.remove(&uri.to_string());
 		self.schedule_analysis(uri);
 	}
 
@@ -165,6 +248,9 @@ impl LanguageServer for Backend {
 		self.document_map.remove(&uri_str);
 		self.hover_map.remove(&uri_str);
 		self.inlay_map.remove(&uri_str);
+		self.diagnostic_map.remove(&uri_str);
+		self.fix_map.remove(&uri_str);
+		self.test_diagnostics.remove(&uri_str);
 		// Drop the revision too: any debounced analysis still pending for this
 		// file finds no matching revision and bails instead of re-publishing
 		// diagnostics for a closed document.
@@ -224,17 +310,15 @@ impl LanguageServer for Backend {
 	}
 
 	async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
-		// Only quick-fixes are offered here; if the client asked exclusively for
-		// other kinds (e.g. a refactor or organize-imports request), there's
-		// nothing to return.
-		if let Some(only) = &params.context.only {
-			if !only
-				.iter()
-				.any(|k| CodeActionKind::QUICKFIX.as_str().starts_with(k.as_str()))
-			{
-				return Ok(None);
-			}
-		}
+		// The kinds the client asked for; a refactor request takes every kind
+		// under `refactor`.
+		let wants = |kind: &CodeActionKind| {
+			params
+				.context
+				.only
+				.as_ref()
+				.is_none_or(|only| only.iter().any(|k| kind.as_str().starts_with(k.as_str())))
+		};
 
 		let uri = params.text_document.uri;
 		let text = match self.document_map.get(&uri.to_string()) {
@@ -242,61 +326,142 @@ impl LanguageServer for Backend {
 			None => return Ok(None),
 		};
 
-		// Re-run the linter against the current text to recover the structured
-		// fix edits (the published diagnostics keep only the message). On a parse
-		// error the linter returns `Err` and there are no fixes to offer.
-		let Ok(findings) = linter::lint_findings(text.as_bytes()) else {
-			return Ok(None);
-		};
-
-		let actions: Vec<CodeActionOrCommand> = findings
-			.into_iter()
-			.filter(|f| !f.fixes.is_empty())
-			.filter_map(|f| {
-				// Offer a finding's fix only when its diagnostic overlaps the range
-				// the client asked about (the cursor or selection).
-				let diag_range = pluma_range_to_lsp(&f.diagnostic.range?);
-				if !ranges_overlap(&diag_range, &params.range) {
-					return None;
-				}
-
-				let edits: Vec<TextEdit> = f
-					.fixes
+		// The analyzer's fixes, from the last analysis, for the diagnostics the
+		// client asked about.
+		let quick_fixes = wants(&CodeActionKind::QUICKFIX);
+		let mut actions: Vec<CodeActionOrCommand> = self
+			.fix_map
+			.get(&uri.to_string())
+			.map(|fixes| fixes.clone())
+			.unwrap_or_default()
+			.iter()
+			.filter(|(diag, _)| quick_fixes && ranges_overlap(&diag.range, &params.range))
+			.map(|(diag, fix)| {
+				let edits = fix
+					.edits
 					.iter()
-					.map(|fix| TextEdit {
-						range: pluma_range_to_lsp(&fix.range),
-						new_text: fix.replacement.clone(),
+					.map(|(range, new_text)| TextEdit {
+						range: pluma_range_to_lsp(range),
+						new_text: new_text.clone(),
 					})
 					.collect();
-
 				let mut changes = std::collections::HashMap::new();
 				changes.insert(uri.clone(), edits);
-
-				// The help line ("replace the wrapper with `f` directly") reads as
-				// the action; fall back to the diagnostic message if a rule has none.
-				let title = f
-					.diagnostic
-					.help
-					.clone()
-					.unwrap_or_else(|| f.diagnostic.message.clone());
-
-				Some(CodeActionOrCommand::CodeAction(CodeAction {
-					title,
+				CodeActionOrCommand::CodeAction(CodeAction {
+					title: fix.title.clone(),
 					kind: Some(CodeActionKind::QUICKFIX),
-					diagnostics: Some(vec![pluma_diagnostic_to_lsp(&f.diagnostic, &uri)]),
+					diagnostics: Some(vec![diag.clone()]),
 					edit: Some(WorkspaceEdit {
 						changes: Some(changes),
 						..WorkspaceEdit::default()
 					}),
+					is_preferred: Some(fix.preferred),
 					..CodeAction::default()
-				}))
+				})
 			})
 			.collect();
 
+		// Refactorings of the selection, typed by the last analysis.
+		let hits = self.hover_map.get(&uri.to_string()).map(|h| h.clone());
+		let path = uri.to_file_path().unwrap_or_default();
+		let selection = lsp_range_to_pluma(&params.range);
+		for r in refactor::refactors(
+			text.as_bytes(),
+			&path,
+			selection,
+			hits.as_deref().map_or(&[], |h| h.as_slice()),
+		) {
+			let kind = match r.kind {
+				refactor::RefactorKind::Extract => CodeActionKind::REFACTOR_EXTRACT,
+				refactor::RefactorKind::Inline => CodeActionKind::REFACTOR_INLINE,
+				refactor::RefactorKind::Rewrite => CodeActionKind::REFACTOR_REWRITE,
+			};
+			if !wants(&kind) {
+				continue;
+			}
+			let (range, new_text) = r.edit;
+			let mut changes = std::collections::HashMap::new();
+			changes.insert(
+				uri.clone(),
+				vec![TextEdit {
+					range: pluma_range_to_lsp(&range),
+					new_text,
+				}],
+			);
+			actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+				title: r.title,
+				kind: Some(kind),
+				edit: Some(WorkspaceEdit {
+					changes: Some(changes),
+					..WorkspaceEdit::default()
+				}),
+				..CodeAction::default()
+			}));
+		}
+
+		if !quick_fixes {
+			return Ok(Some(actions));
+		}
+
+		// Re-run the linter against the current text to recover the structured
+		// fix edits (the published diagnostics keep only the message). On a parse
+		// error the linter returns `Err` and there are no fixes to offer.
+		let Ok(findings) = linter::lint_findings(text.as_bytes()) else {
+			return Ok(Some(actions));
+		};
+
+		actions.extend(
+			findings
+				.into_iter()
+				.filter(|f| !f.fixes.is_empty())
+				.filter_map(|f| {
+					// Offer a finding's fix only when its diagnostic overlaps the range
+					// the client asked about (the cursor or selection).
+					let diag_range = pluma_range_to_lsp(&f.diagnostic.range?);
+					if !ranges_overlap(&diag_range, &params.range) {
+						return None;
+					}
+
+					let edits: Vec<TextEdit> = f
+						.fixes
+						.iter()
+						.map(|fix| TextEdit {
+							range: pluma_range_to_lsp(&fix.range),
+							new_text: fix.replacement.clone(),
+						})
+						.collect();
+
+					let mut changes = std::collections::HashMap::new();
+					changes.insert(uri.clone(), edits);
+
+					// The help line ("replace the wrapper with `f` directly") reads as
+					// the action; fall back to the diagnostic message if a rule has none.
+					let title = f
+						.diagnostic
+						.help
+						.clone()
+						.unwrap_or_else(|| f.diagnostic.message.clone());
+
+					Some(CodeActionOrCommand::CodeAction(CodeAction {
+						title,
+						kind: Some(CodeActionKind::QUICKFIX),
+						diagnostics: Some(vec![pluma_diagnostic_to_lsp(&f.diagnostic, &uri)]),
+						edit: Some(WorkspaceEdit {
+							changes: Some(changes),
+							..WorkspaceEdit::default()
+						}),
+						..CodeAction::default()
+					}))
+				}),
+		);
+
 		Ok(Some(actions))
 	}
 
 	async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+		self
+			.settle(&params.text_document_position_params.text_document.uri)
+			.await?;
 		let uri = params
 			.text_document_position_params
 			.text_document
@@ -304,19 +469,24 @@ impl LanguageServer for Backend {
 			.to_string();
 		let pos = params.text_document_position_params.position;
 
-		let hits = match self.hover_map.get(&uri) {
-			Some(h) => h.clone(),
-			None => return Ok(None),
-		};
+		// The long-form explanation of any coded diagnostic under the cursor,
+		// appended below the type so hovering a squiggle says why it's there.
+		let explanation = self
+			.diagnostic_map
+			.get(&uri)
+			.and_then(|diags| explain_diagnostics_at(&diags, pos.line, pos.character));
 
-		let Some(hit) = hover::lookup(&hits, pos.line, pos.character) else {
-			return Ok(None);
-		};
+		let hits = self.hover_map.get(&uri).map(|h| h.clone());
+		let hit = hits
+			.as_deref()
+			.and_then(|hits| hover::lookup(hits, pos.line, pos.character));
 
 		// The doc resolves through the usage to its definition, so it needs the
 		// source text and path; the direct hit's own doc (def name) is the
 		// fallback.
 		let doc = match (
+			&hits,
+			hit,
 			self.document_map.get(&uri).map(|t| t.clone()),
 			params
 				.text_document_position_params
@@ -325,22 +495,26 @@ impl LanguageServer for Backend {
 				.to_file_path()
 				.ok(),
 		) {
-			(Some(text), Some(path)) => {
-				hover::doc_for_hover(&hits, text.as_bytes(), &path, pos.line, pos.character)
+			(Some(hits), Some(_), Some(text), Some(path)) => {
+				hover::doc_for_hover(hits, text.as_bytes(), &path, pos.line, pos.character)
 			}
-			_ => hit.doc.clone(),
+			(_, Some(hit), _, _) => hit.doc.clone(),
+			_ => None,
 		};
 
-		// Type in a code fence; the doc comment (if any) as prose below a rule.
+		// Type in a code fence; the doc comment (if any) as prose below a rule,
+		// then any diagnostic explanation below another.
 		let mut value = String::new();
-		if !matches!(hit.ty, compiler::types::Type::Unknown) {
+		if let Some(hit) = hit
+			&& !matches!(hit.ty, compiler::types::Type::Unknown)
+		{
 			value.push_str(&format!("```pluma\n{}\n```", hit.ty));
 		}
-		if let Some(doc) = &doc {
+		for section in doc.iter().chain(explanation.iter()) {
 			if !value.is_empty() {
 				value.push_str("\n\n---\n\n");
 			}
-			value.push_str(doc);
+			value.push_str(section);
 		}
 		if value.is_empty() {
 			return Ok(None);
@@ -351,7 +525,7 @@ impl LanguageServer for Backend {
 				kind: MarkupKind::Markdown,
 				value,
 			}),
-			range: Some(pluma_range_to_lsp(&hit.range)),
+			range: hit.map(|hit| pluma_range_to_lsp(&hit.range)),
 		}))
 	}
 
@@ -390,6 +564,232 @@ impl LanguageServer for Backend {
 		Ok(Some(GotoDefinitionResponse::Scalar(location)))
 	}
 
+	async fn goto_type_definition(
+		&self,
+		params: request::GotoTypeDefinitionParams,
+	) -> Result<Option<request::GotoTypeDefinitionResponse>> {
+		let uri = params.text_document_position_params.text_document.uri;
+		let pos = params.text_document_position_params.position;
+		self.settle(&uri).await?;
+
+		let (Some(text), Some(hits)) = (
+			self.document_map.get(&uri.to_string()).map(|t| t.clone()),
+			self.hover_map.get(&uri.to_string()).map(|h| h.clone()),
+		) else {
+			return Ok(None);
+		};
+		let path = uri.to_file_path().unwrap_or_default();
+
+		let found =
+			type_definition::type_definitions(&hits, text.as_bytes(), &path, pos.line, pos.character);
+		Ok(Some(GotoDefinitionResponse::Array(locations(
+			&uri, &path, found,
+		))))
+	}
+
+	async fn goto_implementation(
+		&self,
+		params: request::GotoImplementationParams,
+	) -> Result<Option<request::GotoImplementationResponse>> {
+		let uri = params.text_document_position_params.text_document.uri;
+		let pos = params.text_document_position_params.position;
+
+		let text = match self.document_map.get(&uri.to_string()) {
+			Some(text) => text.clone(),
+			None => return Ok(None),
+		};
+		let path = uri.to_file_path().unwrap_or_default();
+
+		let found = implementations::implementations(
+			text.as_bytes(),
+			&path,
+			pos.line,
+			pos.character,
+			&self.overlay(),
+		);
+		Ok(Some(GotoDefinitionResponse::Array(locations(
+			&uri, &path, found,
+		))))
+	}
+
+	async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+		let uri = params.text_document_position.text_document.uri;
+		let pos = params.text_document_position.position;
+
+		let text = match self.document_map.get(&uri.to_string()) {
+			Some(text) => text.clone(),
+			None => return Ok(None),
+		};
+		// A non-file URI can't anchor a project; same-file references still
+		// resolve with an empty path.
+		let path = uri.to_file_path().unwrap_or_default();
+
+		let locations = references::find_references(
+			text.as_bytes(),
+			&path,
+			pos.line,
+			pos.character,
+			&self.overlay(),
+		)
+		.into_iter()
+		.filter(|o| params.context.include_declaration || !o.declaration)
+		.filter_map(|o| {
+			Some(Location {
+				uri: file_uri(&uri, &path, &o.path)?,
+				range: pluma_range_to_lsp(&o.range),
+			})
+		})
+		.collect();
+
+		Ok(Some(locations))
+	}
+
+	async fn document_highlight(
+		&self,
+		params: DocumentHighlightParams,
+	) -> Result<Option<Vec<DocumentHighlight>>> {
+		let uri = params.text_document_position_params.text_document.uri;
+		let pos = params.text_document_position_params.position;
+
+		let text = match self.document_map.get(&uri.to_string()) {
+			Some(text) => text.clone(),
+			None => return Ok(None),
+		};
+		let path = uri.to_file_path().unwrap_or_default();
+
+		// A declaration binds the name; everything else reads it.
+		let highlights =
+			references::document_highlights(text.as_bytes(), &path, pos.line, pos.character)
+				.into_iter()
+				.map(|o| DocumentHighlight {
+					range: pluma_range_to_lsp(&o.range),
+					kind: Some(if o.declaration {
+						DocumentHighlightKind::WRITE
+					} else {
+						DocumentHighlightKind::READ
+					}),
+				})
+				.collect();
+		Ok(Some(highlights))
+	}
+
+	async fn prepare_rename(
+		&self,
+		params: TextDocumentPositionParams,
+	) -> Result<Option<PrepareRenameResponse>> {
+		let uri = params.text_document.uri;
+		let pos = params.position;
+
+		let text = match self.document_map.get(&uri.to_string()) {
+			Some(text) => text.clone(),
+			None => return Ok(None),
+		};
+		let path = uri.to_file_path().unwrap_or_default();
+
+		match references::prepare_rename(text.as_bytes(), &path, pos.line, pos.character) {
+			Ok(range) => Ok(range.map(|r| PrepareRenameResponse::Range(pluma_range_to_lsp(&r)))),
+			Err(reason) => Err(rename_refused(reason)),
+		}
+	}
+
+	async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+		let uri = params.text_document_position.text_document.uri;
+		let pos = params.text_document_position.position;
+
+		let text = match self.document_map.get(&uri.to_string()) {
+			Some(text) => text.clone(),
+			None => return Ok(None),
+		};
+		let path = uri.to_file_path().unwrap_or_default();
+
+		let edits = references::rename(
+			text.as_bytes(),
+			&path,
+			pos.line,
+			pos.character,
+			&params.new_name,
+			&self.overlay(),
+		)
+		.map_err(rename_refused)?;
+
+		let mut changes: std::collections::HashMap<Url, Vec<TextEdit>> =
+			std::collections::HashMap::new();
+		for (file, range, new_text) in edits {
+			let Some(file_uri) = file_uri(&uri, &path, &file) else {
+				continue;
+			};
+			changes.entry(file_uri).or_default().push(TextEdit {
+				range: pluma_range_to_lsp(&range),
+				new_text,
+			});
+		}
+
+		Ok(Some(WorkspaceEdit {
+			changes: Some(changes),
+			..WorkspaceEdit::default()
+		}))
+	}
+
+	async fn prepare_call_hierarchy(
+		&self,
+		params: CallHierarchyPrepareParams,
+	) -> Result<Option<Vec<CallHierarchyItem>>> {
+		let uri = params.text_document_position_params.text_document.uri;
+		let pos = params.text_document_position_params.position;
+
+		let text = match self.document_map.get(&uri.to_string()) {
+			Some(text) => text.clone(),
+			None => return Ok(None),
+		};
+		let path = uri.to_file_path().unwrap_or_default();
+
+		Ok(
+			calls::prepare(text.as_bytes(), &path, pos.line, pos.character)
+				.and_then(|item| call_hierarchy_item(&uri, &path, item))
+				.map(|item| vec![item]),
+		)
+	}
+
+	async fn incoming_calls(
+		&self,
+		params: CallHierarchyIncomingCallsParams,
+	) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+		let Ok(path) = params.item.uri.to_file_path() else {
+			return Ok(None);
+		};
+		let selection = lsp_range_to_pluma(&params.item.selection_range);
+		let incoming = calls::incoming(&path, &selection, &self.overlay())
+			.into_iter()
+			.filter_map(|call| {
+				Some(CallHierarchyIncomingCall {
+					from: call_hierarchy_item(&params.item.uri, &path, call.item)?,
+					from_ranges: call.ranges.iter().map(pluma_range_to_lsp).collect(),
+				})
+			})
+			.collect();
+		Ok(Some(incoming))
+	}
+
+	async fn outgoing_calls(
+		&self,
+		params: CallHierarchyOutgoingCallsParams,
+	) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+		let Ok(path) = params.item.uri.to_file_path() else {
+			return Ok(None);
+		};
+		let selection = lsp_range_to_pluma(&params.item.selection_range);
+		let outgoing = calls::outgoing(&path, &selection, &self.overlay())
+			.into_iter()
+			.filter_map(|call| {
+				Some(CallHierarchyOutgoingCall {
+					to: call_hierarchy_item(&params.item.uri, &path, call.item)?,
+					from_ranges: call.ranges.iter().map(pluma_range_to_lsp).collect(),
+				})
+			})
+			.collect();
+		Ok(Some(outgoing))
+	}
+
 	async fn document_symbol(
 		&self,
 		params: DocumentSymbolParams,
@@ -403,9 +803,115 @@ impl LanguageServer for Backend {
 		Ok(Some(DocumentSymbolResponse::Nested(symbols)))
 	}
 
+	async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+		let text = match self.document_map.get(&params.text_document.uri.to_string()) {
+			Some(text) => text.clone(),
+			None => return Ok(None),
+		};
+		Ok(Some(ranges::folding_ranges(text.as_bytes())))
+	}
+
+	async fn selection_range(
+		&self,
+		params: SelectionRangeParams,
+	) -> Result<Option<Vec<SelectionRange>>> {
+		let text = match self.document_map.get(&params.text_document.uri.to_string()) {
+			Some(text) => text.clone(),
+			None => return Ok(None),
+		};
+		Ok(Some(ranges::selection_ranges(
+			text.as_bytes(),
+			&params.positions,
+		)))
+	}
+
+	async fn symbol(&self, params: WorkspaceSymbolParams) -> Result<Option<Vec<SymbolInformation>>> {
+		let roots = self.workspace_roots();
+		let overlay = self.overlay();
+		let entries = self
+			.workspace_index
+			.lock()
+			.unwrap()
+			.search(&roots, &overlay, &params.query);
+
+		#[allow(deprecated)] // `deprecated` field is required by the struct literal.
+		let symbols = entries
+			.into_iter()
+			.filter_map(|e| {
+				// A stdlib name only gets a file to open once one is picked, but
+				// the protocol wants a location up front.
+				let file = match e.file {
+					Some(file) => file,
+					None => goto::stdlib_file(&e.module)?,
+				};
+				Some(SymbolInformation {
+					name: e.name,
+					kind: e.kind,
+					tags: None,
+					deprecated: None,
+					location: Location {
+						uri: Url::from_file_path(&file).ok()?,
+						range: pluma_range_to_lsp(&e.range),
+					},
+					container_name: Some(e.module),
+				})
+			})
+			.collect();
+
+		Ok(Some(symbols))
+	}
+
+	async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+		let uri = params.text_document.uri;
+		let text = match self.document_map.get(&uri.to_string()) {
+			Some(text) => text.clone(),
+			None => return Ok(None),
+		};
+		let path = uri.to_file_path().unwrap_or_default();
+		let lenses = lenses::lenses(text.as_bytes(), &path)
+			.iter()
+			.map(|lens| lenses::code_lens(lens, &uri))
+			.collect();
+		Ok(Some(lenses))
+	}
+
+	async fn execute_command(
+		&self,
+		params: ExecuteCommandParams,
+	) -> Result<Option<serde_json::Value>> {
+		let mut args = params.arguments.into_iter();
+		let Some(uri) = args.next().and_then(|arg| Url::parse(arg.as_str()?).ok()) else {
+			return Err(tower_lsp::jsonrpc::Error::invalid_params(
+				"expected the URI of a file",
+			));
+		};
+		let Ok(path) = uri.to_file_path() else {
+			return Err(tower_lsp::jsonrpc::Error::invalid_params(
+				"expected the URI of a file",
+			));
+		};
+		match params.command.as_str() {
+			lenses::RUN_COMMAND => self.spawn_run(path),
+			lenses::TEST_COMMAND => {
+				let case: Vec<String> = args
+					.next()
+					.and_then(|arg| serde_json::from_value(arg).ok())
+					.unwrap_or_default();
+				self.spawn_test(uri, path, case);
+			}
+			other => {
+				return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+					"no command `{other}`"
+				)));
+			}
+		}
+		Ok(None)
+	}
+
 	async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
 		let pos = params.text_document_position;
 		let uri = pos.text_document.uri;
+		self.settle(&uri).await?;
 
 		let text = match self.document_map.get(&uri.to_string()) {
 			Some(text) => text.clone(),
@@ -465,6 +971,7 @@ impl LanguageServer for Backend {
 		let tdp = params.text_document_position_params;
 		let uri = tdp.text_document.uri;
 		let pos = tdp.position;
+		self.settle(&uri).await?;
 
 		let text = match self.document_map.get(&uri.to_string()) {
 			Some(text) => text.clone(),
@@ -502,6 +1009,87 @@ fn position_in_range(line: u32, col: u32, range: &Range) -> bool {
 	after_start && before_end
 }
 
+// Hover text for the coded diagnostics covering a position: each one's
+// explanation from the `pluma explain` registry, headed by its code and title.
+fn explain_diagnostics_at(diags: &[Diagnostic], line: u32, col: u32) -> Option<String> {
+	let mut seen = Vec::new();
+	let sections: Vec<String> = diags
+		.iter()
+		.filter(|d| position_in_range(line, col, &d.range))
+		.filter_map(|d| match &d.code {
+			Some(NumberOrString::String(code)) => compiler::explain::lookup(code),
+			_ => None,
+		})
+		.filter(|e| {
+			let new = !seen.contains(&e.code);
+			seen.push(e.code);
+			new
+		})
+		.map(|e| format!("**{}**: {}\n\n{}", e.code, e.title(), e.body()))
+		.collect();
+	(!sections.is_empty()).then(|| sections.join("\n\n---\n\n"))
+}
+
+// The URI of a file a cross-file lookup landed in: the request's own URI for
+// the document itself (which may not be a file), else the file's.
+fn file_uri(uri: &Url, path: &std::path::Path, file: &std::path::Path) -> Option<Url> {
+	if file == path {
+		Some(uri.clone())
+	} else {
+		Url::from_file_path(file).ok()
+	}
+}
+
+// Found `(file, range)` pairs as locations, the current document keeping
+// its own URI.
+fn locations(
+	uri: &Url,
+	path: &std::path::Path,
+	found: Vec<(std::path::PathBuf, compiler::Range)>,
+) -> Vec<Location> {
+	found
+		.into_iter()
+		.filter_map(|(file, range)| {
+			Some(Location {
+				uri: file_uri(uri, path, &file)?,
+				range: pluma_range_to_lsp(&range),
+			})
+		})
+		.collect()
+}
+
+// A call hierarchy item, with the declaring module as its detail. The client
+// hands it back as is to ask for its calls, which find it by file and name.
+fn call_hierarchy_item(
+	uri: &Url,
+	path: &std::path::Path,
+	item: calls::CallItem,
+) -> Option<CallHierarchyItem> {
+	Some(CallHierarchyItem {
+		uri: file_uri(uri, path, &item.path)?,
+		name: item.name,
+		kind: match item.kind {
+			calls::ItemKind::Function => SymbolKind::FUNCTION,
+			calls::ItemKind::Method => SymbolKind::METHOD,
+			calls::ItemKind::Value => SymbolKind::CONSTANT,
+		},
+		tags: None,
+		detail: item.module,
+		range: pluma_range_to_lsp(&item.range),
+		selection_range: pluma_range_to_lsp(&item.selection),
+		data: None,
+	})
+}
+
+// A rename the server turns down, with the reason shown to the user.
+fn rename_refused(reason: String) -> tower_lsp::jsonrpc::Error {
+	tower_lsp::jsonrpc::Error {
+		code: tower_lsp::jsonrpc::ErrorCode::InvalidRequest,
+		message: reason.into(),
+		data: None,
+	}
+}
+
 impl Backend {
 	async fn on_document_change(&self, params: TextDocumentItem) {
 		self
@@ -509,6 +1097,195 @@ impl Backend {
 			.insert(params.uri.to_string(), params.text.clone());
 	}
 
+	// The roots workspace symbol search covers: the workspace folders' projects
+	// plus the project of every open document, so a file opened from outside
+	// the workspace brings its own project along. A root inside another is
+	// already covered by it.
+	fn workspace_roots(&self) -> Vec<std::path::PathBuf> {
+		let mut roots = self.workspace_roots.read().unwrap().clone();
+		roots.extend(
+			self
+				.overlay()
+				.keys()
+				.filter_map(|path| compiler::find_project_root(path)),
+		);
+		roots.sort();
+		roots.dedup();
+		let all = roots.clone();
+		roots.retain(|root| {
+			!all
+				.iter()
+				.any(|other| other != root && root.starts_with(other))
+		});
+		roots
+	}
+
+	// The open documents by file path, so project-wide lookups read what the
+	// editor shows rather than what was last saved.
+	fn overlay(&self) -> references::Overlay {
+		self
+			.document_map
+			.iter()
+			.filter_map(|entry| {
+				let path = Url::parse(entry.key()).ok()?.to_file_path().ok()?;
+				Some((path, entry.value().clone()))
+			})
+			.collect()
+	}
+
+	// Give queued messages a turn before a position-based request does its
+	// work. The runtime has one thread and the handlers compute synchronously,
+	// so nothing else runs until they return: a `$/cancelRequest` for this
+	// request (which tower-lsp honours by dropping the handler's future) or an
+	// edit that moves the text under it can only land at an await point. This
+	// is that point. If the document changed meanwhile the position is stale,
+	// and the request fails with `ContentModified` for the client to re-ask.
+	async fn settle(&self, uri: &Url) -> Result<()> {
+		let uri = uri.to_string();
+		let rev = self.revisions.current(&uri);
+		tokio::task::yield_now().await;
+		if self.revisions.current(&uri) == rev {
+			Ok(())
+		} else {
+			Err(tower_lsp::jsonrpc::Error::content_modified())
+		}
+	}
+
+	// Publish a document's analysis diagnostics together with the results of
+	// the last test run started from one of its lenses.
+	async fn publish_diagnostics(&self, uri: Url) {
+		let uri_str = uri.to_string();
+		let mut diags: Vec<Diagnostic> = self
+			.diagnostic_map
+			.get(&uri_str)
+			.map(|d| d.to_vec())
+			.unwrap_or_default();
+		if let Some(results) = self.test_diagnostics.get(&uri_str) {
+			diags.extend(results.iter().cloned());
+		}
+		self.client.publish_diagnostics(uri, diags, None).await;
+	}
+
+	// Run a module's `main` with `pluma run` in a child process, relaying what
+	// it prints to the client's log as it goes, then say how it exited. Like a
+	// run from the terminal it runs the saved file, not the buffer.
+	fn spawn_run(&self, path: std::path::PathBuf) {
+		let client = self.client.clone();
+		tokio::spawn(async move {
+			let file = path
+				.file_name()
+				.unwrap_or_default()
+				.to_string_lossy()
+				.into_owned();
+			let child = pluma_command(&path)
+				.arg("run")
+				.arg(&path)
+				.stdout(std::process::Stdio::piped())
+				.stderr(std::process::Stdio::piped())
+				.spawn();
+			let mut child = match child {
+				Ok(child) => child,
+				Err(err) => {
+					let msg = format!("Could not start `pluma run {file}`: {err}");
+					client.show_message(MessageType::ERROR, msg).await;
+					return;
+				}
+			};
+			tokio::join!(
+				relay_lines(&client, child.stdout.take(), MessageType::LOG),
+				relay_lines(&client, child.stderr.take(), MessageType::WARNING),
+			);
+			match child.wait().await {
+				Ok(status) if status.success() => {
+					let msg = format!("`{file}` finished");
+					client.show_message(MessageType::INFO, msg).await;
+				}
+				Ok(status) => {
+					let code = status
+						.code()
+						.map_or("a signal".to_string(), |c| format!("code {c}"));
+					let msg = format!("`{file}` exited with {code}; its output is in the log");
+					client.show_message(MessageType::ERROR, msg).await;
+				}
+				Err(err) => {
+					let msg = format!("`pluma run {file}` failed: {err}");
+					client.show_message(MessageType::ERROR, msg).await;
+				}
+			}
+		});
+	}
+
+	// Run a test file's suite, or one case or group in it, with `pluma test` in
+	// a child process, so it runs exactly as it would from the terminal: the
+	// same discovery, isolates and time limits. The results come back as a
+	// `--reporter json` report and are laid on the cases as diagnostics. Like a
+	// run from the terminal it runs the saved files, not the buffers.
+	fn spawn_test(&self, uri: Url, path: std::path::PathBuf, case: Vec<String>) {
+		let this = self.clone();
+		tokio::spawn(async move {
+			let Some((root, module)) = compiler::find_project_root(&path)
+				.and_then(|root| Some((root.clone(), completion::module_name_of(&root, &path)?)))
+			else {
+				let msg = "`pluma test` runs inside a project; create a `pluma.pa` at its root";
+				this.client.show_message(MessageType::ERROR, msg).await;
+				return;
+			};
+
+			let mut command = pluma_command(&path);
+			command.args(["test", "--module", &module, "--reporter", "json"]);
+			if !case.is_empty() {
+				command.args(["--case", &case.join(" > ")]);
+			}
+			let output = match command.arg(&root).output().await {
+				Ok(output) => output,
+				Err(err) => {
+					let msg = format!("Could not start `pluma test`: {err}");
+					this.client.show_message(MessageType::ERROR, msg).await;
+					return;
+				}
+			};
+
+			// No report means the suite never ran, e.g. it doesn't compile; what
+			// `pluma test` printed says why.
+			let Ok(report) = serde_json::from_slice::<serde_json::Value>(&output.stdout) else {
+				let printed = format!(
+					"{}{}",
+					String::from_utf8_lossy(&output.stdout),
+					String::from_utf8_lossy(&output.stderr)
+				);
+				this.client.log_message(MessageType::ERROR, &printed).await;
+				let first = printed
+					.lines()
+					.find(|l| !l.trim().is_empty())
+					.unwrap_or("no output");
+				let msg = format!("`pluma test` didn't run: {first}");
+				this.client.show_message(MessageType::ERROR, msg).await;
+				return;
+			};
+
+			let text = match this.document_map.get(&uri.to_string()) {
+				Some(text) => text.clone(),
+				None => std::fs::read_to_string(&path).unwrap_or_default(),
+			};
+			let lenses = lenses::lenses(text.as_bytes(), &path);
+			let results = lenses::test_diagnostics(&report, &module, &lenses);
+			this
+				.test_diagnostics
+				.insert(uri.to_string(), Arc::new(results));
+			this.publish_diagnostics(uri).await;
+
+			let kind = match report["failed"].as_u64() {
+				Some(0) => MessageType::INFO,
+				_ => MessageType::ERROR,
+			};
+			let summary = lenses::test_summary(&report);
+			this
+				.client
+				.show_message(kind, format!("{module}: {summary}"))
+				.await;
+		});
+	}
+
 	// Record a new edit for `uri` and schedule a debounced analysis. Returns
 	// immediately so a burst of keystrokes doesn't each block on a full
 	// analysis; only the last edit in the burst actually runs (see
@@ -547,11 +1324,7 @@ impl Backend {
 		// everything we need (the hover/inlay indices + a pre-converted LSP
 		// diagnostic list) into Send-only values.
 		let source = text.into_bytes();
-		let (hover_index, inlay_hints, lsp_diags): (
-			Option<Arc<Vec<HoverHit>>>,
-			Option<Arc<Vec<inlay_hints::InlayHint>>>,
-			Vec<Diagnostic>,
-		) = {
+		let (hover_index, inlay_hints, lsp_diags, fixes) = {
 			let result = analysis::analyze_document(&path, source.clone());
 
 			let module_name = result.module.as_ref().map(|m| m.module_name.clone());
@@ -564,12 +1337,23 @@ impl Backend {
 				.as_ref()
 				.map(|m| Arc::new(inlay_hints::build_hints(m)));
 
-			let mut diags: Vec<Diagnostic> = result
+			let own: Vec<&PlumaDiagnostic> = result
 				.diagnostics
 				.iter()
 				.filter(|d| diagnostic_belongs_to(d, module_name.as_deref()))
+				.collect();
+			let mut diags: Vec<Diagnostic> = own
+				.iter()
 				.map(|d| pluma_diagnostic_to_lsp(d, &uri))
 				.collect();
+			let fixes: Fixes = own
+				.iter()
+				.flat_map(|d| {
+					quick_fixes::quick_fixes(d, &source, &path)
+						.into_iter()
+						.map(|fix| (pluma_diagnostic_to_lsp(d, &uri), fix))
+				})
+				.collect();
 
 			// Lint warnings are computed from the same in-memory text. They only
 			// exist when the module parses; on a parse error the linter returns
@@ -579,7 +1363,7 @@ impl Backend {
 				diags.extend(warnings.iter().map(|d| pluma_diagnostic_to_lsp(d, &uri)));
 			}
 
-			(hover_index, inlay_hints, diags)
+			(hover_index, inlay_hints, diags, fixes)
 		};
 
 		// Superseded by a newer edit while we were analyzing: drop the result
@@ -594,8 +1378,11 @@ impl Backend {
 		if let Some(hints) = inlay_hints {
 			self.inlay_map.insert(uri_str.clone(), hints);
 		}
-
-		self.client.publish_diagnostics(uri, lsp_diags, None).await;
+		self
+			.diagnostic_map
+			.insert(uri_str.clone(), Arc::new(lsp_diags));
+		self.fix_map.insert(uri_str, Arc::new(fixes));
+		self.publish_diagnostics(uri).await;
 
 		// The hint set was just rebuilt against the new text; ask the client to
 		// re-pull so inferred types track edits without waiting for the next
@@ -604,6 +1391,35 @@ impl Backend {
 	}
 }
 
+// A `pluma` child process: this same executable, run from the file's project
+// (or its directory). Its stdin is closed, since the server's own is the
+// protocol stream.
+fn pluma_command(file: &std::path::Path) -> tokio::process::Command {
+	let exe = std::env::current_exe().unwrap_or_else(|_| "pluma".into());
+	let dir = compiler::find_project_root(file)
+		.or_else(|| file.parent().map(std::path::Path::to_path_buf))
+		.unwrap_or_default();
+	let mut command = tokio::process::Command::new(exe);
+	command.current_dir(dir).stdin(std::process::Stdio::null());
+	command
+}
+
+// Relay each line a child prints to the client's log.
+async fn relay_lines(
+	client: &Client,
+	stream: Option<impl tokio::io::AsyncRead + Unpin>,
+	kind: MessageType,
+) {
+	use tokio::io::AsyncBufReadExt;
+	let Some(stream) = stream else {
+		return;
+	};
+	let mut lines = tokio::io::BufReader::new(stream).lines();
+	while let Ok(Some(line)) = lines.next_line().await {
+		client.log_message(kind, line).await;
+	}
+}
+
 fn diagnostic_belongs_to(d: &PlumaDiagnostic, module_name: Option<&str>) -> bool {
 	match (&d.module_name, module_name) {
 		(Some(diag_mod), Some(active_mod)) => diag_mod == active_mod,
@@ -689,6 +1505,13 @@ fn completion_to_lsp(c: completion::Completion) -> CompletionItem {
 		CompletionKind::Folder => CompletionItemKind::FOLDER,
 		CompletionKind::Keyword => CompletionItemKind::KEYWORD,
 	};
+	// An auto-imported module's `use`, added alongside the completed text.
+	let additional_text_edits = c.import.map(|(range, new_text)| {
+		vec![TextEdit {
+			range: pluma_range_to_lsp(&range),
+			new_text,
+		}]
+	});
 	let text_edit = c.edit.map(|(range, new_text)| {
 		CompletionTextEdit::Edit(TextEdit {
 			range: pluma_range_to_lsp(&range),
@@ -714,6 +1537,7 @@ fn completion_to_lsp(c: completion::Completion) -> CompletionItem {
 		}),
 		filter_text: c.filter_text,
 		text_edit,
+		additional_text_edits,
 		command,
 		..CompletionItem::default()
 	}
@@ -764,6 +1588,13 @@ fn pluma_range_to_lsp(r: &compiler::Range) -> Range {
 	}
 }
 
+fn lsp_range_to_pluma(r: &Range) -> compiler::Range {
+	compiler::Range::between(
+		compiler::Point::at(r.start.line as usize, r.start.character as usize),
+		compiler::Point::at(r.end.line as usize, r.end.character as usize),
+	)
+}
+
 // LSP positions are in UTF-16 code units. For Pluma source — overwhelmingly
 // ASCII — chars and code units match for everything outside string/comment
 // content; over-estimating the end column is fine since LSP clients clamp it.
@@ -819,13 +1650,32 @@ async fn serve() {
 		document_map: Arc::new(DashMap::new()),
 		hover_map: Arc::new(DashMap::new()),
 		inlay_map: Arc::new(DashMap::new()),
+		diagnostic_map: Arc::new(DashMap::new()),
+		fix_map: Arc::new(DashMap::new()),
+		test_diagnostics: Arc::new(DashMap::new()),
 		revisions: Revisions::default(),
+		workspace_roots: Arc::new(std::sync::RwLock::new(Vec::new())),
+		workspace_index: Arc::new(std::sync::Mutex::new(symbols::WorkspaceIndex::default())),
 	})
 	.finish();
 
 	Server::new(stdin, stdout, socket).serve(service).await;
 }
 
+// Lay down a throwaway project (a `pluma.pa` marker + the given files) under
+// the temp dir for a test, keyed by `name` so parallel tests don't collide.
+#[cfg(test)]
+pub(crate) fn temp_project(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+	let dir = std::env::temp_dir().join(format!("pluma-lsp-{}-{}", name, std::process::id()));
+	let _ = std::fs::remove_dir_all(&dir);
+	std::fs::create_dir_all(&dir).unwrap();
+	std::fs::write(dir.join("pluma.pa"), "").unwrap();
+	for (file, contents) in files {
+		std::fs::write(dir.join(file), contents).unwrap();
+	}
+	dir
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -884,6 +1734,34 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn hover_explains_coded_diagnostics_under_the_cursor() {
+		let diag = |r: Range, code: Option<&str>| Diagnostic {
+			range: r,
+			code: code.map(|c| NumberOrString::String(c.into())),
+			message: String::new(),
+			..Default::default()
+		};
+		let diags = vec![
+			diag(range(1, 4, 1, 10), Some("E0100")),
+			diag(range(1, 4, 1, 10), Some("E0100")),
+			diag(range(1, 0, 1, 20), None),
+			diag(range(3, 0, 3, 5), Some("L0001")),
+		];
+
+		let text = explain_diagnostics_at(&diags, 1, 6).unwrap();
+		assert!(text.starts_with("**E0100**: "), "{text}");
+		assert_eq!(text.matches("**E0100**").count(), 1, "duplicates collapse");
+		assert!(!text.contains("L0001"));
+
+		// Uncoded or unexplained diagnostics contribute nothing.
+		assert_eq!(explain_diagnostics_at(&diags, 1, 15), None);
+		assert_eq!(
+			explain_diagnostics_at(&[diag(range(0, 0, 0, 5), Some("X9999"))], 0, 2),
+			None
+		);
+	}
+
 	// The debounce contract, exercised with the real `Revisions` + the same
 	// sleep-then-recheck flow `schedule_analysis` uses: in a burst of edits,
 	// every task but the last finds its revision superseded and skips the
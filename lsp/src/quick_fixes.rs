@@ -0,0 +1,459 @@
+use crate::completion::{is_ident_char, use_insertion};
+use crate::goto;
+use compiler::ast::*;
+use compiler::{Diagnostic, Module, Point, Range};
+use std::path::{Path, PathBuf};
+
+// Quick fixes for analyzer diagnostics. The linter attaches its fixes to its
+// findings; the analyzer's errors carry only a message, a range and maybe a
+// `help:` suggestion, so each fix here is rebuilt from those plus the parsed
+// source: the `did you mean` name swapped in, the `use` a module-qualified
+// name is missing, the arms a `when` doesn't cover, the methods an instance
+// leaves out. They're computed with the analysis, against the text its
+// ranges point into.
+
+/// An edit that resolves a diagnostic, offered as a code action.
+pub struct QuickFix {
+	pub title: String,
+	pub edits: Vec<(Range, String)>,
+	// The one fix to apply when the client asks for "the" fix.
+	pub preferred: bool,
+}
+
+/// The fixes for one analyzer diagnostic in `source`, the text of `path`.
+pub fn quick_fixes(d: &Diagnostic, source: &[u8], path: &Path) -> Vec<QuickFix> {
+	let Some(range) = d.range else {
+		return Vec::new();
+	};
+	let Ok(text) = std::str::from_utf8(source) else {
+		return Vec::new();
+	};
+	let mut out = Vec::new();
+	match d.code {
+		Some("E0100") => {
+			out.extend(did_you_mean(d, text, range));
+			out.extend(import_module(d, text, range));
+		}
+		Some("E0107" | "E0108") => out.extend(did_you_mean(d, text, range)),
+		Some("E0109") => out.extend(missing_arms(d, text, path, range)),
+		Some("E0116") => out.extend(missing_method(d, text, path, range)),
+		_ => {}
+	}
+	out
+}
+
+// `help: did you mean `y`?` on a misspelled name, field or variant: the last
+// occurrence of the name in the diagnostic's range becomes the suggestion. A
+// qualified name (`colors.colr`) is misspelled in its last segment.
+fn did_you_mean(d: &Diagnostic, text: &str, range: Range) -> Option<QuickFix> {
+	let suggestion = d
+		.help
+		.as_deref()?
+		.strip_prefix("did you mean `")?
+		.strip_suffix("`?")?;
+	let name = quoted(&d.message).into_iter().next()?;
+	let wrong = name.rsplit('.').next()?;
+	let suggestion = suggestion.rsplit('.').next()?;
+	let (start, end) = (offset(text, range.start)?, offset(text, range.end)?);
+	let found = last_word(text.get(start..end)?, wrong)? + start;
+	Some(QuickFix {
+		title: format!("Change to `{}`", suggestion),
+		edits: vec![(
+			Range::between(point(text, found), point(text, found + wrong.len())),
+			suggestion.to_string(),
+		)],
+		preferred: true,
+	})
+}
+
+// `json.parse` with no `use std/json`: a namespace the stdlib has, imported in
+// order among the file's other uses.
+fn import_module(d: &Diagnostic, text: &str, range: Range) -> Option<QuickFix> {
+	let name = quoted(&d.message).into_iter().next()?;
+	let end = offset(text, range.end)?;
+	if name.contains('.') || !text[end..].starts_with('.') {
+		return None;
+	}
+	let module_name = format!("std/{}", name);
+	compiler::lookup_stdlib_source(&module_name)?;
+	let (at, new_text) = use_insertion(text.as_bytes(), &module_name);
+	Some(QuickFix {
+		title: format!("Add `use {}`", module_name),
+		edits: vec![(Range::between(at, at), new_text)],
+		preferred: false,
+	})
+}
+
+// A non-exhaustive `when` gets an arm per missing case after its last one.
+// A variant's pattern copies the qualifier the existing arms write
+// (`colors.color.`) and binds each payload slot to `_`.
+fn missing_arms(d: &Diagnostic, text: &str, path: &Path, range: Range) -> Option<QuickFix> {
+	// The first quoted name is the `when` keyword itself.
+	let missing: Vec<&str> = quoted(&d.message).into_iter().skip(1).collect();
+	if missing.is_empty() {
+		return None;
+	}
+	let module = parse(text.as_bytes())?;
+	let when = find_when(module.ast.as_ref()?, &range)?;
+	let last = when.cases.last()?;
+	let indent = indentation(text, when.range.start.line);
+
+	let head = when.cases.iter().find_map(|c| match &c.pattern.kind {
+		PatternKind::Constructor(head, _) => Some(head),
+		_ => None,
+	});
+	let qualifier = match head {
+		Some(head) => text
+			.get(offset(text, head.range.start)?..offset(text, head.variant.range.start)?)?
+			.to_string(),
+		None => String::new(),
+	};
+	let variants = head.map_or(Vec::new(), |head| enum_variants(&module, path, head));
+
+	let mut arms = String::new();
+	for case in &missing {
+		if *case == "else" {
+			arms.push_str(" else {");
+		} else {
+			let pattern = match variants.iter().find(|(name, _)| name == case) {
+				Some((_, arity)) => {
+					let mut pattern = format!("{}{}", qualifier, case);
+					for _ in 0..*arity {
+						pattern.push_str(" _");
+					}
+					pattern
+				}
+				None => case.to_string(),
+			};
+			arms.push_str(&format!(" is {} {{", pattern));
+		}
+		arms.push_str(&format!("\n{}}}", indent));
+	}
+	let title = if missing.len() == 1 {
+		"Add the missing arm".to_string()
+	} else {
+		format!("Add the {} missing arms", missing.len())
+	};
+	Some(QuickFix {
+		title,
+		edits: vec![(Range::between(last.range.end, last.range.end), arms)],
+		preferred: true,
+	})
+}
+
+// An instance missing a method gets a stub for it before its closing brace,
+// a `fun` taking as many (unused) parameters as the trait's signature.
+fn missing_method(d: &Diagnostic, text: &str, path: &Path, range: Range) -> Option<QuickFix> {
+	let names = quoted(&d.message);
+	let (trait_name, method) = (*names.first()?, *names.get(1)?);
+	let trait_name = trait_name.rsplit('.').next()?;
+	let module = parse(text.as_bytes())?;
+	let def = module.ast.as_ref()?.body.iter().find(|def| {
+		matches!(def.kind, DefinitionKind::Instance(_))
+			&& goto::contains(&def.range, range.start.line, range.start.col)
+	})?;
+	let arity = trait_method_arity(&module, path, trait_name, method)?;
+
+	let params: String = (0..arity)
+		.map(|i| format!("_{} ", (b'a' + (i % 26) as u8) as char))
+		.collect();
+	let close = text.get(..offset(text, def.range.end)?)?.rfind('}')?;
+	let line_start = text[..close].rfind('\n').map_or(0, |i| i + 1);
+	let before = &text[line_start..close];
+	let (at, new_text) = if before.trim().is_empty() {
+		let inner = format!("{}\t", before);
+		(
+			line_start,
+			format!("{}def {} = fun {}{{\n{}}}\n", inner, method, params, inner),
+		)
+	} else {
+		// `implement show int { }`: open the block up.
+		let indent = indentation(text, def.range.start.line);
+		(
+			close,
+			format!(
+				"\n{}\tdef {} = fun {}{{\n{}\t}}\n{}",
+				indent, method, params, indent, indent
+			),
+		)
+	};
+	let at = point(text, at);
+	Some(QuickFix {
+		title: format!("Add a stub for `{}`", method),
+		edits: vec![(Range::between(at, at), new_text)],
+		preferred: true,
+	})
+}
+
+// How many parameters the trait method `name` takes, from the trait's
+// declaration in this file, one it imports, or the prelude. `None` for a
+// method that isn't a function.
+fn trait_method_arity(module: &Module, path: &Path, trait_name: &str, name: &str) -> Option<usize> {
+	let find = |module: &Module| {
+		module
+			.ast
+			.as_ref()?
+			.body
+			.iter()
+			.find_map(|def| match &def.kind {
+				DefinitionKind::Trait(t) if def.name.name == trait_name => {
+					t.methods.iter().find(|m| m.name.name == name)
+				}
+				_ => None,
+			})
+			.map(|m| arity(&m.signature))
+	};
+	find(module)
+		.or_else(|| {
+			module.ast.as_ref()?.uses.iter().find_map(|u| {
+				let (imported, _) = goto::module_file(&u.module_name(), path)?;
+				find(&imported)
+			})
+		})
+		.or_else(|| find(&parse(compiler::PRELUDE_SOURCE.as_bytes())?))?
+}
+
+fn arity(signature: &TypeExprNode) -> Option<usize> {
+	match &signature.kind {
+		TypeExprKind::Func(params, _) => Some(params.len()),
+		TypeExprKind::Grouping(inner) => arity(inner),
+		_ => None,
+	}
+}
+
+// The variants of the enum a constructor pattern names, with their payload
+// arities: declared in this file (`color.red`), in the module a qualifier
+// names (`colors.color.red`), or in the prelude for a bare `some`.
+fn enum_variants(module: &Module, path: &Path, head: &ConstructorHead) -> Vec<(String, usize)> {
+	let variants = |module: &Module, enum_name: Option<&str>| -> Option<Vec<(String, usize)>> {
+		module
+			.ast
+			.as_ref()?
+			.body
+			.iter()
+			.find_map(|def| match &def.kind {
+				DefinitionKind::Enum(e)
+					if enum_name.map_or_else(
+						|| e.variants.iter().any(|v| v.name.name == head.variant.name),
+						|n| def.name.name == n,
+					) =>
+				{
+					Some(
+						e.variants
+							.iter()
+							.map(|v| (v.name.name.clone(), v.params.as_ref().map_or(0, Vec::len)))
+							.collect(),
+					)
+				}
+				_ => None,
+			})
+	};
+	let enum_name = head.enum_name.as_ref().map(|n| n.name.as_str());
+	let found = match (&head.module, enum_name) {
+		(None, Some(_)) => variants(module, enum_name),
+		(Some(qualifier), _) => module.ast.as_ref().and_then(|ast| {
+			let u = ast
+				.uses
+				.iter()
+				.find(|u| u.local_name().name == qualifier.name)?;
+			let (imported, _) = goto::module_file(&u.module_name(), path)?;
+			variants(&imported, enum_name)
+		}),
+		(None, None) => parse(compiler::PRELUDE_SOURCE.as_bytes()).and_then(|m| variants(&m, None)),
+	};
+	found.unwrap_or_default()
+}
+
+// The `when` whose range is exactly `range`.
+fn find_when<'a>(ast: &'a ModuleNode, range: &Range) -> Option<&'a WhenNode> {
+	ast.body.iter().find_map(|def| match &def.kind {
+		DefinitionKind::Expr(e) => when_in(e, range),
+		DefinitionKind::Instance(inst) => inst.methods.iter().find_map(|m| match &m.kind {
+			DefinitionKind::Expr(e) => when_in(e, range),
+			_ => None,
+		}),
+		DefinitionKind::Trait(t) => t
+			.methods
+			.iter()
+			.find_map(|m| m.default.as_ref().and_then(|e| when_in(e, range))),
+		_ => None,
+	})
+}
+
+fn when_in<'a>(expr: &'a ExprNode, range: &Range) -> Option<&'a WhenNode> {
+	let all = |exprs: &'a [ExprNode]| exprs.iter().find_map(|e| when_in(e, range));
+	match &expr.kind {
+		ExprKind::When(w) if goto::same_range(&w.range, range) => Some(w),
+		ExprKind::When(w) => {
+			when_in(&w.subject, range).or_else(|| w.cases.iter().find_map(|c| all(&c.body)))
+		}
+		ExprKind::BinaryOperation { left, right, .. } => {
+			when_in(left, range).or_else(|| when_in(right, range))
+		}
+		ExprKind::UnaryOperation { right, .. } => when_in(right, range),
+		ExprKind::ElementAccess { receiver, .. } | ExprKind::FieldAccess { receiver, .. } => {
+			when_in(receiver, range)
+		}
+		ExprKind::Fun(f) => all(&f.body),
+		ExprKind::Call(c) => when_in(&c.callee, range).or_else(|| all(&c.args)),
+		ExprKind::Grouping(inner) | ExprKind::Defer(inner) => when_in(inner, range),
+		ExprKind::Interpolation(parts) | ExprKind::Tuple(parts) => all(parts),
+		ExprKind::List(items) => items.iter().find_map(|item| when_in(item.expr(), range)),
+		ExprKind::Let(l) => when_in(&l.value, range),
+		ExprKind::Record(fields) => fields.iter().find_map(|(_, v)| when_in(v, range)),
+		ExprKind::RecordUpdate { base, fields } => {
+			when_in(base, range).or_else(|| fields.iter().find_map(|(_, v)| when_in(v, range)))
+		}
+		ExprKind::If(i) => when_in(&i.subject, range)
+			.or_else(|| all(&i.body))
+			.or_else(|| i.else_body.as_deref().and_then(all)),
+		ExprKind::While(w) => when_in(&w.subject, range).or_else(|| all(&w.body)),
+		ExprKind::Scope(s) => all(&s.body),
+		ExprKind::Using { body, .. } => all(body),
+		ExprKind::Try(t) => when_in(&t.value, range).or_else(|| all(&t.rest)),
+		_ => None,
+	}
+}
+
+// The backtick-quoted names in a diagnostic message, in order.
+fn quoted(message: &str) -> Vec<&str> {
+	message.split('`').skip(1).step_by(2).collect()
+}
+
+// The byte offset of the last whole-identifier occurrence of `word`.
+pub(crate) fn last_word(haystack: &str, word: &str) -> Option<usize> {
+	haystack.rmatch_indices(word).map(|(i, _)| i).find(|&i| {
+		let before = haystack[..i].chars().next_back();
+		let after = haystack[i + word.len()..].chars().next();
+		!before.is_some_and(is_ident_char) && !after.is_some_and(is_ident_char)
+	})
+}
+
+fn indentation(text: &str, line: usize) -> String {
+	let line = text.lines().nth(line).unwrap_or("");
+	line[..line.len() - line.trim_start().len()].to_string()
+}
+
+// Points count bytes within their line.
+pub(crate) fn offset(text: &str, p: Point) -> Option<usize> {
+	let mut start = 0;
+	for _ in 0..p.line {
+		start += text[start..].find('\n')? + 1;
+	}
+	let at = start + p.col;
+	(at <= text.len()).then_some(at)
+}
+
+pub(crate) fn point(text: &str, offset: usize) -> Point {
+	let before = &text[..offset];
+	let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+	Point::at(before.matches('\n').count(), offset - line_start)
+}
+
+fn parse(source: &[u8]) -> Option<Module> {
+	let mut module = Module::new("<lsp>".to_string(), PathBuf::new());
+	let mut diagnostics: Vec<Diagnostic> = Vec::new();
+	module.parse_from_bytes(source.to_vec(), &mut diagnostics);
+	module.ast.as_ref()?;
+	Some(module)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::temp_project;
+
+	// Each diagnostic's code with its fixes applied to the file, one result per
+	// fix.
+	fn fixed(dir: &Path, file: &str) -> Vec<(String, String, String)> {
+		let path = dir.join(file);
+		let source = std::fs::read(&path).unwrap();
+		let text = String::from_utf8(source.clone()).unwrap();
+		let result = crate::analysis::analyze_document(&path, source.clone());
+		let mut out = Vec::new();
+		for d in &result.diagnostics {
+			for fix in quick_fixes(d, &source, &path) {
+				let mut applied = text.clone();
+				for (range, new_text) in fix.edits.iter().rev() {
+					let start = offset(&applied, range.start).unwrap();
+					let end = offset(&applied, range.end).unwrap();
+					applied.replace_range(start..end, new_text);
+				}
+				out.push((d.code.unwrap_or("").to_string(), fix.title, applied));
+			}
+		}
+		out
+	}
+
+	#[test]
+	fn misspellings_and_missing_imports() {
+		let main = "# The entry point.\ndef lenght = 1\n\ndef m = length\n\nenum size {\n\tsmall\n\tlarge\n}\n\ndef k = size.smal\n\ndef r = {width: 1}.widt\n\ndef h = json.parse \"1\"\n";
+		let dir = temp_project("fixes-spelling", &[("main.pa", main)]);
+		let found = fixed(&dir, "main.pa");
+		let titles: Vec<(&str, &str)> = found
+			.iter()
+			.map(|(c, t, _)| (c.as_str(), t.as_str()))
+			.collect();
+		assert_eq!(
+			titles,
+			vec![
+				("E0100", "Change to `lenght`"),
+				("E0108", "Change to `small`"),
+				("E0100", "Add `use std/json`"),
+				("E0107", "Change to `width`"),
+			]
+		);
+		assert!(found[0].2.contains("def m = lenght\n"));
+		assert!(found[1].2.contains("def k = size.small\n"));
+		assert!(found[3].2.contains("def r = {width: 1}.width\n"));
+		// Above the first definition's doc comment.
+		assert!(
+			found[2]
+				.2
+				.starts_with("use std/json\n\n# The entry point.\n")
+		);
+	}
+
+	#[test]
+	fn imports_go_in_order() {
+		let main = "use std/assert\nuse std/string\n\ndef h = json.parse \"1\"\n";
+		let dir = temp_project("fixes-order", &[("main.pa", main)]);
+		let found = fixed(&dir, "main.pa");
+		let (_, _, applied) = found.iter().find(|(c, ..)| c == "E0100").unwrap();
+		assert!(applied.starts_with("use std/assert\nuse std/json\nuse std/string\n"));
+	}
+
+	#[test]
+	fn missing_arms_follow_the_existing_ones() {
+		let colors = "public enum color {\n\tred\n\tgreen\n\tcustom int int int\n}\n";
+		let main = "use colors\n\ndef name = fun c {\n\twhen c is colors.color.red {\n\t\t\"red\"\n\t}\n}\n\ndef count = fun o {\n\twhen o is some 1 {\n\t\t1\n\t}\n}\n\ndef check = fun b {\n\twhen b is true {\n\t\t1\n\t}\n}\n";
+		let dir = temp_project("fixes-arms", &[("colors.pa", colors), ("main.pa", main)]);
+		let found = fixed(&dir, "main.pa");
+		let arms: Vec<&str> = found
+			.iter()
+			.filter(|(c, ..)| c == "E0109")
+			.map(|(_, _, applied)| applied.as_str())
+			.collect();
+		assert_eq!(arms.len(), 3);
+		assert!(arms.iter().any(|a| a.contains(
+			"\t} is colors.color.green {\n\t} is colors.color.custom _ _ _ {\n\t}\n}\n\ndef count"
+		)));
+		assert!(
+			arms
+				.iter()
+				.any(|a| a.contains("\t} is some _ {\n\t} is none {\n\t}\n}\n\ndef check"))
+		);
+		assert!(arms.iter().any(|a| a.ends_with("\t} is false {\n\t}\n}\n")));
+	}
+
+	#[test]
+	fn incomplete_instances_get_method_stubs() {
+		let shapes = "public trait shape a {\n\tarea :: fun a -> int\n\tscale :: fun a int -> a\n}\n";
+		let main = "use shapes\n\nimplement shape int {\n\tdef area = fun n { n }\n}\n";
+		let dir = temp_project("fixes-stubs", &[("shapes.pa", shapes), ("main.pa", main)]);
+		let found = fixed(&dir, "main.pa");
+		let (_, title, applied) = found.iter().find(|(c, ..)| c == "E0116").unwrap();
+		assert_eq!(title, "Add a stub for `scale`");
+		assert!(applied.ends_with("\tdef area = fun n { n }\n\tdef scale = fun _a _b {\n\t}\n}\n"));
+	}
+}
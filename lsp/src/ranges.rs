@@ -0,0 +1,233 @@
+use crate::goto::{range_size, same_range};
+use crate::refactor::expressions;
+use compiler::ast::*;
+use compiler::{Diagnostic, Module, Range};
+use std::path::PathBuf;
+use tower_lsp::lsp_types::{
+	FoldingRange, FoldingRangeKind, Position, Range as LspRange, SelectionRange,
+};
+
+// Folding and selection ranges, read off the parsed AST's ranges. Parse-only,
+// like `symbols`: a file that doesn't parse still folds whatever the parser
+// recovered.
+
+fn parse(source: &[u8]) -> Option<Module> {
+	let mut module = Module::new("<lsp>".to_string(), PathBuf::new());
+	let mut diagnostics: Vec<Diagnostic> = Vec::new();
+	module.parse_from_bytes(source.to_vec(), &mut diagnostics);
+	module.ast.as_ref()?;
+	Some(module)
+}
+
+/// Folds for every multi-line `def`, `when` arm, `using` and `scope` block and
+/// list literal, and for each run of full-line comments.
+pub fn folding_ranges(source: &[u8]) -> Vec<FoldingRange> {
+	let Some(module) = parse(source) else {
+		return Vec::new();
+	};
+	let ast = module.ast.as_ref().expect("parsed");
+	let text = String::from_utf8_lossy(source);
+	let lines: Vec<&str> = text.lines().collect();
+
+	let mut regions: Vec<Range> = ast.body.iter().map(|d| d.range).collect();
+	for e in expressions(ast) {
+		match &e.node.kind {
+			ExprKind::When(w) => regions.extend(w.cases.iter().map(|c| c.range)),
+			ExprKind::Using { .. } | ExprKind::Scope(_) | ExprKind::List(_) => regions.push(e.node.range),
+			_ => {}
+		}
+	}
+
+	let mut out: Vec<FoldingRange> = regions
+		.iter()
+		.filter_map(|r| {
+			// Leave a closing bracket on its own line visible, so the fold
+			// reads `{ … }`.
+			let closer = lines
+				.get(r.end.line)
+				.is_some_and(|l| l.trim_start().starts_with(['}', ']', ')']));
+			let end = if closer {
+				r.end.line.checked_sub(1)?
+			} else {
+				r.end.line
+			};
+			(end > r.start.line).then(|| fold(r.start.line, end, None))
+		})
+		.collect();
+
+	// Trailing comments are recorded too; only whole-line ones make a run.
+	let mut commented: Vec<usize> = module
+		.comments
+		.keys()
+		.copied()
+		.filter(|&l| {
+			lines
+				.get(l)
+				.is_some_and(|t| t.trim_start().starts_with('#'))
+		})
+		.collect();
+	commented.sort();
+	let mut run_start = None;
+	for (i, &line) in commented.iter().enumerate() {
+		let start = *run_start.get_or_insert(line);
+		if commented.get(i + 1) != Some(&(line + 1)) {
+			if line > start {
+				out.push(fold(start, line, Some(FoldingRangeKind::Comment)));
+			}
+			run_start = None;
+		}
+	}
+
+	out.sort_by_key(|f| (f.start_line, std::cmp::Reverse(f.end_line)));
+	out.dedup_by_key(|f| (f.start_line, f.end_line));
+	out
+}
+
+fn fold(start: usize, end: usize, kind: Option<FoldingRangeKind>) -> FoldingRange {
+	FoldingRange {
+		start_line: start as u32,
+		end_line: end as u32,
+		kind,
+		..FoldingRange::default()
+	}
+}
+
+/// For each position, the syntax nodes around it from the innermost out —
+/// what expand selection steps through and shrink selection steps back down.
+pub fn selection_ranges(source: &[u8], positions: &[Position]) -> Vec<SelectionRange> {
+	let nodes = parse(source).map(|m| node_ranges(&m)).unwrap_or_default();
+	positions
+		.iter()
+		.map(|p| {
+			let at = (p.line as usize, p.character as usize);
+			let mut around: Vec<&Range> = nodes
+				.iter()
+				.filter(|r| start(r) <= at && at <= end(r))
+				.collect();
+			around.sort_by_key(|r| (std::cmp::Reverse(range_size(r)), start(r)));
+
+			// Outermost first, each step kept only if it nests in the last — two
+			// siblings can both touch a position on their shared edge.
+			let mut chain: Option<SelectionRange> = None;
+			let mut last: Option<&Range> = None;
+			for r in around {
+				if last.is_some_and(|l| start(r) < start(l) || end(r) > end(l) || same_range(r, l)) {
+					continue;
+				}
+				last = Some(r);
+				chain = Some(SelectionRange {
+					range: to_lsp(r),
+					parent: chain.map(Box::new),
+				});
+			}
+			chain.unwrap_or(SelectionRange {
+				range: LspRange { start: *p, end: *p },
+				parent: None,
+			})
+		})
+		.collect()
+}
+
+// The ranges of the nodes a selection can grow through: definitions and
+// their names, expressions, `when` arms, patterns and parameters.
+fn node_ranges(module: &Module) -> Vec<Range> {
+	let ast = module.ast.as_ref().expect("parsed");
+	let mut out: Vec<Range> = Vec::new();
+	for u in &ast.uses {
+		out.push(u.range);
+	}
+	for d in &ast.body {
+		out.extend([d.range, d.name.range]);
+		if let DefinitionKind::Instance(inst) = &d.kind {
+			for m in &inst.methods {
+				out.extend([m.range, m.name.range]);
+			}
+		}
+	}
+	for e in expressions(ast) {
+		out.push(e.node.range);
+		match &e.node.kind {
+			ExprKind::Fun(f) => out.extend(f.params.iter().map(|p| p.ident.range)),
+			ExprKind::Let(l) => out.push(l.pattern.range),
+			ExprKind::If(i) => out.push(i.pattern.range),
+			ExprKind::When(w) => {
+				for c in &w.cases {
+					out.extend([c.range, c.pattern.range]);
+				}
+			}
+			_ => {}
+		}
+	}
+	out
+}
+
+fn start(r: &Range) -> (usize, usize) {
+	(r.start.line, r.start.col)
+}
+
+fn end(r: &Range) -> (usize, usize) {
+	(r.end.line, r.end.col)
+}
+
+fn to_lsp(r: &Range) -> LspRange {
+	LspRange {
+		start: Position {
+			line: r.start.line as u32,
+			character: r.start.col as u32,
+		},
+		end: Position {
+			line: r.end.line as u32,
+			character: r.end.col as u32,
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn folds(source: &str) -> Vec<(u32, u32, bool)> {
+		folding_ranges(source.as_bytes())
+			.into_iter()
+			.map(|f| (f.start_line, f.end_line, f.kind.is_some()))
+			.collect()
+	}
+
+	#[test]
+	fn folds_blocks_and_comment_runs() {
+		let src = "# Sizes.\n# Two lines.\ndef pick = fun n {\n\twhen n is 1 {\n\t\t\"one\"\n\t} is _ {\n\t\tlet xs = [\n\t\t\t1,\n\t\t\t2,\n\t\t]\n\t\t\"many\"\n\t}\n}\n";
+		assert_eq!(
+			folds(src),
+			vec![
+				(0, 1, true),
+				(2, 11, false),
+				(3, 4, false),
+				(5, 10, false),
+				(6, 8, false),
+			]
+		);
+		// Single-line blocks don't fold.
+		assert!(folds("def x = [1, 2]\n").is_empty());
+	}
+
+	#[test]
+	fn selections_grow_along_the_tree() {
+		let src = "def f = fun n {\n\tn + 1\n}\n";
+		let chain = &selection_ranges(src.as_bytes(), &[Position::new(1, 1)])[0];
+		let mut spans = Vec::new();
+		let mut at = Some(chain);
+		while let Some(s) = at {
+			spans.push((
+				s.range.start.line,
+				s.range.start.character,
+				s.range.end.line,
+				s.range.end.character,
+			));
+			at = s.parent.as_deref();
+		}
+		assert_eq!(
+			spans,
+			vec![(1, 1, 1, 2), (1, 1, 1, 6), (0, 8, 2, 1), (0, 0, 2, 1)]
+		);
+	}
+}
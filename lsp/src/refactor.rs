@@ -0,0 +1,905 @@
+use crate::goto::{self, RefKind, Resolver, same_range};
+use crate::hover::HoverHit;
+use crate::quick_fixes::{offset, point};
+use compiler::ast::*;
+use compiler::types::Type;
+use compiler::{Diagnostic, Module, Range};
+use std::path::Path;
+
+// Refactorings offered as code actions: extract the selected expression into a
+// new top-level def, inline a `let` binding at its uses, and convert between
+// `if` and `when`. Each is built as replacements of the nodes it changes, and
+// only the new text is run through the formatter — the rest of the file is
+// left as it was written, formatted or not.
+
+/// The code action kind a refactoring is offered under.
+#[derive(Clone, Copy)]
+pub enum RefactorKind {
+	Extract,
+	Inline,
+	Rewrite,
+}
+
+pub struct Refactor {
+	pub title: String,
+	pub kind: RefactorKind,
+	// A single replacement: the changed span of the file and its new text.
+	pub edit: (Range, String),
+}
+
+/// The refactorings available for the selection (or the cursor, when it's
+/// empty) in `source`. `hits` is the last analysis's hover index, used for an
+/// extracted def's signature.
+pub fn refactors(source: &[u8], path: &Path, selection: Range, hits: &[HoverHit]) -> Vec<Refactor> {
+	let Ok(text) = std::str::from_utf8(source) else {
+		return Vec::new();
+	};
+	let mut diagnostics: Vec<Diagnostic> = Vec::new();
+	let mut module = Module::new("<lsp>".to_string(), std::path::PathBuf::new());
+	module.parse_from_bytes(source.to_vec(), &mut diagnostics);
+	// The refactorings read the parsed tree, so the file must parse.
+	if diagnostics.iter().any(|d| d.is_error()) {
+		return Vec::new();
+	}
+	let Some(ast) = module.ast.as_ref() else {
+		return Vec::new();
+	};
+	let exprs = expressions(ast);
+	let mut resolver = Resolver::new(ast);
+	resolver.walk_module(ast);
+	let this = crate::references::project_of(path).map(|(_, m)| m);
+	let file = File {
+		text,
+		ast,
+		exprs: &exprs,
+		resolver: &resolver,
+	};
+
+	let mut out = Vec::new();
+	let found = [
+		extract(&file, selection, hits, this.as_deref()),
+		inline(&file, selection),
+		when_to_if(&file, selection),
+		if_to_when(&file, selection),
+	];
+	for (title, kind, changes) in found.into_iter().flatten() {
+		if let Some(edit) = finish(text, changes) {
+			out.push(Refactor { title, kind, edit });
+		}
+	}
+	out
+}
+
+struct File<'a> {
+	text: &'a str,
+	ast: &'a ModuleNode,
+	exprs: &'a [Expr<'a>],
+	resolver: &'a Resolver,
+}
+
+impl File<'_> {
+	fn slice(&self, range: &Range) -> Option<&str> {
+		self
+			.text
+			.get(offset(self.text, range.start)?..offset(self.text, range.end)?)
+	}
+
+	fn span(&self, range: &Range) -> Option<(usize, usize)> {
+		Some((
+			offset(self.text, range.start)?,
+			offset(self.text, range.end)?,
+		))
+	}
+}
+
+// A refactoring's title and kind, and its changes: byte spans of the file,
+// each with the text that replaces it.
+type Found = Option<(String, RefactorKind, Vec<(usize, usize, String)>)>;
+
+// -- extract ----------------------------------------------------------------
+
+// The selected expression becomes `def extracted = fun a b { … }`, right after
+// the def it came from, taking the locals it reads as parameters. The call
+// replaces the selection. The signature is written out when the analysis typed
+// every part of it concretely; a generic one is left for inference, since it
+// may need trait constraints the hover types don't carry.
+fn extract(file: &File, selection: Range, hits: &[HoverHit], this: Option<&str>) -> Found {
+	let (start, end) = file.span(&selection)?;
+	let selected = file.text.get(start..end)?;
+	let start = start + (selected.len() - selected.trim_start().len());
+	let end = end - (selected.len() - selected.trim_end().len());
+	if start >= end {
+		return None;
+	}
+	let expr = file
+		.exprs
+		.iter()
+		.find(|e| file.span(&e.node.range) == Some((start, end)))?;
+	if matches!(
+		expr.node.kind,
+		ExprKind::Let(_) | ExprKind::Try(_) | ExprKind::ImplicitMember { .. }
+	) {
+		return None;
+	}
+	// The whole value of a top-level def is already its own def.
+	if let DefinitionKind::Expr(value) = &expr.def.kind
+		&& same_range(&value.range, &expr.node.range)
+	{
+		return None;
+	}
+
+	// The locals the expression reads that are bound outside it, in order.
+	let inside = |r: &Range| file.span(r).is_some_and(|(s, e)| s >= start && e <= end);
+	let mut free: Vec<(&str, Range)> = Vec::new();
+	let mut seen: Vec<Range> = Vec::new();
+	let mut refs: Vec<_> = file
+		.resolver
+		.refs
+		.iter()
+		.filter(|r| inside(&r.range))
+		.collect();
+	refs.sort_by_key(|r| (r.range.start.line, r.range.start.col));
+	for r in refs {
+		let Some((RefKind::Value, binding)) = file.resolver.resolve_ref(r) else {
+			continue;
+		};
+		if binding.scope.is_none()
+			|| inside(&binding.def_range)
+			|| seen.iter().any(|s| same_range(s, &binding.def_range))
+		{
+			continue;
+		}
+		seen.push(binding.def_range);
+		free.push((&r.name, r.range));
+	}
+
+	let name = fresh_name(file, "extracted");
+	let typed = |range: &Range| {
+		hits
+			.iter()
+			.find(|h| same_range(&h.range, range))
+			.map(|h| h.ty.clone())
+	};
+	let body = file.text.get(start..end)?;
+	let params: Vec<&str> = free.iter().map(|(n, _)| *n).collect();
+	let signature = free
+		.iter()
+		.map(|(_, r)| typed(r))
+		.collect::<Option<Vec<Type>>>()
+		.zip(typed(&expr.node.range))
+		.map(|(params, result)| {
+			if params.is_empty() {
+				result
+			} else {
+				Type::Fun(params, Box::new(result))
+			}
+		})
+		.and_then(|ty| type_syntax(&ty, this, file.ast));
+	let annotation = signature.map_or(String::new(), |s| format!(" :: {}", s));
+	let def = format_def(&if params.is_empty() {
+		format!("def {}{} = {}", name, annotation, body)
+	} else {
+		format!(
+			"def {}{} = fun {} {{\n\t{}\n}}",
+			name,
+			annotation,
+			params.join(" "),
+			body
+		)
+	})?;
+	let call = if params.is_empty() {
+		name.clone()
+	} else if expr.standalone {
+		format!("{} {}", name, params.join(" "))
+	} else {
+		format!("({} {})", name, params.join(" "))
+	};
+
+	let def_end = offset(file.text, expr.def.range.end)?;
+	Some((
+		format!("Extract into `{}`", name),
+		RefactorKind::Extract,
+		vec![
+			(start, end, call),
+			(def_end, def_end, format!("\n\n{}", def)),
+		],
+	))
+}
+
+// `base`, or `base-2`, `base-3`, … — whichever no top-level def or other
+// binding in the file already uses.
+fn fresh_name(file: &File, base: &str) -> String {
+	let taken = |name: &str| {
+		file.ast.body.iter().any(|d| d.name.name == name)
+			|| file.resolver.values.iter().any(|b| b.name == name)
+	};
+	let mut name = base.to_string();
+	let mut n = 2;
+	while taken(&name) {
+		name = format!("{}-{}", base, n);
+		n += 1;
+	}
+	name
+}
+
+// A type as it's written in a signature, or `None` when it can't be written
+// here: an unresolved or generic type, an open record, or an enum from a
+// module the file doesn't import.
+fn type_syntax(ty: &Type, this: Option<&str>, ast: &ModuleNode) -> Option<String> {
+	let nested = |t: &Type| {
+		let s = type_syntax(t, this, ast)?;
+		Some(if s.contains(' ') {
+			format!("({})", s)
+		} else {
+			s
+		})
+	};
+	Some(match ty {
+		Type::Bool => "bool".into(),
+		Type::Int => "int".into(),
+		Type::Float => "float".into(),
+		Type::String => "string".into(),
+		Type::Bytes => "bytes".into(),
+		Type::Instant => "instant".into(),
+		Type::Duration => "duration".into(),
+		Type::Nothing => "nothing".into(),
+		Type::List(t) => format!("list {}", nested(t)?),
+		Type::Ref(t) => format!("ref {}", nested(t)?),
+		Type::Dict(k, v) => format!("dict {} {}", nested(k)?, nested(v)?),
+		Type::Tuple(items) => format!(
+			"({})",
+			items
+				.iter()
+				.map(|t| type_syntax(t, this, ast))
+				.collect::<Option<Vec<_>>>()?
+				.join(", ")
+		),
+		Type::Record(fields, None) => {
+			let mut fields = fields
+				.iter()
+				.map(|(l, t)| Some(format!("{} :: {}", l, type_syntax(t, this, ast)?)))
+				.collect::<Option<Vec<_>>>()?;
+			fields.sort();
+			format!("{{{}}}", fields.join(", "))
+		}
+		Type::Fun(params, result) => {
+			let params = if params.is_empty() {
+				"nothing".to_string()
+			} else {
+				params
+					.iter()
+					.map(nested)
+					.collect::<Option<Vec<_>>>()?
+					.join(" ")
+			};
+			format!("fun {} -> {}", params, type_syntax(result, this, ast)?)
+		}
+		Type::Enum(qualified, args) => {
+			let (module, name) = qualified.rsplit_once('.')?;
+			let head = if module == "__prelude__" || Some(module) == this {
+				name.to_string()
+			} else {
+				let u = ast.uses.iter().find(|u| u.module_name() == module)?;
+				format!("{}.{}", u.local_name().name, name)
+			};
+			let mut out = head;
+			for arg in args {
+				out.push(' ');
+				out.push_str(&nested(arg)?);
+			}
+			out
+		}
+		_ => return None,
+	})
+}
+
+// -- inline -----------------------------------------------------------------
+
+// `let x = value` on the cursor (at the binding or one of its uses): every use
+// becomes the value — parenthesized unless it's atomic — and the `let` goes.
+fn inline(file: &File, selection: Range) -> Found {
+	let (line, col) = (selection.start.line, selection.start.col);
+	let r = file.resolver;
+	let binding = r
+		.values
+		.iter()
+		.filter(|b| b.scope.is_some())
+		.find(|b| goto::contains(&b.def_range, line, col))
+		.or_else(|| {
+			let at = r
+				.refs
+				.iter()
+				.find(|rf| goto::contains(&rf.range, line, col))?;
+			match r.resolve_ref(at)? {
+				(RefKind::Value, b) if b.scope.is_some() => Some(b),
+				_ => None,
+			}
+		})?;
+	let (let_expr, value) = file.exprs.iter().find_map(|e| match &e.node.kind {
+		ExprKind::Let(l) => match &l.pattern.kind {
+			PatternKind::Identifier(id) if same_range(&id.range, &binding.def_range) => {
+				Some((e.node, &l.value))
+			}
+			_ => None,
+		},
+		_ => None,
+	})?;
+	let uses: Vec<&goto::Reference> = r
+		.refs
+		.iter()
+		.filter(|rf| match r.resolve_ref(rf) {
+			Some((RefKind::Value, b)) => same_range(&b.def_range, &binding.def_range),
+			_ => false,
+		})
+		.collect();
+	if uses.is_empty() {
+		return None;
+	}
+
+	let (value_start, _) = file.span(&value.range)?;
+	let value_text = format_expr(file.text, value_start, file.slice(&value.range)?)?;
+	let atomic = matches!(
+		value.kind,
+		ExprKind::Identifier(_)
+			| ExprKind::Literal(_)
+			| ExprKind::Grouping(_)
+			| ExprKind::List(_)
+			| ExprKind::Record(_)
+			| ExprKind::Tuple(_)
+			| ExprKind::EmptyTuple
+			| ExprKind::Interpolation(_)
+			| ExprKind::FieldAccess { .. }
+			| ExprKind::ElementAccess { .. }
+			| ExprKind::NamespaceAccess(_)
+	);
+	let replacement = if atomic {
+		value_text.clone()
+	} else {
+		format!("({})", value_text)
+	};
+
+	// The `let`'s whole line when it has one to itself, else just the `let`.
+	let (mut let_start, mut let_end) = file.span(&let_expr.range)?;
+	let line_start = file.text[..let_start].rfind('\n').map_or(0, |i| i + 1);
+	let line_end = file.text[let_end..]
+		.find('\n')
+		.map_or(file.text.len(), |i| let_end + i + 1);
+	if file.text[line_start..let_start].trim().is_empty()
+		&& file.text[let_end..line_end].trim().is_empty()
+	{
+		(let_start, let_end) = (line_start, line_end);
+	}
+
+	let mut edits: Vec<(usize, usize, String)> = vec![(let_start, let_end, String::new())];
+	for rf in uses {
+		let (s, e) = file.span(&rf.range)?;
+		let shorthand = r
+			.shorthands
+			.contains(&(rf.range.start.line, rf.range.start.col));
+		edits.push((
+			s,
+			e,
+			if shorthand {
+				format!("{}: {}", rf.name, value_text)
+			} else {
+				replacement.clone()
+			},
+		));
+	}
+	Some((
+		format!("Inline `{}`", binding.name),
+		RefactorKind::Inline,
+		edits,
+	))
+}
+
+// -- if / when --------------------------------------------------------------
+
+// A `when` on the cursor (in its head, up to the first arm's pattern) becomes
+// an `if`/`else if` chain. Its last arm becomes the `else`, so that arm may
+// bind nothing its body reads; a boolean `when` becomes a plain `if`/`else`.
+// The subject is repeated in each link, so a chain of more than one needs a
+// subject that's just a name.
+fn when_to_if(file: &File, selection: Range) -> Found {
+	let (at, _) = file.span(&selection)?;
+	let when = file.exprs.iter().rev().find_map(|e| match &e.node.kind {
+		ExprKind::When(w) => {
+			let (start, _) = file.span(&w.range)?;
+			let (_, head_end) = file.span(&w.cases.first()?.pattern.range)?;
+			(start <= at && at <= head_end).then_some(w)
+		}
+		_ => None,
+	})?;
+	let [arms @ .., last] = when.cases.as_slice() else {
+		return None;
+	};
+	if arms.is_empty() {
+		return None;
+	}
+	let subject = file.slice(&when.subject.range)?;
+	let body = |case: &CaseNode| -> Option<String> {
+		let (_, pattern_end) = file.span(&case.pattern.range)?;
+		let (_, end) = file.span(&case.range)?;
+		Some(file.text.get(pattern_end..end)?.trim().to_string())
+	};
+	let bool_of = |case: &CaseNode| match &case.pattern.kind {
+		PatternKind::Literal(LiteralNode {
+			kind: LiteralKind::Bool(b),
+			..
+		}) => Some(*b),
+		_ => None,
+	};
+
+	let chain = match (arms, bool_of(&arms[0]), bool_of(last)) {
+		([first], Some(a), Some(b)) if a != b => {
+			let (yes, no) = if a { (first, last) } else { (last, first) };
+			format!("if {} {} else {}", subject, body(yes)?, body(no)?)
+		}
+		_ => {
+			if arms.len() > 1 && !matches!(when.subject.kind, ExprKind::Identifier(_)) {
+				return None;
+			}
+			let last_body = body(last)?;
+			if bound_names(&last.pattern)
+				.iter()
+				.any(|n| crate::quick_fixes::last_word(&last_body, n).is_some())
+			{
+				return None;
+			}
+			let mut links = Vec::new();
+			for case in arms {
+				links.push(format!(
+					"if {} is {} {}",
+					subject,
+					file.slice(&case.pattern.range)?,
+					body(case)?
+				));
+			}
+			format!("{} else {}", links.join(" else "), last_body)
+		}
+	};
+	let (start, end) = file.span(&when.range)?;
+	Some((
+		"Convert to `if`".to_string(),
+		RefactorKind::Rewrite,
+		vec![(start, end, format_expr(file.text, start, &chain)?)],
+	))
+}
+
+// An `if` chain on the cursor (in its head) becomes a `when`, when every link
+// tests the same subject against a pattern; a plain `if c` becomes a `when`
+// over `true` and `false`. A chain without a final `else` gets an empty one,
+// since a `when` must cover every case.
+fn if_to_when(file: &File, selection: Range) -> Found {
+	let (at, _) = file.span(&selection)?;
+	// The `if`s that continue another's chain, to start only from a head.
+	let links: Vec<&Range> = file
+		.exprs
+		.iter()
+		.filter_map(|e| match &e.node.kind {
+			ExprKind::If(i) => match i.else_body.as_deref() {
+				Some([next]) if matches!(next.kind, ExprKind::If(_)) => Some(&next.range),
+				_ => None,
+			},
+			_ => None,
+		})
+		.collect();
+	let (expr, head) = file.exprs.iter().rev().find_map(|e| match &e.node.kind {
+		ExprKind::If(i) if !links.iter().any(|l| same_range(l, &e.node.range)) => {
+			let (start, _) = file.span(&i.range)?;
+			let (_, head_end) = file.span(&i.pattern.range)?;
+			(start <= at && at <= head_end).then_some((e.node, i))
+		}
+		_ => None,
+	})?;
+
+	// Each link's subject, pattern and braced body, then the final `else` body.
+	let mut arms: Vec<(&str, Option<&str>, &str)> = Vec::new();
+	let mut link = head;
+	let otherwise = loop {
+		let plain = same_range(&link.pattern.range, &link.subject.range);
+		let pattern = (!plain).then(|| file.slice(&link.pattern.range)).flatten();
+		let (_, pattern_end) = file.span(&link.pattern.range)?;
+		let open = pattern_end + file.text[pattern_end..].find('{')?;
+		let after_body = match link.body.last() {
+			Some(e) => file.span(&e.range)?.1,
+			None => open + 1,
+		};
+		let close = after_body + file.text[after_body..].find('}')? + 1;
+		arms.push((
+			file.slice(&link.subject.range)?,
+			pattern,
+			&file.text[open..close],
+		));
+
+		let Some(else_body) = &link.else_body else {
+			break None;
+		};
+		let rest = file.text[close..].trim_start().strip_prefix("else")?;
+		let rest_start = file.text.len() - rest.len();
+		let (_, link_end) = file.span(&link.range)?;
+		match else_body.as_slice() {
+			[next] if rest.trim_start().starts_with("if") => {
+				let ExprKind::If(next) = &next.kind else {
+					return None;
+				};
+				link = next;
+			}
+			_ => break Some(file.text.get(rest_start..link_end)?.trim()),
+		}
+	};
+
+	let subject = arms[0].0;
+	let when = match arms.as_slice() {
+		[(_, None, body)] => format!(
+			"when {} is true {} is false {}",
+			subject,
+			body,
+			otherwise.unwrap_or("{\n}")
+		),
+		_ if arms
+			.iter()
+			.all(|(s, p, _)| p.is_some() && same_text(s, subject)) =>
+		{
+			let mut when = format!("when {}", subject);
+			for (_, pattern, body) in &arms {
+				when.push_str(&format!(" is {} {}", pattern.unwrap_or_default(), body));
+			}
+			when.push_str(&format!(" else {}", otherwise.unwrap_or("{\n}")));
+			when
+		}
+		_ => return None,
+	};
+	let (start, end) = file.span(&expr.range)?;
+	Some((
+		"Convert to `when`".to_string(),
+		RefactorKind::Rewrite,
+		vec![(start, end, format_expr(file.text, start, &when)?)],
+	))
+}
+
+fn same_text(a: &str, b: &str) -> bool {
+	a.split_whitespace().eq(b.split_whitespace())
+}
+
+// The names a pattern binds. A bare identifier may be a nullary variant
+// rather than a binding; counting it errs on the side of keeping the `when`.
+fn bound_names(pattern: &PatternNode) -> Vec<String> {
+	let mut out = Vec::new();
+	let mut stack = vec![pattern];
+	while let Some(p) = stack.pop() {
+		match &p.kind {
+			PatternKind::Identifier(id) => out.push(id.name.clone()),
+			PatternKind::Constructor(_, args) | PatternKind::Tuple(args) => stack.extend(args),
+			PatternKind::Record { fields, rest } => {
+				stack.extend(fields.iter().map(|(_, p)| p));
+				out.extend(
+					rest
+						.iter()
+						.filter_map(|r| r.binding.as_ref())
+						.map(|b| b.name.clone()),
+				);
+			}
+			PatternKind::List { items, rest } => {
+				stack.extend(items);
+				out.extend(
+					rest
+						.iter()
+						.filter_map(|r| r.binding.as_ref())
+						.map(|b| b.name.clone()),
+				);
+			}
+			_ => {}
+		}
+	}
+	out
+}
+
+// -- edits ------------------------------------------------------------------
+
+// A new top-level def, formatted. `None` if the formatter rejects it, which
+// refuses the refactoring rather than leave it unformatted.
+fn format_def(def: &str) -> Option<String> {
+	Some(
+		formatter::format_source(def.as_bytes())
+			.ok()?
+			.trim_end()
+			.to_string(),
+	)
+}
+
+// An expression formatted to sit at byte `at` of `text`: formatted on its own,
+// as the body of a throwaway def, then with its continuation lines indented to
+// match the line it starts on. `None` if the formatter rejects it.
+fn format_expr(text: &str, at: usize, expr: &str) -> Option<String> {
+	const HEAD: &str = "def snippet = fun {\n";
+	const TAIL: &str = "\n}\n";
+	let formatted = formatter::format_source(format!("{HEAD}{expr}{TAIL}").as_bytes()).ok()?;
+	let body = formatted.strip_prefix(HEAD)?.strip_suffix(TAIL)?;
+	let line_start = text[..at].rfind('\n').map_or(0, |i| i + 1);
+	let indent: String = text[line_start..at]
+		.chars()
+		.take_while(|c| c.is_whitespace())
+		.collect();
+	let lines: Vec<String> = body
+		.lines()
+		.enumerate()
+		.map(|(i, line)| {
+			let line = line.strip_prefix('\t').unwrap_or(line);
+			match i {
+				0 => line.to_string(),
+				_ if line.is_empty() => String::new(),
+				_ => format!("{indent}{line}"),
+			}
+		})
+		.collect();
+	Some(lines.join("\n"))
+}
+
+// The changes as one edit: the span from the first to the last, with the text
+// between them kept as it was. `None` if nothing changed.
+fn finish(text: &str, mut changes: Vec<(usize, usize, String)>) -> Option<(Range, String)> {
+	changes.sort_by_key(|(s, ..)| *s);
+	let start = changes.first()?.0;
+	let end = changes.iter().map(|(_, e, _)| *e).max()?;
+	let mut new_text = String::new();
+	let mut at = start;
+	for (s, e, replacement) in &changes {
+		new_text.push_str(text.get(at..*s)?);
+		new_text.push_str(replacement);
+		at = *e;
+	}
+	new_text.push_str(text.get(at..end)?);
+	if new_text == text[start..end] {
+		return None;
+	}
+	Some((
+		Range::between(point(text, start), point(text, end)),
+		new_text,
+	))
+}
+
+// -- expressions --------------------------------------------------------------
+
+/// An expression in a module, with the top-level def it's inside and whether
+/// it stands alone — a statement, a `let`'s value, a def's value, an operand —
+/// so that a call put in its place needs no parentheses.
+pub(crate) struct Expr<'a> {
+	pub node: &'a ExprNode,
+	pub def: &'a DefinitionNode,
+	pub standalone: bool,
+}
+
+/// Every expression in a module, outermost first.
+pub(crate) fn expressions(ast: &ModuleNode) -> Vec<Expr<'_>> {
+	let mut out = Vec::new();
+	for def in &ast.body {
+		match &def.kind {
+			DefinitionKind::Expr(e) => collect(e, def, true, &mut out),
+			DefinitionKind::Instance(inst) => {
+				for m in &inst.methods {
+					if let DefinitionKind::Expr(e) = &m.kind {
+						collect(e, def, true, &mut out);
+					}
+				}
+			}
+			DefinitionKind::Trait(t) => {
+				for e in t.methods.iter().filter_map(|m| m.default.as_ref()) {
+					collect(e, def, true, &mut out);
+				}
+			}
+			_ => {}
+		}
+	}
+	out
+}
+
+fn collect<'a>(
+	e: &'a ExprNode,
+	def: &'a DefinitionNode,
+	standalone: bool,
+	out: &mut Vec<Expr<'a>>,
+) {
+	out.push(Expr {
+		node: e,
+		def,
+		standalone,
+	});
+	let each = |exprs: &'a [ExprNode], standalone: bool, out: &mut Vec<Expr<'a>>| {
+		for x in exprs {
+			collect(x, def, standalone, out);
+		}
+	};
+	match &e.kind {
+		// Application binds tighter than any operator.
+		ExprKind::BinaryOperation { left, right, .. } => {
+			collect(left, def, true, out);
+			collect(right, def, true, out);
+		}
+		ExprKind::UnaryOperation { right, .. } => collect(right, def, false, out),
+		ExprKind::ElementAccess { receiver, .. } | ExprKind::FieldAccess { receiver, .. } => {
+			collect(receiver, def, false, out)
+		}
+		ExprKind::Fun(f) => each(&f.body, true, out),
+		ExprKind::Call(c) => {
+			collect(&c.callee, def, false, out);
+			each(&c.args, false, out);
+		}
+		ExprKind::Grouping(inner) => collect(inner, def, true, out),
+		ExprKind::Defer(inner) => collect(inner, def, false, out),
+		ExprKind::Interpolation(parts) | ExprKind::Tuple(parts) => each(parts, false, out),
+		ExprKind::List(items) => {
+			for item in items {
+				collect(item.expr(), def, false, out);
+			}
+		}
+		ExprKind::Let(l) => collect(&l.value, def, true, out),
+		ExprKind::Record(fields) => {
+			for (_, v) in fields {
+				collect(v, def, false, out);
+			}
+		}
+		ExprKind::RecordUpdate { base, fields } => {
+			collect(base, def, false, out);
+			for (_, v) in fields {
+				collect(v, def, false, out);
+			}
+		}
+		ExprKind::If(i) => {
+			collect(&i.subject, def, false, out);
+			each(&i.body, true, out);
+			if let Some(else_body) = &i.else_body {
+				each(else_body, true, out);
+			}
+		}
+		ExprKind::When(w) => {
+			collect(&w.subject, def, false, out);
+			for case in &w.cases {
+				each(&case.body, true, out);
+			}
+		}
+		ExprKind::While(w) => {
+			collect(&w.subject, def, false, out);
+			each(&w.body, true, out);
+		}
+		ExprKind::Scope(s) => each(&s.body, true, out),
+		ExprKind::Using { body, .. } => each(body, true, out),
+		ExprKind::Try(t) => {
+			collect(&t.value, def, true, out);
+			each(&t.rest, true, out);
+		}
+		_ => {}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::temp_project;
+
+	// The file after applying the refactoring titled `title`, offered for the
+	// selection (sl, sc)–(el, ec).
+	fn apply(
+		source: &str,
+		title: &str,
+		(sl, sc, el, ec): (usize, usize, usize, usize),
+	) -> Option<String> {
+		static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+		let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+		let dir = temp_project(&format!("refactor-{n}"), &[("main.pa", source)]);
+		let path = dir.join("main.pa");
+		let module = crate::analysis::analyze_document(&path, source.as_bytes().to_vec())
+			.module
+			.unwrap();
+		let hits = crate::hover::build_index(&module);
+		let selection = Range::between(compiler::Point::at(sl, sc), compiler::Point::at(el, ec));
+		let found = refactors(source.as_bytes(), &path, selection, &hits);
+		let refactor = found.into_iter().find(|r| r.title == title)?;
+		let (range, new_text) = refactor.edit;
+		let mut text = source.to_string();
+		let (start, end) = (offset(&text, range.start)?, offset(&text, range.end)?);
+		text.replace_range(start..end, &new_text);
+		Some(text)
+	}
+
+	#[test]
+	fn extracts_expressions_with_their_free_locals() {
+		let src = "def area = fun w h {\n\tlet pad = 2\n\tw * h + pad\n}\n";
+		assert_eq!(
+			apply(src, "Extract into `extracted`", (2, 1, 2, 6)).unwrap(),
+			"def area = fun w h {\n\tlet pad = 2\n\textracted w h + pad\n}\n\ndef extracted :: fun int int -> int = fun w h {\n\tw * h\n}\n"
+		);
+		// In argument position the call is parenthesized.
+		let src = "def twice = fun n {\n\tlist.repeat (n + 1) 2\n}\n";
+		let out = apply(src, "Extract into `extracted`", (1, 14, 1, 19)).unwrap();
+		assert!(out.contains("list.repeat (extracted n) 2"), "{}", out);
+		// A closed expression becomes a value.
+		let src = "def main = fun {\n\t1 + 2\n}\n";
+		let out = apply(src, "Extract into `extracted`", (1, 1, 1, 6)).unwrap();
+		assert!(
+			out.ends_with("\textracted\n}\n\ndef extracted :: int = 1 + 2\n"),
+			"{}",
+			out
+		);
+		// A selection that isn't a whole expression offers nothing.
+		assert!(apply(src, "Extract into `extracted`", (1, 1, 1, 4)).is_none());
+	}
+
+	#[test]
+	fn inlines_let_bindings() {
+		let src = "def area = fun w {\n\tlet side = w + 1\n\tside * side\n}\n";
+		assert_eq!(
+			apply(src, "Inline `side`", (2, 1, 2, 1)).unwrap(),
+			"def area = fun w {\n\t(w + 1) * (w + 1)\n}\n"
+		);
+		let src = "def label = fun name {\n\tlet n = name\n\t{n, size: 1}\n}\n";
+		assert_eq!(
+			apply(src, "Inline `n`", (1, 5, 1, 5)).unwrap(),
+			"def label = fun name {\n\t{n: name, size: 1}\n}\n"
+		);
+	}
+
+	#[test]
+	fn converts_between_if_and_when() {
+		let src = "def f = fun o {\n\twhen o is some x {\n\t\tx\n\t} is _ {\n\t\t0\n\t}\n}\n";
+		let as_if = apply(src, "Convert to `if`", (1, 2, 1, 2)).unwrap();
+		assert_eq!(
+			as_if,
+			"def f = fun o {\n\tif o is some x {\n\t\tx\n\t} else {\n\t\t0\n\t}\n}\n"
+		);
+		// And back, with the `else` kept.
+		assert_eq!(
+			apply(&as_if, "Convert to `when`", (1, 2, 1, 2)).unwrap(),
+			"def f = fun o {\n\twhen o is some x {\n\t\tx\n\t} else {\n\t\t0\n\t}\n}\n"
+		);
+
+		// A boolean split.
+		let src = "def f = fun b {\n\twhen b is false {\n\t\t0\n\t} is true {\n\t\t1\n\t}\n}\n";
+		assert_eq!(
+			apply(src, "Convert to `if`", (1, 1, 1, 1)).unwrap(),
+			"def f = fun b {\n\tif b {\n\t\t1\n\t} else {\n\t\t0\n\t}\n}\n"
+		);
+		let src = "def f = fun b {\n\tif b {\n\t\t1\n\t} else {\n\t\t0\n\t}\n}\n";
+		assert_eq!(
+			apply(src, "Convert to `when`", (1, 1, 1, 1)).unwrap(),
+			"def f = fun b {\n\twhen b is true {\n\t\t1\n\t} is false {\n\t\t0\n\t}\n}\n"
+		);
+
+		// A chain over one subject.
+		let src = "def f = fun n {\n\tif n is 1 {\n\t\t\"one\"\n\t} else if n is 2 {\n\t\t\"two\"\n\t} else {\n\t\t\"many\"\n\t}\n}\n";
+		let as_when = apply(src, "Convert to `when`", (1, 1, 1, 1)).unwrap();
+		assert_eq!(
+			as_when,
+			"def f = fun n {\n\twhen n is 1 {\n\t\t\"one\"\n\t} is 2 {\n\t\t\"two\"\n\t} else {\n\t\t\"many\"\n\t}\n}\n"
+		);
+		assert_eq!(
+			apply(&as_when, "Convert to `if`", (1, 1, 1, 1)).unwrap(),
+			src
+		);
+
+		// The last arm's binding is read, so it can't become the `else`.
+		let src = "def f = fun o {\n\twhen o is some 1 {\n\t\t1\n\t} is other {\n\t\tother\n\t}\n}\n";
+		assert!(apply(src, "Convert to `if`", (1, 1, 1, 1)).is_none());
+	}
+
+	#[test]
+	fn formats_only_the_changed_code() {
+		// The rest of an unformatted file stays as it was written.
+		let src = "def   other=[1,2]\n\ndef area = fun w h {\n  let pad = 2\n  w*h + pad\n}\n";
+		assert_eq!(
+			apply(src, "Extract into `extracted`", (4, 2, 4, 5)).unwrap(),
+			"def   other=[1,2]\n\ndef area = fun w h {\n  let pad = 2\n  extracted w h + pad\n}\n\ndef extracted :: fun int int -> int = fun w h {\n\tw * h\n}\n"
+		);
+		let src = "def area = fun w {\n\tlet side = w+1\n\tlet  keep=side\n\tkeep\n}\n";
+		assert_eq!(
+			apply(src, "Inline `side`", (1, 5, 1, 5)).unwrap(),
+			"def area = fun w {\n\tlet  keep=(w + 1)\n\tkeep\n}\n"
+		);
+		// A replaced node is formatted, indented to the line it starts on.
+		let src = "def other =  1\n\ndef f = fun b {\n\tlet x = if b { 1 } else {   0 }\n\tx\n}\n";
+		assert_eq!(
+			apply(src, "Convert to `when`", (3, 10, 3, 10)).unwrap(),
+			"def other =  1\n\ndef f = fun b {\n\tlet x = when b is true {\n\t\t1\n\t} is false {\n\t\t0\n\t}\n\tx\n}\n"
+		);
+	}
+}
@@ -0,0 +1,1032 @@
+use crate::goto::{self, Binding, RefKind, Reference, Resolver};
+use crate::goto::{contains, range_size, same_range, scope_contains, scope_size};
+use compiler::ast::*;
+use compiler::{Diagnostic, Module, Range, Token, Tokenizer, find_project_root};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// Find-references and rename, resolved with goto's syntactic `Resolver`.
+//
+// The name under the cursor is first pinned to an identity that means the
+// same thing in every file: a binding's own range for names only this file
+// sees (locals, parameters, type parameters, imports), the declaring module
+// plus the name for top-level defs, types, traits and variants, and the bare
+// label for record fields, which are structural and so match by name. Then
+// each file that can see the symbol is indexed and every reference in it
+// resolved the way goto resolves the one under the cursor.
+//
+// Other files reach a top-level name as `ns.name` through a `use` — except
+// a trait and its methods, which importers name bare. So a top-level symbol
+// is looked for in its declaring module and in every project file importing
+// it; a field in the file, the modules it imports and the modules importing
+// it. Open editor buffers stand in for their files on disk.
+
+/// Unsaved editor buffers by file path, read instead of the file on disk.
+pub type Overlay = HashMap<PathBuf, String>;
+
+/// One place a symbol is spelled.
+pub struct Occurrence {
+	pub path: PathBuf,
+	pub range: Range,
+	/// Where the symbol is declared, rather than used.
+	pub declaration: bool,
+	form: Form,
+}
+
+// How an occurrence spells the name, for the spots rename can't just
+// overwrite.
+#[derive(Clone, Copy, PartialEq)]
+enum Form {
+	Plain,
+	// The label of a shorthand field `{a}`: the field is renamed, the value
+	// it reads stays.
+	ShorthandLabel,
+	// The value side of a shorthand field: the other way around.
+	ShorthandValue,
+	// The last segment of a `use` without `as`, which names the namespace
+	// but is part of the module path, so renaming adds an alias instead.
+	ImportPath,
+}
+
+impl Occurrence {
+	/// The edit renaming this occurrence from `old` to `new`.
+	pub fn edit(&self, old: &str, new: &str) -> (Range, String) {
+		match self.form {
+			Form::Plain => (self.range, new.to_string()),
+			Form::ShorthandLabel => (self.range, format!("{new}: {old}")),
+			Form::ShorthandValue => (self.range, format!("{old}: {new}")),
+			Form::ImportPath => (
+				Range::collapsed(self.range.end.line, self.range.end.col),
+				format!(" as {new}"),
+			),
+		}
+	}
+}
+
+enum Symbol {
+	// Seen by this file only; the binding's range identifies it.
+	Local {
+		kind: RefKind,
+		def_range: Range,
+	},
+	// A top-level name of `module` (`None` for a file outside any project).
+	// `trait_member` marks a trait or trait method, which importers name bare.
+	Global {
+		module: Option<String>,
+		kind: RefKind,
+		enum_name: Option<String>,
+		trait_member: bool,
+	},
+	// A record field label.
+	Field,
+	// A name no binding claims: a prelude built-in, or a typo.
+	Builtin {
+		kind: RefKind,
+	},
+}
+
+struct Target {
+	symbol: Symbol,
+	name: String,
+	// The identifier under the cursor.
+	range: Range,
+}
+
+// A parsed file and its collected names.
+struct Indexed {
+	path: PathBuf,
+	module: Module,
+	resolver: Resolver,
+}
+
+impl Indexed {
+	fn parse(path: &Path, source: &[u8]) -> Option<Self> {
+		let mut module = Module::new("<lsp>".to_string(), PathBuf::new());
+		let mut diagnostics: Vec<Diagnostic> = Vec::new();
+		module.parse_from_bytes(source.to_vec(), &mut diagnostics);
+		let ast = module.ast.as_ref()?;
+		let mut resolver = Resolver::new(ast);
+		resolver.walk_module(ast);
+		Some(Self {
+			path: path.to_path_buf(),
+			module,
+			resolver,
+		})
+	}
+
+	fn ast(&self) -> &ModuleNode {
+		self.module.ast.as_ref().expect("indexed files parsed")
+	}
+
+	fn imports(&self, module: &str) -> bool {
+		self.ast().uses.iter().any(|u| u.module_name() == module)
+	}
+}
+
+/// Every occurrence of the symbol under the cursor, across the project:
+/// its declaration and each reference, sorted by file and position.
+pub fn find_references(
+	source: &[u8],
+	path: &Path,
+	line: u32,
+	character: u32,
+	overlay: &Overlay,
+) -> Vec<Occurrence> {
+	let project = project_of(path);
+	let Some(here) = Indexed::parse(path, source) else {
+		return Vec::new();
+	};
+	let module = project.as_ref().map(|(_, m)| m.as_str());
+	let Some(target) = target_at(&here, module, line as usize, character as usize) else {
+		return Vec::new();
+	};
+	let mut out: Vec<Occurrence> = related_files(here, &target.symbol, project.as_ref(), overlay)
+		.iter()
+		.flat_map(|(file, declaring)| occurrences_in(file, &target, *declaring))
+		.collect();
+	sort(&mut out);
+	out
+}
+
+/// The occurrences of the symbol under the cursor in this file alone, for
+/// highlighting: its declaration (when it's here) and each reference.
+pub fn document_highlights(
+	source: &[u8],
+	path: &Path,
+	line: u32,
+	character: u32,
+) -> Vec<Occurrence> {
+	let project = project_of(path);
+	let Some(here) = Indexed::parse(path, source) else {
+		return Vec::new();
+	};
+	let module = project.as_ref().map(|(_, m)| m.as_str());
+	let Some(target) = target_at(&here, module, line as usize, character as usize) else {
+		return Vec::new();
+	};
+	let declaring = match &target.symbol {
+		Symbol::Global {
+			module: Some(m), ..
+		} => module == Some(m.as_str()),
+		_ => true,
+	};
+	let mut out = occurrences_in(&here, &target, declaring);
+	sort(&mut out);
+	out
+}
+
+/// The range of the name under the cursor if it can be renamed. `Err`
+/// carries why not for a name that resolves but mustn't change — a stdlib
+/// def, a built-in — so the client can say so before asking for a new name.
+pub fn prepare_rename(
+	source: &[u8],
+	path: &Path,
+	line: u32,
+	character: u32,
+) -> Result<Option<Range>, String> {
+	let project = project_of(path);
+	let Some(here) = Indexed::parse(path, source) else {
+		return Ok(None);
+	};
+	let module = project.as_ref().map(|(_, m)| m.as_str());
+	let Some(target) = target_at(&here, module, line as usize, character as usize) else {
+		return Ok(None);
+	};
+	match refusal(&target) {
+		Some(reason) => Err(reason),
+		None => Ok(Some(target.range)),
+	}
+}
+
+/// The edits renaming the symbol under the cursor to `new_name`, as
+/// (file, range, replacement). `Err` when the rename isn't allowed or would
+/// change what some name refers to.
+pub fn rename(
+	source: &[u8],
+	path: &Path,
+	line: u32,
+	character: u32,
+	new_name: &str,
+	overlay: &Overlay,
+) -> Result<Vec<(PathBuf, Range, String)>, String> {
+	if !is_identifier(new_name) {
+		return Err(format!("`{new_name}` isn't a valid name"));
+	}
+	let project = project_of(path);
+	let here = Indexed::parse(path, source)
+		.ok_or_else(|| "fix the syntax errors in this file before renaming".to_string())?;
+	let module = project.as_ref().map(|(_, m)| m.as_str());
+	let target = target_at(&here, module, line as usize, character as usize)
+		.ok_or_else(|| "there's nothing to rename here".to_string())?;
+	if let Some(reason) = refusal(&target) {
+		return Err(reason);
+	}
+	if target.name == new_name {
+		return Ok(Vec::new());
+	}
+
+	let mut edits = Vec::new();
+	for (file, declaring) in related_files(here, &target.symbol, project.as_ref(), overlay) {
+		let mut occurrences = occurrences_in(&file, &target, declaring);
+		if occurrences.is_empty() {
+			continue;
+		}
+		check_conflicts(&file, &target, declaring, &occurrences, new_name)?;
+		sort(&mut occurrences);
+		edits.extend(occurrences.iter().map(|o| {
+			let (range, text) = o.edit(&target.name, new_name);
+			(o.path.clone(), range, text)
+		}));
+	}
+	Ok(edits)
+}
+
+fn refusal(target: &Target) -> Option<String> {
+	match &target.symbol {
+		Symbol::Builtin { .. } => Some(format!(
+			"`{}` isn't defined in this project, so it can't be renamed",
+			target.name
+		)),
+		Symbol::Global {
+			module: Some(module),
+			..
+		} if compiler::lookup_stdlib_source(module).is_some() => Some(format!(
+			"`{}` is defined in the standard library (`{module}`) and can't be renamed",
+			target.name
+		)),
+		_ => None,
+	}
+}
+
+// Whether `name` reads back as exactly one identifier — so not a keyword,
+// not empty, and nothing the tokenizer would split.
+fn is_identifier(name: &str) -> bool {
+	let source = name.as_bytes().to_vec();
+	let tokens: Vec<Token> = Tokenizer::from_source(&source).collect();
+	matches!(tokens.as_slice(), [Token::Identifier(0, end)] if *end == name.len())
+}
+
+/// The project root and the document's module name within it. `None` for a
+/// document without a file path, which then only sees itself.
+pub fn project_of(path: &Path) -> Option<(PathBuf, String)> {
+	if path.as_os_str().is_empty() {
+		return None;
+	}
+	let root = find_project_root(path).or_else(|| path.parent().map(Path::to_path_buf))?;
+	let module = crate::completion::module_name_of(&root, path)?;
+	Some((root, module))
+}
+
+// -- the symbol under the cursor -------------------------------------------
+
+fn target_at(
+	here: &Indexed,
+	module: Option<&str>,
+	line: usize,
+	character: usize,
+) -> Option<Target> {
+	let ast = here.ast();
+	let r = &here.resolver;
+
+	// An import's local name is the namespace binding; the rest of its path
+	// names a module file, not a symbol.
+	for u in &ast.uses {
+		let on_use = u
+			.path
+			.iter()
+			.chain(&u.alias)
+			.any(|id| contains(&id.range, line, character));
+		if on_use {
+			let local = u.local_name();
+			if !contains(&local.range, line, character) {
+				return None;
+			}
+			return Some(Target {
+				symbol: Symbol::Local {
+					kind: RefKind::Namespace,
+					def_range: local.range,
+				},
+				name: local.name.clone(),
+				range: local.range,
+			});
+		}
+	}
+
+	// `ns.name`: a top-level name of the imported module.
+	if let Some(q) = r
+		.qualified
+		.iter()
+		.filter(|q| contains(&q.range, line, character))
+		.min_by_key(|q| range_size(&q.range))
+	{
+		let imported = ast
+			.uses
+			.iter()
+			.find(|u| u.local_name().name == q.namespace)?;
+		return Some(Target {
+			symbol: Symbol::Global {
+				module: Some(imported.module_name()),
+				kind: q.kind,
+				enum_name: q.enum_name.clone(),
+				trait_member: false,
+			},
+			name: q.name.clone(),
+			range: q.range,
+		});
+	}
+
+	// A declaration.
+	for kind in [
+		RefKind::Namespace,
+		RefKind::Type,
+		RefKind::Variant,
+		RefKind::Value,
+		RefKind::Field,
+	] {
+		if let Some(b) = r
+			.table(kind)
+			.iter()
+			.find(|b| contains(&b.def_range, line, character))
+		{
+			return Some(Target {
+				symbol: classify(ast, module, kind, b),
+				name: b.name.clone(),
+				range: b.def_range,
+			});
+		}
+	}
+
+	// A use.
+	let rf = r
+		.refs
+		.iter()
+		.filter(|rf| contains(&rf.range, line, character))
+		.min_by_key(|rf| range_size(&rf.range))?;
+	let symbol = match r.resolve_ref(rf) {
+		_ if rf.kind == RefKind::Field => Symbol::Field,
+		Some((kind, b)) => classify(ast, module, kind, b),
+		None => imported_trait_member(ast, &here.path, rf).unwrap_or(Symbol::Builtin { kind: rf.kind }),
+	};
+	Some(Target {
+		symbol,
+		name: rf.name.clone(),
+		range: rf.range,
+	})
+}
+
+fn classify(ast: &ModuleNode, module: Option<&str>, kind: RefKind, b: &Binding) -> Symbol {
+	match kind {
+		RefKind::Field => Symbol::Field,
+		RefKind::Namespace => Symbol::Local {
+			kind,
+			def_range: b.def_range,
+		},
+		_ if b.scope.is_some() => Symbol::Local {
+			kind,
+			def_range: b.def_range,
+		},
+		_ => Symbol::Global {
+			module: module.map(str::to_string),
+			kind,
+			enum_name: if kind == RefKind::Variant {
+				enum_declaring(ast, &b.def_range)
+			} else {
+				None
+			},
+			trait_member: is_trait_member(ast, kind, &b.name),
+		},
+	}
+}
+
+// The enum that declares the variant at `range`.
+fn enum_declaring(ast: &ModuleNode, range: &Range) -> Option<String> {
+	ast.body.iter().find_map(|def| match &def.kind {
+		DefinitionKind::Enum(en) if en.variants.iter().any(|v| same_range(&v.name.range, range)) => {
+			Some(def.name.name.clone())
+		}
+		_ => None,
+	})
+}
+
+// Whether `name` is a trait (as a type) or a trait method (as a value)
+// declared in `ast`.
+fn is_trait_member(ast: &ModuleNode, kind: RefKind, name: &str) -> bool {
+	ast.body.iter().any(|def| match &def.kind {
+		DefinitionKind::Trait(t) => match kind {
+			RefKind::Type => def.name.name == name,
+			RefKind::Value => t.methods.iter().any(|m| m.name.name == name),
+			_ => false,
+		},
+		_ => false,
+	})
+}
+
+// A bare name nothing here binds may be a trait or trait method declared by
+// one of the imports.
+fn imported_trait_member(ast: &ModuleNode, path: &Path, rf: &Reference) -> Option<Symbol> {
+	if !matches!(rf.kind, RefKind::Value | RefKind::Type) {
+		return None;
+	}
+	ast.uses.iter().find_map(|u| {
+		let module = goto::imported_module(&u.module_name(), path)?;
+		is_trait_member(module.ast.as_ref()?, rf.kind, &rf.name).then(|| Symbol::Global {
+			module: Some(u.module_name()),
+			kind: rf.kind,
+			enum_name: None,
+			trait_member: true,
+		})
+	})
+}
+
+// -- the files that can see it ----------------------------------------------
+
+// The current file first, then each other project file that can see the
+// symbol, flagged with whether it's the symbol's declaring module.
+fn related_files(
+	here: Indexed,
+	symbol: &Symbol,
+	project: Option<&(PathBuf, String)>,
+	overlay: &Overlay,
+) -> Vec<(Indexed, bool)> {
+	let declaring_here = match symbol {
+		Symbol::Global {
+			module: Some(m), ..
+		} => project.is_some_and(|(_, here_module)| here_module == m),
+		_ => true,
+	};
+	let Some((root, here_module)) = project else {
+		return vec![(here, declaring_here)];
+	};
+	let here_imports: Vec<String> = here.ast().uses.iter().map(|u| u.module_name()).collect();
+	let wanted = |file: &Indexed, module: &str| -> Option<bool> {
+		match symbol {
+			Symbol::Global {
+				module: Some(m), ..
+			} => {
+				if module == m {
+					Some(true)
+				} else {
+					file.imports(m).then_some(false)
+				}
+			}
+			Symbol::Field => {
+				(here_imports.iter().any(|m| m == module) || file.imports(here_module)).then_some(false)
+			}
+			_ => None,
+		}
+	};
+
+	let mut out = Vec::new();
+	if matches!(
+		symbol,
+		Symbol::Global {
+			module: Some(_),
+			..
+		} | Symbol::Field
+	) {
+		for path in project_files(root) {
+			if path == here.path {
+				continue;
+			}
+			let Some(module) = crate::completion::module_name_of(root, &path) else {
+				continue;
+			};
+			let Some(source) = overlay
+				.get(&path)
+				.cloned()
+				.or_else(|| std::fs::read_to_string(&path).ok())
+			else {
+				continue;
+			};
+			let Some(file) = Indexed::parse(&path, source.as_bytes()) else {
+				continue;
+			};
+			if let Some(declaring) = wanted(&file, &module) {
+				out.push((file, declaring));
+			}
+		}
+	}
+	out.insert(0, (here, declaring_here));
+	out
+}
+
+/// Every `.pa` file under the project root, tests and benchmarks included.
+/// Hidden, build and dependency directories are skipped.
+pub fn project_files(root: &Path) -> Vec<PathBuf> {
+	let mut out = Vec::new();
+	let mut stack = vec![root.to_path_buf()];
+	while let Some(dir) = stack.pop() {
+		let Ok(entries) = std::fs::read_dir(&dir) else {
+			continue;
+		};
+		for entry in entries.flatten() {
+			let path = entry.path();
+			let name = entry.file_name();
+			let name = name.to_string_lossy();
+			if path.is_dir() {
+				if !name.starts_with('.') && name != "target" && name != "node_modules" {
+					stack.push(path);
+				}
+			} else if name.ends_with(".pa") {
+				out.push(path);
+			}
+		}
+	}
+	out.sort();
+	out
+}
+
+// -- occurrences in one file ------------------------------------------------
+
+fn occurrences_in(file: &Indexed, target: &Target, declaring: bool) -> Vec<Occurrence> {
+	let r = &file.resolver;
+	match &target.symbol {
+		Symbol::Local { kind, def_range } => bound_occurrences(file, *kind, def_range),
+		Symbol::Global { kind, .. } if declaring => match top_level_binding(r, *kind, &target.name) {
+			Some(b) => bound_occurrences(file, *kind, &b.def_range),
+			None => Vec::new(),
+		},
+		Symbol::Global {
+			module,
+			kind,
+			enum_name,
+			trait_member,
+		} => {
+			let namespaces: Vec<&str> = file
+				.ast()
+				.uses
+				.iter()
+				.filter(|u| Some(u.module_name()) == *module)
+				.map(|u| u.local_name().name.as_str())
+				.collect();
+			let mut out: Vec<Occurrence> = r
+				.qualified
+				.iter()
+				.filter(|q| {
+					q.name == target.name
+						&& q.kind == *kind
+						&& q.enum_name == *enum_name
+						&& namespaces.contains(&q.namespace.as_str())
+				})
+				.map(|q| plain(file, q.range, false))
+				.collect();
+			if *trait_member {
+				out.extend(
+					r.refs
+						.iter()
+						.filter(|rf| rf.kind == *kind && rf.name == target.name && r.resolve_ref(rf).is_none())
+						.map(|rf| plain(file, rf.range, false)),
+				);
+			}
+			out
+		}
+		Symbol::Field => {
+			let declared = r
+				.fields
+				.iter()
+				.filter(|b| b.name == target.name)
+				.map(|b| plain(file, b.def_range, true));
+			let used = r
+				.refs
+				.iter()
+				.filter(|rf| rf.kind == RefKind::Field && rf.name == target.name)
+				.map(|rf| Occurrence {
+					form: if is_shorthand(r, &rf.range) {
+						Form::ShorthandLabel
+					} else {
+						Form::Plain
+					},
+					..plain(file, rf.range, false)
+				});
+			declared.chain(used).collect()
+		}
+		Symbol::Builtin { kind } => r
+			.refs
+			.iter()
+			.filter(|rf| rf.kind == *kind && rf.name == target.name && r.resolve_ref(rf).is_none())
+			.map(|rf| plain(file, rf.range, false))
+			.collect(),
+	}
+}
+
+// The binding at `def_range` and every reference resolving to it.
+fn bound_occurrences(file: &Indexed, kind: RefKind, def_range: &Range) -> Vec<Occurrence> {
+	let r = &file.resolver;
+	let mut out = Vec::new();
+	if let Some(b) = r
+		.table(kind)
+		.iter()
+		.find(|b| same_range(&b.def_range, def_range))
+	{
+		let unaliased_import = kind == RefKind::Namespace
+			&& file
+				.ast()
+				.uses
+				.iter()
+				.any(|u| u.alias.is_none() && same_range(&u.local_name().range, def_range));
+		let form = if unaliased_import {
+			Form::ImportPath
+		} else if kind == RefKind::Value && is_shorthand(r, &b.def_range) {
+			Form::ShorthandValue
+		} else {
+			Form::Plain
+		};
+		out.push(Occurrence {
+			form,
+			..plain(file, b.def_range, true)
+		});
+	}
+	for rf in &r.refs {
+		let Some((found, b)) = r.resolve_ref(rf) else {
+			continue;
+		};
+		if found != kind || !same_range(&b.def_range, def_range) {
+			continue;
+		}
+		let form = if rf.kind == RefKind::Value && is_shorthand(r, &rf.range) {
+			Form::ShorthandValue
+		} else {
+			Form::Plain
+		};
+		out.push(Occurrence {
+			form,
+			..plain(file, rf.range, false)
+		});
+	}
+	out
+}
+
+fn top_level_binding<'a>(r: &'a Resolver, kind: RefKind, name: &str) -> Option<&'a Binding> {
+	r.table(kind)
+		.iter()
+		.find(|b| b.name == name && b.scope.is_none())
+}
+
+fn is_shorthand(r: &Resolver, range: &Range) -> bool {
+	r.shorthands.contains(&(range.start.line, range.start.col))
+}
+
+fn plain(file: &Indexed, range: Range, declaration: bool) -> Occurrence {
+	Occurrence {
+		path: file.path.clone(),
+		range,
+		declaration,
+		form: Form::Plain,
+	}
+}
+
+fn sort(occurrences: &mut [Occurrence]) {
+	occurrences.sort_by_key(|o| (o.path.clone(), o.range.start.line, o.range.start.col));
+}
+
+// -- rename safety ----------------------------------------------------------
+
+// Refuse a rename that would change what some name in `file` refers to.
+fn check_conflicts(
+	file: &Indexed,
+	target: &Target,
+	declaring: bool,
+	occurrences: &[Occurrence],
+	new: &str,
+) -> Result<(), String> {
+	let r = &file.resolver;
+	match &target.symbol {
+		Symbol::Field => {
+			if r
+				.records
+				.iter()
+				.any(|labels| labels.contains(&target.name) && labels.iter().any(|l| l == new))
+			{
+				return Err(format!("a record here already has a field named `{new}`"));
+			}
+			Ok(())
+		}
+		Symbol::Local {
+			kind: RefKind::Namespace,
+			..
+		} => {
+			let taken = r.refs.iter().any(|rf| rf.name == new)
+				|| [
+					RefKind::Namespace,
+					RefKind::Type,
+					RefKind::Variant,
+					RefKind::Value,
+				]
+				.iter()
+				.any(|k| r.table(*k).iter().any(|b| b.name == new));
+			if taken {
+				return Err(format!("`{new}` is already used as a name in this file"));
+			}
+			Ok(())
+		}
+		Symbol::Local { kind, def_range } => scope_conflicts(r, *kind, def_range, occurrences, new),
+		Symbol::Global { kind, .. } if declaring => match top_level_binding(r, *kind, &target.name) {
+			Some(b) => scope_conflicts(r, *kind, &b.def_range, occurrences, new),
+			None => Ok(()),
+		},
+		Symbol::Global {
+			kind,
+			trait_member: true,
+			..
+		} => {
+			// A bare trait name in an importer: a local of the new name would
+			// capture it.
+			for o in occurrences {
+				if let Some(b) = visible(r, *kind, new, &o.range) {
+					return Err(format!(
+						"`{new}` is already defined on line {} of {}",
+						b.def_range.start.line + 1,
+						file.path.display()
+					));
+				}
+			}
+			Ok(())
+		}
+		_ => Ok(()),
+	}
+}
+
+// The two ways renaming the binding at `def_range` to `new` goes wrong: an
+// existing `new` at least as close would capture one of its occurrences, or
+// a use of some farther `new` inside its scope would start resolving to it.
+fn scope_conflicts(
+	r: &Resolver,
+	kind: RefKind,
+	def_range: &Range,
+	occurrences: &[Occurrence],
+	new: &str,
+) -> Result<(), String> {
+	let Some(target) = r
+		.table(kind)
+		.iter()
+		.find(|b| same_range(&b.def_range, def_range))
+	else {
+		return Ok(());
+	};
+	let size = scope_size(&target.scope);
+
+	for o in occurrences {
+		if let Some(b) = visible(r, kind, new, &o.range)
+			&& scope_size(&b.scope) <= size
+		{
+			return Err(format!(
+				"`{new}` is already defined on line {}",
+				b.def_range.start.line + 1
+			));
+		}
+	}
+
+	for rf in &r.refs {
+		let resolves_here = match kind {
+			RefKind::Value => rf.kind == RefKind::Value,
+			RefKind::Variant => matches!(rf.kind, RefKind::Value | RefKind::Variant),
+			k => rf.kind == k,
+		};
+		if rf.name != new
+			|| !resolves_here
+			|| !scope_contains(&target.scope, rf.range.start.line, rf.range.start.col)
+		{
+			continue;
+		}
+		match r.resolve_ref(rf) {
+			Some((_, b)) if scope_size(&b.scope) < size => {}
+			_ => {
+				return Err(format!(
+					"renaming to `{new}` would capture the `{new}` used on line {}",
+					rf.range.start.line + 1
+				));
+			}
+		}
+	}
+	Ok(())
+}
+
+// The binding of `name` in `kind`'s table visible at `range`, innermost first.
+fn visible<'a>(r: &'a Resolver, kind: RefKind, name: &str, range: &Range) -> Option<&'a Binding> {
+	r.table(kind)
+		.iter()
+		.filter(|b| b.name == name && scope_contains(&b.scope, range.start.line, range.start.col))
+		.min_by_key(|b| scope_size(&b.scope))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::temp_project;
+
+	// The occurrences of the symbol at (line, col), as (line, col) pairs.
+	fn refs(src: &str, line: u32, col: u32) -> Vec<(usize, usize)> {
+		find_references(src.as_bytes(), &PathBuf::new(), line, col, &Overlay::new())
+			.iter()
+			.map(|o| (o.range.start.line, o.range.start.col))
+			.collect()
+	}
+
+	// Apply a same-file rename and return the rewritten source.
+	fn renamed(src: &str, line: u32, col: u32, new: &str) -> Result<String, String> {
+		let edits = rename(
+			src.as_bytes(),
+			&PathBuf::new(),
+			line,
+			col,
+			new,
+			&Overlay::new(),
+		)?;
+		Ok(apply(
+			src,
+			edits.into_iter().map(|(_, r, t)| (r, t)).collect(),
+		))
+	}
+
+	fn apply(src: &str, mut edits: Vec<(Range, String)>) -> String {
+		let mut lines: Vec<String> = src.split('\n').map(str::to_string).collect();
+		edits.sort_by_key(|(r, _)| (r.start.line, r.start.col));
+		for (r, text) in edits.into_iter().rev() {
+			let line = &mut lines[r.start.line];
+			line.replace_range(r.start.col..r.end.col, &text);
+		}
+		lines.join("\n")
+	}
+
+	#[test]
+	fn local_references_respect_shadowing() {
+		let src = "def f = fun x {\n\tlet y = x + 1\n\tlet g = fun x { x }\n\ty + x\n}\n";
+		// The outer `x`: its param, line 1 and line 3 — not the inner fun's.
+		assert_eq!(refs(src, 0, 12), vec![(0, 12), (1, 9), (3, 5)]);
+		// From a use of the inner `x`, only the inner param and its use.
+		assert_eq!(refs(src, 2, 17), vec![(2, 13), (2, 17)]);
+	}
+
+	#[test]
+	fn rename_local_and_top_level() {
+		let src = "def helper = fun n { n }\ndef main = fun {\n\thelper (helper 1)\n}\n";
+		assert_eq!(
+			renamed(src, 2, 2, "assist").unwrap(),
+			"def assist = fun n { n }\ndef main = fun {\n\tassist (assist 1)\n}\n"
+		);
+		assert_eq!(
+			renamed(src, 0, 17, "count").unwrap(),
+			"def helper = fun count { count }\ndef main = fun {\n\thelper (helper 1)\n}\n"
+		);
+	}
+
+	#[test]
+	fn rename_refuses_to_capture_or_shadow() {
+		let src = "def f = fun a {\n\tlet b = 1\n\ta + b\n}\n";
+		// `b` is already in scope wherever `a` is used.
+		assert!(renamed(src, 0, 12, "b").is_err());
+		// A global renamed to a name a use inside it already reads.
+		let src = "def limit = 3\ndef f = fun {\n\tprint 1\n}\n";
+		assert!(renamed(src, 0, 4, "print").unwrap_err().contains("capture"));
+		// Keywords and junk aren't names.
+		assert!(renamed(src, 0, 4, "when").is_err());
+		assert!(renamed(src, 0, 4, "two words").is_err());
+	}
+
+	#[test]
+	fn variants_and_enums_rename_through_qualified_uses() {
+		let src = "enum color {\n\tred\n\tgreen\n}\ndef c = color.red\ndef f = fun x {\n\twhen x is color.red { 1 } is _ { 0 }\n}\n";
+		assert_eq!(refs(src, 1, 1), vec![(1, 1), (4, 14), (6, 17)]);
+		let out = renamed(src, 0, 6, "hue").unwrap();
+		assert!(out.contains("enum hue {"), "{out}");
+		assert!(out.contains("def c = hue.red"), "{out}");
+		assert!(out.contains("is hue.red"), "{out}");
+	}
+
+	#[test]
+	fn record_fields_match_by_label() {
+		let src = "alias point {x :: int, y :: int}\ndef p :: point = {x: 1, y: 2}\ndef f = fun q {\n\tq.x\n}\n";
+		assert_eq!(refs(src, 3, 3), vec![(0, 13), (1, 18), (3, 3)]);
+		// A field can't take a sibling's name.
+		assert!(renamed(src, 3, 3, "y").is_err());
+	}
+
+	#[test]
+	fn shorthand_fields_are_spelled_out() {
+		let src = "def f = fun name {\n\t{name}\n}\n";
+		// Renaming the local keeps the label.
+		assert_eq!(
+			renamed(src, 0, 12, "who").unwrap(),
+			"def f = fun who {\n\t{name: who}\n}\n"
+		);
+		let pattern = "def f = fun r {\n\twhen r is {name} { name }\n}\n";
+		// Renaming the field through a use elsewhere keeps the binding.
+		let src = format!("{pattern}def g = fun r {{\n\tr.name\n}}\n");
+		assert_eq!(
+			renamed(&src, 4, 4, "label").unwrap(),
+			"def f = fun r {\n\twhen r is {label: name} { name }\n}\ndef g = fun r {\n\tr.label\n}\n"
+		);
+	}
+
+	#[test]
+	fn renaming_an_import_adds_an_alias() {
+		let src = "use std/list\n\ndef x = list.reverse [1]\n";
+		assert_eq!(
+			renamed(src, 2, 9, "l").unwrap(),
+			"use std/list as l\n\ndef x = l.reverse [1]\n"
+		);
+		// An existing alias is renamed in place.
+		let src = "use std/list as xs\n\ndef x = xs.reverse [1]\n";
+		assert_eq!(
+			renamed(src, 0, 17, "l").unwrap(),
+			"use std/list as l\n\ndef x = l.reverse [1]\n"
+		);
+	}
+
+	#[test]
+	fn trait_methods_rename_with_their_instances() {
+		let src = "trait shape a {\n\tarea :: fun a -> int\n}\nimplement shape int {\n\tdef area = fun n { n }\n}\ndef f = fun {\n\tarea 2\n}\n";
+		let out = renamed(src, 7, 2, "size").unwrap();
+		assert!(out.contains("\tsize :: fun a -> int"), "{out}");
+		assert!(out.contains("\tdef size = fun n { n }"), "{out}");
+		assert!(out.contains("\tsize 2"), "{out}");
+	}
+
+	#[test]
+	fn prepare_rename_refuses_stdlib_and_builtins() {
+		let src = "use std/list\n\ndef x = list.reverse [1]\n";
+		let path = PathBuf::from("/proj/main.pa");
+		let err = prepare_rename(src.as_bytes(), &path, 2, 15).unwrap_err();
+		assert!(err.contains("standard library"), "{err}");
+		let src = "def x = fun {\n\tprint 1\n}\n";
+		assert!(prepare_rename(src.as_bytes(), &path, 1, 2).is_err());
+		// A project def is fine, and the range is the name under the cursor.
+		let range = prepare_rename(src.as_bytes(), &path, 0, 4)
+			.unwrap()
+			.unwrap();
+		assert_eq!((range.start.col, range.end.col), (4, 5));
+	}
+
+	#[test]
+	fn references_and_rename_span_the_project() {
+		let dir = temp_project(
+			"refs-project",
+			&[
+				(
+					"colors.pa",
+					"public enum color {\n\tred\n}\npublic def helper = fun { 1 }\n",
+				),
+				("other.pa", "use colors\n\ndef y = colors.helper ()\n"),
+				("unrelated.pa", "def helper = fun { 2 }\n"),
+			],
+		);
+		let main_path = dir.join("main.pa");
+		// The editor's unsaved buffer wins over the file on disk.
+		let main = "use colors as c\n\ndef x = c.helper ()\ndef r = c.color.red\n";
+		std::fs::write(&main_path, "").unwrap();
+		let overlay: Overlay = [(main_path.clone(), main.to_string())]
+			.into_iter()
+			.collect();
+
+		let found: Vec<(String, usize)> = find_references(main.as_bytes(), &main_path, 2, 11, &overlay)
+			.iter()
+			.map(|o| {
+				let file = o.path.file_name().unwrap().to_string_lossy().into_owned();
+				(file, o.range.start.line)
+			})
+			.collect();
+		assert_eq!(
+			found,
+			vec![
+				("colors.pa".into(), 3),
+				("main.pa".into(), 2),
+				("other.pa".into(), 2)
+			]
+		);
+
+		let edits = rename(main.as_bytes(), &main_path, 3, 17, "crimson", &overlay).unwrap();
+		let files: Vec<String> = edits
+			.iter()
+			.map(|(p, _, t)| format!("{}:{t}", p.file_name().unwrap().to_string_lossy()))
+			.collect();
+		assert_eq!(files, vec!["main.pa:crimson", "colors.pa:crimson"]);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn highlights_stay_in_the_file() {
+		let dir = temp_project(
+			"refs-highlights",
+			&[
+				("shapes.pa", "def area = fun n { n }\n"),
+				(
+					"main.pa",
+					"use shapes\ndef main = fun {\n\tlet a = 1\n\tshapes.area (shapes.area a)\n}\n",
+				),
+			],
+		);
+		let path = dir.join("main.pa");
+		let src = std::fs::read_to_string(&path).unwrap();
+		let highlights = |line, col| -> Vec<(usize, usize, bool)> {
+			document_highlights(src.as_bytes(), &path, line, col)
+				.iter()
+				.map(|o| (o.range.start.line, o.range.start.col, o.declaration))
+				.collect()
+		};
+		// The local: its binding, then its read.
+		assert_eq!(highlights(2, 5), vec![(2, 5, true), (3, 26, false)]);
+		// An imported def: only the uses here, not its declaration in `shapes`.
+		assert_eq!(highlights(3, 9), vec![(3, 8, false), (3, 21, false)]);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+}
@@ -1,12 +1,24 @@
+use crate::references::{Overlay, project_files};
 use compiler::ast::*;
 use compiler::{Diagnostic, Module};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
 use tower_lsp::lsp_types::{DocumentSymbol, Position, Range as LspRange, SymbolKind};
 
 // A document outline built from parser output: one top-level symbol per
 // `def`, with enum variants and trait methods nested as children. Powers
 // the editor's outline view and breadcrumb bar. Parse-only, like
 // `semantic_tokens` and `goto` — no analyzer needed.
+//
+// Workspace symbols are the same top-level names gathered from every module
+// under the project root, plus the stdlib's public ones, and matched fuzzily
+// against what the user types.
+
+// Enough for any query worth typing; an empty or one-letter query on a big
+// project would otherwise ship every name in it.
+const MAX_WORKSPACE_SYMBOLS: usize = 256;
 
 pub fn document_symbols(source: &[u8]) -> Vec<DocumentSymbol> {
 	let mut module = Module::new("<lsp>".to_string(), PathBuf::new());
@@ -134,3 +146,269 @@ fn to_lsp(r: compiler::Range) -> LspRange {
 		},
 	}
 }
+
+/// A top-level def, enum, alias or trait, as workspace symbol search finds it.
+#[derive(Clone)]
+pub struct WorkspaceEntry {
+	pub name: String,
+	pub kind: SymbolKind,
+	/// The declaring module, e.g. `std/list` or `app/routes`.
+	pub module: String,
+	/// The declaring file; `None` for a stdlib module, which has none until
+	/// it's materialized.
+	pub file: Option<PathBuf>,
+	/// The name's range in that file.
+	pub range: compiler::Range,
+}
+
+// What a cached file's entries were built from: an open buffer's contents,
+// or the file on disk as of its modification time.
+#[derive(PartialEq)]
+enum Stamp {
+	Buffer(u64),
+	Disk(SystemTime),
+}
+
+/// The workspace's top-level names, kept per file and re-parsed only when a
+/// file changes, so a search walks the tree but doesn't re-read all of it.
+#[derive(Default)]
+pub struct WorkspaceIndex {
+	files: HashMap<PathBuf, (Stamp, String, Arc<Vec<WorkspaceEntry>>)>,
+}
+
+impl WorkspaceIndex {
+	/// The names under `roots` and in the stdlib matching `query`, best first.
+	pub fn search(
+		&mut self,
+		roots: &[PathBuf],
+		overlay: &Overlay,
+		query: &str,
+	) -> Vec<WorkspaceEntry> {
+		self.refresh(roots, overlay);
+
+		let mut hits: Vec<(u32, bool, &WorkspaceEntry)> = self
+			.files
+			.values()
+			.flat_map(|(_, _, entries)| entries.iter())
+			.map(|e| (e, false))
+			.chain(stdlib_entries().iter().map(|e| (e, true)))
+			.filter_map(|(e, stdlib)| Some((fuzzy_score(query, &e.name)?, stdlib, e)))
+			.collect();
+		// Project names ahead of the stdlib's at the same score, then shorter
+		// names (closer to what was typed), then alphabetical for stability.
+		hits.sort_by(|a, b| {
+			(a.0, a.1, a.2.name.len(), &a.2.name, &a.2.module).cmp(&(
+				b.0,
+				b.1,
+				b.2.name.len(),
+				&b.2.name,
+				&b.2.module,
+			))
+		});
+		hits
+			.into_iter()
+			.take(MAX_WORKSPACE_SYMBOLS)
+			.map(|(_, _, e)| e.clone())
+			.collect()
+	}
+
+	// Bring the cache in line with the files under `roots`: parse new and
+	// changed ones, drop the ones that are gone.
+	fn refresh(&mut self, roots: &[PathBuf], overlay: &Overlay) {
+		let mut seen: HashSet<PathBuf> = HashSet::new();
+		for root in roots {
+			for path in project_files(root) {
+				let Some(module) = crate::completion::module_name_of(root, &path) else {
+					continue;
+				};
+				let stamp = match overlay.get(&path) {
+					Some(text) => Stamp::Buffer(hash(text)),
+					None => match std::fs::metadata(&path).and_then(|m| m.modified()) {
+						Ok(time) => Stamp::Disk(time),
+						Err(_) => continue,
+					},
+				};
+				seen.insert(path.clone());
+				if self
+					.files
+					.get(&path)
+					.is_some_and(|(s, m, _)| *s == stamp && *m == module)
+				{
+					continue;
+				}
+				let source = match overlay.get(&path) {
+					Some(text) => text.clone(),
+					None => std::fs::read_to_string(&path).unwrap_or_default(),
+				};
+				let entries = file_entries(source.as_bytes(), &module, Some(&path), false);
+				self.files.insert(path, (stamp, module, Arc::new(entries)));
+			}
+		}
+		self.files.retain(|path, _| seen.contains(path));
+	}
+}
+
+// The stdlib's public names, gathered once: its source is baked in.
+fn stdlib_entries() -> &'static [WorkspaceEntry] {
+	static ENTRIES: OnceLock<Vec<WorkspaceEntry>> = OnceLock::new();
+	ENTRIES.get_or_init(|| {
+		compiler::stdlib_sources()
+			.iter()
+			.flat_map(|(name, source)| file_entries(source.as_bytes(), name, None, true))
+			.collect()
+	})
+}
+
+// The top-level defs, enums, aliases and traits of one module. Instances
+// have no name of their own, so they're left out.
+fn file_entries(
+	source: &[u8],
+	module_name: &str,
+	file: Option<&Path>,
+	public_only: bool,
+) -> Vec<WorkspaceEntry> {
+	let mut module = Module::new(module_name.to_string(), PathBuf::new());
+	let mut diagnostics: Vec<Diagnostic> = Vec::new();
+	module.parse_from_bytes(source.to_vec(), &mut diagnostics);
+	let Some(ast) = module.ast.as_ref() else {
+		return Vec::new();
+	};
+
+	ast
+		.body
+		.iter()
+		.filter(|d| !public_only || d.visibility != Visibility::Private)
+		.filter_map(|d| {
+			let kind = match &d.kind {
+				DefinitionKind::Expr(expr) if matches!(expr.kind, ExprKind::Fun(_)) => SymbolKind::FUNCTION,
+				DefinitionKind::Expr(_) => SymbolKind::VARIABLE,
+				DefinitionKind::Alias(_) => SymbolKind::STRUCT,
+				DefinitionKind::Enum(_) => SymbolKind::ENUM,
+				DefinitionKind::Trait(_) => SymbolKind::INTERFACE,
+				DefinitionKind::Instance(_) => return None,
+			};
+			Some(WorkspaceEntry {
+				name: d.name.name.clone(),
+				kind,
+				module: module_name.to_string(),
+				file: file.map(Path::to_path_buf),
+				range: d.name.range,
+			})
+		})
+		.collect()
+}
+
+fn hash(text: &str) -> u64 {
+	use std::hash::{Hash, Hasher};
+	let mut hasher = std::collections::hash_map::DefaultHasher::new();
+	text.hash(&mut hasher);
+	hasher.finish()
+}
+
+// Match `query` against `name` as a case-insensitive subsequence. Lower
+// scores are better: the whole name, then a prefix, then a run of the name,
+// then letters spread through it, matched leftmost — a letter on a word's
+// start (after a `-`) costs nothing, any other one plus the letters skipped
+// to reach it. An empty query matches everything equally.
+fn fuzzy_score(query: &str, name: &str) -> Option<u32> {
+	let query = query.trim().to_lowercase();
+	let name_lower = name.to_lowercase();
+	if query.is_empty() {
+		return Some(0);
+	}
+	if name_lower == query {
+		return Some(0);
+	}
+	if name_lower.starts_with(&query) {
+		return Some(1);
+	}
+	if name_lower.contains(&query) {
+		return Some(2);
+	}
+
+	let chars: Vec<char> = name_lower.chars().collect();
+	let mut at = 0;
+	let mut cost = 3;
+	for q in query.chars() {
+		let i = (at..chars.len()).find(|&i| chars[i] == q)?;
+		if !(i == 0 || chars[i - 1] == '-') {
+			cost += (i - at) as u32 + 1;
+		}
+		at = i + 1;
+	}
+	Some(cost)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fuzzy_ranks_exact_prefix_run_then_scatter() {
+		assert_eq!(fuzzy_score("map", "map"), Some(0));
+		assert_eq!(fuzzy_score("map", "map-values"), Some(1));
+		assert_eq!(fuzzy_score("val", "map-values"), Some(2));
+		// Word starts: `mv` lands on `m`ap-`v`alues for free.
+		assert_eq!(fuzzy_score("mv", "map-values"), Some(3));
+		assert!(fuzzy_score("mpv", "map-values") > fuzzy_score("mv", "map-values"));
+		assert_eq!(fuzzy_score("xyz", "map-values"), None);
+		// Case doesn't matter.
+		assert_eq!(fuzzy_score("MAP", "map"), Some(0));
+	}
+
+	#[test]
+	fn workspace_search_covers_unopened_files_and_the_stdlib() {
+		let dir = std::env::temp_dir().join(format!("pluma-wsym-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(dir.join("app")).unwrap();
+		std::fs::write(dir.join("pluma.pa"), "").unwrap();
+		std::fs::write(
+			dir.join("app/routes.pa"),
+			"enum route {\n\thome\n}\ndef render-route = fun r { 1 }\n",
+		)
+		.unwrap();
+
+		let mut index = WorkspaceIndex::default();
+		let roots = vec![dir.clone()];
+		let found: Vec<(String, String)> = index
+			.search(&roots, &Overlay::new(), "route")
+			.into_iter()
+			.map(|e| (e.name, e.module))
+			.take(3)
+			.collect();
+		// Exact matches first, the project's ahead of the stdlib's.
+		assert_eq!(
+			found,
+			vec![
+				("route".to_string(), "app/routes".to_string()),
+				("route".to_string(), "std/router".to_string()),
+				("render-route".to_string(), "app/routes".to_string()),
+			]
+		);
+
+		// An open buffer is indexed as the editor shows it.
+		let overlay: Overlay = [(
+			dir.join("app/routes.pa"),
+			"def route-table = 1\n".to_string(),
+		)]
+		.into_iter()
+		.collect();
+		let names: Vec<String> = index
+			.search(&roots, &overlay, "route")
+			.into_iter()
+			.filter(|e| e.file.is_some())
+			.map(|e| e.name)
+			.collect();
+		assert_eq!(names, vec!["route-table".to_string()]);
+
+		// The stdlib's public names come along, without a file of their own.
+		let reverse = index
+			.search(&roots, &Overlay::new(), "reverse")
+			.into_iter()
+			.find(|e| e.module == "std/list")
+			.expect("std/list's reverse");
+		assert!(reverse.file.is_none());
+
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+}
@@ -0,0 +1,230 @@
+use crate::goto;
+use crate::hover::{self, HoverHit};
+use compiler::ast::*;
+use compiler::types::Type;
+use compiler::{Diagnostic, Module, Range};
+use std::path::{Path, PathBuf};
+
+// Go to type definition: from a value to the declarations of the types it
+// has. The type is the one hover shows, so it's only as good as the last
+// analysis. An enum type carries the module declaring it, so it maps straight
+// to its `enum`. Aliases are structural — a record type doesn't remember the
+// alias it was written as — so a record lands on each alias declaring exactly
+// its fields, in this file or one it imports.
+
+/// The declarations of the types of the value under the cursor, each as a
+/// file and the declared name's range. A function's cover its parameters
+/// and result; a `list<option<user>>` both `option` and `user`.
+pub fn type_definitions(
+	hits: &[HoverHit],
+	source: &[u8],
+	path: &Path,
+	line: u32,
+	character: u32,
+) -> Vec<(PathBuf, Range)> {
+	let Some(hit) = hover::lookup(hits, line, character) else {
+		return Vec::new();
+	};
+	let mut enums = Vec::new();
+	let mut records = Vec::new();
+	named_types(&hit.ty, &mut enums, &mut records);
+	if enums.is_empty() && records.is_empty() {
+		return Vec::new();
+	}
+	let Some(here) = parse(source) else {
+		return Vec::new();
+	};
+	let module = crate::references::project_of(path).map(|(_, m)| m);
+
+	let mut out: Vec<(PathBuf, Range)> = Vec::new();
+	let mut push = |found: (PathBuf, Range)| {
+		if !out
+			.iter()
+			.any(|(p, r)| *p == found.0 && goto::same_range(r, &found.1))
+		{
+			out.push(found);
+		}
+	};
+	for qualified in &enums {
+		let Some((declaring, name)) = qualified.rsplit_once('.') else {
+			continue;
+		};
+		// Built into the language, with no source to open.
+		if declaring == "__prelude__" {
+			continue;
+		}
+		let found = if module.as_deref() == Some(declaring) {
+			find_enum(&here, name).map(|r| (path.to_path_buf(), r))
+		} else {
+			goto::module_file(declaring, path)
+				.and_then(|(m, file)| find_enum(&m, name).map(|r| (file, r)))
+		};
+		if let Some(found) = found {
+			push(found);
+		}
+	}
+	if !records.is_empty() {
+		let imports = here.ast.as_ref().map_or(Vec::new(), |ast| {
+			ast
+				.uses
+				.iter()
+				.filter_map(|u| goto::module_file(&u.module_name(), path))
+				.collect()
+		});
+		let files = std::iter::once((&here, path.to_path_buf()))
+			.chain(imports.iter().map(|(m, file)| (m, file.clone())));
+		for (m, file) in files {
+			for labels in &records {
+				for range in find_aliases(m, labels) {
+					push((file.clone(), range));
+				}
+			}
+		}
+	}
+	out
+}
+
+// The enums (by qualified name) and record shapes (by sorted labels) a type
+// mentions, outermost first.
+fn named_types(ty: &Type, enums: &mut Vec<String>, records: &mut Vec<Vec<String>>) {
+	match ty {
+		Type::Enum(name, args) => {
+			enums.push(name.clone());
+			for arg in args {
+				named_types(arg, enums, records);
+			}
+		}
+		Type::Record(fields, _) => {
+			let mut labels: Vec<String> = fields.iter().map(|(l, _)| l.clone()).collect();
+			labels.sort();
+			records.push(labels);
+			for (_, t) in fields {
+				named_types(t, enums, records);
+			}
+		}
+		Type::Fun(params, result) => {
+			for t in params.iter().chain(std::iter::once(result.as_ref())) {
+				named_types(t, enums, records);
+			}
+		}
+		Type::Tuple(items) => {
+			for t in items {
+				named_types(t, enums, records);
+			}
+		}
+		Type::PartialTuple(items, _) => {
+			for (_, t) in items {
+				named_types(t, enums, records);
+			}
+		}
+		Type::List(t) | Type::Ref(t) => named_types(t, enums, records),
+		Type::Dict(k, v) => {
+			named_types(k, enums, records);
+			named_types(v, enums, records);
+		}
+		_ => {}
+	}
+}
+
+fn find_enum(module: &Module, name: &str) -> Option<Range> {
+	module
+		.ast
+		.as_ref()?
+		.body
+		.iter()
+		.find_map(|def| match &def.kind {
+			DefinitionKind::Enum(_) if def.name.name == name => Some(def.name.range),
+			_ => None,
+		})
+}
+
+fn find_aliases(module: &Module, labels: &[String]) -> Vec<Range> {
+	let Some(ast) = module.ast.as_ref() else {
+		return Vec::new();
+	};
+	ast
+		.body
+		.iter()
+		.filter_map(|def| {
+			let DefinitionKind::Alias(TypeExprNode {
+				kind: TypeExprKind::Record(fields),
+				..
+			}) = &def.kind
+			else {
+				return None;
+			};
+			let mut declared: Vec<&str> = fields.iter().map(|(l, _)| l.name.as_str()).collect();
+			declared.sort();
+			declared.iter().eq(labels.iter()).then_some(def.name.range)
+		})
+		.collect()
+}
+
+fn parse(source: &[u8]) -> Option<Module> {
+	let mut module = Module::new("<lsp>".to_string(), PathBuf::new());
+	let mut diagnostics: Vec<Diagnostic> = Vec::new();
+	module.parse_from_bytes(source.to_vec(), &mut diagnostics);
+	module.ast.as_ref()?;
+	Some(module)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::temp_project;
+
+	fn at(dir: &Path, file: &str, line: u32, character: u32) -> Vec<(String, usize, usize)> {
+		let path = dir.join(file);
+		let source = std::fs::read(&path).unwrap();
+		let module = crate::analysis::analyze_document(&path, source.clone())
+			.module
+			.unwrap();
+		let hits = hover::build_index(&module);
+		type_definitions(&hits, &source, &path, line, character)
+			.iter()
+			.map(|(p, r)| {
+				(
+					p.file_name().unwrap().to_string_lossy().into_owned(),
+					r.start.line,
+					r.start.col,
+				)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn values_lead_to_their_enums_and_aliases() {
+		let colors = "public enum color {\n\tred\n\tgreen\n}\n\npublic alias swatch {\n\tname :: string,\n\tcolor :: color,\n}\n";
+		let main = "use colors\nuse std/json\n\nenum size {\n\tsmall\n\tlarge\n}\n\ndef pick = fun s {\n\twhen s is size.small {\n\t\tcolors.color.red\n\t} is size.large {\n\t\tcolors.color.green\n\t}\n}\n\ndef sample = {name: \"moss\", color: colors.color.green}\n\ndef maybe = some json.value.null\n";
+		let dir = temp_project(
+			"typedef-values",
+			&[("colors.pa", colors), ("main.pa", main)],
+		);
+
+		// A function: its parameter's enum here, its result's in the import.
+		assert_eq!(
+			at(&dir, "main.pa", 8, 5),
+			vec![
+				("main.pa".to_string(), 3, 5),
+				("colors.pa".to_string(), 0, 12)
+			]
+		);
+		// A record: the alias declaring its fields, then the enum of a field.
+		assert_eq!(
+			at(&dir, "main.pa", 16, 5),
+			vec![
+				("colors.pa".to_string(), 0, 12),
+				("colors.pa".to_string(), 5, 13)
+			]
+		);
+		// A stdlib enum lands in its materialized source; the built-in
+		// `option` has none.
+		let found = at(&dir, "main.pa", 18, 5);
+		assert_eq!(
+			found.iter().map(|(f, ..)| f.as_str()).collect::<Vec<_>>(),
+			vec!["json.pa"]
+		);
+		// A literal has no declared type.
+		assert!(at(&dir, "main.pa", 0, 0).is_empty());
+	}
+}
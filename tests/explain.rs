@@ -0,0 +1,109 @@
+// The `pluma explain` registry audit. Two guarantees:
+//
+//   - Coverage: every code the compiler and linter can emit has an explanation,
+//     and every explanation belongs to a code that still exists. Codes are read
+//     straight from the `code()` tables in `compiler/src/errors/` and the lint
+//     rules, so adding an error kind without writing its explanation fails here.
+//   - Honesty: each explanation's examples are compiled (E-codes) or linted
+//     (L-codes). The `pluma fails` example must report the code; every other
+//     example must be free of errors and must not report it. An explanation
+//     can't drift from what the compiler actually says.
+
+use compiler::explain::{self, Explanation};
+use compiler::{Compiler, Diagnostic};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Every `"E0123"` / `"L0123"` string literal in the `.rs` files under `dir`.
+fn codes_in(dir: &Path, out: &mut BTreeSet<String>) {
+	for entry in fs::read_dir(dir).unwrap().flatten() {
+		let path = entry.path();
+		if path.extension().is_none_or(|ext| ext != "rs") {
+			continue;
+		}
+		let source = fs::read_to_string(&path).unwrap();
+		for (i, _) in source.match_indices('"') {
+			let code = source.get(i + 1..i + 6).unwrap_or("");
+			let closed = source.get(i + 6..i + 7) == Some("\"");
+			let shaped = code.len() == 5
+				&& (code.starts_with('E') || code.starts_with('L'))
+				&& code[1..].bytes().all(|b| b.is_ascii_digit());
+			if closed && shaped {
+				out.insert(code.to_string());
+			}
+		}
+	}
+}
+
+fn emitted_codes() -> BTreeSet<String> {
+	let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+	let mut codes = BTreeSet::new();
+	codes_in(&workspace.join("compiler/src/errors"), &mut codes);
+	codes_in(&workspace.join("linter/src/rules"), &mut codes);
+	codes
+}
+
+#[test]
+fn every_emitted_code_is_explained() {
+	let emitted = emitted_codes();
+	let explained: BTreeSet<String> = explain::all().iter().map(|e| e.code.to_string()).collect();
+
+	let missing: Vec<&String> = emitted.difference(&explained).collect();
+	assert!(missing.is_empty(), "codes with no explanation: {missing:?}");
+
+	let stale: Vec<&String> = explained.difference(&emitted).collect();
+	assert!(
+		stale.is_empty(),
+		"explanations for codes nothing emits: {stale:?}"
+	);
+}
+
+/// The diagnostics an example produces: the full compile for an error code, the
+/// linter's warnings for a lint code.
+fn diagnose(e: &Explanation, source: &str) -> Vec<Diagnostic> {
+	if e.code.starts_with('L') {
+		return match linter::lint_source(source.as_bytes()) {
+			Ok(warnings) => warnings,
+			Err(errors) => errors,
+		};
+	}
+	let mut compiler = Compiler::for_root_dir(std::env::temp_dir());
+	compiler.add_entry_module("main".into());
+	compiler.set_module_source("main".into(), source.as_bytes().to_vec());
+	compiler.check().err().unwrap_or_default()
+}
+
+fn describe(diagnostics: &[Diagnostic]) -> String {
+	diagnostics
+		.iter()
+		.map(|d| format!("[{}] {}", d.code.unwrap_or("-"), d.message))
+		.collect::<Vec<_>>()
+		.join("; ")
+}
+
+#[test]
+fn examples_report_exactly_what_they_claim() {
+	let mut failures = Vec::new();
+	for e in explain::all() {
+		for example in e.examples() {
+			let diagnostics = diagnose(e, &example.source);
+			let reports_code = diagnostics.iter().any(|d| d.code == Some(e.code));
+			if example.fails && !reports_code {
+				failures.push(format!(
+					"{}: the failing example doesn't report it (got: {})",
+					e.code,
+					describe(&diagnostics)
+				));
+			}
+			if !example.fails && (reports_code || diagnostics.iter().any(Diagnostic::is_error)) {
+				failures.push(format!(
+					"{}: a fixed example still fails (got: {})",
+					e.code,
+					describe(&diagnostics)
+				));
+			}
+		}
+	}
+	assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
@@ -0,0 +1,412 @@
+//! Debug info: what lets a runtime failure be reported in Pluma terms rather than
+//! as an opaque wasm trap.
+//!
+//! The emitter writes two custom sections. The standard `name` section names
+//! every function (IR functions by their lowered name, `__*` helpers, builtin
+//! wrappers, imports), so engines and tools print `main.divide` instead of
+//! `wasm-function[42]`. The `pluma.lines` section is Pluma's own line table: per
+//! IR function its def name, module, and a list of `(body byte offset, line)`
+//! rows marking where each source line's code starts.
+//!
+//! The host reads both back with [`LineTable`]: V8 reports a wasm frame as
+//! `wasm-function[<index>]:0x<module offset>`, which the table maps to the def,
+//! module and line that emitted the instruction at that offset.
+//!
+//! `pluma.lines` layout (all integers unsigned LEB128, strings length-prefixed
+//! UTF-8): a version byte, the index of the first defined function, the entry
+//! count, then per entry its function index, name, module, row count, and rows
+//! as `(offset from the start of the function body, 1-based line)`.
+//!
+//! `wasm-opt` rewrites function bodies, so an optimized build drops the table
+//! (see `opt::optimize`) — its offsets would point at the wrong code.
+
+use std::collections::HashMap;
+use wasm_encoder::{CustomSection, Encode, NameMap, NameSection};
+
+/// The custom section holding Pluma's line table.
+pub const LINES_SECTION: &str = "pluma.lines";
+
+const LINES_VERSION: u8 = 1;
+
+/// One IR function's line rows, as recorded at emit time.
+struct FuncLines {
+	index: u32,
+	name: String,
+	module: String,
+	rows: Vec<(u32, u32)>,
+}
+
+/// Collects names and line rows during `Module::build`, then encodes both
+/// sections. Functions must be added in increasing index order (the name
+/// section requires it).
+pub(crate) struct DebugInfoBuilder {
+	code_base: u32,
+	names: NameMap,
+	funcs: Vec<FuncLines>,
+}
+
+impl DebugInfoBuilder {
+	/// `code_base` is the index of the first defined (non-imported) function.
+	pub(crate) fn new(code_base: u32) -> Self {
+		DebugInfoBuilder {
+			code_base,
+			names: NameMap::new(),
+			funcs: Vec::new(),
+		}
+	}
+
+	/// Name a function that has no source lines (an import, helper, or wrapper).
+	pub(crate) fn name(&mut self, index: u32, name: &str) {
+		self.names.append(index, name);
+	}
+
+	/// Name an IR function and record its line rows.
+	pub(crate) fn function(&mut self, index: u32, name: &str, module: &str, rows: &[(u32, u32)]) {
+		self.names.append(index, name);
+		if !rows.is_empty() {
+			self.funcs.push(FuncLines {
+				index,
+				name: name.to_string(),
+				module: module.to_string(),
+				rows: rows.to_vec(),
+			});
+		}
+	}
+
+	/// Append the `name` and `pluma.lines` sections to `module`.
+	pub(crate) fn finish(self, module: &mut wasm_encoder::Module) {
+		let mut names = NameSection::new();
+		names.functions(&self.names);
+		module.section(&names);
+
+		let mut data = vec![LINES_VERSION];
+		self.code_base.encode(&mut data);
+		(self.funcs.len() as u32).encode(&mut data);
+		for f in &self.funcs {
+			f.index.encode(&mut data);
+			f.name.encode(&mut data);
+			f.module.encode(&mut data);
+			(f.rows.len() as u32).encode(&mut data);
+			for &(offset, line) in &f.rows {
+				offset.encode(&mut data);
+				line.encode(&mut data);
+			}
+		}
+		module.section(&CustomSection {
+			name: LINES_SECTION.into(),
+			data: data.into(),
+		});
+	}
+}
+
+/// One frame of a Pluma-level stack trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+	/// The def the code belongs to, e.g. `divide` (lowering suffixes like
+	/// `@thunk` and the module prefix stripped).
+	pub def: String,
+	/// The module, e.g. `main` or `std/list`.
+	pub module: String,
+	/// 1-based source line, when the offset falls on a recorded row.
+	pub line: Option<u32>,
+}
+
+impl std::fmt::Display for Frame {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.line {
+			Some(line) => write!(f, "at {} ({}.pa:{})", self.def, self.module, line),
+			None => write!(f, "at {} ({}.pa)", self.def, self.module),
+		}
+	}
+}
+
+/// The line table of an emitted module, read back for symbolication.
+pub struct LineTable {
+	funcs: HashMap<u32, FuncLines>,
+	/// Module byte offset of each defined function's body, in code-section order.
+	body_starts: Vec<u32>,
+	code_base: u32,
+}
+
+impl LineTable {
+	/// Read the table out of `module`'s bytes. `None` if the module carries no
+	/// `pluma.lines` section (an optimized build) or it can't be parsed.
+	pub fn read(module: &[u8]) -> Option<LineTable> {
+		let mut r = Reader::new(module);
+		if r.bytes(8)? != b"\0asm\x01\0\0\0" {
+			return None;
+		}
+		let mut lines: Option<&[u8]> = None;
+		let mut body_starts = Vec::new();
+		while !r.done() {
+			let id = r.byte()?;
+			let size = r.u32()? as usize;
+			let start = r.pos;
+			let mut section = Reader {
+				bytes: &module[..start + size],
+				pos: start,
+			};
+			match id {
+				0 if section.str()? == LINES_SECTION => {
+					lines = Some(&module[section.pos..start + size]);
+				}
+				10 => {
+					for _ in 0..section.u32()? {
+						let len = section.u32()? as usize;
+						body_starts.push(section.pos as u32);
+						section.bytes(len)?;
+					}
+				}
+				_ => {}
+			}
+			r.bytes(size)?;
+		}
+
+		let mut r = Reader::new(lines?);
+		if r.byte()? != LINES_VERSION {
+			return None;
+		}
+		let code_base = r.u32()?;
+		let mut funcs = HashMap::new();
+		for _ in 0..r.u32()? {
+			let index = r.u32()?;
+			let name = r.str()?.to_string();
+			let module = r.str()?.to_string();
+			let mut rows = Vec::new();
+			for _ in 0..r.u32()? {
+				rows.push((r.u32()?, r.u32()?));
+			}
+			funcs.insert(
+				index,
+				FuncLines {
+					index,
+					name,
+					module,
+					rows,
+				},
+			);
+		}
+		Some(LineTable {
+			funcs,
+			body_starts,
+			code_base,
+		})
+	}
+
+	/// The Pluma frame for the instruction at module byte `offset` in function
+	/// `index`, or `None` for a function with no source (a runtime helper).
+	pub fn frame(&self, index: u32, offset: u32) -> Option<Frame> {
+		let f = self.funcs.get(&index)?;
+		let body = *self
+			.body_starts
+			.get(index.checked_sub(self.code_base)? as usize)?;
+		let at = offset.checked_sub(body);
+		let line = at.and_then(|at| {
+			let i = f.rows.partition_point(|&(row, _)| row <= at);
+			i.checked_sub(1).map(|i| f.rows[i].1)
+		});
+		Some(Frame {
+			def: def_name(&f.name, &f.module),
+			module: f.module.clone(),
+			line,
+		})
+	}
+
+	/// Map every `wasm-function[<index>]:0x<offset>` frame in a V8 stack string
+	/// to its Pluma frame, innermost first. Frames in runtime helpers, imports,
+	/// and JS are skipped.
+	pub fn symbolicate(&self, v8_stack: &str) -> Vec<Frame> {
+		v8_stack
+			.lines()
+			.filter_map(|line| {
+				let rest = &line[line.find("wasm-function[")? + "wasm-function[".len()..];
+				let (index, rest) = rest.split_once(']')?;
+				let offset = rest.strip_prefix(":0x")?;
+				let offset = &offset[..offset
+					.find(|c: char| !c.is_ascii_hexdigit())
+					.unwrap_or(offset.len())];
+				self.frame(index.parse().ok()?, u32::from_str_radix(offset, 16).ok()?)
+			})
+			.collect()
+	}
+}
+
+/// Render frames one per line under a failure message, indented, collapsing a
+/// run of identical frames (deep recursion) into one line and a count.
+pub fn render_trace(frames: &[Frame]) -> String {
+	let mut out = String::new();
+	let mut i = 0;
+	while i < frames.len() {
+		let run = frames[i..].iter().take_while(|f| **f == frames[i]).count();
+		out.push_str(&format!("  {}\n", frames[i]));
+		if run > 1 {
+			out.push_str(&format!("  ... repeated {} more times\n", run - 1));
+		}
+		i += run;
+	}
+	out
+}
+
+/// The def a function belongs to, for display: `main.divide@thunk` and the
+/// lambda `main.divide.fun@3:17` in module `main` are both `divide`.
+fn def_name(name: &str, module: &str) -> String {
+	let name = name.split('@').next().unwrap_or(name);
+	let name = name
+		.strip_prefix(module)
+		.and_then(|n| n.strip_prefix('.'))
+		.unwrap_or(name);
+	name.split('.').next().unwrap_or(name).to_string()
+}
+
+/// Remove the `pluma.lines` section from a module, leaving everything else
+/// byte-for-byte. Returns the input unchanged if it doesn't parse.
+pub fn strip_line_table(module: &[u8]) -> Vec<u8> {
+	let mut r = Reader::new(module);
+	if r.bytes(8).is_none() {
+		return module.to_vec();
+	}
+	let mut out = module[..8].to_vec();
+	while !r.done() {
+		let start = r.pos;
+		let Some((id, size)) = r.byte().zip(r.u32()) else {
+			return module.to_vec();
+		};
+		let payload = r.pos;
+		if r.bytes(size as usize).is_none() {
+			return module.to_vec();
+		}
+		let mut section = Reader {
+			bytes: &module[..r.pos],
+			pos: payload,
+		};
+		if id == 0 && section.str() == Some(LINES_SECTION) {
+			continue;
+		}
+		out.extend_from_slice(&module[start..r.pos]);
+	}
+	out
+}
+
+/// A bounds-checked cursor over wasm bytes.
+struct Reader<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn new(bytes: &'a [u8]) -> Self {
+		Reader { bytes, pos: 0 }
+	}
+
+	fn done(&self) -> bool {
+		self.pos >= self.bytes.len()
+	}
+
+	fn byte(&mut self) -> Option<u8> {
+		let b = *self.bytes.get(self.pos)?;
+		self.pos += 1;
+		Some(b)
+	}
+
+	fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+		let s = self.bytes.get(self.pos..self.pos.checked_add(n)?)?;
+		self.pos += n;
+		Some(s)
+	}
+
+	fn u32(&mut self) -> Option<u32> {
+		let mut value = 0u32;
+		for shift in (0..35).step_by(7) {
+			let b = self.byte()?;
+			value |= ((b & 0x7f) as u32).checked_shl(shift)?;
+			if b & 0x80 == 0 {
+				return Some(value);
+			}
+		}
+		None
+	}
+
+	fn str(&mut self) -> Option<&'a str> {
+		let len = self.u32()? as usize;
+		std::str::from_utf8(self.bytes(len)?).ok()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// A module with one import and two defined functions: `main.divide@thunk`
+	// (lines 3 and 4, starting at body offsets 1 and 5) and a lambda inside it.
+	fn module() -> Vec<u8> {
+		use wasm_encoder::*;
+		let mut types = TypeSection::new();
+		types.ty().function([], []);
+		let mut imports = ImportSection::new();
+		imports.import("pluma", "print", EntityType::Function(0));
+		let mut functions = FunctionSection::new();
+		functions.function(0);
+		functions.function(0);
+		let mut code = CodeSection::new();
+		for _ in 0..2 {
+			let mut f = Function::new([]);
+			for _ in 0..8 {
+				f.instruction(&Instruction::Nop);
+			}
+			f.instruction(&Instruction::End);
+			code.function(&f);
+		}
+		let mut module = Module::new();
+		module.section(&types);
+		module.section(&imports);
+		module.section(&functions);
+		module.section(&code);
+
+		let mut debug = DebugInfoBuilder::new(1);
+		debug.name(0, "pluma.print");
+		debug.function(1, "main.divide@thunk", "main", &[(1, 3), (5, 4)]);
+		debug.function(2, "main.divide.fun@2:4", "main", &[(0, 9)]);
+		debug.finish(&mut module);
+		module.finish()
+	}
+
+	#[test]
+	fn symbolicates_v8_frames_to_defs_and_lines() {
+		let bytes = module();
+		let table = LineTable::read(&bytes).unwrap();
+		let body = table.body_starts[0];
+
+		let stack = format!(
+			"Error: io.fail\n\
+			 \x20   at Object.<anonymous> (<anonymous>:1:1)\n\
+			 \x20   at pluma.print (wasm://wasm/1a2b:wasm-function[0]:0x40)\n\
+			 \x20   at main.divide.fun@2:4 (wasm://wasm/1a2b:wasm-function[2]:{:#x})\n\
+			 \x20   at main.divide@thunk (wasm://wasm/1a2b:wasm-function[1]:{:#x})\n\
+			 \x20   at main.divide@thunk (wasm://wasm/1a2b:wasm-function[1]:{:#x})",
+			table.body_starts[1] + 2,
+			body + 6,
+			body + 2,
+		);
+		let frames = table.symbolicate(&stack);
+		let at = |line| Frame {
+			def: "divide".into(),
+			module: "main".into(),
+			line: Some(line),
+		};
+		assert_eq!(frames, vec![at(9), at(4), at(3)]);
+		assert_eq!(
+			render_trace(&[at(4), at(4), at(4), at(3)]),
+			"  at divide (main.pa:4)\n  ... repeated 2 more times\n  at divide (main.pa:3)\n"
+		);
+	}
+
+	#[test]
+	fn stripping_drops_only_the_line_table() {
+		let bytes = module();
+		let stripped = strip_line_table(&bytes);
+		assert!(LineTable::read(&stripped).is_none());
+		assert!(stripped.len() < bytes.len());
+		// The name section survives.
+		assert!(stripped.windows(4).any(|w| w == b"name"));
+	}
+}
@@ -72,6 +72,12 @@ pub(crate) struct FnEmitter<'a> {
 	/// per `Stmt` in `block`. Only consumed by `debug`, which renders a
 	/// `[<module>:<line>]` call-site header.
 	cur_line: usize,
+	/// Where each new source line starts, as `(index into body, 0-based line)`,
+	/// recorded per `Stmt` in `block`. `emit` turns the indices into body byte
+	/// offsets for the `pluma.lines` table (see `debuginfo`).
+	line_marks: Vec<(usize, usize)>,
+	/// `line_marks` resolved by `emit`: `(body byte offset, 1-based line)`.
+	lines: Vec<(u32, u32)>,
 	body: Vec<Instruction<'static>>,
 }
 
@@ -143,6 +149,8 @@ impl<'a> FnEmitter<'a> {
 			loop_stack: Vec::new(),
 			defers_local: None,
 			cur_line: 0,
+			line_marks: Vec::new(),
+			lines: Vec::new(),
 			body: Vec::new(),
 		}
 	}
@@ -181,13 +189,28 @@ impl<'a> FnEmitter<'a> {
 		let body = self.f.body.clone();
 		self.block(&body);
 		let mut func = Function::new_with_locals_types(self.local_types.iter().copied());
-		for ins in &self.body {
+		let mut marks = self.line_marks.iter().peekable();
+		for (i, ins) in self.body.iter().enumerate() {
+			while let Some(&(_, line)) = marks.next_if(|(at, _)| *at == i) {
+				// Marks with no instruction between them: the last one wins.
+				let row = (func.byte_len() as u32, line as u32 + 1);
+				match self.lines.last_mut() {
+					Some(last) if last.0 == row.0 => *last = row,
+					_ => self.lines.push(row),
+				}
+			}
 			func.instruction(ins);
 		}
 		func.instruction(&Instruction::End);
 		func
 	}
 
+	/// The function's line table, `(body byte offset, 1-based line)` in offset
+	/// order — filled by `emit`.
+	pub(crate) fn lines(&self) -> &[(u32, u32)] {
+		&self.lines
+	}
+
 	/// Allocate a fresh wasm local of the given type, returning its index.
 	fn fresh_local(&mut self, ty: ValType) -> u32 {
 		let idx = self.next_local;
@@ -199,7 +222,25 @@ impl<'a> FnEmitter<'a> {
 	fn block(&mut self, b: &Block) {
 		for s in &b.0 {
 			self.cur_line = s.range.start.line;
+			// Synthetic stmts (collapsed at 0:0) have no line; they run under the
+			// last real one.
+			let synthetic = s.range.is_collapsed() && s.range.start.line == 0 && s.range.start.col == 0;
+			if !synthetic {
+				self.mark_line(s.range.start.line);
+			}
 			self.stmt(&s.kind);
+			// A nested block marked its own lines; whatever this stmt emits after
+			// it (a join, a result move) is this stmt's line again.
+			if !synthetic {
+				self.mark_line(s.range.start.line);
+			}
+		}
+	}
+
+	/// Attribute the instructions emitted from here on to `line`.
+	fn mark_line(&mut self, line: usize) {
+		if self.line_marks.last().is_none_or(|&(_, l)| l != line) {
+			self.line_marks.push((self.body.len(), line));
 		}
 	}
 
@@ -311,6 +352,13 @@ impl<'a> FnEmitter<'a> {
 				None => self.diags.push("Continue outside loop"),
 			},
 			StmtKind::Match { subject, arms } => self.match_stmt(subject, arms),
+			StmtKind::Probe(n) => match self.host_index.get("coverage-hit").copied() {
+				Some(hit) => {
+					self.ins(Instruction::I32Const(*n as i32));
+					self.ins(Instruction::Call(hit));
+				}
+				None => self.diags.push("Probe without the coverage-hit import"),
+			},
 			other => self.diags.push(format!("unsupported stmt: {other:?}")),
 		}
 	}
@@ -2467,6 +2515,48 @@ impl<'a> FnEmitter<'a> {
 				self.ins(Instruction::Call(idx));
 				self.ins(Instruction::StructNew(types::T_EXTERN));
 			}
+			// `(externref, ptr, len) -> i32` — `test-dom-count`/`test-dom-dispatch`: node +
+			// one string in, box the answer as an `int`.
+			DomKind::NodeStrInt => {
+				let (Some(alloc), Some(store)) = (alloc, store) else {
+					self
+						.diags
+						.push(format!("`{tag}` needs the marshalling helpers"));
+					self.push_nothing();
+					return;
+				};
+				self.reset_bump();
+				let (ptr, len) = self.marshal_strlike_arg(&args[1], alloc, store);
+				self.unbox_extern(&args[0]);
+				self.ins(Instruction::LocalGet(ptr));
+				self.ins(Instruction::LocalGet(len));
+				self.ins(Instruction::Call(idx));
+				self.ins(Instruction::I64ExtendI32S);
+				self.box_int();
+			}
+			// `(externref, sp, sl, i32) -> externref` — `test-dom-match`: marshal the
+			// selector first (it writes scratch), then stack `[TAG_EXTERN, node, sp, sl, i]`
+			// so the returned node lands ready for the `StructNew` (like `ChildAt`).
+			DomKind::Query => {
+				let (Some(alloc), Some(store)) = (alloc, store) else {
+					self
+						.diags
+						.push(format!("`{tag}` needs the marshalling helpers"));
+					self.push_nothing();
+					return;
+				};
+				self.reset_bump();
+				let (sp, sl) = self.marshal_strlike_arg(&args[1], alloc, store);
+				self.ins(Instruction::I32Const(types::TAG_EXTERN));
+				self.unbox_extern(&args[0]);
+				self.ins(Instruction::LocalGet(sp));
+				self.ins(Instruction::LocalGet(sl));
+				self.atom(&args[2]);
+				self.unbox_int();
+				self.ins(Instruction::I32WrapI64);
+				self.ins(Instruction::Call(idx));
+				self.ins(Instruction::StructNew(types::T_EXTERN));
+			}
 			// `(kp, kl, vp, vl) -> ()` — the dev store write: two scratch strings, no node.
 			DomKind::DevStoreSet => {
 				let (Some(alloc), Some(store)) = (alloc, store) else {
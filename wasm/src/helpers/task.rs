@@ -179,6 +179,7 @@ pub(crate) fn build_run_task_fn(
 			w.i32_lt_s();
 			w.if_else(
 				|w| {
+					shuffle_next_ready(w, g);
 					// entry = ready[rhead]; rhead += 1.
 					w.global_get(g.ready)
 						.ref_cast(types::T_LIST)
@@ -420,6 +421,7 @@ pub(crate) fn build_browser_run_fn(
 			w.i32_lt_s();
 			w.if_else(
 				|w| {
+					shuffle_next_ready(w, g);
 					// entry = ready[rhead]; rhead += 1.
 					w.global_get(g.ready)
 						.ref_cast(types::T_LIST)
@@ -4270,6 +4272,71 @@ fn compact_ready(w: &mut Wat, g: TaskGlobals) {
 	w.i32(0).global_set(g.rhead);
 }
 
+/// Seeded interleaving (`pluma test --schedule-seed`): swap a pseudo-random entry of
+/// the live queue `ready[rhead..len]` into `ready[rhead]` just before it's dequeued, so
+/// whichever ready fiber runs next is the seed's choice rather than FIFO order. The
+/// picks come from xorshift64 over `sched_seed` (set by the host before `_entry`), so
+/// one seed replays the same interleaving exactly. A zero seed — every ordinary run —
+/// leaves the queue alone.
+fn shuffle_next_ready(w: &mut Wat, g: TaskGlobals) {
+	let elems = w.local(types::valarray_ref());
+	let n = w.local(ValType::I32);
+	let j = w.local(ValType::I32);
+	let x = w.local(ValType::I64);
+	let held = w.local(types::value_ref());
+	// n = len(ready) - rhead; only a choice of two or more is worth a draw.
+	list_len(w, g.ready);
+	w.global_get(g.rhead).i32_sub().local_set(n);
+	w.global_get(g.sched_seed).i64_eqz().i32_eqz();
+	w.local_get(n).i32(1).i32_gt_s();
+	w.i32_and();
+	w.if_(|w| {
+		// x ^= x << 13; x ^= x >> 7; x ^= x << 17.
+		w.global_get(g.sched_seed).local_set(x);
+		w.local_get(x)
+			.local_get(x)
+			.i64(13)
+			.i64_shl()
+			.i64_xor()
+			.local_set(x);
+		w.local_get(x)
+			.local_get(x)
+			.i64(7)
+			.i64_shr_u()
+			.i64_xor()
+			.local_set(x);
+		w.local_get(x)
+			.local_get(x)
+			.i64(17)
+			.i64_shl()
+			.i64_xor()
+			.local_tee(x);
+		w.global_set(g.sched_seed);
+		// j = rhead + (x >> 33) % n — the high bits, non-negative, so `rem_s` is safe.
+		w.local_get(x).i64(33).i64_shr_u();
+		w.local_get(n).i64_extend_i32_u();
+		w.i64_rem_s().i32_wrap_i64();
+		w.global_get(g.rhead).i32_add().local_set(j);
+		// swap(ready[rhead], ready[j]).
+		w.global_get(g.ready)
+			.ref_cast(types::T_LIST)
+			.struct_get(types::T_LIST, 1)
+			.ref_cast(types::T_VALARRAY)
+			.local_set(elems);
+		w.local_get(elems)
+			.local_get(j)
+			.array_get(types::T_VALARRAY)
+			.local_set(held);
+		w.local_get(elems).local_get(j);
+		w.local_get(elems)
+			.global_get(g.rhead)
+			.array_get(types::T_VALARRAY);
+		w.array_set(types::T_VALARRAY);
+		w.local_get(elems).global_get(g.rhead).local_get(held);
+		w.array_set(types::T_VALARRAY);
+	});
+}
+
 /// Push i32 1 if every child of scope `sid` has settled (none alive), else 0.
 /// O(1): reads the scope's live-child counter rather than scanning CHILDREN (which
 /// retains every fid ever spawned), so `s.next` stays flat as a scope serves more.
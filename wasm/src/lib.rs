@@ -16,6 +16,7 @@ use ir::{Block, Callee, GlobalInit, IrProgram, PreEval, Rvalue, StmtKind};
 use std::collections::{HashMap, HashSet};
 
 mod async_lower;
+pub mod debuginfo;
 mod emit;
 mod helpers;
 mod module;
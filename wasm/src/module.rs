@@ -13,13 +13,16 @@ mod globals;
 mod imports;
 mod lits;
 
+use crate::debuginfo::DebugInfoBuilder;
 use crate::emit::FnEmitter;
 use crate::helpers::{REGISTRY, build_builtin_wrapper, builtin_arity, close_deps};
 use crate::runtime::{
 	Helper, HelperCtx, HelperSet, IoImports, NetImports, OffloadImports, Runtime, is_net_builtin,
 	is_offload_builtin, scan_helpers,
 };
-use crate::scan::{StrPool, collect_host_calls, collect_zero_arg_closures, scan_strings};
+use crate::scan::{
+	StrPool, block_has_probe, collect_host_calls, collect_zero_arg_closures, scan_strings,
+};
 use crate::types::FuncTypes;
 use crate::{Diagnostics, Reach, builtin_globals};
 use globals::build_globals;
@@ -224,6 +227,15 @@ impl Module {
 			requested.insert(Helper::ListAppend);
 			imports.register("web-fetch-open");
 		}
+		// `pluma test --coverage`: an instrumented program bumps its counters through
+		// the `coverage-hit` host import (see `ir::coverage`).
+		if reach
+			.order
+			.iter()
+			.any(|&fid| block_has_probe(&p.functions[fid as usize].body))
+		{
+			imports.register("coverage-hit");
+		}
 		let num_imports = imports.len();
 
 		// Dense FuncId -> wasm function index (imports occupy the low indices).
@@ -403,10 +415,15 @@ impl Module {
 		// Function-type interning + section building.
 		let mut ftypes = FuncTypes::new();
 
+		// Function names and per-def line rows, for the `name` and `pluma.lines`
+		// sections (see `debuginfo`). Filled in wasm index order as we go.
+		let mut debug = DebugInfoBuilder::new(num_imports);
+
 		let mut import_sec = ImportSection::new();
-		for tag in imports.order() {
+		for (i, tag) in imports.order().iter().enumerate() {
 			let ty = import_type(tag, &mut ftypes);
 			import_sec.import("pluma", tag, wasm_encoder::EntityType::Function(ty));
+			debug.name(i as u32, &format!("pluma.{tag}"));
 		}
 
 		// Each function's return repr, indexed by `FuncId.0` — the emitter reads it to
@@ -454,6 +471,7 @@ impl Module {
 			);
 			let func = em.emit();
 			code.function(&func);
+			debug.function(wasm_index[&fid], &f.name, &f.module, em.lines());
 		}
 
 		// Append the synthetic helpers after the IR functions, walking `REGISTRY` in
@@ -470,6 +488,7 @@ impl Module {
 					ctor_names: &ctor_names,
 				};
 				code.function(&(def.build)(&mut ctx));
+				debug.name(self_idx, &format!("__{:?}", def.id));
 			}
 		}
 
@@ -478,7 +497,8 @@ impl Module {
 		// gets an unbox/compute/rebox wrapper. (Builtins used as a first-class
 		// *value* are wrapped earlier, in `ir::lower`, as ordinary forwarding
 		// closures — they never reach this table.)
-		for tag in &wrapper_order {
+		for (i, tag) in wrapper_order.iter().enumerate() {
+			debug.name(wrapper_base + i as u32, &format!("__builtin.{tag}"));
 			let arity = builtin_arity(tag).unwrap();
 			functions.function(ftypes.for_arity(arity));
 			match build_builtin_wrapper(tag, &runtime.ord) {
@@ -567,6 +587,12 @@ impl Module {
 		if let Some(w) = runtime.idx(Helper::RpcStreamEvent) {
 			exports.export("__rpc_stream_event", ExportKind::Func, w);
 		}
+		// `__now`: the scheduler's virtual clock (i64 nanos), read by the host so a test's
+		// fake clock (`test.with-fake-clock`) moves with `task.sleep`.
+		exports.export("__now", ExportKind::Global, runtime.taskg.now);
+		// `__sched_seed`: the ready-queue shuffle's state, which the host seeds for
+		// `pluma test --schedule-seed` (0, the default, keeps FIFO order).
+		exports.export("__sched_seed", ExportKind::Global, runtime.taskg.sched_seed);
 
 		let mut data = DataSection::new();
 		data.passive(strpool.bytes.iter().copied());
@@ -584,6 +610,7 @@ impl Module {
 		module.section(&data_count);
 		module.section(&code);
 		module.section(&data);
+		debug.finish(&mut module);
 		module.finish()
 	}
 }
@@ -217,6 +217,7 @@ pub(super) fn build_globals(
 		out_arg: task_global(ValType::I32, &zero_i32),
 		out_arg64: task_global(ValType::I64, &zero_i64),
 		current_fiber: task_global(ValType::I32, &zero_i32),
+		sched_seed: task_global(ValType::I64, &zero_i64),
 	};
 
 	(globals_sec, gmap)
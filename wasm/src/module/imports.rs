@@ -203,7 +203,9 @@ pub(super) fn classify_host_call(
 				| DomKind::SetAttr
 				| DomKind::SetProp
 				| DomKind::SetBoolProp
-				| DomKind::DevStoreSet,
+				| DomKind::DevStoreSet
+				| DomKind::NodeStrInt
+				| DomKind::Query,
 			) => {
 				requested.insert(Helper::MarshalAlloc);
 				requested.insert(Helper::MarshalStore);
@@ -268,8 +270,9 @@ pub(super) fn import_type(tag: &str, ftypes: &mut FuncTypes) -> u32 {
 		ftypes.for_dom_set_timeout()
 	} else if is_byte_writer(tag) {
 		ftypes.for_host_write()
-	} else if tag == "io-copyout" || tag == "io-exit" {
-		// Both are `(i32) -> ()`: io-copyout's `dst`, io-exit's `code`.
+	} else if tag == "io-copyout" || tag == "io-exit" || tag == "coverage-hit" {
+		// All `(i32) -> ()`: io-copyout's `dst`, io-exit's `code`, coverage-hit's
+		// counter id.
 		ftypes.for_io_copyout()
 	} else if tag == "io-last-error" {
 		ftypes.for_io2()
@@ -324,7 +327,9 @@ pub(super) fn import_type(tag: &str, ftypes: &mut FuncTypes) -> u32 {
 			// `SetBoolProp` shares `Listen`'s `(externref, np, nl, i32)` shape.
 			Some(DomKind::SetAttr | DomKind::SetProp) => ftypes.for_dom_set_attr(),
 			Some(DomKind::SetText | DomKind::NodeStr) => ftypes.for_dom_node_str(),
-			Some(DomKind::GetValue) => ftypes.for_dom_get_value(),
+			// `NodeStrInt` is node + string in, i32 out: `GetValue`'s `(externref, i32, i32) -> i32`.
+			Some(DomKind::GetValue | DomKind::NodeStrInt) => ftypes.for_dom_get_value(),
+			Some(DomKind::Query) => ftypes.for_dom_query(),
 			Some(DomKind::Extern3) => ftypes.for_dom_extern3(),
 			Some(DomKind::Extern1) => ftypes.for_dom_extern1(),
 			Some(DomKind::ChildAt) => ftypes.for_dom_child_at(),
@@ -39,6 +39,9 @@ impl OptLevel {
 /// uniquely-named pair in the temp dir. Every WasmGC feature is enabled
 /// (`all_features`) — our module uses GC structs/arrays, tail calls, and
 /// reference types, all of which Binaryen rejects unless explicitly turned on.
+///
+/// The `pluma.lines` table is stripped first: its offsets index the unoptimized
+/// function bodies, so an optimized module reports traps without source lines.
 pub fn optimize(bytes: &[u8], level: OptLevel) -> Result<Vec<u8>, String> {
 	use std::sync::atomic::{AtomicU64, Ordering};
 	// pid + a process-local counter keeps concurrent emits (the test harness) from
@@ -50,6 +53,7 @@ pub fn optimize(bytes: &[u8], level: OptLevel) -> Result<Vec<u8>, String> {
 	let infile = dir.join(format!("pluma-opt-{pid}-{n}.in.wasm"));
 	let outfile = dir.join(format!("pluma-opt-{pid}-{n}.out.wasm"));
 
+	let bytes = crate::debuginfo::strip_line_table(bytes);
 	std::fs::write(&infile, bytes).map_err(|e| format!("wasm-opt: writing input: {e}"))?;
 
 	let mut opts = match level {
@@ -975,6 +975,7 @@ pub(crate) struct TaskGlobals {
 	pub(crate) out_arg: u32, // mut i32 — on park: wait arg (fid/sid), or sleep nanos low bits unused
 	pub(crate) out_arg64: u32, // mut i64 — on park sleep: nanos
 	pub(crate) current_fiber: u32, // mut i32 — fid of the fiber the pump is currently running (or reaping). The task-local builtins (`local-get`/`-enter`/`-exit`) index `fibers[current_fiber].ENV` through it.
+	pub(crate) sched_seed: u32, // mut i64 — xorshift64 state for `pluma test --schedule-seed`'s ready-queue shuffle (`shuffle_next_ready`); 0 = FIFO. Set by the host, never reset by the scheduler.
 }
 
 /// What the async driver needs to build `result`/`option` variants and find a
@@ -1211,6 +1212,12 @@ pub(crate) fn host_sig(tag: &str) -> Option<HostSig> {
 			arity: 1,
 			returns_value: true,
 		}),
+		// `std/test`'s fakes: one command string in, the host's answer as a `result
+		// string string` out — the same marshalled read as `io-read-file`.
+		"test-fake" => Some(HostSig {
+			arity: 1,
+			returns_value: true,
+		}),
 		// `io.exit code` diverges: `(i32 code) -> ()`, the host exits the process. Not
 		// `is_io_host`/`io_kind` — emitted by `emit_exit`, typed `(i32)->()` in `module`.
 		"io-exit" => Some(HostSig {
@@ -1339,6 +1346,22 @@ pub(crate) fn host_sig(tag: &str) -> Option<HostSig> {
 			arity: 1,
 			returns_value: true,
 		}),
+		// `std/test/browser`'s probes of the headless DOM the sys host keeps: render a
+		// node as HTML or text (the `dom-get-value` shape), fire an event at it and count
+		// the descendants matching a selector (node + string in, an int back), and pick
+		// the `i`-th match.
+		"test-dom-html" | "test-dom-text" => Some(HostSig {
+			arity: 1,
+			returns_value: true,
+		}),
+		"test-dom-dispatch" | "test-dom-count" => Some(HostSig {
+			arity: 2,
+			returns_value: true,
+		}),
+		"test-dom-match" => Some(HostSig {
+			arity: 3,
+			returns_value: true,
+		}),
 		// `std/web/fetch` (the Web target HTTP transport): one request string in, the
 		// reply produced back. The sys host lowers it like an io read (the blocking
 		// `emit_web_fetch` path, classified below); the browser routes it to the
@@ -1484,6 +1507,12 @@ pub(crate) enum DomKind {
 	/// `dom-dev-store-get`: `(kp, kl, dst, cap) -> len`; a scratch-string key in,
 	/// probe-read the stored value into scratch (the `GetValue` shape minus the node).
 	DevStoreGet,
+	/// `test-dom-count` / `test-dom-dispatch`: `(externref, ptr, len) -> i32`; node + one
+	/// string in, box the returned i32 as an `int`. The wasm type is `GetValue`'s.
+	NodeStrInt,
+	/// `test-dom-match`: `(externref, sp, sl, i32) -> externref`; node + selector + the
+	/// unboxed index in, box the returned node (a null handle past the last match).
+	Query,
 }
 
 /// Classify a `std/web/dom` host builtin emitted via `emit_dom`. `None` for non-dom tags.
@@ -1519,6 +1548,11 @@ pub(crate) fn dom_kind(tag: &str) -> Option<DomKind> {
 		// `dom-element-text` reads an element's text by id — a key-string-in,
 		// value-string-out probe, exactly the `dev-store-get` shape.
 		"dom-dev-store-get" | "dom-element-text" => DomKind::DevStoreGet,
+		// `std/test/browser`, against the sys host's headless DOM. Rendering a node reads
+		// a string back like `dom-get-value`.
+		"test-dom-html" | "test-dom-text" => DomKind::GetValue,
+		"test-dom-dispatch" | "test-dom-count" => DomKind::NodeStrInt,
+		"test-dom-match" => DomKind::Query,
 		_ => return None,
 	})
 }
@@ -1587,7 +1621,10 @@ pub(crate) fn io_kind(tag: &str) -> Option<IoKind> {
 		// string` out — so it reuses the `(path, plen, dst, cap)` read marshalling.
 		// `compile-wasm-hex`/`sandbox-run-hex` aren't io either, but share the shape —
 		// a string in, a `result string` out (the playground compile + run primitives).
-		"io-read-file" | "uuid-parse" | "compile-wasm-hex" | "sandbox-run-hex" => IoKind::ReadFileStr,
+		// `test-fake` (std/test's clock/fs/net fakes) shares it too: a command in, an answer out.
+		"io-read-file" | "uuid-parse" | "compile-wasm-hex" | "sandbox-run-hex" | "test-fake" => {
+			IoKind::ReadFileStr
+		}
 		"io-read-file-bytes" => IoKind::ReadFileBytes,
 		"io-read-dir" => IoKind::ReadDir,
 		"io-args" => IoKind::Args,
@@ -1650,6 +1687,8 @@ pub(crate) fn is_io_result(tag: &str) -> bool {
 			// the playground compile + run primitives — `result string` via `__io_result`.
 			| "compile-wasm-hex"
 			| "sandbox-run-hex"
+			// a `std/test` fake command — `result string` via `__io_result`.
+			| "test-fake"
 	)
 }
 
@@ -513,3 +513,18 @@ pub(crate) fn block_has_pushdefer(block: &Block) -> bool {
 		_ => false,
 	})
 }
+
+/// True if `block` (or any nested block) bumps a coverage counter — i.e. the
+/// program was instrumented by `ir::coverage` and needs the `coverage-hit` import.
+pub(crate) fn block_has_probe(block: &Block) -> bool {
+	block.0.iter().any(|s| match &s.kind {
+		StmtKind::Probe(_) => true,
+		StmtKind::If(_, t, e) => block_has_probe(t) || block_has_probe(e),
+		StmtKind::Switch { arms, default, .. } => {
+			arms.iter().any(|(_, b)| block_has_probe(b)) || block_has_probe(default)
+		}
+		StmtKind::Match { arms, .. } => arms.iter().any(|a| block_has_probe(&a.body)),
+		StmtKind::Loop(b) => block_has_probe(b),
+		_ => false,
+	})
+}
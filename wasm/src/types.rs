@@ -432,6 +432,10 @@ enum FuncKind {
 	/// `dom-child-at(externref node, i32 i) -> externref` — the node's i-th child
 	/// (used by `render.hydrate` to walk a server-rendered tree by index).
 	DomChildAt,
+	/// `test-dom-match(externref root, i32 sp, i32 sl, i32 i) -> externref` — the
+	/// `i`-th descendant of `root` matching the scratch-string selector
+	/// (`std/test/browser`).
+	DomQuery,
 	/// `dom-set-timeout(i32 delay_ms, i32 token) -> ()` — ask the host to `setTimeout`
 	/// a call to the exported `__browser_resume(token)` (the browser command runtime's
 	/// real-timer source).
@@ -864,6 +868,11 @@ impl FuncTypes {
 		self.intern(FuncKind::DomChildAt)
 	}
 
+	/// `test-dom-match`: `(externref, i32, i32, i32) -> externref`.
+	pub fn for_dom_query(&mut self) -> u32 {
+		self.intern(FuncKind::DomQuery)
+	}
+
 	/// `dom-set-timeout`: `(i32, i32) -> ()`.
 	pub fn for_dom_set_timeout(&mut self) -> u32 {
 		self.intern(FuncKind::DomSetTimeout)
@@ -1487,6 +1496,13 @@ impl FuncTypes {
 						.function([extern_ref(), ValType::I32], [extern_ref()]);
 					continue;
 				}
+				FuncKind::DomQuery => {
+					types.ty().function(
+						[extern_ref(), ValType::I32, ValType::I32, ValType::I32],
+						[extern_ref()],
+					);
+					continue;
+				}
 				FuncKind::DomSetTimeout => {
 					types.ty().function([ValType::I32, ValType::I32], []);
 					continue;